@@ -0,0 +1,40 @@
+// writes the server side of the service in proto/chess.proto when the grpc
+// feature is on. its messages are written out by hand in src/grpc.rs, so the
+// build doesn't need protoc
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    grpc::generate();
+}
+
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    fn method(name: &str, route: &str, input: &str, output: &str) -> Method {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("super::{}", input))
+            .output_type(format!("super::{}", output))
+            .codec_path("tonic_prost::ProstCodec")
+            .build()
+    }
+
+    pub fn generate() {
+        let service = Service::builder()
+            .name("Chess")
+            .package("chess")
+            .method(method(
+                "validate_move",
+                "ValidateMove",
+                "ValidateMoveRequest",
+                "ValidateMoveReply",
+            ))
+            .method(method("legal_moves", "LegalMoves", "Position", "MoveList"))
+            .method(method("analyse", "Analyse", "AnalyseRequest", "Analysis"))
+            .method(method("play_game", "PlayGame", "Game", "GameState"))
+            .build();
+        Builder::new().build_client(false).compile(&[service]);
+    }
+}
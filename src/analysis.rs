@@ -0,0 +1,373 @@
+use crate::cache::{AnalysisCache, Cached};
+use crate::display::DisplayOptions;
+use crate::engine::{self, MATE};
+use crate::game::Game;
+use crate::uci::UciEngine;
+use crate::{ChessBoard, Color, Move};
+
+// how much a move may give away, in centipawns, before it counts as an
+// inaccuracy, a mistake or a blunder
+const INACCURACY: i32 = 50;
+const MISTAKE: i32 = 100;
+const BLUNDER: i32 = 300;
+
+// how good a move was by what it gave away compared to the engine's best
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quality {
+    // the engine's own choice
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Quality {
+    fn of(loss: i32, best: bool) -> Self {
+        match loss {
+            _ if best => Quality::Best,
+            l if l >= BLUNDER => Quality::Blunder,
+            l if l >= MISTAKE => Quality::Mistake,
+            l if l >= INACCURACY => Quality::Inaccuracy,
+            _ => Quality::Good,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Quality::Best => "best",
+            Quality::Good => "good",
+            Quality::Inaccuracy => "inaccuracy",
+            Quality::Mistake => "mistake",
+            Quality::Blunder => "blunder",
+        }
+    }
+
+    // the annotation symbol written after the move, none for sound moves
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Quality::Best | Quality::Good => "",
+            Quality::Inaccuracy => "?!",
+            Quality::Mistake => "?",
+            Quality::Blunder => "??",
+        }
+    }
+}
+
+// what the analysis made of a played move
+#[derive(Debug, Clone)]
+pub struct Review {
+    pub quality: Quality,
+    // white's score after the move, none once the game is over there
+    pub score: Option<i32>,
+    // the engine's main line from the position before, when it starts with
+    // another move than the one played
+    pub line: Vec<Move>,
+}
+
+// the most critical moments listed in the summary
+const CRITICAL: usize = 3;
+
+// what the engine thinks of one position: white's score and the main line,
+// empty once the game is over there
+struct Verdict {
+    score: i32,
+    line: Vec<Move>,
+}
+
+impl Verdict {
+    fn best(&self) -> Option<Move> {
+        self.line.first().copied()
+    }
+
+    // the score, or the result once there are no moves left
+    fn text(&self) -> String {
+        match (self.best(), self.score) {
+            (None, MATE) => String::from("1-0"),
+            (None, score) if score == -MATE => String::from("0-1"),
+            (None, _) => String::from("1/2-1/2"),
+            (Some(_), score) => engine::format_score(score),
+        }
+    }
+}
+
+// the cache is asked first, and keeps what the engine finds for next time
+fn judge(
+    board: &ChessBoard,
+    depth: u8,
+    uci: &mut Option<UciEngine>,
+    cache: &mut AnalysisCache,
+) -> Verdict {
+    let engine = uci
+        .as_ref()
+        .map_or("builtin", |uci| uci.name.as_str())
+        .to_string();
+    if let Some(cached) = cache.get(board, &engine, depth) {
+        return Verdict {
+            score: cached.score,
+            line: cached.line.clone(),
+        };
+    }
+    let verdict = search(board, depth, uci);
+    if !verdict.line.is_empty() {
+        let cached = Cached {
+            depth,
+            score: verdict.score,
+            line: verdict.line.clone(),
+        };
+        cache.insert(board, &engine, cached);
+    }
+    verdict
+}
+
+fn search(board: &ChessBoard, depth: u8, uci: &mut Option<UciEngine>) -> Verdict {
+    let mut line = Vec::new();
+    let keep = |_, pv: &[Move], _| line = pv.to_vec();
+    let searched = match uci {
+        Some(uci) => uci.search(board, depth, keep),
+        None => engine::deepen(board, depth, keep),
+    };
+    match searched {
+        Some((best, score)) => Verdict {
+            score: engine::white_score(board.turn, score),
+            // the line only goes with the last depth's best move
+            line: match line.first() {
+                Some(&first) if first == best => line,
+                _ => vec![best],
+            },
+        },
+        // no moves left: mate, stalemate or whatever the variant makes of it
+        None => {
+            let score = match board.rules().no_moves(board).winner() {
+                Some(Color::White) => MATE,
+                Some(Color::Black) => -MATE,
+                None => 0,
+            };
+            Verdict {
+                score,
+                line: Vec::new(),
+            }
+        }
+    }
+}
+
+// mates are counted as ten pawns, so a missed mate doesn't drown every other
+// swing in the report
+pub fn capped(score: i32) -> i32 {
+    score.clamp(-1000, 1000)
+}
+
+// white's chance to win in percent at a score, on the curve lichess fits to
+// its games
+fn win_percent(score: i32) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * capped(score) as f64).exp()) - 1.0)
+}
+
+// how accurate a move was, 100 when it gave none of the mover's chance to win
+// away, falling off quickly as it gives more
+fn move_accuracy(before: f64, after: f64) -> f64 {
+    let lost = (before - after).max(0.0);
+    (103.1668 * (-0.04354 * lost).exp() - 3.1669 + 1.0).clamp(0.0, 100.0)
+}
+
+// the accuracy of a side over the game, as lichess works it out: the mean of
+// its moves' accuracies weighted by how much the game swung around them, and
+// their harmonic mean, which a few bad moves pull down hard. wins is white's
+// chance to win before every move and after the last, movers who made each
+fn accuracy(wins: &[f64], movers: &[Color], color: Color) -> Option<f64> {
+    let window = (movers.len() / 10).clamp(2, 8);
+    let mut weighted = 0.0;
+    let mut weights = 0.0;
+    let mut inverses = 0.0;
+    let mut count = 0;
+    for i in (0..movers.len()).filter(|&i| movers[i] == color) {
+        let mine = |win: f64| match color {
+            Color::White => win,
+            Color::Black => 100.0 - win,
+        };
+        let accuracy = move_accuracy(mine(wins[i]), mine(wins[i + 1]));
+        // the spread of the chances over the last few positions
+        let around = &wins[(i + 1).saturating_sub(window)..=i + 1];
+        let mean = around.iter().sum::<f64>() / around.len() as f64;
+        let spread = (around.iter().map(|w| (w - mean).powi(2)).sum::<f64>() / around.len() as f64)
+            .sqrt()
+            .clamp(0.5, 12.0);
+        weighted += accuracy * spread;
+        weights += spread;
+        inverses += 1.0 / accuracy.max(1.0);
+        count += 1;
+    }
+    (count > 0).then(|| (weighted / weights + count as f64 / inverses) / 2.0)
+}
+
+// what the review found: a line for every move and the summary, then each
+// side's accuracy in percent, none for a side without moves
+pub struct Report {
+    pub lines: Vec<String>,
+    pub accuracy: [Option<f64>; 2],
+}
+
+// the engine's view of every move of the game: the evaluation before and
+// after it, how good it was and the best move when it was another one. then
+// each side's accuracy, how many inaccuracies, mistakes and blunders each
+// made and the moves that cost the most. the quality of every move is kept in
+// the history
+pub fn report(
+    game: &mut Game,
+    depth: u8,
+    uci: &mut Option<UciEngine>,
+    cache: &mut AnalysisCache,
+    options: &DisplayOptions,
+) -> Report {
+    let mut verdicts: Vec<Verdict> = game
+        .history
+        .iter()
+        .map(|played| judge(&played.before, depth, uci, cache))
+        .collect();
+    verdicts.push(judge(&game.board, depth, uci, cache));
+
+    let mut lines = Vec::new();
+    // the loss of every move with its number and text, for the summary
+    let mut losses: Vec<(i32, Color, String)> = Vec::new();
+    let mut counts = [[0; 3]; 2];
+    for (i, played) in game.history.iter_mut().enumerate() {
+        let mover = played.before.turn;
+        let (before, after) = (&verdicts[i], &verdicts[i + 1]);
+        let loss = engine::white_score(mover, capped(before.score) - capped(after.score));
+        let dots = match mover {
+            Color::White => ".",
+            Color::Black => "...",
+        };
+        let quality = Quality::of(loss, before.best() == Some(played.mve));
+        played.review = Some(Review {
+            quality,
+            score: after.best().map(|_| after.score),
+            line: match before.best() {
+                Some(best) if best != played.mve => before.line.clone(),
+                _ => Vec::new(),
+            },
+        });
+        let text = format!(
+            "{}{} {}{}",
+            played.before.fullmove,
+            dots,
+            options.move_text(&played.san, &played.mve),
+            quality.symbol()
+        );
+        let mut line = format!("{:<16}{:>7} -> {}", text, before.text(), after.text());
+        match quality {
+            Quality::Best | Quality::Good => line.push_str(&format!(" ({})", quality.name())),
+            _ => line.push_str(&format!(
+                " ({:+.2} {})",
+                -loss as f64 / 100.0,
+                quality.name()
+            )),
+        }
+        let kind = [Quality::Inaccuracy, Quality::Mistake, Quality::Blunder]
+            .iter()
+            .position(|&q| q == quality);
+        if let Some(kind) = kind {
+            counts[mover.idx()][kind] += 1;
+        }
+        if let Some(best) = before.best().filter(|&best| best != played.mve) {
+            let san = played.before.san(&best);
+            line.push_str(&format!(", best {}", options.move_text(&san, &best)));
+        }
+        lines.push(line);
+        losses.push((loss, mover, text));
+    }
+
+    lines.push(String::new());
+    let scores: Vec<i32> = verdicts.iter().map(|v| v.score).collect();
+    lines.extend(graph(&scores));
+    lines.push(String::new());
+    let wins: Vec<f64> = scores.iter().map(|&s| win_percent(s)).collect();
+    let movers: Vec<Color> = game.history.iter().map(|p| p.before.turn).collect();
+    let accuracy = [Color::White, Color::Black].map(|color| accuracy(&wins, &movers, color));
+    for color in [Color::White, Color::Black] {
+        let [inaccuracies, mistakes, blunders] = counts[color.idx()];
+        let mut line = format!("{}: ", color.name());
+        if let Some(accuracy) = accuracy[color.idx()] {
+            line.push_str(&format!("accuracy {:.1}%, ", accuracy));
+        }
+        line.push_str(&format!(
+            "inaccuracies {}, mistakes {}, blunders {}",
+            inaccuracies, mistakes, blunders
+        ));
+        lines.push(line);
+    }
+    losses.sort_by_key(|&(loss, _, _)| -loss);
+    let critical: Vec<String> = losses
+        .iter()
+        .take(CRITICAL)
+        .filter(|&&(loss, _, _)| loss >= MISTAKE)
+        .map(|(loss, mover, text)| {
+            format!(
+                "{} by {}, giving away {:.2}",
+                text,
+                mover.name(),
+                *loss as f64 / 100.0
+            )
+        })
+        .collect();
+    if critical.is_empty() {
+        lines.push(String::from(
+            "no critical moments, neither side gave much away",
+        ));
+    } else {
+        lines.push(String::from("critical moments:"));
+        lines.extend(critical.into_iter().map(|line| format!("  {}", line)));
+    }
+    Report { lines, accuracy }
+}
+
+// rows of the graph on each side of the axis, each a full block and a half
+const GRAPH_ROWS: i32 = 5;
+
+// the widest the graph gets, longer games are sampled down to it
+const GRAPH_WIDTH: usize = 64;
+
+// white's score after every move as columns, growing up from the axis while
+// white is better and down from it while black is, up to ten pawns. a mate
+// fills the whole column
+fn graph(scores: &[i32]) -> Vec<String> {
+    let width = scores.len().min(GRAPH_WIDTH);
+    // the height of every column in half rows, negative below the axis
+    let heights: Vec<i32> = (0..width)
+        .map(|x| {
+            let score = capped(scores[x * scores.len() / width]);
+            score * GRAPH_ROWS * 2 / 1000
+        })
+        .collect();
+    let cell = |height: i32, row: i32| -> char {
+        // row counts from 1 next to the axis outwards, on the side of height
+        match height.abs() - (row - 1) * 2 {
+            h if h >= 2 => '█',
+            1 if height > 0 => '▄',
+            1 => '▀',
+            _ => ' ',
+        }
+    };
+    let mut lines = Vec::new();
+    for row in (1..=GRAPH_ROWS).rev() {
+        let label = if row == GRAPH_ROWS { "+10" } else { "" };
+        let line: String = heights
+            .iter()
+            .map(|&h| if h > 0 { cell(h, row) } else { ' ' })
+            .collect();
+        lines.push(format!("{:>3} {}", label, line));
+    }
+    lines.push(format!("{:>3} {}", "0", "─".repeat(width)));
+    for row in 1..=GRAPH_ROWS {
+        let label = if row == GRAPH_ROWS { "-10" } else { "" };
+        let line: String = heights
+            .iter()
+            .map(|&h| if h < 0 { cell(h, row) } else { ' ' })
+            .collect();
+        lines.push(format!("{:>3} {}", label, line));
+    }
+    lines
+        .iter()
+        .map(|line| line.trim_end().to_string())
+        .collect()
+}
@@ -0,0 +1,51 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+
+use crate::game::{Game, GameResult, Observer, PlayedMove};
+
+// hands every played move in words to an external command like espeak on its
+// stdin. one runs at a time so quick moves don't talk over each other
+pub struct Announcer {
+    sender: Sender<String>,
+}
+
+impl Announcer {
+    pub fn new(command: String) -> Self {
+        let (sender, receiver) = mpsc::channel::<String>();
+        std::thread::spawn(move || {
+            for text in receiver {
+                // a missing or failing command just stays silent, there is no
+                // good place to complain about it in the middle of a game
+                let child = Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn();
+                if let Ok(mut child) = child {
+                    if let Some(mut stdin) = child.stdin.take() {
+                        let _ = writeln!(stdin, "{}", text);
+                    }
+                    let _ = child.wait();
+                }
+            }
+        });
+        Announcer { sender }
+    }
+
+    pub fn say(&self, text: String) {
+        let _ = self.sender.send(text);
+    }
+}
+
+impl Observer for Announcer {
+    fn on_move(&self, _game: &Game, played: &PlayedMove) {
+        self.say(played.before.describe_move(&played.mve));
+    }
+
+    fn on_game_end(&self, _game: &Game, result: GameResult) {
+        self.say(result.describe());
+    }
+}
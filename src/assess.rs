@@ -0,0 +1,215 @@
+use crate::describe::piece_name;
+use crate::display::DisplayOptions;
+use crate::{BoardPos, ChessBoard, Color, Piece, PieceType, RANKS};
+
+const STRAIGHT: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const DIAGONAL: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+// what a piece is worth in an exchange. the king goes last, nothing may take
+// back after it
+fn worth(piece: PieceType) -> i32 {
+    match piece {
+        PieceType::King => 100,
+        piece => piece.value(),
+    }
+}
+
+// "knight e5"
+fn named(piece: Piece) -> String {
+    format!("{} {}", piece_name(piece.piece), piece.pos)
+}
+
+// how much by wins by starting to take on pos, each side taking back with its
+// cheapest piece for as long as that pays. pieces behind the ones that took
+// join in as the line opens. 0 when by can't take there or shouldn't
+fn see(board: &ChessBoard, pos: BoardPos, by: Color) -> i32 {
+    let Some(target) = board.piece_at(pos) else {
+        return 0;
+    };
+    let mut board = board.clone();
+    // the worth of every piece taken in turn, the first by by
+    let mut taken = Vec::new();
+    let mut on_square = target.piece;
+    let mut side = by;
+    while let Some(attacker) = board.attackers(pos, side).min_by_key(|p| worth(p.piece)) {
+        taken.push(worth(on_square));
+        on_square = attacker.piece;
+        board.take(attacker.pos);
+        board.take(pos);
+        board.put(Piece { pos, ..attacker });
+        side = side.other();
+    }
+    // from the last capture back, each side only takes when it gains by it
+    let Some((first, rest)) = taken.split_first() else {
+        return 0;
+    };
+    let answer = rest
+        .iter()
+        .rev()
+        .fold(0, |after, &gain| (gain - after).max(0));
+    (first - answer).max(0)
+}
+
+// the directions a piece slides in, none for the pieces that step or jump
+fn slides(piece: PieceType) -> Vec<(i8, i8)> {
+    match piece {
+        PieceType::Bishop | PieceType::Archbishop => DIAGONAL.to_vec(),
+        PieceType::Rook | PieceType::Chancellor => STRAIGHT.to_vec(),
+        PieceType::Queen => [STRAIGHT, DIAGONAL].concat(),
+        _ => Vec::new(),
+    }
+}
+
+// the pieces of the board from pos outwards in a direction, the empty squares
+// skipped
+fn along(
+    board: &ChessBoard,
+    pos: BoardPos,
+    (dr, dc): (i8, i8),
+) -> impl Iterator<Item = Piece> + '_ {
+    let width = board.width;
+    (1..)
+        .map(move |n| (pos.row as i8 + dr * n, pos.col as i8 + dc * n))
+        .take_while(move |&(row, col)| {
+            (0..RANKS as i8).contains(&row) && (0..width as i8).contains(&col)
+        })
+        .filter_map(move |(row, col)| {
+            board.piece_at(BoardPos {
+                row: row as u8,
+                col: col as u8,
+            })
+        })
+}
+
+// the board with color to move, to look at what it could do
+fn to_move(board: &ChessBoard, color: Color) -> ChessBoard {
+    let mut board = board.clone();
+    board.turn = color;
+    board.turn_moves = 0;
+    board
+}
+
+// what a side has to watch out for and what it threatens, without searching:
+// its pieces hanging or lost in exchanges, its pinned pieces, and the mates
+// in one and forks it has
+pub fn assess(board: &ChessBoard, options: &DisplayOptions) -> Vec<String> {
+    let mut lines = Vec::new();
+    for color in [board.turn, board.turn.other()] {
+        let enemy = color.other();
+        let mut notes = Vec::new();
+
+        let mut hanging = Vec::new();
+        let mut outnumbered = Vec::new();
+        for piece in board.pieces(color).filter(|p| p.piece != PieceType::King) {
+            let loss = see(board, piece.pos, enemy);
+            if loss == 0 {
+                continue;
+            }
+            if board.is_attacked(piece.pos, color) {
+                outnumbered.push(format!(
+                    "{} loses {} {}",
+                    named(piece),
+                    loss,
+                    if loss == 1 { "pawn" } else { "pawns" }
+                ));
+            } else {
+                hanging.push(named(piece));
+            }
+        }
+        if !hanging.is_empty() {
+            notes.push(format!("hanging: {}", hanging.join(", ")));
+        }
+        if !outnumbered.is_empty() {
+            notes.push(format!(
+                "attacked more than defended: {}",
+                outnumbered.join(", ")
+            ));
+        }
+
+        // a piece of color between an enemy slider and the king, or a piece
+        // worth more, can't move off the line without a loss. the piece behind
+        // has to be worth taking with the slider
+        let mut pinned = Vec::new();
+        for slider in board.pieces(enemy) {
+            for direction in slides(slider.piece) {
+                let mut behind = along(board, slider.pos, direction);
+                let (Some(first), Some(second)) = (behind.next(), behind.next()) else {
+                    continue;
+                };
+                if first.color == color
+                    && second.color == color
+                    && first.piece != PieceType::King
+                    && worth(second.piece) > worth(first.piece)
+                    && (worth(second.piece) > worth(slider.piece)
+                        || !board.is_attacked(second.pos, color))
+                {
+                    pinned.push(format!(
+                        "{} by the {} to the {}",
+                        named(first),
+                        named(slider),
+                        named(second)
+                    ));
+                }
+            }
+        }
+        if !pinned.is_empty() {
+            notes.push(format!("pinned: {}", pinned.join(", ")));
+        }
+
+        // the other side's moves can't be looked at while it is in check
+        if color == board.turn || !board.in_check(board.turn) {
+            let mover = to_move(board, color);
+            let mut mates = Vec::new();
+            let mut forks = Vec::new();
+            for mve in mover.legal_moves() {
+                let mut next = mover.clone();
+                next.make_move(&mve);
+                let text = options.move_text(&mover.san(&mve), &mve);
+                if next.in_check(enemy) && next.legal_moves().is_empty() {
+                    mates.push(text);
+                    continue;
+                }
+                // the moved piece attacks two pieces that are each the king,
+                // worth more than it or undefended, and can't just be taken
+                let Some(piece) = next.piece_at(mve.to).filter(|p| p.color == color) else {
+                    continue;
+                };
+                if see(&next, mve.to, enemy) > 0 {
+                    continue;
+                }
+                let targets: Vec<String> = next
+                    .pieces(enemy)
+                    .filter(|target| piece.attacks(target.pos, &next))
+                    .filter(|target| {
+                        target.piece == PieceType::King
+                            || worth(target.piece) > worth(piece.piece)
+                            || !next.is_attacked(target.pos, enemy)
+                    })
+                    .map(named)
+                    .collect();
+                if targets.len() >= 2 {
+                    forks.push(format!("{} on the {}", text, targets.join(" and the ")));
+                }
+            }
+            if !mates.is_empty() {
+                notes.push(format!("mates in one: {}", mates.join(", ")));
+            }
+            if !forks.is_empty() {
+                notes.push(format!("forks: {}", forks.join(", ")));
+            }
+        }
+
+        let who = match color == board.turn {
+            true => format!("{}, to move", color.name()),
+            false => color.name().to_string(),
+        };
+        match notes.is_empty() {
+            true => lines.push(format!("{}: nothing to note", who)),
+            false => {
+                lines.push(format!("{}:", who));
+                lines.extend(notes.into_iter().map(|note| format!("  {}", note)));
+            }
+        }
+    }
+    lines
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use crate::game::Game;
+use crate::pgn;
+use crate::Color;
+
+// plays the moves of text from the game's position, in san or uci and with or
+// without move numbers like in pgn movetext, and prints where they lead: the
+// fen and the result, * while the game goes on. the first move that isn't
+// legal is the error, and so is a move after the end of the game
+pub fn play(mut game: Game, text: &str) -> Result<(), String> {
+    for (i, text) in pgn::movetext(text).iter().enumerate() {
+        let board = &game.board;
+        let dots = match board.turn {
+            Color::White => ".",
+            Color::Black => "...",
+        };
+        let at = format!("{}{}{} at ply {}", board.fullmove, dots, text, i + 1);
+        if let Some(result) = game.result {
+            return Err(format!("{} comes after the end, {}", at, result.describe()));
+        }
+        let mve = board
+            .parse_san(text)
+            .or_else(|| board.parse_uci(text).filter(|m| board.is_legal(m)))
+            .ok_or_else(|| format!("illegal move {}", at))?;
+        game.play(&mve);
+    }
+    println!("{}", game.board.to_fen());
+    match game.result {
+        Some(result) => println!("{} {}", result.score(), result.describe()),
+        None => println!("* {}", game.status()),
+    }
+    Ok(())
+}
+
+// reads the moves from file, or from stdin without one, and plays them
+pub fn run(game: Game, file: Option<&Path>) -> Result<(), String> {
+    let text = match file {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?,
+        None => std::io::read_to_string(std::io::stdin()).map_err(|e| e.to_string())?,
+    };
+    play(game, &text)
+}
@@ -0,0 +1,66 @@
+use std::time::Instant;
+
+use crate::engine;
+use crate::ChessBoard;
+
+// positions of every phase of the game, searched in this order. the node
+// count they add up to only changes when the search or the evaluation do
+const POSITIONS: [(&str, &str); 8] = [
+    (
+        "initial position",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    ),
+    (
+        "kiwipete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ),
+    (
+        "italian",
+        "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/2NP1N2/PPP2PPP/R1BQK2R b KQkq - 0 5",
+    ),
+    (
+        "open middlegame",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    ),
+    (
+        "queen's gambit",
+        "r1bq1rk1/pp2bppp/2n1pn2/2pp4/2PP4/2N1PN2/PP2BPPP/R1BQ1RK1 w - - 0 8",
+    ),
+    ("rook ending", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+    (
+        "minor piece ending",
+        "8/5pk1/4p1p1/3bP3/2pP2P1/2B2K2/5P2/8 w - - 0 40",
+    ),
+    (
+        "mate in two",
+        "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 10",
+    ),
+];
+
+// searches every position to depth and prints the best move and the nodes
+// of each, then the nodes of all together, the signature to compare before
+// and after a change, and how fast they went
+pub fn run(depth: u8) {
+    let start = Instant::now();
+    let mut total = 0;
+    for (name, fen) in POSITIONS {
+        let board = ChessBoard::from_fen(fen).expect("the bench's fens are valid");
+        let before = engine::nodes();
+        let best = engine::search(&board, depth);
+        let nodes = engine::nodes() - before;
+        total += nodes;
+        let best = best.map_or(String::from("none"), |(mve, score)| {
+            format!("{} {}", board.san(&mve), engine::format_score(score))
+        });
+        println!("{:<20} {:<14} {:>10} nodes", name, best, nodes);
+    }
+    let elapsed = start.elapsed();
+    println!();
+    println!("depth {}", depth);
+    println!("nodes {}", total);
+    println!("time  {:.3}s", elapsed.as_secs_f64());
+    println!(
+        "nps   {:.0}",
+        total as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::save;
+use crate::{ChessBoard, Move};
+
+// what an engine made of a position at some depth: white's score and the
+// main line
+#[derive(Debug, Clone)]
+pub struct Cached {
+    pub depth: u8,
+    pub score: i32,
+    pub line: Vec<Move>,
+}
+
+// the evaluations of every position analyzed so far, kept on disk so the
+// same positions aren't searched again in the next session. one line per
+// position and engine: key, engine, depth, score and the line in uci
+#[derive(Default)]
+pub struct AnalysisCache {
+    entries: HashMap<(String, String), Cached>,
+    changed: bool,
+}
+
+// analysis.cache in the data directory
+pub fn default_path() -> Option<PathBuf> {
+    Some(config::data_dir()?.join("analysis.cache"))
+}
+
+// the position's zobrist key with the variant it is played by, since the key
+// only covers the pieces, the side to move, castling and the en passant square
+fn key(board: &ChessBoard) -> String {
+    let variant = board.variant.pgn_name().unwrap_or("Standard");
+    format!("{}:{:016x}", variant, board.zobrist_key())
+}
+
+impl AnalysisCache {
+    // a missing file just means nothing was analyzed yet. lines that don't
+    // read are left out, they are only lost work
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(AnalysisCache::default())
+            }
+            Err(e) => return Err(format!("could not read {}: {}", path.display(), e)),
+        };
+        let mut cache = AnalysisCache::default();
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [key, engine, depth, score, moves] = fields[..] else {
+                continue;
+            };
+            let (Ok(depth), Ok(score)) = (depth.parse(), score.parse()) else {
+                continue;
+            };
+            let Some(line) = moves.split_whitespace().map(Move::parse).collect() else {
+                continue;
+            };
+            cache.entries.insert(
+                (key.to_string(), engine.to_string()),
+                Cached { depth, score, line },
+            );
+        }
+        Ok(cache)
+    }
+
+    // only writes when something was added since it was loaded
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if !self.changed {
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let mut lines: Vec<String> = self
+            .entries
+            .iter()
+            .map(|((key, engine), cached)| {
+                let line: Vec<String> = cached.line.iter().map(Move::to_string).collect();
+                format!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    key,
+                    engine,
+                    cached.depth,
+                    cached.score,
+                    line.join(" ")
+                )
+            })
+            .collect();
+        lines.sort();
+        save::write(path, &(lines.join("\n") + "\n"))
+    }
+
+    // what the engine found in the position at depth or deeper
+    pub fn get(&self, board: &ChessBoard, engine: &str, depth: u8) -> Option<&Cached> {
+        self.entries
+            .get(&(key(board), engine.to_string()))
+            .filter(|cached| cached.depth >= depth)
+    }
+
+    // keeps the deepest evaluation of every position
+    pub fn insert(&mut self, board: &ChessBoard, engine: &str, cached: Cached) {
+        let key = (key(board), engine.to_string());
+        if self
+            .entries
+            .get(&key)
+            .is_some_and(|old| old.depth >= cached.depth)
+        {
+            return;
+        }
+        self.entries.insert(key, cached);
+        self.changed = true;
+    }
+}
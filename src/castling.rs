@@ -0,0 +1,180 @@
+use crate::{BoardPos, ChessBoard, Color, Move, Piece, PieceType};
+
+// the two ways to castle, the second index of ChessBoard::castling: with the
+// rook towards the last file and with the one towards the a-file
+pub const SHORT: usize = 0;
+pub const LONG: usize = 1;
+
+// the row a side's pieces start on
+pub fn back_row(color: Color) -> u8 {
+    match color {
+        Color::White => 7,
+        Color::Black => 0,
+    }
+}
+
+impl ChessBoard {
+    // the columns the king and rook end up on, the same wherever they started:
+    // g and f on a standard board, and the king two files from the edge on a
+    // wider one, like i and h in capablanca chess
+    pub fn castling_targets(&self, side: usize) -> (u8, u8) {
+        match side {
+            SHORT => (self.width - 2, self.width - 3),
+            _ => (2, 3),
+        }
+    }
+
+    // the rook furthest from the king on its side of the back rank, the one
+    // k and q stand for in a fen
+    pub fn outer_rook(&self, color: Color, side: usize) -> Option<u8> {
+        let row = back_row(color);
+        let king = self.king_pos(color).filter(|pos| pos.row == row)?;
+        let rook = |col: &u8| {
+            self.piece_at(BoardPos { row, col: *col })
+                .is_some_and(|p| p.color == color && p.piece == PieceType::Rook)
+        };
+        match side {
+            SHORT => (king.col + 1..self.width).rev().find(rook),
+            _ => (0..king.col).find(rook),
+        }
+    }
+
+    // which way a move castles, if it does. castling is written as the king
+    // taking its own rook, the only way that is never ambiguous in chess960
+    pub fn castling_side(&self, mve: &Move) -> Option<usize> {
+        let row = back_row(self.turn);
+        if mve.from.row != row || mve.to.row != row {
+            return None;
+        }
+        let is = |pos, piece| {
+            self.piece_at(pos)
+                .is_some_and(|p: Piece| p.color == self.turn && p.piece == piece)
+        };
+        if !is(mve.from, PieceType::King) || !is(mve.to, PieceType::Rook) {
+            return None;
+        }
+        [SHORT, LONG]
+            .into_iter()
+            .find(|&side| self.castling[self.turn.idx()][side] == Some(mve.to.col))
+    }
+
+    // whether the castling move may be played, apart from where the king ends
+    // up: only the king and the rook stand between them and their targets, and
+    // the king isn't in check and doesn't pass an attacked square
+    pub fn can_castle(&self, mve: &Move, side: usize) -> bool {
+        let (king_to, rook_to) = self.castling_targets(side);
+        let row = mve.from.row;
+        let cols = [mve.from.col, mve.to.col, king_to, rook_to];
+        let low = cols.into_iter().min().unwrap();
+        let high = cols.into_iter().max().unwrap();
+        let blocked = (low..=high)
+            .map(|col| BoardPos { row, col })
+            .any(|pos| pos != mve.from && pos != mve.to && !self.is_empty(pos));
+        let passed = mve.from.col.min(king_to)..=mve.from.col.max(king_to);
+        !blocked
+            && !passed
+                .into_iter()
+                .any(|col| self.is_attacked(BoardPos { row, col }, self.turn.other()))
+    }
+
+    // the castling moves the side to move may play, apart from where the king
+    // ends up
+    pub fn castling_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        let row = back_row(self.turn);
+        let king = self.king_pos(self.turn).filter(|pos| pos.row == row);
+        [SHORT, LONG].into_iter().filter_map(move |side| {
+            let col = self.castling[self.turn.idx()][side]?;
+            let mve = Move {
+                from: king?,
+                to: BoardPos { row, col },
+                promotion: None,
+            };
+            (self.castling_side(&mve) == Some(side) && self.can_castle(&mve, side)).then_some(mve)
+        })
+    }
+
+    // puts the king and rook on their targets, without checking anything
+    pub fn castle(&mut self, mve: &Move, side: usize) {
+        let (king_to, rook_to) = self.castling_targets(side);
+        let row = mve.from.row;
+        let king = self.take(mve.from);
+        let rook = self.take(mve.to);
+        for (piece, col) in [(king, king_to), (rook, rook_to)] {
+            if let Some(piece) = piece {
+                self.put(Piece {
+                    pos: BoardPos { row, col },
+                    ..piece
+                });
+            }
+        }
+    }
+
+    // a king that moves can't castle any more, and neither can a rook that
+    // moves or is taken. called before the move is made
+    pub fn update_castling(&mut self, mve: &Move) {
+        if self.kinds[mve.from.to_idx()] == Some(PieceType::King) {
+            if let Some(color) = self.color_at(mve.from) {
+                self.castling[color.idx()] = [None; 2];
+            }
+        }
+        for color in [Color::White, Color::Black] {
+            let row = back_row(color);
+            for right in &mut self.castling[color.idx()] {
+                if right.is_some_and(|col| {
+                    let rook = BoardPos { row, col };
+                    mve.from == rook || mve.to == rook
+                }) {
+                    *right = None;
+                }
+            }
+        }
+    }
+
+    // whether the move takes an enemy piece. castling looks like the king
+    // taking its own rook and en passant goes to an empty square, so the
+    // target square alone doesn't tell
+    pub fn is_capture(&self, mve: &Move) -> bool {
+        self.color_at(mve.to) == Some(self.turn.other()) || self.en_passant_capture(mve).is_some()
+    }
+
+    // a move in uci notation. castling may also be written with the king's
+    // target square, like e1g1, unless that is a king move of its own, and a
+    // pawn reaching the far row without a piece named becomes a queen
+    pub fn parse_uci(&self, text: &str) -> Option<Move> {
+        let mve = Move::parse(text)?;
+        if self.is_legal(&mve) {
+            return Some(mve);
+        }
+        let queen = Move {
+            promotion: Some(PieceType::Queen),
+            ..mve
+        };
+        if mve.promotion.is_none() && self.is_legal(&queen) {
+            return Some(queen);
+        }
+        let castling = self.castling_moves().find(|m| {
+            m.from == mve.from && mve.to.row == m.from.row && {
+                let side = self.castling_side(m).unwrap();
+                self.castling_targets(side).0 == mve.to.col
+            }
+        });
+        Some(castling.unwrap_or(mve))
+    }
+
+    // the move in uci notation as engines expect it outside chess960, with
+    // castling written as the king's move, like e1g1
+    pub fn standard_uci(&self, mve: &Move) -> String {
+        match self.castling_side(mve) {
+            Some(side) => Move {
+                from: mve.from,
+                to: BoardPos {
+                    row: mve.from.row,
+                    col: self.castling_targets(side).0,
+                },
+                promotion: None,
+            }
+            .to_string(),
+            None => mve.to_string(),
+        }
+    }
+}
@@ -0,0 +1,589 @@
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+
+use crate::book::Limits;
+use crate::clock::TimeControl;
+use crate::database::Filter;
+use crate::endgame::Ending;
+use crate::image::Theme;
+use crate::locale::Locale;
+use crate::notation::Notation;
+use crate::odds::Odds;
+use crate::selfplay::Settings;
+use crate::tournament::{Rules, Schedule};
+use crate::transport::{Certificate, Trust};
+use crate::variant::VariantKind;
+use crate::Color;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "chess in the terminal")]
+#[command(group(ArgGroup::new("clocks").multiple(true)))]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Start from this position instead of the initial one
+    #[arg(long, global = true)]
+    pub fen: Option<String>,
+
+    /// Replay the moves of a PGN file, then continue the game from there
+    #[arg(long, conflicts_with = "fen")]
+    pub pgn: Option<PathBuf>,
+
+    /// Play the moves of a known opening first, by name or ECO code like
+    /// "Sicilian Najdorf" or B90
+    #[arg(long, conflicts_with_all = ["fen", "pgn"])]
+    pub opening: Option<String>,
+
+    /// Start without a piece of the stronger player, who plays White, or Black
+    /// with pawn-and-move
+    #[arg(long, value_enum, conflicts_with_all = ["fen", "pgn", "opening"])]
+    pub odds: Option<Odds>,
+
+    /// Chess960 starting position from 0 to 959, 518 being the usual one.
+    /// Picked at random with --variant chess960 if not given
+    #[arg(long, value_parser = clap::value_parser!(u16).range(0..960), conflicts_with_all = ["fen", "pgn", "opening", "odds"])]
+    pub position: Option<u16>,
+
+    /// Who plays the white pieces
+    #[arg(long, value_enum, default_value_t = Player::Human)]
+    pub white: Player,
+
+    /// Who plays the black pieces
+    #[arg(long, value_enum, default_value_t = Player::Human)]
+    pub black: Player,
+
+    /// How many plies the engine looks ahead [default: 3]
+    #[arg(long)]
+    pub depth: Option<u8>,
+
+    /// Let the engine play like a person of about this rating, from 400 to
+    /// 2800: looking no further ahead than --depth, making the mistakes people
+    /// make and taking its time over its moves
+    #[arg(long, value_parser = clap::value_parser!(u32).range(400..=2800))]
+    pub human_like: Option<u32>,
+
+    /// Command starting an external UCI engine like stockfish, which plays as
+    /// the uci player and takes over the evaluation
+    #[arg(long)]
+    pub uci: Option<String>,
+
+    /// Option for the UCI engine like Hash=64, given once per option
+    #[arg(long = "uci-option", requires = "uci")]
+    pub uci_options: Vec<String>,
+
+    /// Engine option like hash=64, threads=4, skill=10, contempt=20,
+    /// nnue=<file> or book=off, checked and passed to the UCI engine under
+    /// the name it has there. They are for external engines only, the
+    /// built-in one has none of them. Given once per option, over the config
+    /// file's [engine] table
+    #[arg(long = "engine-option", requires = "uci")]
+    pub engine_options: Vec<String>,
+
+    /// Minutes on each player's clock, with seconds of increment like 5+3 or of
+    /// delay like 5d3 (5b3 for Bronstein delay). No clocks if not given
+    #[arg(long, group = "clocks")]
+    pub tc: Option<TimeControl>,
+
+    /// Black wins a drawn game but gets less time: 4 minutes against 5, or 4/5
+    /// of the --tc time
+    #[arg(long, group = "clocks")]
+    pub armageddon: bool,
+
+    /// Minutes on White's clock instead of the --tc ones. Without --tc, Black
+    /// gets them too unless --black-tc is given
+    #[arg(long)]
+    pub white_tc: Option<TimeControl>,
+
+    /// Minutes on Black's clock instead of the --tc ones. Without --tc, White
+    /// gets them too unless --white-tc is given
+    #[arg(long)]
+    pub black_tc: Option<TimeControl>,
+
+    /// Seed for every random choice, like the moves of the random player, so a
+    /// game can be played again exactly. Taken from the clock if not given
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Rule set to play by
+    #[arg(long, value_enum, default_value_t = VariantKind::Standard)]
+    pub variant: VariantKind,
+
+    /// Draw pieces as chess symbols instead of letters
+    #[arg(long)]
+    pub unicode: bool,
+
+    /// Never use ANSI colors, even on a terminal
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Show the board from Black's side
+    #[arg(long)]
+    pub flip: bool,
+
+    /// Show the board from the side to move
+    #[arg(long)]
+    pub auto_flip: bool,
+
+    /// Language of the piece letters in moves and of the messages, like de for
+    /// Sf3 instead of Nf3 and Weiß am Zug. Taken from LANG when not given
+    #[arg(long, value_enum)]
+    pub locale: Option<Locale>,
+
+    /// How moves are shown, whichever way they are typed
+    #[arg(long, value_enum)]
+    pub notation: Option<Notation>,
+
+    /// Show the engine's evaluation next to the board
+    #[arg(long)]
+    pub eval: bool,
+
+    /// Use the full-screen terminal interface
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Describe positions and moves in words instead of drawing the board
+    #[arg(long, conflicts_with = "tui")]
+    pub accessible: bool,
+
+    /// Draw a big board with ASCII art pieces
+    #[arg(long, conflicts_with_all = ["tui", "accessible"])]
+    pub large: bool,
+
+    /// Print every position, move, reply and result as a line of JSON instead
+    /// of boards and messages, for scripts to drive the program
+    #[arg(long, conflicts_with_all = ["tui", "accessible"])]
+    pub json: bool,
+
+    /// Draw a board of one character per square, picked by itself on small terminals
+    #[arg(long, conflicts_with_all = ["tui", "accessible", "large"])]
+    pub compact: bool,
+
+    /// Leave out the rank numbers and file letters of the compact board
+    #[arg(long)]
+    pub no_coords: bool,
+
+    /// Print every board below the last one instead of clearing the screen
+    #[arg(long)]
+    pub no_redraw: bool,
+
+    /// Command that gets every move in words on its stdin, like espeak
+    #[arg(long)]
+    pub announce: Option<String>,
+
+    /// Secret shared with a correspondence opponent to sign the exchanged files
+    #[arg(long)]
+    pub secret: Option<String>,
+
+    /// Write the seed, every line typed and every move the program chose to a
+    /// file, to play the session back with --replay
+    #[arg(long, conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Play a session written with --record back from its file, telling the
+    /// first move of the program that differs. Typing goes on once it ends
+    #[arg(long, conflicts_with = "seed")]
+    pub replay: Option<PathBuf>,
+
+    /// Don't keep the game in progress to offer resuming it on the next start
+    #[arg(long)]
+    pub no_autosave: bool,
+
+    /// Don't count games against the engine for the ratings
+    #[arg(long)]
+    pub unrated: bool,
+
+    /// Don't keep games in the game library or add finished ones to the game log
+    #[arg(long)]
+    pub no_library: bool,
+
+    /// Don't keep the engine's evaluations to reuse when analyzing the same
+    /// positions again
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Read defaults from this file instead of ~/.config/chess/config.toml
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Log the engine's searches, the network and the games at this level and
+    /// above: error, warn, info, debug or trace. To stderr without --log-file
+    #[arg(long, global = true)]
+    pub log_level: Option<tracing::level_filters::LevelFilter>,
+
+    /// Write the log to this file, at info level unless --log-level says otherwise
+    #[arg(long, global = true)]
+    pub log_file: Option<PathBuf>,
+
+    /// Report every search of the built-in engine to this file: nodes, cutoff
+    /// rates by move ordering stage and the branching factor of every depth
+    #[arg(long, global = true)]
+    pub search_stats: Option<PathBuf>,
+
+    /// Add the moves of the first plies of every search to the report, with
+    /// their window and score
+    #[arg(long, global = true, default_value_t = 0, requires = "search_stats")]
+    pub search_trace: u8,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Count the leaf nodes of the legal move tree to verify move generation
+    Perft {
+        depth: u8,
+
+        /// Remember subtree counts of transpositions
+        #[arg(long)]
+        hash: bool,
+
+        /// Split the root moves over all cores
+        #[arg(long)]
+        parallel: bool,
+
+        /// Number of threads for --parallel, implies it
+        #[arg(long)]
+        threads: Option<usize>,
+    },
+    /// Check the perft counts of positions known for catching move generation
+    /// bugs, failing if any changed
+    PerftSuite,
+    /// Search a fixed set of positions to a fixed depth and print the nodes
+    /// searched and how fast. The total is a signature of the engine that
+    /// only changes with changes to what it does
+    Bench {
+        /// How many plies to search each position
+        #[arg(long, default_value_t = 4)]
+        depth: u8,
+    },
+    /// Play random games and compare the legal moves of every position with
+    /// those of the UCI engine given with --uci, which has to answer go perft 1
+    /// like stockfish does. The first difference is shown with its FEN
+    Difftest {
+        /// How many games to play
+        #[arg(long, default_value_t = 100)]
+        games: u32,
+
+        /// The most plies of each game
+        #[arg(long, default_value_t = 200)]
+        plies: u32,
+    },
+    /// Play the moves of a file, or of stdin without one, from the starting
+    /// position or --fen, and print the FEN and result they lead to. Fails on
+    /// the first illegal move
+    Batch { file: Option<PathBuf> },
+    /// Check a FEN and say what is wrong with it, if anything, for scripts
+    /// that want to know before passing it on
+    ValidateFen { fen: String },
+    /// Save a picture of the position as a PNG file
+    Png {
+        output: PathBuf,
+
+        /// Height of the picture in pixels, and its width on a standard board
+        #[arg(long, default_value_t = 480)]
+        size: u32,
+
+        /// Colors of the squares
+        #[arg(long, value_enum, default_value_t = Theme::Brown)]
+        theme: Theme,
+    },
+    /// Save the game from --pgn as an animated GIF, one frame per position
+    Gif {
+        output: PathBuf,
+
+        /// Height of the animation in pixels, and its width on a standard board
+        #[arg(long, default_value_t = 480)]
+        size: u32,
+
+        /// Colors of the squares
+        #[arg(long, value_enum, default_value_t = Theme::Brown)]
+        theme: Theme,
+
+        /// How long each position is shown, in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        delay: u32,
+    },
+    /// Follow a PGN file that another program is writing, without ever asking for input
+    Watch {
+        pgn: PathBuf,
+
+        /// How often to look for new moves, in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+
+        /// Go on to the games added after this one instead of stopping when it ends
+        #[arg(long)]
+        follow: bool,
+    },
+    /// Solve the puzzles of an EPD file, each a position with its solution
+    /// in a pv or bm operation
+    Puzzle {
+        #[arg(required_unless_present = "daily")]
+        file: Option<PathBuf>,
+
+        /// Set the daily puzzle of Lichess instead, kept to be set again
+        /// without a connection
+        #[arg(long, conflicts_with = "file")]
+        daily: bool,
+    },
+    /// Learn how the pieces move, with an exercise for each rule
+    Tutorial,
+    /// Practise winning an ending, playing White against perfect defence
+    Train {
+        #[arg(value_enum)]
+        ending: Ending,
+    },
+    /// Drill the lines of a repertoire PGN, variations included: the other
+    /// side's moves are played and yours have to be found. missed lines come back
+    Repertoire {
+        file: PathBuf,
+
+        /// The side the repertoire is for
+        #[arg(long, value_enum, default_value_t = Side::White)]
+        color: Side,
+
+        /// Write the positions and their repertoire moves to this CSV file
+        /// as flashcards to import into Anki, instead of drilling them
+        #[arg(long)]
+        export: Option<PathBuf>,
+
+        /// Put a picture of the board on the front of every flashcard, written
+        /// to this folder as PNG files for Anki's media folder
+        #[arg(long, requires = "export")]
+        images: Option<PathBuf>,
+    },
+    /// Look through the games of the library for single winning moves, played
+    /// or missed, and write them as puzzles to an EPD file
+    Tactics {
+        output: PathBuf,
+
+        /// Search depth of the engine
+        #[arg(long, default_value_t = 3)]
+        depth: u8,
+    },
+    /// Run the engine over an EPD test suite like WAC or STS and report how
+    /// many of its bm and am operations it gets right
+    Testsuite {
+        file: PathBuf,
+
+        /// Time the engine gets for each position, in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        time: u64,
+    },
+    /// Wait for another instance to join over TCP and play it
+    Host {
+        /// Port to listen on
+        #[arg(long, default_value_t = 7777)]
+        port: u16,
+
+        /// The side played here, the one joining gets the other
+        #[arg(long, value_enum, default_value_t = Side::White)]
+        color: Side,
+
+        #[command(flatten)]
+        certificate: Certificate,
+
+        /// Meet the other player at this relay server instead of listening,
+        /// for when neither can take connections. --tls and --pin are for it
+        #[arg(long, conflicts_with_all = ["cert", "key"])]
+        relay: Option<String>,
+
+        /// Game code to give the other player, made up if not given
+        #[arg(long, requires = "relay")]
+        code: Option<String>,
+
+        #[command(flatten)]
+        trust: Trust,
+    },
+    /// Play an instance started with host, at an address like 192.168.1.20:7777
+    Join {
+        /// Address of the host, or the game code the host got with --relay
+        addr: String,
+
+        /// Relay server both players connect out to, like relay.example.org:7779
+        #[arg(long)]
+        relay: Option<String>,
+
+        #[command(flatten)]
+        trust: Trust,
+    },
+    /// Serve the game over WebSocket to web or mobile clients, with a board to
+    /// play on in a browser at the same address
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Let the engine play the color the first player to join doesn't take,
+        /// searching this deep
+        #[arg(long)]
+        engine: Option<u8>,
+
+        #[command(flatten)]
+        certificate: Certificate,
+    },
+    /// Run a relay server that pairs players by game code and forwards their moves
+    Relay {
+        /// Port to listen on
+        #[arg(long, default_value_t = 7779)]
+        port: u16,
+
+        #[command(flatten)]
+        certificate: Certificate,
+    },
+    /// Run a lobby server where players post seeks and get paired into games
+    Lobby {
+        /// Port to listen on
+        #[arg(long, default_value_t = 7778)]
+        port: u16,
+
+        #[command(flatten)]
+        certificate: Certificate,
+    },
+    /// Serve move checking, legal move lists, analysis and whole games over
+    /// gRPC, as described in proto/chess.proto
+    #[cfg(feature = "grpc")]
+    Grpc {
+        /// Port to listen on
+        #[arg(long, default_value_t = 50051)]
+        port: u16,
+    },
+    /// Play as a Lichess bot account, accepting challenges and answering with engine moves
+    Lichess {
+        /// API token of the bot account, read from LICHESS_TOKEN when left out
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Search depth of the engine
+        #[arg(long, default_value_t = 3)]
+        depth: u8,
+
+        /// Address of the Lichess server
+        #[arg(long, default_value = "https://lichess.org")]
+        server: String,
+    },
+    /// Play engines of different depths against each other and keep the standings.
+    /// Games that reach king and queen, rook or pawn against king are decided by
+    /// the endgame tables
+    Tournament {
+        #[command(flatten)]
+        schedule: Schedule,
+
+        /// Where all games are written as PGN
+        #[arg(long, default_value = "tournament.pgn")]
+        output: PathBuf,
+
+        #[command(flatten)]
+        rules: Rules,
+
+        /// Go on with the tournament that was interrupted, from the progress
+        /// kept next to --output. It has to be given the same settings
+        #[arg(long)]
+        resume: bool,
+    },
+    /// List the games of a PGN file or of the PGN files in a directory that
+    /// match all the filters given, or write them to one PGN file
+    Search {
+        path: PathBuf,
+
+        #[command(flatten)]
+        filter: Filter,
+
+        /// Write the games found to this PGN file instead of listing them
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Find the games of a PGN file or of the PGN files in a directory that
+    /// repeat the moves of an earlier one, whatever their tags and comments
+    Dedupe {
+        path: PathBuf,
+
+        /// Write the games to this PGN file with the copies left out
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Merge the tags of the copies into the game kept instead of
+        /// dropping them, filling in the ones it lacks or leaves unknown
+        #[arg(long, requires = "output")]
+        merge: bool,
+    },
+    /// Compare the moves of a player in the games of a PGN file, or of the
+    /// PGN files in a directory, with the engine's choices: how often they
+    /// match and how much they give away in the opening, middlegame and endgame
+    Similarity {
+        path: PathBuf,
+
+        /// The player whose name contains this, in any case. Both sides of
+        /// every game if not given
+        #[arg(long)]
+        player: Option<String>,
+
+        /// How many plies the engine looks ahead
+        #[arg(long, default_value_t = 3)]
+        depth: u8,
+
+        /// How many of the engine's best moves count as a match besides its
+        /// first choice
+        #[arg(long, default_value_t = 3)]
+        top: usize,
+    },
+    /// Work with opening books in the Polyglot format
+    Book {
+        #[command(subcommand)]
+        action: BookAction,
+    },
+    /// Play the engine against itself and write the positions it searched,
+    /// with its moves, scores and the results, as data to tune the evaluation
+    Selfplay {
+        /// Where the positions are written
+        output: PathBuf,
+
+        #[command(flatten)]
+        settings: Settings,
+
+        #[command(flatten)]
+        rules: Rules,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BookAction {
+    /// Make a book of the moves played in the games of a PGN file
+    Build {
+        pgn: PathBuf,
+
+        /// Where the book is written
+        output: PathBuf,
+
+        #[command(flatten)]
+        limits: Limits,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Player {
+    Human,
+    Engine,
+    // plays any legal move, picked with the --seed randomness
+    Random,
+    // the external engine started with --uci
+    Uci,
+    // the other side of a host or join game, never picked on the command line
+    #[value(skip)]
+    Remote,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Side {
+    White,
+    Black,
+}
+
+impl Side {
+    pub fn color(self) -> Color {
+        match self {
+            Side::White => Color::White,
+            Side::Black => Color::Black,
+        }
+    }
+}
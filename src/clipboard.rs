@@ -0,0 +1,37 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// the clipboard programs tried in turn, each taking the text on stdin
+const PROGRAMS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["pbcopy"],
+    &["clip.exe"],
+];
+
+// puts text on the system clipboard with the first program found that takes
+// it. the error says none did
+pub fn copy(text: &str) -> Result<(), String> {
+    for program in PROGRAMS {
+        let child = Command::new(program[0])
+            .args(&program[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        // not installed, try the next one
+        let Ok(mut child) = child else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(text.as_bytes());
+        }
+        if child.wait().is_ok_and(|status| status.success()) {
+            return Ok(());
+        }
+    }
+    Err(String::from(
+        "no clipboard program worked, like xclip, wl-copy or pbcopy",
+    ))
+}
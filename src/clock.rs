@@ -0,0 +1,194 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::Color;
+
+// time given back or held off on every move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bonus {
+    // fischer: added after every move
+    Increment(Duration),
+    // us delay: the clock only starts counting once this has passed
+    Delay(Duration),
+    // bronstein: what was used of this is added back after the move
+    Bronstein(Duration),
+}
+
+// the time each side gets for the whole game, "5" for five minutes, "5+3"
+// with three seconds of increment, "5d3" or "5b3" with three seconds of
+// us or bronstein delay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub bonus: Option<Bonus>,
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("invalid time control: {}. examples: 5, 5+3, 5d3, 5b3", s);
+        // a number of seconds times scale, too large a duration is invalid too
+        let amount = |text: &str, allow_zero: bool, scale: f64| {
+            let value: f64 = text.trim().parse().map_err(|_| error())?;
+            if !value.is_finite() || value < 0.0 || (value == 0.0 && !allow_zero) {
+                return Err(error());
+            }
+            Duration::try_from_secs_f64(value * scale).map_err(|_| error())
+        };
+        let (minutes, bonus) = match s.find(['+', 'd', 'b']) {
+            Some(at) => {
+                let seconds = amount(&s[at + 1..], true, 1.0)?;
+                let bonus = match &s[at..at + 1] {
+                    "+" => Bonus::Increment(seconds),
+                    "d" => Bonus::Delay(seconds),
+                    _ => Bonus::Bronstein(seconds),
+                };
+                (&s[..at], Some(bonus))
+            }
+            None => (s, None),
+        };
+        Ok(TimeControl {
+            base: amount(minutes, false, 60.0)?,
+            bonus,
+        })
+    }
+}
+
+impl TimeControl {
+    // the part of the thinking time that comes off the clock
+    fn charged(&self, elapsed: Duration) -> Duration {
+        match self.bonus {
+            Some(Bonus::Delay(delay)) => elapsed.saturating_sub(delay),
+            _ => elapsed,
+        }
+    }
+
+    // the time left after a move that took elapsed with left on the clock.
+    // the bonus is only given to a side that still had time left
+    pub fn spend(&self, left: Duration, elapsed: Duration) -> Duration {
+        let left = left.saturating_sub(self.charged(elapsed));
+        let bonus = match self.bonus {
+            _ if left.is_zero() => Duration::ZERO,
+            Some(Bonus::Increment(increment)) => increment,
+            Some(Bonus::Bronstein(delay)) => elapsed.min(delay),
+            _ => Duration::ZERO,
+        };
+        left.saturating_add(bonus)
+    }
+}
+
+// the same notation FromStr reads
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.base.as_secs_f64() / 60.0)?;
+        match self.bonus {
+            Some(Bonus::Increment(time)) => write!(f, "+{}", time.as_secs_f64()),
+            Some(Bonus::Delay(time)) => write!(f, "d{}", time.as_secs_f64()),
+            Some(Bonus::Bronstein(time)) => write!(f, "b{}", time.as_secs_f64()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    // indexed by Color::idx
+    pub controls: [TimeControl; 2],
+    // time left as of when the running side started thinking
+    remaining: [Duration; 2],
+    // the side whose clock is counting down and since when
+    running: Option<(Color, Instant)>,
+}
+
+impl Clock {
+    // starts counting down for the side to move right away. controls are
+    // indexed by Color::idx so a side can be given less time as a handicap
+    pub fn new(controls: [TimeControl; 2], turn: Color) -> Self {
+        Clock::resume(controls, controls.map(|control| control.base), turn, true)
+    }
+
+    // a clock with the given time left, counting down for turn if running
+    pub fn resume(
+        controls: [TimeControl; 2],
+        remaining: [Duration; 2],
+        turn: Color,
+        running: bool,
+    ) -> Self {
+        Clock {
+            controls,
+            remaining,
+            running: running.then(|| (turn, Instant::now())),
+        }
+    }
+
+    // the side whose time is going down, none while the clock is stopped
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    pub fn remaining(&self, color: Color) -> Duration {
+        let left = self.remaining[color.idx()];
+        match self.running {
+            Some((running, since)) if running == color => {
+                left.saturating_sub(self.controls[color.idx()].charged(since.elapsed()))
+            }
+            _ => left,
+        }
+    }
+
+    // stops the running clock and starts the one of color, returning how
+    // long the stopped side thought
+    pub fn switch(&mut self, color: Color) -> Option<Duration> {
+        let elapsed = self.stop();
+        self.running = Some((color, Instant::now()));
+        elapsed
+    }
+
+    pub fn stop(&mut self) -> Option<Duration> {
+        let (running, since) = self.running?;
+        self.running = None;
+        let elapsed = since.elapsed();
+        let left = &mut self.remaining[running.idx()];
+        *left = self.controls[running.idx()].spend(*left, elapsed);
+        Some(elapsed)
+    }
+
+    // both clocks on one line: "White 04:58  Black 05:00"
+    pub fn describe(&self) -> String {
+        format!(
+            "White {}  Black {}",
+            format_time(self.remaining(Color::White)),
+            format_time(self.remaining(Color::Black))
+        )
+    }
+}
+
+// the time spent on a move: "4.2s", or "1:05" from a minute on
+pub fn format_elapsed(time: Duration) -> String {
+    let secs = time.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", time.as_secs_f64())
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+// mm:ss, with tenths once less than ten seconds are left. rounded up so a
+// fresh clock shows its full time and 00:00 only once it has run out
+pub fn format_time(time: Duration) -> String {
+    let tenths = time.as_millis().div_ceil(100);
+    if tenths < 100 {
+        format!("00:0{}.{}", tenths / 10, tenths % 10)
+    } else {
+        let secs = tenths.div_ceil(10);
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+// h:mm:ss as the %clk comments of pgn want it, rounded up like format_time
+pub fn format_clk(time: Duration) -> String {
+    let secs = time.as_millis().div_ceil(1000);
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
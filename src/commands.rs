@@ -0,0 +1,1766 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use clap::ValueEnum;
+use tracing::span::EnteredSpan;
+
+use crate::analysis;
+use crate::assess;
+use crate::cache::AnalysisCache;
+use crate::cli::Player;
+use crate::clipboard;
+use crate::clock::Clock;
+use crate::correspondence;
+use crate::display::{Annotations, Brush, DisplayOptions, Highlights};
+use crate::dump;
+use crate::editor;
+use crate::endgame::Oracle;
+use crate::engine;
+use crate::explorer::Explorer;
+use crate::game::{Game, GameResult, Variation};
+use crate::heatmap::Heatmap;
+use crate::humanlike::Persona;
+use crate::library;
+use crate::lichess;
+use crate::net::Remote;
+use crate::notation::Notation;
+use crate::openings;
+use crate::options::EngineOptions;
+use crate::pgn;
+use crate::rating::Ratings;
+use crate::rng::Rng;
+use crate::save;
+use crate::scoresheet::{self, Form};
+use crate::solve;
+use crate::theme::Theme;
+use crate::uci::UciEngine;
+use crate::variant::VariantKind;
+use crate::{BoardPos, ChessBoard, Color, Move};
+
+// everything an interactive frontend keeps between two inputs
+pub struct Session {
+    pub game: Game,
+    // the games played so far, this one included, and the span its log lines
+    // carry
+    pub games: usize,
+    pub span: EnteredSpan,
+    pub options: DisplayOptions,
+    pub highlights: Highlights,
+    pub annotations: Annotations,
+    // indexed by Color::idx
+    pub players: [Player; 2],
+    pub depth: u8,
+    // the built-in engine plays like a person of this rating instead of as
+    // well as it can
+    pub human_like: Option<Persona>,
+    // the side that offered a draw, until the opponent answers or a move is played
+    pub draw_offer: Option<Color>,
+    // the side that asked to take back its last move, until the opponent answers
+    pub takeback_request: Option<Color>,
+    // the moves listed for an ambiguous input, picked by typing their number
+    pub choices: Vec<Move>,
+    // the board colors to pick from by name
+    pub themes: Vec<(String, Theme)>,
+    // where the game in progress is kept in case the program dies
+    pub autosave: Option<PathBuf>,
+    // while looking back through the game, how many plies in the shown position is
+    pub viewing: Option<usize>,
+    // where finished games are kept as pgn, none to not keep them
+    pub library: Option<PathBuf>,
+    // whether the current game is already in the library
+    pub logged: bool,
+    // the pgn file every finished game is added to
+    pub game_log: Option<PathBuf>,
+    // for every random choice, seeded by --seed
+    pub rng: Rng,
+    // the external engine from --uci
+    pub uci: Option<UciEngine>,
+    // the options given to it, checked against what they take
+    pub engine_options: EngineOptions,
+    // signs correspondence files, empty when none was set
+    pub secret: String,
+    // the other player in a game over the network
+    pub remote: Option<Remote>,
+    // moves the remote player knows of
+    pub sent: usize,
+    // where ratings are kept, none for unrated games
+    pub ratings: Option<PathBuf>,
+    // whether the current game already counted for the ratings
+    pub rated: bool,
+    // whether the accuracy of the current game was already kept with them
+    pub reviewed: bool,
+    // where the engine's evaluations are kept between sessions, none to
+    // search every position again
+    pub analysis_cache: Option<PathBuf>,
+    // the position being set up in the editor, shown instead of the game's
+    pub editor: Option<ChessBoard>,
+    // the games whose moves are listed under the board
+    pub explorer: Option<Explorer>,
+    // the endgame tables, whose verdict on every move is listed under the board
+    pub oracle: Option<Oracle>,
+    // the side line being explored, shown instead of the game
+    pub sideline: Option<Sideline>,
+    // the move typed ahead and the ply it was typed at, read once the
+    // opponent has moved after that
+    pub premove: Option<(String, usize)>,
+}
+
+// moves tried off the game and the position they lead to
+pub struct Sideline {
+    pub variation: Variation,
+    pub board: ChessBoard,
+}
+
+impl Session {
+    // puts another game in place of the one played, followed by the same
+    // observers and logged under a span of its own
+    pub fn start_game(&mut self, game: Game) {
+        self.game.replace(game);
+        self.games += 1;
+        self.premove = None;
+        self.span = tracing::info_span!(parent: None, "game", number = self.games).entered();
+        tracing::info!("started from {}", self.game.start().to_fen());
+    }
+
+    // the position in the editor, the one looked back at, or the one of the game
+    pub fn shown_board(&self) -> &ChessBoard {
+        if let Some(board) = &self.editor {
+            return board;
+        }
+        if let Some(sideline) = &self.sideline {
+            return &sideline.board;
+        }
+        match self.viewing.and_then(|ply| self.game.history.get(ply)) {
+            Some(played) => &played.before,
+            None => &self.game.board,
+        }
+    }
+
+    // plays the pre-move once it is the human's turn again, or drops it when
+    // the opponent's move made it illegal. what happened to it, if anything
+    pub fn play_premove(&mut self) -> Option<String> {
+        if self.game.result.is_some()
+            || self.editor.is_some()
+            || self.sideline.is_some()
+            || self.viewing.is_some()
+            || self.players[self.game.board.turn.idx()] != Player::Human
+            || self
+                .premove
+                .as_ref()
+                .is_none_or(|(_, ply)| self.game.history.len() <= *ply)
+        {
+            return None;
+        }
+        let (text, _) = self.premove.take()?;
+        let board = &self.game.board;
+        let san = self.options.locale.delocalize(&text);
+        let legal = board
+            .parse_uci(&text)
+            .or_else(|| board.parse_san(&san))
+            .filter(|mve| board.is_legal(mve));
+        let Some(mve) = legal else {
+            return Some(format!("pre-move {} is not legal now, dropped", text));
+        };
+        let note = format!(
+            "pre-move {} played",
+            self.options.move_text(&board.san(&mve), &mve)
+        );
+        play_move(self, mve);
+        Some(note)
+    }
+
+    // the moves the explorer's games played from the position on the board
+    pub fn explorer_lines(&self) -> Vec<String> {
+        match &self.explorer {
+            Some(explorer) => explorer.lines(self.shown_board(), &self.options),
+            None => Vec::new(),
+        }
+    }
+
+    // how every move of the position on the board ends with perfect play
+    pub fn oracle_lines(&self) -> Vec<String> {
+        match &self.oracle {
+            Some(oracle) => oracle.lines(self.shown_board(), &self.options),
+            None => Vec::new(),
+        }
+    }
+
+    // the position after ply moves of the game
+    fn board_after(&self, ply: usize) -> &ChessBoard {
+        self.game
+            .history
+            .get(ply)
+            .map_or(&self.game.board, |played| &played.before)
+    }
+
+    // "after 2... g5: 3. Nc3 Nc6", or "from the start: 1. d4"
+    fn variation_text(&self, variation: &Variation) -> String {
+        let line = self
+            .options
+            .line_text(self.board_after(variation.ply), &variation.moves);
+        match variation.ply.checked_sub(1).map(|i| &self.game.history[i]) {
+            Some(played) => {
+                let dots = match played.before.turn {
+                    Color::White => ".",
+                    Color::Black => "...",
+                };
+                format!(
+                    "after {}{} {}: {}",
+                    played.before.fullmove,
+                    dots,
+                    self.options.move_text(&played.san, &played.mve),
+                    line
+                )
+            }
+            None => format!("from the start: {}", line),
+        }
+    }
+
+    // closes the side line, keeping it with the game unless it is empty or
+    // already there. the board goes back to where it branched off
+    fn leave_sideline(&mut self) {
+        if let Some(sideline) = self.sideline.take() {
+            let variation = sideline.variation;
+            if !variation.moves.is_empty() && !self.game.variations.contains(&variation) {
+                self.game.variations.push(variation);
+            }
+        }
+    }
+
+    // the game status, or which earlier position is on the board
+    pub fn status(&self) -> String {
+        if let Some(board) = &self.editor {
+            return format!(
+                "setting up a position, {} to move. type done to play from it",
+                board.turn.name()
+            );
+        }
+        if let Some(sideline) = &self.sideline {
+            return format!(
+                "exploring {}, type main to return to the game",
+                self.variation_text(&sideline.variation)
+            );
+        }
+        match self.viewing.filter(|&ply| ply < self.game.history.len()) {
+            Some(0) => {
+                String::from("viewing the starting position, type live to return to the game")
+            }
+            Some(ply) => {
+                let played = &self.game.history[ply - 1];
+                let dots = if ply % 2 == 1 { "." } else { "..." };
+                format!(
+                    "viewing the position after {}{} {}, type live to return to the game",
+                    ply.div_ceil(2),
+                    dots,
+                    self.options.move_text(&played.san, &played.mve)
+                )
+            }
+            None => self.options.locale.status(&self.game),
+        }
+    }
+
+    // the opening the game went through, under the status
+    pub fn opening(&self) -> Option<String> {
+        openings::name(&self.game).map(|name| format!("opening: {}", name))
+    }
+
+    // called after anything that changes the game
+    pub fn record(&mut self) {
+        self.send_moves();
+        self.autosave();
+        self.rate();
+        if self.game.result.is_some() {
+            self.log_game();
+        }
+    }
+
+    // the move of the side to move if it is played by the program. searched
+    // is the engine's move when it was already looked for
+    pub fn computer_move(&mut self, searched: Option<Move>) -> Option<Move> {
+        let board = &self.game.board;
+        match self.players[board.turn.idx()] {
+            Player::Human | Player::Remote => None,
+            // what was searched for the evaluation came from the uci engine if
+            // there is one
+            Player::Engine => match self.human_like {
+                // it takes as long over the move as a person would, the
+                // search counting towards that
+                Some(persona) => {
+                    let started = Instant::now();
+                    let left = self.game.clock.as_ref().map(|c| c.remaining(board.turn));
+                    let last = self.game.history.last().map(|played| &played.mve);
+                    let (mve, think) =
+                        persona.choose(board, last, left, self.depth, &mut self.rng)?;
+                    std::thread::sleep(think.saturating_sub(started.elapsed()));
+                    Some(mve)
+                }
+                None => searched
+                    .filter(|_| self.uci.is_none())
+                    .or_else(|| engine::search(board, self.depth).map(|(mve, _)| mve)),
+            },
+            Player::Uci => searched.or_else(|| {
+                let uci = self.uci.as_mut()?;
+                uci.search(board, self.depth, |_, _, _| {})
+                    .map(|(mve, _)| mve)
+            }),
+            Player::Random => {
+                let moves = board.legal_moves();
+                match moves.len() {
+                    0 => None,
+                    n => Some(moves[self.rng.below(n)]),
+                }
+            }
+        }
+    }
+
+    // tells the remote player about the moves played here since the last time
+    fn send_moves(&mut self) {
+        let Some(remote) = &mut self.remote else {
+            return;
+        };
+        for played in self.game.history.iter().skip(self.sent) {
+            if self.players[played.before.turn.idx()] != Player::Remote {
+                remote.send(&played.mve.to_string());
+            }
+        }
+        self.sent = self.game.history.len();
+    }
+
+    // sends a message other than a move to the remote player, if there is one
+    fn tell(&mut self, message: &str) {
+        if let Some(remote) = &mut self.remote {
+            remote.send(message);
+        }
+    }
+
+    // waits until the remote player moves or ends the game, and returns what
+    // happened besides the move
+    pub fn wait_for_remote(&mut self) -> Vec<String> {
+        let turn = self.game.board.turn;
+        let mut notes = Vec::new();
+        while self.game.result.is_none() && self.game.board.turn == turn {
+            let Some(remote) = &mut self.remote else {
+                break;
+            };
+            let Some(message) = remote.receive() else {
+                notes.push(String::from("the other player left the game"));
+                self.game.end(GameResult::Resignation(turn.other()));
+                break;
+            };
+            match message.as_str() {
+                "resign" => self.game.end(GameResult::Resignation(turn.other())),
+                "draw" if self.draw_offer == Some(turn.other()) => {
+                    self.game.end(GameResult::DrawAgreed)
+                }
+                "draw" => {
+                    self.draw_offer = Some(turn);
+                    notes.push(format!(
+                        "{} offers a draw, type draw to accept",
+                        turn.name()
+                    ));
+                }
+                "claim" => match self.game.draw_claim() {
+                    Some(claim) => self.game.end(GameResult::DrawClaimed(claim)),
+                    None => notes.push(format!("{} claimed a draw without one", turn.name())),
+                },
+                uci => match self.game.board.parse_uci(uci) {
+                    Some(mve) if self.game.board.is_legal(&mve) => {
+                        let san = self.game.board.san(&mve);
+                        let text = self.options.move_text(&san, &mve);
+                        notes.push(self.options.locale.plays(turn, &text));
+                        self.game.play(&mve);
+                        if self.draw_offer != Some(turn) {
+                            self.draw_offer = None;
+                        }
+                    }
+                    // a peer that breaks the rules is treated like one that left
+                    _ => {
+                        notes.push(format!("the other player sent an invalid move: {}", uci));
+                        self.game.end(GameResult::Resignation(turn.other()));
+                        self.remote = None;
+                    }
+                },
+            }
+        }
+        self.sent = self.game.history.len();
+        self.record();
+        notes
+    }
+
+    // "human" or "engine depth 3", indexed by Color::idx
+    fn player_names(&self) -> [String; 2] {
+        self.players.map(|player| match player {
+            Player::Human => String::from("human"),
+            Player::Engine => match self.human_like {
+                Some(persona) => format!("human-like {}", persona.rating),
+                None => format!("engine depth {}", self.depth),
+            },
+            Player::Random => String::from("random"),
+            Player::Remote => String::from("remote"),
+            Player::Uci => match &self.uci {
+                Some(uci) => format!("{} depth {}", uci.name, self.depth),
+                None => String::from("uci"),
+            },
+        })
+    }
+
+    fn pgn_tags(&self) -> Vec<(&'static str, String)> {
+        library::tags(
+            &self.game,
+            "casual game",
+            String::from("-"),
+            self.player_names(),
+        )
+    }
+
+    // puts the game in the library once, finished or not, as long as it has
+    // moves, and adds it to the game log once it is finished
+    pub fn log_game(&mut self) {
+        if self.logged || self.game.history.is_empty() {
+            return;
+        }
+        self.logged = true;
+        let tags = self.pgn_tags();
+        if let Some(dir) = &self.library {
+            let _ = library::log(dir, &self.game, &tags);
+        }
+        if let (Some(path), Some(_)) = (&self.game_log, self.game.result) {
+            let _ = library::append(path, &self.game, &tags);
+        }
+    }
+
+    // a finished game between a person and the engine counts once for both
+    // ratings. as with the autosave, a failure to write them stays quiet
+    fn rate(&mut self) {
+        let Some(path) = &self.ratings else {
+            return;
+        };
+        let Some(result) = self.game.result.filter(|_| !self.rated) else {
+            return;
+        };
+        let Some(human) = self.players.iter().position(|&p| p == Player::Human) else {
+            return;
+        };
+        if self.players[1 - human] != Player::Engine || self.game.history.is_empty() {
+            return;
+        }
+        self.rated = true;
+        let score = match result.winner() {
+            Some(winner) if winner.idx() == human => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        };
+        let engine = self.player_names()[1 - human].clone();
+        if let Ok(mut ratings) = Ratings::load(path) {
+            ratings.record("human", &engine, score);
+            let _ = ratings.save(path);
+        }
+    }
+
+    // keeps the accuracy of both sides of the analyzed game with the ratings,
+    // once a game, so it can be followed from game to game. two people at
+    // the same board share a name and aren't told apart
+    fn track_accuracy(&mut self, accuracy: [Option<f64>; 2]) {
+        let Some(path) = self.ratings.as_ref().filter(|_| !self.reviewed) else {
+            return;
+        };
+        let names = self.player_names();
+        if names[0] == names[1] {
+            return;
+        }
+        self.reviewed = true;
+        if let Ok(mut ratings) = Ratings::load(path) {
+            for (name, accuracy) in names.iter().zip(accuracy) {
+                if let Some(accuracy) = accuracy {
+                    ratings.record_accuracy(name, accuracy);
+                }
+            }
+            let _ = ratings.save(path);
+        }
+    }
+
+    // writes the game in progress to the autosave file, or removes the file
+    // once there is nothing left to resume. failing to do so isn't worth
+    // interrupting the game for
+    fn autosave(&self) {
+        let unfinished = self.game.result.is_none() && !self.game.history.is_empty();
+        let text = save::to_text(&self.game).ok().filter(|_| unfinished);
+        // a crash leaves the game where it would be resumed from, or next to it
+        let kept = self.autosave.clone().or_else(save::crash_path);
+        save::keep_for_crash(kept.zip(text.clone()));
+        let Some(path) = &self.autosave else {
+            return;
+        };
+        match text {
+            Some(text) => {
+                if let Some(dir) = path.parent() {
+                    let _ = std::fs::create_dir_all(dir);
+                }
+                let _ = save::write(path, &text);
+            }
+            None => {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    Continue,
+    ShowBoard,
+    Quit,
+}
+
+pub struct Reply {
+    pub lines: Vec<String>,
+    pub flow: Flow,
+}
+
+impl Reply {
+    pub fn text(line: impl Into<String>) -> Self {
+        Reply {
+            lines: vec![line.into()],
+            flow: Flow::Continue,
+        }
+    }
+
+    pub fn board() -> Self {
+        Reply {
+            lines: Vec::new(),
+            flow: Flow::ShowBoard,
+        }
+    }
+}
+
+struct Spec {
+    name: &'static str,
+    usage: &'static str,
+    help: &'static str,
+    run: fn(&mut Session, &str) -> Reply,
+}
+
+const COMMANDS: &[Spec] = &[
+    Spec {
+        name: "help",
+        usage: "help",
+        help: "list the commands",
+        run: help,
+    },
+    Spec {
+        name: "board",
+        usage: "board",
+        help: "show the board again",
+        run: |_, _| Reply::board(),
+    },
+    Spec {
+        name: "fen",
+        usage: "fen",
+        help: "print the position as fen",
+        run: |s, _| Reply::text(s.game.board.to_fen()),
+    },
+    Spec {
+        name: "moves",
+        usage: "moves [square]",
+        help: "list the legal moves, or those of the piece on a square",
+        run: moves,
+    },
+    Spec {
+        name: "history",
+        usage: "history",
+        help: "list the moves played so far",
+        run: history,
+    },
+    Spec {
+        name: "show",
+        usage: "show <square>",
+        help: "highlight where the piece on a square can move",
+        run: show,
+    },
+    Spec {
+        name: "threats",
+        usage: "threats",
+        help: "mark the squares the opponent attacks and the pieces left hanging",
+        run: threats,
+    },
+    Spec {
+        name: "assess",
+        usage: "assess",
+        help: "list hanging and outnumbered pieces, pins, mates in one and forks for both sides",
+        run: |s, _| Reply {
+            lines: assess::assess(s.shown_board(), &s.options),
+            flow: Flow::Continue,
+        },
+    },
+    Spec {
+        name: "solve",
+        usage: "solve mate <n> [fen]",
+        help: "find every first move that forces mate in n, with the defences and the tries a single move refutes",
+        run: solve,
+    },
+    Spec {
+        name: "dump",
+        usage: "dump",
+        help: "print the internal state of the position: hash, castling rights, bitboards and draw counters",
+        run: |s, _| Reply {
+            lines: dump::dump(&s.game, s.shown_board()),
+            flow: Flow::Continue,
+        },
+    },
+    Spec {
+        name: "mark",
+        usage: "mark <square> [color]",
+        help: "mark a square in the tui, green, red, blue or yellow",
+        run: mark,
+    },
+    Spec {
+        name: "arrow",
+        usage: "arrow <move> [color]",
+        help: "draw an arrow like g1f3 in the tui",
+        run: arrow,
+    },
+    Spec {
+        name: "clear",
+        usage: "clear",
+        help: "remove the marks and arrows",
+        run: |s, _| {
+            s.annotations = Annotations::default();
+            Reply::board()
+        },
+    },
+    Spec {
+        name: "undo",
+        usage: "undo",
+        help: "take back the last move, or your last move and the engine's reply",
+        run: undo,
+    },
+    Spec {
+        name: "takeback",
+        usage: "takeback",
+        help: "ask to take back the last move, or accept when asked",
+        run: takeback,
+    },
+    Spec {
+        name: "premove",
+        usage: "premove [move]",
+        help: "play a move the instant the opponent has moved, if still legal. premove cancel drops it",
+        run: premove,
+    },
+    Spec {
+        name: "redo",
+        usage: "redo",
+        help: "play a taken back move again",
+        run: redo,
+    },
+    Spec {
+        name: "back",
+        usage: "back",
+        help: "show the position one move earlier",
+        run: back,
+    },
+    Spec {
+        name: "forward",
+        usage: "forward",
+        help: "show the position one move later",
+        run: forward,
+    },
+    Spec {
+        name: "goto",
+        usage: "goto <n>",
+        help: "show the position after n moves of both sides together, 0 for the start",
+        run: goto,
+    },
+    Spec {
+        name: "live",
+        usage: "live",
+        help: "return to the position of the game",
+        run: |s, _| {
+            s.leave_sideline();
+            s.viewing = None;
+            Reply::board()
+        },
+    },
+    Spec {
+        name: "try",
+        usage: "try [move]",
+        help: "branch off from the shown position with a move of your own, or list the side lines",
+        run: try_move,
+    },
+    Spec {
+        name: "main",
+        usage: "main",
+        help: "leave the side line, keeping it with the game, and go back to where it branched off",
+        run: |s, _| {
+            s.leave_sideline();
+            Reply::board()
+        },
+    },
+    Spec {
+        name: "new",
+        usage: "new",
+        help: "start a new game with the same sides",
+        run: |s, _| new_game(s, false),
+    },
+    Spec {
+        name: "rematch",
+        usage: "rematch",
+        help: "start a new game with the sides swapped",
+        run: |s, _| new_game(s, true),
+    },
+    Spec {
+        name: "edit",
+        usage: "edit",
+        help: "set up a position by hand to play or analyse from",
+        run: editor::open,
+    },
+    Spec {
+        name: "analyze",
+        usage: "analyze [file]",
+        help: "once the game is over, go through every move with the engine, and write it annotated as pgn",
+        run: analyze,
+    },
+    Spec {
+        name: "games",
+        usage: "games [n]",
+        help: "list the games in the library, or open one of them",
+        run: games,
+    },
+    Spec {
+        name: "explore",
+        usage: "explore [folder|off]",
+        help: "list the moves played from each position in a folder of pgn games",
+        run: explore,
+    },
+    Spec {
+        name: "oracle",
+        usage: "oracle [on|off]",
+        help: "list win, draw or loss and the distance for every move of a king and queen or rook against king ending",
+        run: oracle,
+    },
+    Spec {
+        name: "heatmap",
+        usage: "heatmap [svg] [file]",
+        help: "show where each side's pieces moved and captured, or write it to a file as text or an svg picture",
+        run: heatmap,
+    },
+    Spec {
+        name: "ratings",
+        usage: "ratings",
+        help: "show the ratings and accuracy and how they went over the last games",
+        run: ratings,
+    },
+    Spec {
+        name: "share",
+        usage: "share [pgn]",
+        help: "print a lichess link to analyse the position, or upload the game with pgn",
+        run: share,
+    },
+    Spec {
+        name: "chat",
+        usage: "chat [emoji]",
+        help: "print the board to paste into a chat, as a code block or in emoji",
+        run: chat,
+    },
+    Spec {
+        name: "copy",
+        usage: "copy fen|pgn|chat",
+        help: "put the position, the game or the chat board on the clipboard, or print it without one",
+        run: copy,
+    },
+    Spec {
+        name: "save",
+        usage: "save <file>",
+        help: "write the game to a file",
+        run: save,
+    },
+    Spec {
+        name: "scoresheet",
+        usage: "scoresheet [print] [file]",
+        help: "show the game as a two-column score sheet, with print boxed and paged for paper, or write it to a file",
+        run: scoresheet,
+    },
+    Spec {
+        name: "send",
+        usage: "send <file>",
+        help: "write the game for a correspondence opponent, after your move",
+        run: send,
+    },
+    Spec {
+        name: "receive",
+        usage: "receive <file>",
+        help: "play the move from a correspondence opponent's file",
+        run: receive,
+    },
+    Spec {
+        name: "load",
+        usage: "load <file>",
+        help: "continue a game written by save",
+        run: load,
+    },
+    Spec {
+        name: "resign",
+        usage: "resign",
+        help: "give up the game",
+        run: resign,
+    },
+    Spec {
+        name: "draw",
+        usage: "draw",
+        help: "offer a draw, accept the one offered, or claim one",
+        run: draw,
+    },
+    Spec {
+        name: "flip",
+        usage: "flip",
+        help: "turn the board around",
+        run: |s, _| {
+            s.options.flipped = !s.options.flipped;
+            s.options.auto_flip = false;
+            Reply::board()
+        },
+    },
+    Spec {
+        name: "autoflip",
+        usage: "autoflip",
+        help: "always show the board from the side to move",
+        run: |s, _| {
+            s.options.auto_flip = !s.options.auto_flip;
+            Reply::board()
+        },
+    },
+    Spec {
+        name: "unicode",
+        usage: "unicode",
+        help: "switch between letters and chess symbols",
+        run: |s, _| {
+            s.options.unicode = !s.options.unicode;
+            Reply::board()
+        },
+    },
+    Spec {
+        name: "notation",
+        usage: "notation <style>",
+        help: "show moves as san, uci or figurine",
+        run: notation,
+    },
+    Spec {
+        name: "theme",
+        usage: "theme [name]",
+        help: "switch the board colors, or list the themes",
+        run: theme,
+    },
+    Spec {
+        name: "eval",
+        usage: "eval [terms]",
+        help: "show or hide the engine's evaluation, or list the terms of the static evaluation of the position",
+        run: eval,
+    },
+    Spec {
+        name: "option",
+        usage: "option [name value]",
+        help: "list the options of the uci engine, or change one for its next search. the built-in engine has none",
+        run: option,
+    },
+    Spec {
+        name: "quit",
+        usage: "quit",
+        help: "leave the program",
+        run: |_, _| Reply {
+            lines: Vec::new(),
+            flow: Flow::Quit,
+        },
+    },
+];
+
+// runs a command, anything that isn't one is tried as a move
+// commands that rewrite the game, which the other side of a network game
+// wouldn't follow
+const OFFLINE_ONLY: &[&str] = &[
+    "undo", "redo", "takeback", "load", "new", "rematch", "games", "edit",
+];
+
+pub fn execute(session: &mut Session, input: &str) -> Reply {
+    let input = input.trim();
+    let name = input.split(' ').next().unwrap_or_default();
+    if session.editor.is_some() && !editor::KEPT.contains(&name) {
+        return editor::execute(session, input);
+    }
+    // whatever was typed came too late once the clock ran out
+    if session.game.check_time() {
+        session.record();
+        return Reply::board();
+    }
+    let choices = std::mem::take(&mut session.choices);
+    if let Some(mve) = input
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| choices.get(n.wrapping_sub(1)))
+    {
+        let reply = play_move(session, *mve);
+        session.record();
+        return reply;
+    }
+    let (name, args) = input.split_once(' ').unwrap_or((input, ""));
+    if session.remote.is_some() && OFFLINE_ONLY.contains(&name) {
+        return Reply::text(format!("{} is not available in a network game", name));
+    }
+    let reply = match COMMANDS.iter().find(|c| c.name == name) {
+        Some(command) => (command.run)(session, args.trim()),
+        None => play(session, input),
+    };
+    session.record();
+    reply
+}
+
+fn help(_: &mut Session, _: &str) -> Reply {
+    // the help lines up two spaces past the longest usage
+    let width = COMMANDS.iter().map(|c| c.usage.len()).max().unwrap_or(0) + 2;
+    let mut lines: Vec<String> = COMMANDS
+        .iter()
+        .map(|c| format!("{:<width$}{}", c.usage, c.help))
+        .collect();
+    lines.push(String::from(
+        "anything else is read as a move, like e2e4 or Nf3. a number picks from a list of matching moves",
+    ));
+    Reply {
+        lines,
+        flow: Flow::Continue,
+    }
+}
+
+fn moves(session: &mut Session, args: &str) -> Reply {
+    let board = &session.game.board;
+    let from = if args.is_empty() {
+        None
+    } else {
+        match BoardPos::parse(args) {
+            Some(pos) => Some(pos),
+            None => return Reply::text("invalid square. example: moves e2"),
+        }
+    };
+    let moves: Vec<String> = board
+        .legal_moves()
+        .iter()
+        .filter(|m| from.is_none_or(|f| m.from == f))
+        .map(|m| session.options.move_text(&board.san(m), m))
+        .collect();
+    match (moves.is_empty(), from) {
+        (false, _) => Reply::text(moves.join(" ")),
+        (true, None) => Reply::text("no legal moves"),
+        (true, Some(pos)) => Reply::text(format!("no legal moves from {}", pos)),
+    }
+}
+
+fn history(session: &mut Session, _: &str) -> Reply {
+    let lines = session.game.history_lines(&session.options);
+    if lines.is_empty() {
+        return Reply::text("no moves played yet");
+    }
+    Reply {
+        lines,
+        flow: Flow::Continue,
+    }
+}
+
+fn threats(session: &mut Session, _: &str) -> Reply {
+    let highlights = Highlights::threats(&session.game.board);
+    let hanging: Vec<String> = highlights.hanging.iter().map(|p| p.to_string()).collect();
+    session.highlights = highlights;
+    let line = if hanging.is_empty() {
+        String::from("nothing is hanging")
+    } else {
+        format!("hanging: {}", hanging.join(", "))
+    };
+    Reply {
+        lines: vec![line],
+        flow: Flow::ShowBoard,
+    }
+}
+
+fn mark(session: &mut Session, args: &str) -> Reply {
+    let (square, color) = args.split_once(' ').unwrap_or((args, ""));
+    match (BoardPos::parse(square), Brush::parse(color.trim())) {
+        (Some(pos), Some(brush)) => {
+            session.annotations.toggle_mark(pos, brush);
+            Reply::board()
+        }
+        _ => Reply::text("usage: mark <square> [green|red|blue|yellow]. example: mark e4"),
+    }
+}
+
+fn arrow(session: &mut Session, args: &str) -> Reply {
+    let (squares, color) = args.split_once(' ').unwrap_or((args, ""));
+    match (Move::parse(squares), Brush::parse(color.trim())) {
+        (Some(mve), Some(brush)) if mve.from != mve.to => {
+            session.annotations.toggle_arrow(mve, brush);
+            Reply::board()
+        }
+        _ => Reply::text("usage: arrow <from><to> [green|red|blue|yellow]. example: arrow g1f3"),
+    }
+}
+
+fn notation(session: &mut Session, args: &str) -> Reply {
+    match Notation::from_str(args, true) {
+        Ok(notation) => {
+            session.options.notation = notation;
+            Reply::text(format!("moves are shown as {}", args.to_lowercase()))
+        }
+        Err(_) => Reply::text("usage: notation san|uci|figurine"),
+    }
+}
+
+fn theme(session: &mut Session, args: &str) -> Reply {
+    match session.themes.iter().find(|(name, _)| name == args) {
+        Some((_, theme)) => {
+            session.options.theme = *theme;
+            Reply::board()
+        }
+        None => {
+            let names: Vec<&str> = session.themes.iter().map(|(n, _)| n.as_str()).collect();
+            Reply::text(format!("themes: {}", names.join(", ")))
+        }
+    }
+}
+
+fn show(session: &mut Session, args: &str) -> Reply {
+    match BoardPos::parse(args) {
+        Some(pos) => {
+            session.highlights = Highlights::legal_targets(&session.game.board, pos);
+            Reply::board()
+        }
+        None => Reply::text("invalid square. example: show e2"),
+    }
+}
+
+// against the engine a single move back would only hand it the turn again,
+// so its reply and the move before it go together
+fn plies(session: &Session) -> usize {
+    let [white, black] = session.players;
+    if white != black {
+        2
+    } else {
+        1
+    }
+}
+
+fn undo(session: &mut Session, args: &str) -> Reply {
+    session.leave_sideline();
+    // between two people the opponent has a say in it
+    if session.players == [Player::Human; 2] {
+        return takeback(session, args);
+    }
+    if !session.game.undo() {
+        return Reply::text("there is no move to take back");
+    }
+    // back to where the player last had the move, past the engine's turn
+    while plies(session) == 2
+        && session.players[session.game.board.turn.idx()] != Player::Human
+        && session.game.undo()
+    {}
+    session.draw_offer = None;
+    Reply::board()
+}
+
+fn takeback(session: &mut Session, _: &str) -> Reply {
+    let Some(last) = session.game.history.last() else {
+        return Reply::text("there is no move to take back");
+    };
+    let mover = last.before.turn;
+    // against the engine there is nobody to ask
+    if session.players != [Player::Human; 2] {
+        return undo(session, "");
+    }
+    if session.takeback_request == Some(mover) {
+        session.game.undo();
+        session.takeback_request = None;
+        session.draw_offer = None;
+        return Reply::board();
+    }
+    session.takeback_request = Some(mover);
+    Reply::text(format!(
+        "{} asks to take back {}, {} can type takeback to accept or play on to decline",
+        mover.name(),
+        session.options.move_text(&last.san, &last.mve),
+        mover.other().name()
+    ))
+}
+
+fn premove(session: &mut Session, args: &str) -> Reply {
+    if session.players == [Player::Human; 2] {
+        return Reply::text("pre-moves are for games against an engine or over the network");
+    }
+    match args {
+        "" => match &session.premove {
+            Some((text, _)) => Reply::text(format!("pre-move {}, premove cancel drops it", text)),
+            None => Reply::text("no pre-move. example: premove Nf3"),
+        },
+        "cancel" => match session.premove.take() {
+            Some((text, _)) => Reply::text(format!("pre-move {} dropped", text)),
+            None => Reply::text("there is no pre-move to drop"),
+        },
+        // checked only against the position it is played in
+        text => {
+            session.premove = Some((text.to_string(), session.game.history.len()));
+            Reply::text(format!(
+                "pre-move {}, played when the opponent has moved",
+                text
+            ))
+        }
+    }
+}
+
+fn redo(session: &mut Session, _: &str) -> Reply {
+    session.leave_sideline();
+    if !session.game.redo() {
+        return Reply::text("there is no move to redo");
+    }
+    while plies(session) == 2
+        && session.players[session.game.board.turn.idx()] != Player::Human
+        && session.game.redo()
+    {}
+    session.draw_offer = None;
+    Reply::board()
+}
+
+fn back(session: &mut Session, _: &str) -> Reply {
+    // in a side line its last move is taken back, and the side line closes
+    // once none are left
+    if let Some(mut sideline) = session.sideline.take() {
+        sideline.variation.moves.pop();
+        if !sideline.variation.moves.is_empty() {
+            sideline.board = session.board_after(sideline.variation.ply).clone();
+            for mve in &sideline.variation.moves {
+                sideline.board.make_move(mve);
+            }
+            session.sideline = Some(sideline);
+        }
+        return Reply::board();
+    }
+    match session.viewing.unwrap_or(session.game.history.len()) {
+        0 => Reply::text("already at the start of the game"),
+        ply => {
+            session.viewing = Some(ply - 1);
+            Reply::board()
+        }
+    }
+}
+
+fn forward(session: &mut Session, _: &str) -> Reply {
+    session.leave_sideline();
+    match session.viewing {
+        Some(ply) => {
+            session.viewing = Some(ply + 1).filter(|&ply| ply < session.game.history.len());
+            Reply::board()
+        }
+        None => Reply::text("already at the position of the game"),
+    }
+}
+
+fn goto(session: &mut Session, args: &str) -> Reply {
+    session.leave_sideline();
+    let plies = session.game.history.len();
+    match args.parse::<usize>() {
+        Ok(ply) if ply <= plies => {
+            session.viewing = Some(ply).filter(|&ply| ply < plies);
+            Reply::board()
+        }
+        _ => Reply::text(format!(
+            "invalid number of moves, the game has {}. example: goto 0",
+            plies
+        )),
+    }
+}
+
+// opens a side line at the shown position, or goes on with the open one, with
+// the move. without one the side lines kept with the game are listed
+fn try_move(session: &mut Session, args: &str) -> Reply {
+    if args.is_empty() {
+        let lines: Vec<String> = session
+            .game
+            .variations
+            .iter()
+            .map(|v| session.variation_text(v))
+            .collect();
+        if lines.is_empty() {
+            return Reply::text("no side lines yet. type try and a move to branch off the game");
+        }
+        return Reply {
+            lines,
+            flow: Flow::Continue,
+        };
+    }
+    if session.sideline.is_none() {
+        let ply = session.viewing.unwrap_or(session.game.history.len());
+        session.sideline = Some(Sideline {
+            variation: Variation {
+                ply,
+                moves: Vec::new(),
+            },
+            board: session.board_after(ply).clone(),
+        });
+    }
+    let reply = play(session, args);
+    // a move that wasn't played leaves nothing to explore
+    if session
+        .sideline
+        .as_ref()
+        .is_some_and(|s| s.variation.moves.is_empty())
+    {
+        session.sideline = None;
+    }
+    reply
+}
+
+// puts the current game in the library and sets up the initial position,
+// with fresh clocks of the same time control. in chess960 a rematch starts
+// from the same position and a new game from another random one
+fn new_game(session: &mut Session, swap: bool) -> Reply {
+    session.log_game();
+    let game = match session.game.board.variant {
+        VariantKind::Chess960 if swap => Game::from_board(session.game.start().clone()),
+        variant => Game::from_board(variant.rules().setup(&mut session.rng)),
+    };
+    if swap {
+        if let Some(clock) = &mut session.game.clock {
+            clock.controls.swap(0, 1);
+        }
+        session.players.swap(0, 1);
+        // the one person playing stays at the bottom of the board
+        if session
+            .players
+            .iter()
+            .filter(|&&p| p == Player::Human)
+            .count()
+            == 1
+        {
+            session.options.flipped = !session.options.flipped;
+        }
+    }
+    replace_game(session, game)
+}
+
+// plays on with another game, with fresh clocks of the same time control
+pub fn replace_game(session: &mut Session, mut game: Game) -> Reply {
+    if let Some(clock) = &session.game.clock {
+        game.clock = Some(Clock::new(clock.controls, game.board.turn));
+    }
+    session.start_game(game);
+    session.draw_offer = None;
+    session.takeback_request = None;
+    session.viewing = None;
+    session.sideline = None;
+    session.rated = false;
+    session.reviewed = false;
+    session.logged = false;
+    session.highlights = Highlights::default();
+    session.annotations = Annotations::default();
+    Reply::board()
+}
+
+// a lichess analysis link for the position shown, or with pgn the game
+// uploaded through lichess's import api
+fn share(session: &mut Session, args: &str) -> Reply {
+    match args {
+        "" => Reply::text(match lichess::analysis_url(session.shown_board()) {
+            Some(url) => url,
+            None => String::from("lichess has no analysis board for this variant"),
+        }),
+        "pgn" => {
+            if session.game.history.is_empty() {
+                return Reply::text("there are no moves to share, share alone links the position");
+            }
+            let text = pgn::write(&session.game, &session.pgn_tags());
+            Reply::text(match lichess::import(&text) {
+                Ok(url) => url,
+                Err(e) => e,
+            })
+        }
+        _ => Reply::text("share takes nothing or pgn. example: share pgn"),
+    }
+}
+
+fn solve(session: &mut Session, args: &str) -> Reply {
+    let usage = "solve takes mate, the number of moves and maybe a fen. example: solve mate 2";
+    let mut words = args.splitn(3, ' ');
+    let (Some("mate"), Some(n)) = (words.next(), words.next()) else {
+        return Reply::text(usage);
+    };
+    let Some(n) = n.parse::<u32>().ok().filter(|&n| n > 0) else {
+        return Reply::text(usage);
+    };
+    let shown = session.shown_board();
+    let board = match words.next().map(str::trim) {
+        Some(fen) => match ChessBoard::parse_fen(fen)
+            .map(|board| board.with_variant(shown.variant))
+            .and_then(|board| board.validate().map(|_| board))
+        {
+            Ok(board) => board,
+            Err(e) => return Reply::text(format!("invalid fen: {}", e)),
+        },
+        None => shown.clone(),
+    };
+    Reply {
+        lines: solve::mate(&board, n, &session.options),
+        flow: Flow::Continue,
+    }
+}
+
+fn chat(session: &mut Session, args: &str) -> Reply {
+    let emoji = match args {
+        "" => false,
+        "emoji" => true,
+        _ => return Reply::text("chat takes nothing or emoji. example: chat emoji"),
+    };
+    Reply {
+        lines: session.shown_board().chat_text(&session.options, emoji),
+        flow: Flow::Continue,
+    }
+}
+
+fn copy(session: &mut Session, args: &str) -> Reply {
+    let text = match args {
+        "fen" => session.shown_board().to_fen(),
+        "pgn" => pgn::write(&session.game, &session.pgn_tags()),
+        "chat" => {
+            let board = session.shown_board();
+            board.chat_text(&session.options, false).join("\n")
+        }
+        _ => return Reply::text("copy takes fen, pgn or chat. example: copy fen"),
+    };
+    match clipboard::copy(&text) {
+        Ok(()) => Reply::text(format!("copied the {} to the clipboard", args)),
+        Err(e) => {
+            let mut lines = vec![format!("{}, so here it is to copy by hand:", e)];
+            lines.extend(text.lines().map(String::from));
+            Reply {
+                lines,
+                flow: Flow::Continue,
+            }
+        }
+    }
+}
+
+// reviews the finished game with the engine, and with a file name writes it
+// there as pgn with the engine's scores, glyphs and lines
+fn analyze(session: &mut Session, args: &str) -> Reply {
+    if session.game.result.is_none() {
+        return Reply::text("the game isn't over yet, analyze is for finished games");
+    }
+    if session.game.history.is_empty() {
+        return Reply::text("there are no moves to analyze");
+    }
+    // a cache that can't be read or written only costs the time to search
+    // again, so that stays quiet
+    let mut cache = session
+        .analysis_cache
+        .as_ref()
+        .and_then(|path| AnalysisCache::load(path).ok())
+        .unwrap_or_default();
+    let report = analysis::report(
+        &mut session.game,
+        session.depth,
+        &mut session.uci,
+        &mut cache,
+        &session.options,
+    );
+    if let Some(path) = &session.analysis_cache {
+        let _ = cache.save(path);
+    }
+    let mut lines = report.lines;
+    session.track_accuracy(report.accuracy);
+    if !args.is_empty() {
+        let text = pgn::write(&session.game, &session.pgn_tags());
+        lines.push(match std::fs::write(args, text) {
+            Ok(()) => format!("wrote the annotated game to {}", args),
+            Err(e) => format!("could not write {}: {}", args, e),
+        });
+    }
+    Reply {
+        lines,
+        flow: Flow::Continue,
+    }
+}
+
+// works out the endgame tables and lists their verdict on every move under
+// the board from then on, in the positions they cover
+fn oracle(session: &mut Session, args: &str) -> Reply {
+    match args {
+        "" => match &session.oracle {
+            Some(_) => Reply {
+                lines: session.oracle_lines(),
+                flow: Flow::Continue,
+            },
+            None => Reply::text("usage: oracle on, or oracle off"),
+        },
+        "on" => {
+            if session.oracle.is_none() {
+                session.oracle = Some(Oracle::build());
+            }
+            Reply::board()
+        }
+        "off" => {
+            session.oracle = None;
+            Reply::board()
+        }
+        _ => Reply::text("usage: oracle [on|off]"),
+    }
+}
+
+// indexes the games of a folder and lists their moves under every board from
+// then on, so playing, undoing and viewing moves walks through them
+fn explore(session: &mut Session, args: &str) -> Reply {
+    match args {
+        "" => match &session.explorer {
+            Some(_) => Reply {
+                lines: session.explorer_lines(),
+                flow: Flow::Continue,
+            },
+            None => Reply::text("usage: explore <folder>, a folder of pgn files"),
+        },
+        "off" => {
+            session.explorer = None;
+            Reply::board()
+        }
+        dir => match Explorer::index(Path::new(dir), session.game.board.variant) {
+            Ok(explorer) if explorer.games == 0 => {
+                Reply::text(format!("no finished games in {}", explorer.dir.display()))
+            }
+            Ok(explorer) => {
+                let games = explorer.games;
+                session.explorer = Some(explorer);
+                let mut reply = Reply::board();
+                reply
+                    .lines
+                    .push(format!("exploring {} games from {}", games, dir));
+                reply
+            }
+            Err(e) => Reply::text(e),
+        },
+    }
+}
+
+// lists the library, or opens one of its games at the start to step through
+fn games(session: &mut Session, args: &str) -> Reply {
+    let Some(dir) = &session.library else {
+        return Reply::text("the game library is turned off");
+    };
+    let entries = library::list(dir);
+    if entries.is_empty() {
+        return Reply::text("no games in the library yet");
+    }
+    if args.is_empty() {
+        let mut lines: Vec<String> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| format!("{}. {}", i + 1, entry.describe()))
+            .collect();
+        lines.push(String::from("type games <number> to open one"));
+        return Reply {
+            lines,
+            flow: Flow::Continue,
+        };
+    }
+    let Some(entry) = args
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| entries.get(n.wrapping_sub(1)))
+    else {
+        return Reply::text(format!(
+            "invalid game number, the library has {}. example: games 1",
+            entries.len()
+        ));
+    };
+    let game = std::fs::read_to_string(&entry.path)
+        .map_err(|e| format!("could not read {}: {}", entry.path.display(), e))
+        .and_then(|text| pgn::read(&text, VariantKind::Standard));
+    match game {
+        Ok(game) => {
+            session.log_game();
+            session.start_game(game);
+            // it is in the library already and was played by others than the sides now
+            session.logged = true;
+            session.rated = true;
+            session.reviewed = true;
+            session.viewing = Some(0);
+            session.draw_offer = None;
+            session.takeback_request = None;
+            session.highlights = Highlights::default();
+            session.annotations = Annotations::default();
+            Reply {
+                lines: vec![format!(
+                    "opened {}, step through it with forward and back",
+                    entry.describe()
+                )],
+                flow: Flow::ShowBoard,
+            }
+        }
+        Err(e) => Reply::text(e),
+    }
+}
+
+fn ratings(session: &mut Session, _: &str) -> Reply {
+    let Some(path) = &session.ratings else {
+        return Reply::text("ratings are turned off");
+    };
+    match Ratings::load(path) {
+        Ok(ratings) if ratings.players.is_empty() && ratings.accuracy.is_empty() => {
+            Reply::text("no rated games yet, finish a game against the engine")
+        }
+        Ok(ratings) => Reply {
+            lines: ratings.describe(),
+            flow: Flow::Continue,
+        },
+        Err(e) => Reply::text(e),
+    }
+}
+
+fn save(session: &mut Session, args: &str) -> Reply {
+    if args.is_empty() {
+        return Reply::text("missing file name. example: save game.toml");
+    }
+    match save::save(&session.game, Path::new(args)) {
+        Ok(()) => Reply::text(format!("saved to {}", args)),
+        Err(e) => Reply::text(e),
+    }
+}
+
+fn scoresheet(session: &mut Session, args: &str) -> Reply {
+    let (form, file) = match args.split_once(' ').unwrap_or((args, "")) {
+        ("print", file) => (Form::Print, file.trim()),
+        _ => (Form::Text, args),
+    };
+    let text = scoresheet::sheet(&session.game, &session.pgn_tags(), &session.options, form);
+    if file.is_empty() {
+        return Reply {
+            lines: text.lines().map(String::from).collect(),
+            flow: Flow::Continue,
+        };
+    }
+    match std::fs::write(file, text) {
+        Ok(()) => Reply::text(format!("wrote the score sheet to {}", file)),
+        Err(e) => Reply::text(format!("can't write {}: {}", file, e)),
+    }
+}
+
+fn heatmap(session: &mut Session, args: &str) -> Reply {
+    let map = Heatmap::new(&session.game);
+    let (text, file) = match args.split_once(' ').unwrap_or((args, "")) {
+        ("svg", "") => return Reply::text("missing file name. example: heatmap svg heatmap.svg"),
+        ("svg", file) => (map.svg(), file.trim()),
+        _ if args.is_empty() => {
+            return Reply {
+                lines: map.text(),
+                flow: Flow::Continue,
+            }
+        }
+        _ => (map.text().join("\n") + "\n", args),
+    };
+    match std::fs::write(file, text) {
+        Ok(()) => Reply::text(format!("wrote the heatmap to {}", file)),
+        Err(e) => Reply::text(format!("can't write {}: {}", file, e)),
+    }
+}
+
+fn option(session: &mut Session, args: &str) -> Reply {
+    let Some(engine) = &mut session.uci else {
+        return Reply::text("there is no engine to set options of, start one with --uci");
+    };
+    if args.is_empty() {
+        let mut lines = vec![format!("options of {}:", engine.name)];
+        lines.extend(session.engine_options.describe(engine));
+        return Reply {
+            lines,
+            flow: Flow::Continue,
+        };
+    }
+    let Some((name, value)) = args.split_once(' ') else {
+        return Reply::text("missing value. example: option hash 64");
+    };
+    match session.engine_options.change(engine, name, value) {
+        Ok(()) => Reply::text(format!("set {} to {}", name, value.trim())),
+        Err(e) => Reply::text(e),
+    }
+}
+
+fn eval(session: &mut Session, args: &str) -> Reply {
+    match args {
+        "" => {
+            session.options.eval = !session.options.eval;
+            Reply::board()
+        }
+        "terms" => Reply {
+            lines: engine::breakdown(session.shown_board()),
+            flow: Flow::Continue,
+        },
+        _ => Reply::text("eval takes nothing, or terms to list what the evaluation adds up"),
+    }
+}
+
+fn load(session: &mut Session, args: &str) -> Reply {
+    if args.is_empty() {
+        return Reply::text("missing file name. example: load game.toml");
+    }
+    match save::load(Path::new(args)) {
+        Ok(game) => {
+            session.log_game();
+            // a game that was already over doesn't count again
+            session.rated = game.result.is_some();
+            session.reviewed = game.result.is_some();
+            session.logged = game.result.is_some();
+            session.start_game(game);
+            session.viewing = None;
+            session.draw_offer = None;
+            session.highlights = Highlights::default();
+            session.annotations = Annotations::default();
+            Reply::board()
+        }
+        Err(e) => Reply::text(e),
+    }
+}
+
+fn send(session: &mut Session, args: &str) -> Reply {
+    if args.is_empty() {
+        return Reply::text("missing file name. example: send move.toml");
+    }
+    if session.secret.is_empty() {
+        return Reply::text(
+            "set a secret shared with your opponent first, with --secret or in the config",
+        );
+    }
+    match correspondence::write(&session.game, Path::new(args), &session.secret) {
+        Ok(()) => Reply::text(format!("wrote {}, send it to your opponent", args)),
+        Err(e) => Reply::text(e),
+    }
+}
+
+fn receive(session: &mut Session, args: &str) -> Reply {
+    if args.is_empty() {
+        return Reply::text("missing file name. example: receive reply.toml");
+    }
+    if session.secret.is_empty() {
+        return Reply::text(
+            "set a secret shared with your opponent first, with --secret or in the config",
+        );
+    }
+    match correspondence::read_reply(&session.game, Path::new(args), &session.secret) {
+        Ok(mve) => play_move(session, mve),
+        Err(e) => Reply::text(e),
+    }
+}
+
+fn resign(session: &mut Session, _: &str) -> Reply {
+    if session.game.result.is_some() {
+        return Reply::text("the game is already over");
+    }
+    let winner = session.game.board.turn.other();
+    session.game.end(GameResult::Resignation(winner));
+    session.tell("resign");
+    Reply::board()
+}
+
+fn draw(session: &mut Session, _: &str) -> Reply {
+    if session.game.result.is_some() {
+        return Reply::text("the game is already over");
+    }
+    let turn = session.game.board.turn;
+    if let Some(claim) = session.game.draw_claim() {
+        session.game.end(GameResult::DrawClaimed(claim));
+        session.tell("claim");
+        return Reply::board();
+    }
+    if session.draw_offer == Some(turn.other()) {
+        session.game.end(GameResult::DrawAgreed);
+        session.tell("draw");
+        return Reply::board();
+    }
+    if matches!(
+        session.players[turn.other().idx()],
+        Player::Engine | Player::Uci
+    ) {
+        // the engine takes the draw when it thinks it is worse off, or in
+        // armageddon when the draw is a win
+        let board = &session.game.board;
+        let score = engine::search(board, session.depth).map_or(0, |(_, score)| score);
+        let accept = match session.game.armageddon {
+            true => turn.other() == Color::Black,
+            false => score > 0,
+        };
+        if accept {
+            session.game.end(GameResult::DrawAgreed);
+            return Reply::board();
+        }
+        return Reply::text(format!("{} declines the draw", turn.other().name()));
+    }
+    session.draw_offer = Some(turn);
+    session.tell("draw");
+    Reply::text(format!(
+        "{} offers a draw, {} can type draw to accept",
+        turn.name(),
+        turn.other().name()
+    ))
+}
+
+fn play(session: &mut Session, input: &str) -> Reply {
+    let board = match &session.sideline {
+        Some(sideline) => &sideline.board,
+        None => &session.game.board,
+    };
+    let locale = session.options.locale;
+    let san = locale.delocalize(input);
+    if let Some(mve) = board.parse_uci(input).or_else(|| board.parse_san(&san)) {
+        return play_move(session, mve);
+    }
+    let choices = board.san_candidates(&san);
+    if choices.is_empty() {
+        let square = san
+            .get(san.len().saturating_sub(2)..)
+            .and_then(BoardPos::parse);
+        if square.is_some() && board.must_capture() {
+            return Reply::text(locale.capture_compulsory());
+        }
+        return Reply::text(locale.unknown_input());
+    }
+    let listed: Vec<String> = choices
+        .iter()
+        .enumerate()
+        .map(|(i, m)| format!("{}) {}", i + 1, session.options.move_text(&board.san(m), m)))
+        .collect();
+    session.choices = choices;
+    Reply::text(format!(
+        "{} could be {}. type a number to pick",
+        input,
+        listed.join(" ")
+    ))
+}
+
+fn play_move(session: &mut Session, mve: Move) -> Reply {
+    if let Some(sideline) = &mut session.sideline {
+        if !sideline.board.is_legal(&mve) {
+            return Reply::text(session.options.locale.invalid_move());
+        }
+        sideline.board.make_move(&mve);
+        sideline.variation.moves.push(mve);
+        session.highlights = Highlights::default();
+        return Reply::board();
+    }
+    if session.viewing.is_some() {
+        return Reply::text(
+            "looking at an earlier position, type live to return to the game first, or try to play a move from here",
+        );
+    }
+    let mover = session.game.board.turn;
+    if session.game.play(&mve) {
+        // moving instead of answering a draw offer declines it
+        if session.draw_offer != Some(mover) {
+            session.draw_offer = None;
+        }
+        session.takeback_request = None;
+        session.highlights = Highlights::default();
+        session.annotations = Annotations::default();
+        Reply::board()
+    } else if session.game.board.must_capture() {
+        Reply::text(session.options.locale.capture_compulsory())
+    } else {
+        Reply::text(session.options.locale.invalid_move())
+    }
+}
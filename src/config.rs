@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::locale::Locale;
+use crate::notation::Notation;
+use crate::theme::Theme;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Renderer {
+    #[default]
+    Plain,
+    Tui,
+    // positions and moves described in words for screen readers
+    Accessible,
+    // wide squares with ascii art pieces, for big terminals
+    Large,
+    // one character per square, for small terminals
+    Compact,
+}
+
+// defaults read from the config file, command line flags win over these
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub renderer: Renderer,
+    pub unicode: bool,
+    // unset means colors are used whenever the terminal supports them
+    pub color: Option<bool>,
+    pub flip: bool,
+    pub auto_flip: bool,
+    // language of the piece letters and the messages, unset to go by LANG
+    pub locale: Option<Locale>,
+    // how moves are shown: san, uci or figurine
+    pub notation: Notation,
+    // show the engine's evaluation next to the board
+    pub eval: bool,
+    // false leaves out the coordinates of the compact board
+    pub coordinates: Option<bool>,
+    // false keeps every board on screen instead of clearing it before the next
+    pub redraw: Option<bool>,
+    // command that gets every move in words on its stdin, like espeak
+    pub announce: Option<String>,
+    // shared with a correspondence opponent to sign the exchanged files
+    pub secret: Option<String>,
+    // false stops keeping the game in progress for the next start
+    pub autosave: Option<bool>,
+    // name of the board colors to start with
+    pub theme: Option<String>,
+    // extra themes under [themes.<name>]
+    pub themes: BTreeMap<String, Theme>,
+    // how many plies the engine looks ahead
+    pub depth: Option<u8>,
+    // options for the uci engine under [engine], like hash = 64. the built-in
+    // engine has none of them
+    pub engine: BTreeMap<String, toml::Value>,
+}
+
+// $XDG_CONFIG_HOME/chess/config.toml, falling back to ~/.config/chess/config.toml
+pub fn default_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("chess").join("config.toml"))
+}
+
+// $XDG_DATA_HOME/chess, falling back to ~/.local/share/chess, for what the
+// program keeps between sessions
+pub fn data_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?)
+            .join(".local")
+            .join("share"),
+    };
+    Some(base.join("chess"))
+}
+
+impl Config {
+    // a missing file just means the defaults are used
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(format!("could not read {}: {}", path.display(), e)),
+        };
+        toml::from_str(&text).map_err(|e| format!("invalid config {}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+
+use crate::digest::{hex, unhex};
+use crate::game::Game;
+use crate::Move;
+
+// what goes back and forth in a correspondence game: the whole game so far,
+// signed with the secret both players share so a changed file is noticed
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Exchange {
+    start: String,
+    // in uci notation
+    moves: Vec<String>,
+    // hmac-sha256 of the start and the moves, in hex
+    signature: String,
+}
+
+fn key(secret: &str) -> hmac::Key {
+    hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes())
+}
+
+fn message(start: &str, moves: &[String]) -> String {
+    format!("{}\n{}", start, moves.join(" "))
+}
+
+fn sign(start: &str, moves: &[String], secret: &str) -> String {
+    hex(hmac::sign(&key(secret), message(start, moves).as_bytes()).as_ref())
+}
+
+// whether the exchange was signed with the secret. ring compares in constant
+// time, so how long a forged signature takes to refuse gives nothing away
+fn verify(exchange: &Exchange, secret: &str) -> bool {
+    let Some(signature) = unhex(&exchange.signature) else {
+        return false;
+    };
+    let message = message(&exchange.start, &exchange.moves);
+    hmac::verify(&key(secret), message.as_bytes(), &signature).is_ok()
+}
+
+fn start_fen(game: &Game) -> String {
+    game.history
+        .first()
+        .map_or(&game.board, |played| &played.before)
+        .to_fen()
+}
+
+pub fn write(game: &Game, path: &Path, secret: &str) -> Result<(), String> {
+    let start = start_fen(game);
+    let moves: Vec<String> = game.history.iter().map(|p| p.mve.to_string()).collect();
+    let exchange = Exchange {
+        signature: sign(&start, &moves, secret),
+        start,
+        moves,
+    };
+    let text = toml::to_string(&exchange).map_err(|e| e.to_string())?;
+    std::fs::write(path, text).map_err(|e| format!("could not write {}: {}", path.display(), e))
+}
+
+// the opponent's reply to the game as it stands here: the same game with one
+// move more. anything else, or a file signed with another secret, is refused
+pub fn read_reply(game: &Game, path: &Path, secret: &str) -> Result<Move, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let exchange: Exchange = toml::from_str(&text)
+        .map_err(|e| format!("{} is not a correspondence file: {}", path.display(), e))?;
+    if !verify(&exchange, secret) {
+        return Err(String::from(
+            "the signature doesn't match, the file was changed or signed with another secret",
+        ));
+    }
+    let played: Vec<String> = game.history.iter().map(|p| p.mve.to_string()).collect();
+    if exchange.start != start_fen(game) || !exchange.moves.starts_with(&played) {
+        return Err(String::from("the file doesn't continue this game"));
+    }
+    match &exchange.moves[played.len()..] {
+        [] => Err(String::from("the file has no new move")),
+        [uci] => match game.board.parse_uci(uci) {
+            Some(mve) if game.board.is_legal(&mve) => Ok(mve),
+            _ => Err(format!("the new move {} is not legal here", uci)),
+        },
+        more => Err(format!(
+            "the file is {} moves ahead of this game",
+            more.len()
+        )),
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use crate::dedupe;
+use crate::library::Entry;
+use crate::openings;
+use crate::pgn;
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Color};
+
+// what the games searched for have to match, everything left out matches
+#[derive(Debug, Clone, Args)]
+pub struct Filter {
+    /// A player of either color whose name contains this, in any case
+    #[arg(long)]
+    pub player: Option<String>,
+
+    /// The result: 1-0, 0-1 or 1/2-1/2
+    #[arg(long, value_parser = ["1-0", "0-1", "1/2-1/2"])]
+    pub result: Option<String>,
+
+    /// An ECO code or the start of one, like B or B90. Games without an ECO
+    /// tag are looked up by their moves
+    #[arg(long)]
+    pub eco: Option<String>,
+
+    /// Games played on this date or later, like 2024, 2024.03 or 2024.03.15
+    #[arg(long)]
+    pub from: Option<String>,
+
+    /// Games played on this date or earlier, written like --from
+    #[arg(long)]
+    pub to: Option<String>,
+
+    /// Games where both players are rated at least this, by the Elo tags
+    #[arg(long)]
+    pub min_rating: Option<u32>,
+
+    /// Games that reach this position, given as a FEN, by any move order
+    #[arg(long)]
+    pub position: Option<String>,
+
+    /// Leave out games with the same moves as one found before
+    #[arg(long)]
+    pub unique: bool,
+}
+
+// a game of the database that matched, with where it was found
+pub struct Found {
+    pub entry: Entry,
+    // its number in the file, from 1
+    pub number: usize,
+    pub eco: Option<String>,
+    // when searching by position, where the game reached it and what was
+    // played there
+    pub reached: Option<String>,
+    pub text: String,
+}
+
+impl Found {
+    // "games.pgn #3: B90 2024.03.15 Carlsen vs Nakamura: 1-0, 41 moves"
+    pub fn describe(&self) -> String {
+        let file = self.entry.path.file_name().unwrap_or_default();
+        let mut text = format!(
+            "{} #{}: {}{}",
+            file.to_string_lossy(),
+            self.number,
+            self.eco
+                .as_ref()
+                .map_or(String::new(), |eco| format!("{} ", eco)),
+            self.entry.describe()
+        );
+        if let Some(reached) = &self.reached {
+            text.push_str(&format!(", {}", reached));
+        }
+        text
+    }
+}
+
+// the pgn files of a directory in name order, or the file itself
+pub fn files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let files =
+        std::fs::read_dir(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let mut paths: Vec<PathBuf> = files
+        .filter_map(|file| Some(file.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pgn"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+// whether a pgn date is within the bounds. a bound may leave out the month
+// and day, and the game's date is cut to as many characters before comparing
+// with the last one. games without a year never are
+fn within(date: &str, from: Option<&str>, to: Option<&str>) -> bool {
+    if !date.starts_with(|c: char| c.is_ascii_digit()) {
+        return from.is_none() && to.is_none();
+    }
+    from.is_none_or(|from| date >= from)
+        && to.is_none_or(|to| date.get(..to.len()).unwrap_or(date) <= to)
+}
+
+// the eco code of the game by its tag, or when there is none by the last
+// known opening its moves reach
+fn eco(entry: &Entry, text: &str) -> Option<String> {
+    match entry.tag("ECO") {
+        "?" => {
+            let game = pgn::read(text, VariantKind::Standard).ok()?;
+            let name = openings::name(&game)?;
+            Some(name.split_whitespace().next()?.to_string())
+        }
+        eco => Some(eco.to_string()),
+    }
+}
+
+// where the game first reaches the position with the key, by replaying it:
+// "reached after 7. Bxf7+, then Kxf7", with "at the end" when no move followed
+fn reached(text: &str, key: u64) -> Option<String> {
+    let game = pgn::read(text, VariantKind::Standard).ok()?;
+    let boards = game
+        .history
+        .iter()
+        .map(|played| (&played.before, Some(played)));
+    let (ply, (_, next)) = boards
+        .chain([(&game.board, None)])
+        .enumerate()
+        .find(|(_, (board, _))| board.zobrist_key() == key)?;
+    let after = match ply.checked_sub(1).map(|ply| &game.history[ply]) {
+        Some(last) => {
+            let dots = match last.before.turn {
+                Color::White => ".",
+                Color::Black => "...",
+            };
+            format!("after {}{} {}", last.before.fullmove, dots, last.san)
+        }
+        None => String::from("at the start"),
+    };
+    Some(match next {
+        Some(next) => format!("reached {}, then {}", after, next.san),
+        None => format!("reached {}, at the end", after),
+    })
+}
+
+impl Filter {
+    fn rated(&self, entry: &Entry) -> bool {
+        let Some(min) = self.min_rating else {
+            return true;
+        };
+        ["WhiteElo", "BlackElo"]
+            .iter()
+            .all(|tag| entry.tag(tag).parse::<u32>().is_ok_and(|elo| elo >= min))
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        let player = |wanted: &String| {
+            let wanted = wanted.to_lowercase();
+            ["White", "Black"]
+                .iter()
+                .any(|side| entry.tag(side).to_lowercase().contains(&wanted))
+        };
+        self.player.as_ref().is_none_or(player)
+            && self
+                .result
+                .as_ref()
+                .is_none_or(|result| entry.tag("Result") == result)
+            && within(entry.tag("Date"), self.from.as_deref(), self.to.as_deref())
+            && self.rated(entry)
+    }
+}
+
+// the games of the pgn files under path that match the filter, in the order
+// of the files and the games in them. files that can't be read are left out
+pub fn search(path: &Path, filter: &Filter) -> Result<Vec<Found>, String> {
+    let key = match &filter.position {
+        Some(fen) => Some(
+            ChessBoard::parse_fen(fen)
+                .map_err(|e| format!("invalid position: {}", e))?
+                .zobrist_key(),
+        ),
+        None => None,
+    };
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    for file in files(path)? {
+        let Ok(text) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        for (i, text) in pgn::games(&text).into_iter().enumerate() {
+            let entry = Entry {
+                path: file.clone(),
+                tags: pgn::tags(&text),
+                moves: pgn::movetext(&text).len().div_ceil(2),
+            };
+            if !filter.matches(&entry) {
+                continue;
+            }
+            // looking the opening or the position up means replaying the
+            // game, so that is only done when searching by them
+            let eco = match &filter.eco {
+                Some(wanted) => {
+                    let eco = eco(&entry, &text);
+                    if !eco
+                        .as_ref()
+                        .is_some_and(|eco| eco.to_uppercase().starts_with(&wanted.to_uppercase()))
+                    {
+                        continue;
+                    }
+                    eco
+                }
+                None => Some(entry.tag("ECO").to_string()).filter(|eco| eco != "?"),
+            };
+            let reached = match key {
+                Some(key) => match reached(&text, key) {
+                    Some(reached) => Some(reached),
+                    None => continue,
+                },
+                None => None,
+            };
+            if filter.unique && !seen.insert(dedupe::fingerprint(&text)) {
+                continue;
+            }
+            found.push(Found {
+                entry,
+                number: i + 1,
+                eco,
+                reached,
+                text,
+            });
+        }
+    }
+    Ok(found)
+}
+
+// the search subcommand: lists the games found, or writes them to output
+pub fn run(path: &Path, filter: &Filter, output: Option<&Path>) -> Result<(), String> {
+    let found = search(path, filter)?;
+    let count = format!(
+        "{} {}",
+        found.len(),
+        if found.len() == 1 { "game" } else { "games" }
+    );
+    match output {
+        Some(output) => {
+            export(&found, output)?;
+            println!("wrote {} to {}", count, output.display());
+        }
+        None => {
+            for found in &found {
+                println!("{}", found.describe());
+            }
+            println!("found {}", count);
+        }
+    }
+    Ok(())
+}
+
+// the games found one after another as a single pgn file, as they were written
+pub fn export(found: &[Found], output: &Path) -> Result<(), String> {
+    let text: Vec<&str> = found.iter().map(|f| f.text.trim_end()).collect();
+    std::fs::write(output, text.join("\n\n") + "\n")
+        .map_err(|e| format!("could not write {}: {}", output.display(), e))
+}
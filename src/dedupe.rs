@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::database;
+use crate::pgn;
+
+// what two copies of a game share whatever their tags, comments, variations
+// and annotations: where they start and the moves of the main line, check
+// marks and glyphs written after them left out
+pub fn fingerprint(text: &str) -> String {
+    let tags = pgn::tags(text);
+    let tag = |wanted: &str| {
+        tags.iter()
+            .find(|(name, _)| name == wanted)
+            .map_or("", |(_, value)| value.as_str())
+    };
+    let moves: Vec<String> = pgn::movetext(text)
+        .iter()
+        .map(|san| san.trim_end_matches(['+', '#', '!', '?']).to_string())
+        .collect();
+    format!("{}|{}|{}", tag("Variant"), tag("FEN"), moves.join(" "))
+}
+
+// the games as groups of copies of the same one, by their index and in the
+// order they first come. games without copies are groups of one
+pub fn groups(games: &[&str]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (i, text) in games.iter().enumerate() {
+        let group = *seen.entry(fingerprint(text)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(i);
+    }
+    groups
+}
+
+// the copies of a game as one: the first copy with the tags it leaves out or
+// leaves unknown filled in from the others, in the order they come
+pub fn merge(copies: &[&str]) -> String {
+    let mut tags = pgn::tags(copies[0]);
+    for copy in &copies[1..] {
+        for (name, value) in pgn::tags(copy) {
+            if value.is_empty() || value.contains('?') {
+                continue;
+            }
+            match tags.iter_mut().find(|(tag, _)| *tag == name) {
+                Some((_, known)) if known.is_empty() || known.contains('?') => *known = value,
+                Some(_) => {}
+                None => tags.push((name, value)),
+            }
+        }
+    }
+    let mut text: String = tags
+        .iter()
+        .map(|(name, value)| format!("[{} \"{}\"]\n", name, value))
+        .collect();
+    text.push('\n');
+    let moves = copies[0]
+        .lines()
+        .skip_while(|line| line.starts_with('[') || line.trim().is_empty());
+    for line in moves {
+        text.push_str(line);
+        text.push('\n');
+    }
+    text
+}
+
+// looks for copies among the games of a pgn file or of the pgn files in a
+// directory and lists them, or writes the games to output with only the first
+// copy of each, or all copies merged into it. what was found or written
+pub fn run(path: &Path, output: Option<&Path>, merged: bool) -> Result<Vec<String>, String> {
+    // every game with the file it comes from and its number there
+    let mut games: Vec<(String, usize, String)> = Vec::new();
+    for file in database::files(path)? {
+        let Ok(text) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        for (i, text) in pgn::games(&text).into_iter().enumerate() {
+            games.push((name.to_string(), i + 1, text));
+        }
+    }
+    let texts: Vec<&str> = games.iter().map(|(_, _, text)| text.as_str()).collect();
+    let groups = groups(&texts);
+    let copies = games.len() - groups.len();
+    let Some(output) = output else {
+        let mut lines = Vec::new();
+        for group in groups.iter().filter(|group| group.len() > 1) {
+            let (file, number, _) = &games[group[0]];
+            for &i in &group[1..] {
+                let (copy, copy_number, _) = &games[i];
+                lines.push(format!(
+                    "{} #{} is a copy of {} #{}",
+                    copy, copy_number, file, number
+                ));
+            }
+        }
+        lines.push(format!(
+            "found {} {} among {} games",
+            copies,
+            if copies == 1 { "copy" } else { "copies" },
+            games.len()
+        ));
+        return Ok(lines);
+    };
+    let kept: Vec<String> = groups
+        .iter()
+        .map(|group| match merged {
+            true => merge(&group.iter().map(|&i| texts[i]).collect::<Vec<_>>()),
+            false => texts[group[0]].to_string(),
+        })
+        .collect();
+    let text: Vec<&str> = kept.iter().map(|text| text.trim_end()).collect();
+    std::fs::write(output, text.join("\n\n") + "\n")
+        .map_err(|e| format!("could not write {}: {}", output.display(), e))?;
+    Ok(vec![format!(
+        "wrote {} {} to {}, {} {} {}",
+        kept.len(),
+        if kept.len() == 1 { "game" } else { "games" },
+        output.display(),
+        if merged { "merging" } else { "dropping" },
+        copies,
+        if copies == 1 { "copy" } else { "copies" }
+    )])
+}
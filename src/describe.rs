@@ -0,0 +1,103 @@
+use crate::castling;
+use crate::{ChessBoard, Color, Move, PieceType};
+
+pub fn piece_name(piece: PieceType) -> &'static str {
+    match piece {
+        PieceType::Pawn => "pawn",
+        PieceType::Bishop => "bishop",
+        PieceType::Knight => "knight",
+        PieceType::Rook => "rook",
+        PieceType::Queen => "queen",
+        PieceType::King => "king",
+        PieceType::Archbishop => "archbishop",
+        PieceType::Chancellor => "chancellor",
+    }
+}
+
+fn plural(piece: PieceType) -> String {
+    format!("{}s", piece_name(piece))
+}
+
+// "a", "a and b", "a, b and c"
+fn list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+impl ChessBoard {
+    // the position in words, one sentence per side, for screen readers
+    pub fn describe_position(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for color in [Color::White, Color::Black] {
+            let mut kinds = PieceType::ALL;
+            // king first, then the most valuable pieces
+            kinds.sort_by_key(|&p| {
+                if p == PieceType::King {
+                    i32::MIN
+                } else {
+                    -p.value()
+                }
+            });
+            let groups: Vec<String> = kinds
+                .iter()
+                .filter_map(|&kind| {
+                    let mut squares: Vec<String> = self
+                        .pieces(color)
+                        .filter(|p| p.piece == kind)
+                        .map(|p| p.pos.to_string())
+                        .collect();
+                    squares.sort();
+                    match squares.len() {
+                        0 => None,
+                        1 => Some(format!("{} {}", piece_name(kind), squares[0])),
+                        _ => Some(format!("{} {}", plural(kind), list(&squares))),
+                    }
+                })
+                .collect();
+            lines.push(format!("{}: {}.", color.name(), groups.join(", ")));
+        }
+        lines
+    }
+
+    // a legal move in words, like "White knight g1 to f3, check"
+    pub fn describe_move(&self, mve: &Move) -> String {
+        let piece = match self.piece_at(mve.from) {
+            Some(p) => p,
+            None => return mve.to_string(),
+        };
+        let mut text = match (self.castling_side(mve), self.piece_at(mve.to)) {
+            (Some(castling::SHORT), _) => format!("{} castles short", piece.color.name()),
+            (Some(_), _) => format!("{} castles long", piece.color.name()),
+            (None, Some(taken)) => format!(
+                "{} {} {} takes {} {} {}",
+                piece.color.name(),
+                piece_name(piece.piece),
+                mve.from,
+                taken.color.name().to_lowercase(),
+                piece_name(taken.piece),
+                mve.to
+            ),
+            (None, None) => format!(
+                "{} {} {} to {}",
+                piece.color.name(),
+                piece_name(piece.piece),
+                mve.from,
+                mve.to
+            ),
+        };
+
+        let mut next = self.clone();
+        next.make_move(mve);
+        if next.in_check(next.turn) {
+            if next.legal_moves().is_empty() {
+                text.push_str(", checkmate");
+            } else {
+                text.push_str(", check");
+            }
+        }
+        text
+    }
+}
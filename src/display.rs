@@ -0,0 +1,593 @@
+use std::io::IsTerminal;
+
+use crate::engine;
+use crate::locale::Locale;
+use crate::notation::{self, Notation};
+use crate::theme::Theme;
+use crate::{BoardPos, ChessBoard, Color, Move, Piece, PieceType, RANKS};
+
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayOptions {
+    // draw pieces as chess symbols instead of letters
+    pub unicode: bool,
+    // draw the squares and pieces with ansi colors instead of the bracket grid
+    pub color: bool,
+    // show the board from black's side, rank 1 at the top
+    pub flipped: bool,
+    // show the board from the side to move, overrides flipped
+    pub auto_flip: bool,
+    pub style: BoardStyle,
+    // language of the piece letters in moves
+    pub locale: Locale,
+    pub notation: Notation,
+    // show the engine's evaluation of the position next to the board
+    pub eval: bool,
+    // draw the rank numbers and file letters of the compact board
+    pub coordinates: bool,
+    // clear the terminal before drawing the board instead of scrolling
+    pub redraw: bool,
+    pub theme: Theme,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoardStyle {
+    // one character per square in brackets or on colored squares
+    Grid,
+    // wide squares with multi-line ascii art pieces
+    Large,
+    // the position described in words instead of drawn
+    Accessible,
+    // one character per square without brackets, for small terminals
+    Compact,
+}
+
+impl DisplayOptions {
+    pub fn is_flipped(&self, turn: Color) -> bool {
+        if self.auto_flip {
+            turn == Color::Black
+        } else {
+            self.flipped
+        }
+    }
+
+    // the len rows or columns in the order they are drawn, top to bottom or
+    // left to right
+    pub fn draw_order(&self, turn: Color, len: u8) -> Vec<u8> {
+        if self.is_flipped(turn) {
+            (0..len).rev().collect()
+        } else {
+            (0..len).collect()
+        }
+    }
+
+    // the grid doesn't fit a terminal this small next to the prompt and status
+    pub fn terminal_is_small() -> bool {
+        if !std::io::stdout().is_terminal() {
+            return false;
+        }
+        match ratatui::crossterm::terminal::size() {
+            Ok((cols, rows)) => cols < 32 || rows < 16,
+            Err(_) => false,
+        }
+    }
+
+    // a move for display in the chosen notation, san is its english san
+    pub fn move_text(&self, san: &str, mve: &Move) -> String {
+        match self.notation {
+            Notation::San => self.locale.localize(san),
+            Notation::Uci => mve.to_string(),
+            Notation::Figurine => notation::figurine(san),
+        }
+    }
+
+    // a line of moves played one after the other from board
+    pub fn line_text(&self, board: &ChessBoard, line: &[Move]) -> String {
+        let sans = board.san_line(line);
+        let moves: Vec<String> = sans
+            .iter()
+            .zip(line)
+            .map(|(san, mve)| self.move_text(san, mve))
+            .collect();
+        moves.join(" ")
+    }
+
+    // colors are only used when stdout is a terminal and NO_COLOR isn't set
+    pub fn color_supported() -> bool {
+        std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+    }
+}
+
+// squares to draw attention to for a single render
+#[derive(Debug, Clone, Default)]
+pub struct Highlights {
+    pub selected: Option<BoardPos>,
+    pub targets: Vec<BoardPos>,
+    // squares the opponent attacks
+    pub threats: Vec<BoardPos>,
+    // own pieces the opponent can win
+    pub hanging: Vec<BoardPos>,
+}
+
+// colors for the squares and arrows drawn during analysis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Brush {
+    Green,
+    Red,
+    Blue,
+    Yellow,
+}
+
+impl Brush {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "" | "green" => Some(Brush::Green),
+            "red" => Some(Brush::Red),
+            "blue" => Some(Brush::Blue),
+            "yellow" => Some(Brush::Yellow),
+            _ => None,
+        }
+    }
+}
+
+// square marks and arrows drawn by the player, kept until the next move
+#[derive(Debug, Clone, Default)]
+pub struct Annotations {
+    pub marks: Vec<(BoardPos, Brush)>,
+    pub arrows: Vec<(Move, Brush)>,
+}
+
+impl Annotations {
+    // drawing the same mark or arrow again removes it
+    pub fn toggle_mark(&mut self, pos: BoardPos, brush: Brush) {
+        match self.marks.iter().position(|&(p, _)| p == pos) {
+            Some(i) if self.marks[i].1 == brush => {
+                self.marks.remove(i);
+            }
+            Some(i) => self.marks[i].1 = brush,
+            None => self.marks.push((pos, brush)),
+        }
+    }
+
+    pub fn toggle_arrow(&mut self, mve: Move, brush: Brush) {
+        match self.arrows.iter().position(|&(m, _)| m == mve) {
+            Some(i) if self.arrows[i].1 == brush => {
+                self.arrows.remove(i);
+            }
+            Some(i) => self.arrows[i].1 = brush,
+            None => self.arrows.push((mve, brush)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mark {
+    Selected,
+    Target,
+    Hanging,
+    Threat,
+    LastMove,
+}
+
+impl Highlights {
+    // the piece on from and every square it can legally move to
+    pub fn legal_targets(board: &ChessBoard, from: BoardPos) -> Self {
+        Highlights {
+            selected: Some(from),
+            targets: board
+                .legal_moves()
+                .iter()
+                .filter(|m| m.from == from)
+                .map(|m| m.to)
+                .collect(),
+            ..Highlights::default()
+        }
+    }
+
+    // every square the side not to move attacks apart from its own pieces, and
+    // the pieces of the side to move that are attacked and either undefended or
+    // attacked by something cheaper
+    pub fn threats(board: &ChessBoard) -> Self {
+        let (own, enemy) = (board.turn, board.turn.other());
+        let mut highlights = Highlights::default();
+        for pos in board.squares() {
+            if board.color_at(pos) == Some(enemy) {
+                continue;
+            }
+            let cheapest = board.attackers(pos, enemy).map(|p| p.piece.value()).min();
+            let Some(cheapest) = cheapest else {
+                continue;
+            };
+            highlights.threats.push(pos);
+            if let Some(piece) = board.piece_at(pos).filter(|p| p.color == own) {
+                if piece.piece != PieceType::King
+                    && (!board.is_attacked(pos, own) || cheapest < piece.piece.value())
+                {
+                    highlights.hanging.push(pos);
+                }
+            }
+        }
+        highlights
+    }
+
+    // the previous move is marked on the board unless something more important covers it
+    pub fn mark(&self, pos: BoardPos, board: &ChessBoard) -> Option<Mark> {
+        if self.selected == Some(pos) {
+            Some(Mark::Selected)
+        } else if self.targets.contains(&pos) {
+            Some(Mark::Target)
+        } else if self.hanging.contains(&pos) {
+            Some(Mark::Hanging)
+        } else if self.threats.contains(&pos) {
+            Some(Mark::Threat)
+        } else if board
+            .last_move
+            .is_some_and(|m| m.from == pos || m.to == pos)
+        {
+            Some(Mark::LastMove)
+        } else {
+            None
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+pub fn row_to_display(row: u8) -> u8 {
+    RANKS - row
+}
+
+pub fn piece_char(piece: Option<Piece>, options: &DisplayOptions) -> char {
+    match piece {
+        Some(p) if options.unicode => p.to_unicode(),
+        Some(p) => p.to_char(),
+        None => ' ',
+    }
+}
+
+// wipes the terminal so the board is drawn at the top instead of below the last one
+pub fn clear_screen(options: &DisplayOptions) {
+    if options.redraw {
+        print!("\x1b[2J\x1b[H");
+    }
+}
+
+// how many of width cells belong to white on the evaluation bar, half each
+// when level and everything once white is about ten pawns or a mate ahead
+pub fn eval_share(white_score: i32, width: usize) -> usize {
+    let score = white_score.clamp(-1000, 1000) as f64;
+    ((0.5 + score / 2000.0) * width as f64).round() as usize
+}
+
+// white's evaluation as a bar, "+0.35 [########........]"
+pub fn eval_line(white_score: i32, depth: u8) -> String {
+    let width = 20;
+    let white = eval_share(white_score, width);
+    format!(
+        "eval {:>6} [{}{}] depth {}",
+        engine::format_score(white_score),
+        "#".repeat(white),
+        ".".repeat(width - white),
+        depth
+    )
+}
+
+// ansi background and foreground of a square in the theme's colors
+fn square_colors(
+    pos: BoardPos,
+    piece: Option<Piece>,
+    mark: Option<Mark>,
+    options: &DisplayOptions,
+) -> (String, String) {
+    // a8 is light, so a square is light when row and col have the same parity
+    let light = (pos.row + pos.col).is_multiple_of(2);
+    let background = format!("\x1b[48;5;{}m", options.theme.square(mark, light));
+    let foreground = match piece {
+        Some(p) => format!("\x1b[1;38;5;{}m", options.theme.piece(p.color)),
+        None => String::new(),
+    };
+    (background, foreground)
+}
+
+// a single character per square. without colors empty squares are dots,
+// empty targets stars and attacked empty squares x, other marks don't fit
+fn compact_square(
+    pos: BoardPos,
+    piece: Option<Piece>,
+    mark: Option<Mark>,
+    options: &DisplayOptions,
+) -> String {
+    let ch = piece_char(piece, options);
+    if options.color {
+        let (background, foreground) = square_colors(pos, piece, mark, options);
+        return format!("{}{}{}{}", background, foreground, ch, RESET);
+    }
+    match (mark, piece) {
+        (Some(Mark::Target), None) => String::from("*"),
+        (Some(Mark::Threat), None) => String::from("x"),
+        (_, None) => String::from("."),
+        (_, Some(_)) => ch.to_string(),
+    }
+}
+
+fn square(
+    pos: BoardPos,
+    piece: Option<Piece>,
+    mark: Option<Mark>,
+    options: &DisplayOptions,
+) -> String {
+    let ch = piece_char(piece, options);
+    if !options.color {
+        // without colors the selected piece gets parentheses, empty targets a
+        // star, capturable pieces angle brackets and the last move braces.
+        // attacked squares get an x or colons, hanging pieces exclamation marks
+        return match (mark, piece) {
+            (Some(Mark::Selected), _) => format!("({})", ch),
+            (Some(Mark::LastMove), _) => format!("{{{}}}", ch),
+            (Some(Mark::Target), None) => String::from("[*]"),
+            (Some(Mark::Target), Some(_)) => format!("<{}>", ch),
+            (Some(Mark::Threat), None) => String::from("[x]"),
+            (Some(Mark::Threat), Some(_)) => format!(":{}:", ch),
+            (Some(Mark::Hanging), _) => format!("!{}!", ch),
+            (None, _) => format!("[{}]", ch),
+        };
+    }
+    let (background, foreground) = square_colors(pos, piece, mark, options);
+    format!("{}{} {} {}", background, foreground, ch, RESET)
+}
+
+impl ChessBoard {
+    // how much more material color has on the board than the other side,
+    // which unlike what was taken counts promoted pawns as their new piece
+    pub fn material_lead(&self, color: Color) -> i32 {
+        self.material(color) - self.material(color.other())
+    }
+
+    // value of the pieces color still has on the board, in pawns
+    pub fn material(&self, color: Color) -> i32 {
+        self.pieces(color).map(|p| p.piece.value()).sum()
+    }
+
+    // "material: White 39, Black 36, White +3"
+    pub fn material_line(&self) -> String {
+        let (white, black) = (self.material(Color::White), self.material(Color::Black));
+        let mut line = format!("material: White {}, Black {}", white, black);
+        if white != black {
+            let leader = if white > black {
+                Color::White
+            } else {
+                Color::Black
+            };
+            line.push_str(&format!(", {} +{}", leader.name(), (white - black).abs()));
+        }
+        line
+    }
+
+    // the pieces color has taken, most valuable first, followed by its material lead
+    pub fn capture_tray(&self, color: Color, options: &DisplayOptions) -> String {
+        let mut kinds = PieceType::ALL;
+        kinds.sort_by_key(|p| -p.value());
+        let mut tray: String = kinds
+            .iter()
+            .flat_map(|&piece| {
+                let taken = Piece {
+                    color: color.other(),
+                    piece,
+                    pos: BoardPos { row: 0, col: 0 },
+                };
+                let count = self.captured[color.idx()][piece.idx()] as usize;
+                std::iter::repeat_n(piece_char(Some(taken), options), count)
+            })
+            .collect();
+        let lead = self.material_lead(color);
+        if lead > 0 {
+            tray.push_str(&format!(" +{}", lead));
+        }
+        tray
+    }
+
+    pub fn print(&self, options: &DisplayOptions, highlights: &Highlights) {
+        println!("{}", self.material_line());
+        match options.style {
+            BoardStyle::Grid => self.print_grid(options, highlights),
+            BoardStyle::Large => self.print_large(options, highlights),
+            BoardStyle::Compact => self.print_compact(options, highlights),
+            BoardStyle::Accessible => {
+                for line in self.describe_position() {
+                    println!("{}", line);
+                }
+            }
+        }
+    }
+
+    // the board as text to paste into a chat like discord, slack or matrix,
+    // turned like the terminal's. as a code block it keeps its columns in any
+    // font; with emoji the squares are colored, which chat clients draw at
+    // one width, and there are no coordinates to keep in line
+    pub fn chat_text(&self, options: &DisplayOptions, emoji: bool) -> Vec<String> {
+        let order = options.draw_order(self.turn, RANKS);
+        let columns = options.draw_order(self.turn, self.width);
+        let mut lines = Vec::new();
+        if !emoji {
+            lines.push(String::from("```"));
+        }
+        for &row in &order {
+            let mut line = String::new();
+            if !emoji {
+                line.push_str(&format!("{} ", row_to_display(row)));
+            }
+            for &col in &columns {
+                let pos = BoardPos { row, col };
+                match (self.piece_at(pos), emoji) {
+                    (Some(piece), true) => line.push(piece.to_unicode()),
+                    (Some(piece), false) => line.push(piece_char(Some(piece), options)),
+                    (None, true) if (row + col) % 2 == 0 => line.push('⬜'),
+                    (None, true) => line.push('🟫'),
+                    (None, false) => line.push('·'),
+                }
+                if !emoji {
+                    line.push(' ');
+                }
+            }
+            lines.push(line.trim_end().to_string());
+        }
+        if !emoji {
+            let files: Vec<String> = columns
+                .iter()
+                .map(|col| ((b'a' + col) as char).to_string())
+                .collect();
+            lines.push(format!("  {}", files.join(" ")));
+            lines.push(String::from("```"));
+        }
+        lines.push(format!("{} to move", self.turn.name()));
+        lines
+    }
+
+    fn print_grid(&self, options: &DisplayOptions, highlights: &Highlights) {
+        let order = options.draw_order(self.turn, RANKS);
+        let columns = options.draw_order(self.turn, self.width);
+        let files: String = columns
+            .iter()
+            .map(|col| format!("  {}", (b'a' + col) as char))
+            .collect();
+
+        println!(" {}", files);
+        // each side's captures are shown next to its own back rank
+        let top = if options.is_flipped(self.turn) {
+            Color::White
+        } else {
+            Color::Black
+        };
+        for (i, &row) in order.iter().enumerate() {
+            print!("{} ", row_to_display(row));
+            for &col in &columns {
+                let pos = BoardPos { row, col };
+                print!(
+                    "{}",
+                    square(pos, self.piece_at(pos), highlights.mark(pos, self), options)
+                );
+            }
+            let tray = match i {
+                0 => self.capture_tray(top, options),
+                i if i == order.len() - 1 => self.capture_tray(top.other(), options),
+                _ => String::new(),
+            };
+            if tray.is_empty() {
+                println!(" {}", row_to_display(row));
+            } else {
+                println!(" {}  {}", row_to_display(row), tray);
+            }
+        }
+        println!(" {}", files);
+    }
+
+    fn print_compact(&self, options: &DisplayOptions, highlights: &Highlights) {
+        let order = options.draw_order(self.turn, RANKS);
+        let columns = options.draw_order(self.turn, self.width);
+        let files: String = columns.iter().map(|col| (b'a' + col) as char).collect();
+        if options.coordinates {
+            println!("  {}", files);
+        }
+        let top = if options.is_flipped(self.turn) {
+            Color::White
+        } else {
+            Color::Black
+        };
+        for (i, &row) in order.iter().enumerate() {
+            let mut line = String::new();
+            if options.coordinates {
+                line.push_str(&format!("{} ", row_to_display(row)));
+            }
+            for &col in &columns {
+                let pos = BoardPos { row, col };
+                line.push_str(&compact_square(
+                    pos,
+                    self.piece_at(pos),
+                    highlights.mark(pos, self),
+                    options,
+                ));
+            }
+            let tray = match i {
+                0 => self.capture_tray(top, options),
+                i if i == order.len() - 1 => self.capture_tray(top.other(), options),
+                _ => String::new(),
+            };
+            if !tray.is_empty() {
+                line.push_str(&format!(" {}", tray));
+            }
+            println!("{}", line);
+        }
+    }
+
+    fn print_large(&self, options: &DisplayOptions, highlights: &Highlights) {
+        let order = options.draw_order(self.turn, RANKS);
+        let columns = options.draw_order(self.turn, self.width);
+        let files: String = columns
+            .iter()
+            .map(|col| format!("    {}   ", (b'a' + col) as char))
+            .collect();
+        let separator = format!("  {}+", "+-------".repeat(self.width.into()));
+
+        println!("  {}", files.trim_end());
+        if !options.color {
+            println!("{}", separator);
+        }
+        for &row in &order {
+            for line in 0..3 {
+                // the rank number sits on the middle line of the rank
+                let label = if line == 1 {
+                    row_to_display(row).to_string()
+                } else {
+                    String::from(" ")
+                };
+                print!("{} ", label);
+                for &col in &columns {
+                    let pos = BoardPos { row, col };
+                    let piece = self.piece_at(pos);
+                    let art = match piece {
+                        Some(p) => large_art(p)[line],
+                        None => "     ",
+                    };
+                    let light = (row + col).is_multiple_of(2);
+                    if options.color {
+                        let (background, foreground) =
+                            square_colors(pos, piece, highlights.mark(pos, self), options);
+                        print!("{}{} {} {}", background, foreground, art, RESET);
+                    } else if light {
+                        print!("| {} ", art);
+                    } else {
+                        // dark squares are dotted when there are no colors
+                        print!("|.{}.", art.replace(' ', "."));
+                    }
+                }
+                let edge = if options.color { "" } else { "|" };
+                println!("{}", format!("{} {}", edge, label).trim_end());
+            }
+            if !options.color {
+                println!("{}", separator);
+            }
+        }
+        println!("  {}", files.trim_end());
+    }
+}
+
+// 5x3 drawings of the pieces, black pieces are filled in with #
+fn large_art(piece: Piece) -> [&'static str; 3] {
+    match (piece.color, piece.piece) {
+        (Color::White, PieceType::Pawn) => ["     ", "  O  ", " /_\\ "],
+        (Color::White, PieceType::Knight) => [" _/| ", " ) | ", " /_\\ "],
+        (Color::White, PieceType::Bishop) => ["  o  ", " ( ) ", " /_\\ "],
+        (Color::White, PieceType::Rook) => [" UUU ", " | | ", " /_\\ "],
+        (Color::White, PieceType::Queen) => [" \\|/ ", " ) ( ", " /_\\ "],
+        (Color::White, PieceType::King) => [" _+_ ", " ) ( ", " /_\\ "],
+        (Color::White, PieceType::Archbishop) => [" _o| ", " ) | ", " /_\\ "],
+        (Color::White, PieceType::Chancellor) => [" UU| ", " ) | ", " /_\\ "],
+        (Color::Black, PieceType::Pawn) => ["     ", "  @  ", " /#\\ "],
+        (Color::Black, PieceType::Knight) => [" _/| ", " )#| ", " /#\\ "],
+        (Color::Black, PieceType::Bishop) => ["  o  ", " (#) ", " /#\\ "],
+        (Color::Black, PieceType::Rook) => [" UUU ", " |#| ", " /#\\ "],
+        (Color::Black, PieceType::Queen) => [" \\|/ ", " )#( ", " /#\\ "],
+        (Color::Black, PieceType::King) => [" _+_ ", " )#( ", " /#\\ "],
+        (Color::Black, PieceType::Archbishop) => [" _o| ", " )#| ", " /#\\ "],
+        (Color::Black, PieceType::Chancellor) => [" UU| ", " )#| ", " /#\\ "],
+    }
+}
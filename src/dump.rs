@@ -0,0 +1,125 @@
+use crate::castling::{LONG, SHORT};
+use crate::game::Game;
+use crate::{BoardPos, ChessBoard, Color, PieceType, RANKS, SQUARES};
+
+// a bitboard as hex, one bit per square by BoardPos::to_idx
+fn bits(mask: u128) -> String {
+    format!("{:#026x}", mask)
+}
+
+// where the square lists, the piece types and the color bitboards don't agree,
+// each of them should say the same about every square
+fn mismatches(board: &ChessBoard) -> Vec<String> {
+    let mut found = Vec::new();
+    for idx in 0..SQUARES {
+        let Some(pos) = BoardPos::from_idx(idx) else {
+            continue;
+        };
+        let bit = 1u128 << idx;
+        let colors: Vec<Color> = [Color::White, Color::Black]
+            .into_iter()
+            .filter(|c| board.colors[c.idx()] & bit != 0)
+            .collect();
+        let listed: Vec<Color> = [Color::White, Color::Black]
+            .into_iter()
+            .filter(|c| board.lists[c.idx()].iter().any(|i| i == idx))
+            .collect();
+        let kind = board.kinds[idx];
+        let off = pos.col >= board.width || pos.row >= RANKS;
+        if colors.len() > 1
+            || colors != listed
+            || kind.is_some() != (colors.len() == 1)
+            || off && kind.is_some()
+        {
+            found.push(format!(
+                "{}: kind {:?}, bitboards {:?}, lists {:?}",
+                pos, kind, colors, listed
+            ));
+        }
+    }
+    found
+}
+
+// the internal state of a position and of the game around it, to see where a
+// rule goes wrong
+pub fn dump(game: &Game, board: &ChessBoard) -> Vec<String> {
+    let mut lines = vec![
+        format!("fen: {}", board.to_fen()),
+        format!("variant: {:?}, {} files", board.variant, board.width),
+        format!("zobrist: {:#018x}", board.zobrist_key()),
+        format!(
+            "turn: {}, move {}, {} moves made this turn",
+            board.turn.name(),
+            board.fullmove,
+            board.turn_moves
+        ),
+        format!(
+            "last move: {}",
+            board
+                .last_move
+                .map_or(String::from("none"), |m| m.to_string())
+        ),
+    ];
+    for color in [Color::White, Color::Black] {
+        let rook = |side: usize| {
+            board.castling[color.idx()][side]
+                .map_or(String::from("-"), |col| ((b'a' + col) as char).to_string())
+        };
+        lines.push(format!(
+            "castling {}: short rook {}, long rook {}",
+            color.name(),
+            rook(SHORT),
+            rook(LONG)
+        ));
+    }
+    lines.push(format!(
+        "en passant: {}",
+        board
+            .en_passant
+            .map_or(String::from("none"), |pos| pos.to_string())
+    ));
+    for color in [Color::White, Color::Black] {
+        let captured: Vec<String> = PieceType::ALL
+            .iter()
+            .filter(|p| board.captured[color.idx()][p.idx()] > 0)
+            .map(|p| format!("{:?} {}", p, board.captured[color.idx()][p.idx()]))
+            .collect();
+        lines.push(format!(
+            "bitboard {}: {}, {} pieces listed, took {}",
+            color.name(),
+            bits(board.colors[color.idx()]),
+            board.lists[color.idx()].iter().count(),
+            match captured.is_empty() {
+                true => String::from("nothing"),
+                false => captured.join(", "),
+            }
+        ));
+    }
+    for piece in PieceType::ALL {
+        let mask = (0..SQUARES)
+            .filter(|&idx| board.kinds[idx] == Some(piece))
+            .fold(0u128, |mask, idx| mask | 1 << idx);
+        if mask != 0 {
+            lines.push(format!("bitboard {:?}: {}", piece, bits(mask)));
+        }
+    }
+    let found = mismatches(board);
+    match found.is_empty() {
+        true => lines.push(String::from("square lists and bitboards agree")),
+        false => {
+            lines.push(String::from("square lists and bitboards disagree:"));
+            lines.extend(found.into_iter().map(|line| format!("  {}", line)));
+        }
+    }
+
+    lines.push(format!(
+        "game: halfmove clock {} of 100, position seen {} of 3 times, {} plies played",
+        game.halfmove_clock(),
+        game.repetitions(),
+        game.history.len()
+    ));
+    if let Some(claim) = game.draw_claim() {
+        lines.push(format!("draw claimable by {}", claim.describe()));
+    }
+    lines
+}
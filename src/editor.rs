@@ -0,0 +1,321 @@
+use crate::castling::{self, LONG, SHORT};
+use crate::cli::Player;
+use crate::commands::{self, Reply, Session};
+use crate::game::Game;
+use crate::{BoardPos, ChessBoard, Color, Piece, PieceType, MAX_PIECES};
+
+struct Spec {
+    name: &'static str,
+    usage: &'static str,
+    help: &'static str,
+    run: fn(&mut Session, &str) -> Reply,
+}
+
+const COMMANDS: &[Spec] = &[
+    Spec {
+        name: "help",
+        usage: "help",
+        help: "list the editor commands",
+        run: help,
+    },
+    Spec {
+        name: "remove",
+        usage: "remove <square>...",
+        help: "take the pieces off the squares",
+        run: remove,
+    },
+    Spec {
+        name: "clear",
+        usage: "clear",
+        help: "take every piece off the board",
+        run: clear,
+    },
+    Spec {
+        name: "start",
+        usage: "start",
+        help: "set up the initial position",
+        run: start,
+    },
+    Spec {
+        name: "turn",
+        usage: "turn <white|black>",
+        help: "set the side to move",
+        run: turn,
+    },
+    Spec {
+        name: "castle",
+        usage: "castle <rights>",
+        help: "set who may castle which way, like KQkq, Kq or -",
+        run: castle,
+    },
+    Spec {
+        name: "passant",
+        usage: "passant <square|->",
+        help: "set the square a pawn just skipped and can be taken on, or - for none",
+        run: passant,
+    },
+    Spec {
+        name: "fen",
+        usage: "fen [fen]",
+        help: "print the position as fen, or set it up from one",
+        run: fen,
+    },
+    Spec {
+        name: "done",
+        usage: "done",
+        help: "start a game from the position with the same sides",
+        run: |s, _| finish(s, false),
+    },
+    Spec {
+        name: "analyse",
+        usage: "analyse",
+        help: "start from the position with both sides yours and the evaluation shown",
+        run: |s, _| finish(s, true),
+    },
+    Spec {
+        name: "cancel",
+        usage: "cancel",
+        help: "leave the editor and go back to the game",
+        run: |s, _| {
+            s.editor = None;
+            Reply::board()
+        },
+    },
+];
+
+// commands of the game that still make sense while editing, they only change
+// how the board is shown
+pub const KEPT: &[&str] = &[
+    "board", "flip", "autoflip", "unicode", "theme", "notation", "quit",
+];
+
+// starts editing a copy of the position on the board
+pub fn open(session: &mut Session, _: &str) -> Reply {
+    session.editor = Some(session.shown_board().clone());
+    session.viewing = None;
+    let mut reply = Reply::board();
+    reply.lines.push(String::from(
+        "editing the position. type pieces like Ke1 or ke8 to place them, help for more",
+    ));
+    reply
+}
+
+// runs an editor command, the game's commands in KEPT don't get here. pieces are placed by typing their fen letter and
+// square, several at once like Ke1 Qd1 ke8
+pub fn execute(session: &mut Session, input: &str) -> Reply {
+    let (name, args) = input.split_once(' ').unwrap_or((input, ""));
+    if let Some(command) = COMMANDS.iter().find(|c| c.name == name) {
+        return (command.run)(session, args.trim());
+    }
+    let placed: Option<Vec<Piece>> = input.split_whitespace().map(parse_piece).collect();
+    match placed {
+        Some(pieces) if !pieces.is_empty() => place(session, &pieces),
+        _ => Reply::text("unknown editor command or piece. type help for a list of commands"),
+    }
+}
+
+// a piece and its square like Ke1, white in uppercase and black in lowercase
+fn parse_piece(text: &str) -> Option<Piece> {
+    let mut chars = text.chars();
+    let piece = Piece::from_char(chars.next()?)?;
+    let pos = BoardPos::parse(chars.as_str())?;
+    Some(Piece { pos, ..piece })
+}
+
+fn editor(session: &mut Session) -> &mut ChessBoard {
+    session.editor.as_mut().expect("the editor is open")
+}
+
+// the pieces go on a copy, so the board is left as it was when one of them
+// can't be placed
+fn place(session: &mut Session, pieces: &[Piece]) -> Reply {
+    let mut board = editor(session).clone();
+    for &piece in pieces {
+        if piece.pos.col >= board.width {
+            return Reply::text(format!("{} is off the board", piece.pos));
+        }
+        // a piece of the same color on the square makes room for the new one
+        let replaced = board
+            .piece_at(piece.pos)
+            .is_some_and(|p| p.color == piece.color);
+        if board.lists[piece.color.idx()].len as usize == MAX_PIECES && !replaced {
+            return Reply::text(format!(
+                "{} has no room for more pieces",
+                piece.color.name()
+            ));
+        }
+        board.take(piece.pos);
+        board.put(piece);
+    }
+    board.tidy();
+    *editor(session) = board;
+    Reply::board()
+}
+
+fn remove(session: &mut Session, args: &str) -> Reply {
+    let squares: Option<Vec<BoardPos>> = args.split_whitespace().map(BoardPos::parse).collect();
+    match squares {
+        Some(squares) if !squares.is_empty() => {
+            let board = editor(session);
+            for pos in squares {
+                board.take(pos);
+            }
+            board.tidy();
+            Reply::board()
+        }
+        _ => Reply::text("usage: remove <square>..."),
+    }
+}
+
+fn clear(session: &mut Session, _: &str) -> Reply {
+    let board = editor(session);
+    for pos in board.squares().collect::<Vec<_>>() {
+        board.take(pos);
+    }
+    board.tidy();
+    Reply::board()
+}
+
+fn start(session: &mut Session, _: &str) -> Reply {
+    let variant = editor(session).variant;
+    session.editor = Some(variant.rules().setup(&mut session.rng));
+    Reply::board()
+}
+
+fn turn(session: &mut Session, args: &str) -> Reply {
+    let color = match args {
+        "white" | "w" => Color::White,
+        "black" | "b" => Color::Black,
+        _ => return Reply::text("usage: turn <white|black>"),
+    };
+    let board = editor(session);
+    board.turn = color;
+    board.tidy();
+    Reply::board()
+}
+
+// the rights are read like the castling field of a fen, so KQkq and the
+// chess960 rook files both work
+fn castle(session: &mut Session, args: &str) -> Reply {
+    let board = editor(session);
+    if args.is_empty() || args.contains(char::is_whitespace) {
+        return Reply::text("usage: castle <rights>, like KQkq, Kq or -");
+    }
+    let fen = board.to_fen();
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let fen = format!("{} {} {} - 0 {}", fields[0], fields[1], args, fields[5]);
+    match ChessBoard::from_fen(&fen) {
+        Some(castled) => {
+            board.castling = castled.castling;
+            Reply::board()
+        }
+        None => Reply::text(format!(
+            "can't castle {}: the king and rooks aren't where it needs them",
+            args
+        )),
+    }
+}
+
+// the square is read like the en passant field of a fen. it is only kept
+// while a pawn of the side to move can take there
+fn passant(session: &mut Session, args: &str) -> Reply {
+    let board = editor(session);
+    if args.is_empty() || args.contains(char::is_whitespace) {
+        return Reply::text("usage: passant <square|->");
+    }
+    let mut passed = board.clone();
+    passed.en_passant = None;
+    if let Err(e) = passed.read_en_passant(args) {
+        return Reply::text(e);
+    }
+    if args != "-" && passed.en_passant.is_none() {
+        return Reply::text(format!(
+            "no {} pawn could take on {} en passant",
+            board.turn.name(),
+            args
+        ));
+    }
+    board.en_passant = passed.en_passant;
+    Reply::board()
+}
+
+fn fen(session: &mut Session, args: &str) -> Reply {
+    let board = editor(session);
+    if args.is_empty() {
+        return Reply::text(board.to_fen());
+    }
+    match ChessBoard::parse_fen(args) {
+        Ok(read) => {
+            *board = read.with_variant(board.variant);
+            Reply::board()
+        }
+        Err(e) => Reply::text(format!("invalid fen: {}", e)),
+    }
+}
+
+// puts the current game in the library and plays on from the edited position,
+// with fresh clocks of the same time control. analysing hands both sides to
+// the player and shows the evaluation
+fn finish(session: &mut Session, analyse: bool) -> Reply {
+    let board = editor(session);
+    if let Err(e) = board.validate() {
+        return Reply::text(format!("the position can't be played: {}", e));
+    }
+    // going through fen counts the missing pieces as taken
+    let board = match ChessBoard::parse_fen(&board.to_fen()) {
+        Ok(read) => read.with_variant(board.variant),
+        Err(e) => return Reply::text(format!("the position can't be played: {}", e)),
+    };
+    session.editor = None;
+    session.log_game();
+    if analyse {
+        session.players = [Player::Human; 2];
+        session.options.eval = true;
+    }
+    commands::replace_game(session, Game::from_board(board))
+}
+
+fn help(_: &mut Session, _: &str) -> Reply {
+    let mut lines: Vec<String> = COMMANDS
+        .iter()
+        .map(|c| format!("{:<20}{}", c.usage, c.help))
+        .collect();
+    lines.push(String::from(
+        "a piece letter and square like Ke1 or pa7 places a piece, white in uppercase. \
+         board, flip, autoflip, unicode, theme, notation and quit work as in the game",
+    ));
+    Reply {
+        lines,
+        flow: commands::Flow::Continue,
+    }
+}
+
+impl ChessBoard {
+    // after an edit, castling rights whose king or rook has been moved away
+    // are dropped, and so is an en passant square the pawns no longer fit
+    fn tidy(&mut self) {
+        if let Some(square) = self.en_passant.take() {
+            // read again, it stays only if the pawns still fit it
+            let _ = self.read_en_passant(&square.to_string());
+        }
+        self.tidy_castling();
+    }
+
+    fn tidy_castling(&mut self) {
+        for color in [Color::White, Color::Black] {
+            let row = castling::back_row(color);
+            let king = self.king_pos(color).filter(|pos| pos.row == row);
+            for side in [SHORT, LONG] {
+                let kept = self.castling[color.idx()][side].filter(|&col| {
+                    let rook = self.piece_at(BoardPos { row, col });
+                    let beside = king.is_some_and(|king| match side {
+                        SHORT => col > king.col,
+                        _ => col < king.col,
+                    });
+                    beside && rook.is_some_and(|p| p.color == color && p.piece == PieceType::Rook)
+                });
+                self.castling[color.idx()][side] = kept;
+            }
+        }
+    }
+}
@@ -0,0 +1,883 @@
+use clap::ValueEnum;
+use rayon::prelude::*;
+
+use crate::display::{clear_screen, DisplayOptions, Highlights};
+use crate::game::Game;
+use crate::pawns::promotion_row;
+use crate::rng::Rng;
+use crate::variant::VariantKind;
+use crate::{BoardPos, ChessBoard, Color, Move, Piece, PieceType};
+
+// the endings that can be practised, white always has the winning side
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Ending {
+    // king and queen against king
+    Kqk,
+    // king and rook against king
+    Krk,
+    // king and pawn against king, won by promoting
+    Kpk,
+    // king and rook against king and pawn, won by taking the pawn before it
+    // promotes
+    Krkp,
+}
+
+impl Ending {
+    // the men of the ending besides the two kings
+    fn men(self) -> &'static [(Color, PieceType)] {
+        match self {
+            Ending::Kqk => &[(Color::White, PieceType::Queen)],
+            Ending::Krk => &[(Color::White, PieceType::Rook)],
+            Ending::Kpk => &[(Color::White, PieceType::Pawn)],
+            Ending::Krkp => &[
+                (Color::White, PieceType::Rook),
+                (Color::Black, PieceType::Pawn),
+            ],
+        }
+    }
+
+    // the endings a capture or a promotion leads to, worked out first
+    fn lower(self) -> &'static [Ending] {
+        match self {
+            Ending::Kqk | Ending::Krk => &[],
+            Ending::Kpk => &[Ending::Kqk, Ending::Krk],
+            Ending::Krkp => &[Ending::Krk],
+        }
+    }
+
+    // whether black has nothing but the king, so a position white doesn't
+    // win is a draw
+    fn lone_king(self) -> bool {
+        self.men().iter().all(|&(color, _)| color == Color::White)
+    }
+}
+
+// the positions of a table that aren't a win for white, or can't happen
+const DRAWN: u8 = u8::MAX;
+
+const STRAIGHT: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const DIAGONAL: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const KNIGHT_JUMPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+// what a pawn of the tables can become
+const PROMOTIONS: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+// the most men of a table position, the kings included
+const MOST: usize = 4;
+
+// the square a step away, if it is on the board. squares count along the
+// rows of BoardPos, a8 is 0
+fn step(sq: usize, (dr, dc): (i8, i8)) -> Option<usize> {
+    let row = (sq / 8) as i8 + dr;
+    let col = (sq % 8) as i8 + dc;
+    ((0..8).contains(&row) && (0..8).contains(&col)).then_some((row * 8 + col) as usize)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Man {
+    color: Color,
+    piece: PieceType,
+    sq: usize,
+}
+
+impl Man {
+    const NONE: Man = Man {
+        color: Color::White,
+        piece: PieceType::King,
+        sq: 0,
+    };
+
+    // the lines the man slides along
+    fn lines(self) -> &'static [(i8, i8)] {
+        match self.piece {
+            PieceType::Queen => &KING_STEPS,
+            PieceType::Rook => &STRAIGHT,
+            PieceType::Bishop => &DIAGONAL,
+            _ => &[],
+        }
+    }
+
+    // the squares the man reaches in one step, whatever is in between
+    fn steps(self) -> &'static [(i8, i8)] {
+        match self.piece {
+            PieceType::King => &KING_STEPS,
+            PieceType::Knight => &KNIGHT_JUMPS,
+            _ => &[],
+        }
+    }
+
+    // the way the man goes if it is a pawn, white's go up the board
+    fn forward(self) -> i8 {
+        match self.color {
+            Color::White => -1,
+            Color::Black => 1,
+        }
+    }
+
+    // the row a pawn of the man's side starts on
+    fn start_row(self) -> usize {
+        match self.color {
+            Color::White => 6,
+            Color::Black => 1,
+        }
+    }
+}
+
+// a position of the tables: the white king, the black king, the other men
+// and whose move it is. the two sides never both have pawns, so there is no
+// en passant, and no rook may castle
+#[derive(Debug, Clone, Copy)]
+struct Setup {
+    men: [Man; MOST],
+    len: usize,
+    white: bool,
+}
+
+impl Setup {
+    // the position at index of the ending's table
+    fn decode(ending: Ending, index: usize) -> Setup {
+        let kinds = [
+            (Color::White, PieceType::King),
+            (Color::Black, PieceType::King),
+        ];
+        let len = 2 + ending.men().len();
+        let mut men = [Man::NONE; MOST];
+        for (k, &(color, piece)) in kinds.iter().chain(ending.men()).enumerate() {
+            let sq = (index >> (6 * (len - 1 - k))) & 63;
+            men[k] = Man { color, piece, sq };
+        }
+        Setup {
+            men,
+            len,
+            white: (index >> (6 * len)) & 1 == 1,
+        }
+    }
+
+    // where the position is in its ending's table, the men in the ending's
+    // order
+    fn index(&self) -> usize {
+        self.men()
+            .iter()
+            .fold(usize::from(self.white), |i, man| i * 64 + man.sq)
+    }
+
+    // the position of the board, upside down with the colours swapped when
+    // flipped. none when it has more men than the tables or lacks a king
+    fn of(board: &ChessBoard, flip: bool) -> Option<Setup> {
+        let mut men = [Man::NONE; MOST];
+        let mut len = 2;
+        let mut kings = [false; 2];
+        for piece in board.pieces(Color::White).chain(board.pieces(Color::Black)) {
+            let (color, row) = match flip {
+                false => (piece.color, piece.pos.row),
+                true => (piece.color.other(), 7 - piece.pos.row),
+            };
+            let man = Man {
+                color,
+                piece: piece.piece,
+                sq: row as usize * 8 + piece.pos.col as usize,
+            };
+            if piece.piece == PieceType::King {
+                men[color.idx()] = man;
+                kings[color.idx()] = true;
+            } else if len < MOST {
+                men[len] = man;
+                len += 1;
+            } else {
+                return None;
+            }
+        }
+        (kings == [true; 2]).then_some(Setup {
+            men,
+            len,
+            white: (board.turn == Color::White) != flip,
+        })
+    }
+
+    fn board(&self) -> ChessBoard {
+        let mut board = ChessBoard::empty();
+        for man in self.men() {
+            board.put(Piece {
+                pos: BoardPos {
+                    row: (man.sq / 8) as u8,
+                    col: (man.sq % 8) as u8,
+                },
+                color: man.color,
+                piece: man.piece,
+            });
+        }
+        if !self.white {
+            board.turn = Color::Black;
+        }
+        // going through fen counts the missing pieces as taken
+        ChessBoard::from_fen(&board.to_fen()).expect("a legal position")
+    }
+
+    fn men(&self) -> &[Man] {
+        &self.men[..self.len]
+    }
+
+    fn mover(&self) -> Color {
+        match self.white {
+            true => Color::White,
+            false => Color::Black,
+        }
+    }
+
+    fn occupied(&self, sq: usize) -> Option<usize> {
+        self.men().iter().position(|man| man.sq == sq)
+    }
+
+    // the position with its men in the order of the ending's table, if it
+    // has just the ending's men
+    fn in_order(&self, ending: Ending) -> Option<Setup> {
+        if self.len != 2 + ending.men().len() {
+            return None;
+        }
+        let mut ordered = *self;
+        for (k, &(color, piece)) in ending.men().iter().enumerate() {
+            ordered.men[2 + k] = *self.men()[2..]
+                .iter()
+                .find(|man| man.color == color && man.piece == piece)?;
+        }
+        Some(ordered)
+    }
+
+    // whether the man attacks sq, every other man blocks its lines
+    fn attacks(&self, man: Man, sq: usize) -> bool {
+        let dr = (sq / 8) as i8 - (man.sq / 8) as i8;
+        let dc = (sq % 8) as i8 - (man.sq % 8) as i8;
+        let line = match man.piece {
+            PieceType::Pawn => return dr == man.forward() && dc.abs() == 1,
+            PieceType::King => return dr.abs().max(dc.abs()) == 1,
+            PieceType::Knight => return dr.abs() * dc.abs() == 2,
+            _ if dr == 0 && dc == 0 => return false,
+            _ if dr == 0 || dc == 0 => STRAIGHT,
+            _ if dr.abs() == dc.abs() => DIAGONAL,
+            _ => return false,
+        };
+        let d = (dr.signum(), dc.signum());
+        man.lines().contains(&d)
+            && line.contains(&d)
+            && std::iter::successors(step(man.sq, d), |&at| step(at, d))
+                .take_while(|&at| at != sq)
+                .all(|at| self.occupied(at).is_none())
+    }
+
+    fn in_check(&self, color: Color) -> bool {
+        let king = self.men[color.idx()].sq;
+        self.men()
+            .iter()
+            .any(|&man| man.color != color && self.attacks(man, king))
+    }
+
+    // no two men on a square, no pawn on the first or last row and the side
+    // that just moved not in check
+    fn legal(&self) -> bool {
+        let men = self.men();
+        men.iter().enumerate().all(|(i, man)| {
+            men[..i].iter().all(|other| other.sq != man.sq)
+                && (man.piece != PieceType::Pawn || (1..7).contains(&(man.sq / 8)))
+        }) && !self.in_check(self.mover().other())
+    }
+
+    // the squares the man can go to: empty ones, or ones with an enemy that
+    // isn't the king
+    fn targets(&self, man: Man) -> ([usize; 27], usize) {
+        let mut squares = [0; 27];
+        let mut count = 0;
+        let mut add = |sq: usize| {
+            squares[count] = sq;
+            count += 1;
+        };
+        let enemy = |sq: usize| {
+            self.occupied(sq).is_some_and(|i| {
+                self.men[i].color != man.color && self.men[i].piece != PieceType::King
+            })
+        };
+        if man.piece == PieceType::Pawn {
+            let forward = man.forward();
+            if let Some(one) = step(man.sq, (forward, 0)).filter(|&sq| self.occupied(sq).is_none())
+            {
+                add(one);
+                if man.sq / 8 == man.start_row() {
+                    if let Some(two) =
+                        step(one, (forward, 0)).filter(|&sq| self.occupied(sq).is_none())
+                    {
+                        add(two);
+                    }
+                }
+            }
+            for dc in [-1, 1] {
+                if let Some(sq) = step(man.sq, (forward, dc)).filter(|&sq| enemy(sq)) {
+                    add(sq);
+                }
+            }
+        }
+        for &d in man.steps() {
+            if let Some(sq) = step(man.sq, d).filter(|&sq| self.occupied(sq).is_none() || enemy(sq))
+            {
+                add(sq);
+            }
+        }
+        for &d in man.lines() {
+            let mut at = man.sq;
+            while let Some(next) = step(at, d) {
+                if self.occupied(next).is_some() {
+                    if enemy(next) {
+                        add(next);
+                    }
+                    break;
+                }
+                add(next);
+                at = next;
+            }
+        }
+        (squares, count)
+    }
+
+    // every legal move, as the position it leads to and whether it was quiet:
+    // neither a capture nor a promotion, so the men stay the same
+    fn moves(&self, mut f: impl FnMut(Setup, bool)) {
+        let mover = self.mover();
+        for i in 0..self.len {
+            let man = self.men[i];
+            if man.color != mover {
+                continue;
+            }
+            let (squares, count) = self.targets(man);
+            for &to in &squares[..count] {
+                let mut next = *self;
+                next.white = !self.white;
+                next.men[i].sq = to;
+                let taken = self.occupied(to);
+                // the kings stay first, nothing takes them
+                let mut at = i;
+                if let Some(taken) = taken {
+                    next.men.copy_within(taken + 1..self.len, taken);
+                    next.len -= 1;
+                    at -= usize::from(taken < i);
+                }
+                if next.in_check(mover) {
+                    continue;
+                }
+                if man.piece == PieceType::Pawn && to / 8 == promotion_row(mover) as usize {
+                    for piece in PROMOTIONS {
+                        next.men[at].piece = piece;
+                        f(next, false);
+                    }
+                } else {
+                    f(next, taken.is_none());
+                }
+            }
+        }
+    }
+
+    // every position the side that just moved could have come from with a
+    // quiet move
+    fn unmoves(&self, mut f: impl FnMut(Setup)) {
+        let moved = self.mover().other();
+        let empty = |sq: &usize| self.occupied(*sq).is_none();
+        for i in 0..self.len {
+            let man = self.men[i];
+            if man.color != moved {
+                continue;
+            }
+            let mut squares = [0; 27];
+            let mut count = 0;
+            let mut add = |sq: usize| {
+                squares[count] = sq;
+                count += 1;
+            };
+            if man.piece == PieceType::Pawn {
+                let back = -man.forward();
+                if let Some(one) = step(man.sq, (back, 0)).filter(empty) {
+                    if (1..7).contains(&(one / 8)) {
+                        add(one);
+                    }
+                    let two = step(one, (back, 0)).filter(empty);
+                    if let Some(two) = two.filter(|&two| two / 8 == man.start_row()) {
+                        add(two);
+                    }
+                }
+            }
+            for &d in man.steps() {
+                if let Some(sq) = step(man.sq, d).filter(empty) {
+                    add(sq);
+                }
+            }
+            for &d in man.lines() {
+                for sq in
+                    std::iter::successors(step(man.sq, d), |&at| step(at, d)).take_while(empty)
+                {
+                    add(sq);
+                }
+            }
+            for &from in &squares[..count] {
+                let mut before = *self;
+                before.white = !self.white;
+                before.men[i].sq = from;
+                if before.legal() {
+                    f(before);
+                }
+            }
+        }
+    }
+}
+
+// how many plies white needs to mate from every position of one ending,
+// worked out backwards from the mates. captures and promotions go over to
+// the tables of the endings they lead to, so black's defence is perfect
+// but in one way: when black's pawn promotes, white only counts as winning
+// if it takes the new piece at once
+pub struct Table {
+    ending: Ending,
+    plies: Vec<u8>,
+    lower: Vec<Table>,
+}
+
+impl Table {
+    pub fn build(ending: Ending) -> Table {
+        let lower: Vec<Table> = ending.lower().iter().map(|&e| Table::build(e)).collect();
+        let size = 2 << (6 * (2 + ending.men().len()));
+        let half = size / 2;
+        // with black to move, the quiet moves whose result isn't known yet,
+        // SAVED once one of black's moves escapes, and the longest loss
+        // known. black's positions come first in the table
+        const SAVED: u8 = u8::MAX;
+        // the plies, open moves and longest loss of a position from what its
+        // captures and promotions lead to. the quiet moves are filled in
+        // backwards from the positions they lead to as those are found
+        let first = |i: usize| {
+            let setup = Setup::decode(ending, i);
+            if !setup.legal() {
+                return (DRAWN, SAVED, 0);
+            }
+            let mut quiet = 0;
+            let mut any = false;
+            let mut best = DRAWN;
+            let mut worst = None;
+            setup.moves(|next, is_quiet| {
+                any = true;
+                if is_quiet {
+                    quiet += 1;
+                    return;
+                }
+                let plies = outcome(&lower, &next);
+                best = best.min(plies.map_or(DRAWN, |p| p + 1));
+                worst = worst.max(Some(plies.map_or(SAVED, |p| p + 1)));
+            });
+            match worst {
+                _ if setup.white => (best, 0, 0),
+                // mate, or stalemate
+                None if !any => match setup.in_check(Color::Black) {
+                    true => (0, SAVED, 0),
+                    false => (DRAWN, SAVED, 0),
+                },
+                Some(SAVED) => (DRAWN, SAVED, 0),
+                worst => {
+                    let longest = worst.unwrap_or(0);
+                    match quiet {
+                        0 => (longest, 0, longest),
+                        _ => (DRAWN, quiet, longest),
+                    }
+                }
+            }
+        };
+        let mut plies = vec![DRAWN; size];
+        let mut open = vec![0; half];
+        let mut longest = vec![0; half];
+        let (black, white) = plies.split_at_mut(half);
+        black
+            .par_iter_mut()
+            .zip(open.par_iter_mut().zip(longest.par_iter_mut()))
+            .enumerate()
+            .for_each(|(i, (plies, (open, longest)))| (*plies, *open, *longest) = first(i));
+        white
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(i, plies)| *plies = first(half + i).0);
+        // the highest number of plies found so far
+        let mut last = plies
+            .iter()
+            .copied()
+            .filter(|&p| p != DRAWN)
+            .max()
+            .unwrap_or(0);
+        let mut n = 0;
+        while n <= last {
+            for i in 0..size {
+                if plies[i] != n {
+                    continue;
+                }
+                let setup = Setup::decode(ending, i);
+                if setup.white {
+                    // black came here with a quiet move, that one's known
+                    setup.unmoves(|before| {
+                        let j = before.index();
+                        if open[j] == SAVED || open[j] == 0 {
+                            return;
+                        }
+                        open[j] -= 1;
+                        longest[j] = longest[j].max(n + 1);
+                        if open[j] == 0 {
+                            plies[j] = longest[j];
+                            last = last.max(longest[j]);
+                        }
+                    });
+                } else {
+                    setup.unmoves(|before| {
+                        let j = before.index();
+                        if plies[j] > n + 1 {
+                            plies[j] = n + 1;
+                            last = last.max(n + 1);
+                        }
+                    });
+                }
+            }
+            n += 1;
+        }
+        Table {
+            ending,
+            plies,
+            lower,
+        }
+    }
+
+    // the plies white needs to mate from the board, none when it can't be
+    // forced or the board has men no table has
+    pub fn plies(&self, board: &ChessBoard) -> Option<u8> {
+        outcome(std::slice::from_ref(self), &Setup::of(board, false)?)
+    }
+
+    // a position white wins, with at least half the plies of the longest win
+    // so there's something to practise
+    fn start(&self, rng: &mut Rng) -> ChessBoard {
+        let half = self.plies.len() / 2;
+        let longest = self.plies[half..]
+            .iter()
+            .copied()
+            .filter(|&p| p != DRAWN)
+            .max()
+            .unwrap_or(0);
+        loop {
+            let i = half + rng.below(half);
+            let plies = self.plies[i];
+            if plies != DRAWN && plies >= longest / 2 {
+                return Setup::decode(self.ending, i).board();
+            }
+        }
+    }
+}
+
+// the table of the position's men among the tables and the ones below them,
+// and the position in its order
+fn find<'a>(tables: &'a [Table], setup: &Setup) -> Option<(&'a Table, Setup)> {
+    tables
+        .iter()
+        .find_map(|table| match setup.in_order(table.ending) {
+            Some(ordered) => Some((table, ordered)),
+            None => find(&table.lower, setup),
+        })
+}
+
+// the plies white needs to mate from the position, none when it can't be
+// forced. men no table has aren't a win, unless white is to move and can take
+// its way back into a table, like a rook taking the piece a pawn just
+// promoted to
+fn outcome(tables: &[Table], setup: &Setup) -> Option<u8> {
+    if let Some((table, ordered)) = find(tables, setup) {
+        return Some(table.plies[ordered.index()]).filter(|&p| p != DRAWN);
+    }
+    if !setup.white {
+        return None;
+    }
+    let mut best: Option<u8> = None;
+    setup.moves(|next, quiet| {
+        if quiet {
+            return;
+        }
+        if let Some(plies) = outcome(tables, &next) {
+            best = Some(best.map_or(plies + 1, |b| b.min(plies + 1)));
+        }
+    });
+    best
+}
+
+// what a position is worth to the side to move with perfect play, and in how
+// many plies the mate comes. that is the distance to mate, a pawn move
+// resets the fifty move count well before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wdl {
+    Win(u8),
+    Draw,
+    Loss(u8),
+}
+
+impl Wdl {
+    // the same result from the other side
+    fn other(self) -> Wdl {
+        match self {
+            Wdl::Win(plies) => Wdl::Loss(plies),
+            Wdl::Draw => Wdl::Draw,
+            Wdl::Loss(plies) => Wdl::Win(plies),
+        }
+    }
+
+    // wins first, the fastest of them leading, and losses last, the longest
+    // of them leading
+    fn rank(self) -> i32 {
+        match self {
+            Wdl::Win(plies) => -1000 + i32::from(plies),
+            Wdl::Draw => 0,
+            Wdl::Loss(plies) => 1000 - i32::from(plies),
+        }
+    }
+}
+
+// the tables of the endings against a lone king, for either side having the
+// men
+pub struct Oracle {
+    tables: Vec<Table>,
+}
+
+impl Oracle {
+    pub fn build() -> Oracle {
+        Oracle {
+            tables: Ending::value_variants()
+                .iter()
+                .filter(|ending| ending.lone_king())
+                .map(|&ending| Table::build(ending))
+                .collect(),
+        }
+    }
+
+    // the result of the board, none when it isn't one of the endings. bare
+    // kings are a draw
+    pub fn probe(&self, board: &ChessBoard) -> Option<Wdl> {
+        // a rook that may still castle moves in a way the tables don't know
+        if board.variant != VariantKind::Standard
+            || board.width != 8
+            || board.castling != [[None; 2]; 2]
+        {
+            return None;
+        }
+        // the tables give white the men, black's are looked up upside down
+        for flip in [false, true] {
+            let setup = Setup::of(board, flip)?;
+            if setup.len == 2 {
+                return Some(Wdl::Draw);
+            }
+            let Some((table, ordered)) = find(&self.tables, &setup) else {
+                continue;
+            };
+            return Some(match (table.plies[ordered.index()], setup.white) {
+                (DRAWN, _) => Wdl::Draw,
+                (plies, true) => Wdl::Win(plies),
+                (plies, false) => Wdl::Loss(plies),
+            });
+        }
+        None
+    }
+
+    // the result of the board and of every legal move from it, the best
+    // first. the distance of a move is counted from the position it leads to
+    pub fn lines(&self, board: &ChessBoard, options: &DisplayOptions) -> Vec<String> {
+        let Some(wdl) = self.probe(board) else {
+            return vec![String::from("oracle: no table for this position")];
+        };
+        let side = board.turn.name();
+        let mut lines = vec![match wdl {
+            Wdl::Win(plies) => format!("oracle: {} wins, {}", side, mate_text(plies)),
+            Wdl::Draw => format!("oracle: {} to move, drawn", side),
+            Wdl::Loss(0) => format!("oracle: {} is mated", side),
+            Wdl::Loss(plies) => format!("oracle: {} loses, {}", side, mate_text(plies)),
+        }];
+        let mut moves: Vec<(Move, Wdl)> = board
+            .legal_moves()
+            .into_iter()
+            .filter_map(|mve| {
+                let mut next = board.clone();
+                next.make_move(&mve);
+                Some((mve, self.probe(&next)?.other()))
+            })
+            .collect();
+        moves.sort_by_key(|&(_, wdl)| wdl.rank());
+        for (mve, wdl) in moves {
+            let result = match wdl {
+                Wdl::Win(0) => String::from("win   mate"),
+                Wdl::Win(plies) => format!("win   dtm {}", plies),
+                Wdl::Draw => String::from("draw"),
+                Wdl::Loss(plies) => format!("loss  dtm {}", plies),
+            };
+            lines.push(format!(
+                "  {:<8}{}",
+                options.move_text(&board.san(&mve), &mve),
+                result
+            ));
+        }
+        lines
+    }
+}
+
+const KING_STEPS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 0),
+    (0, -1),
+    (-1, 0),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+fn mate_text(plies: u8) -> String {
+    let moves = plies.div_ceil(2);
+    format!(
+        "mate in {} {}",
+        moves,
+        if moves == 1 { "move" } else { "moves" }
+    )
+}
+
+// plays the winning side of the ending against the table's defence, from
+// random positions until quit. every move that lets the win slip is pointed
+// out with one that kept it, and can be taken back
+pub fn run(ending: Ending, options: &DisplayOptions, rng: &mut Rng) -> Result<(), String> {
+    println!("working out the ending...");
+    let table = Table::build(ending);
+    let mut notes = Vec::new();
+    loop {
+        let mut game = Game::from_board(table.start(rng));
+        let best = table.plies(&game.board).unwrap_or(0);
+        notes.push(format!(
+            "White to move, {} with the best play",
+            mate_text(best)
+        ));
+        loop {
+            clear_screen(options);
+            game.board.print(options, &Highlights::default());
+            for note in notes.drain(..) {
+                println!("{}", note);
+            }
+            // losing the last man leaves white nothing to win with, the game
+            // doesn't see that on its own
+            let bare = game.board.pieces(Color::White).count() == 1;
+            let over = game.result.is_some() || bare;
+            match game.result.and_then(|result| result.winner()) {
+                Some(Color::Black) => println!("black mates"),
+                Some(Color::White) => println!(
+                    "mated in {} moves, the best was {}",
+                    game.history.len().div_ceil(2),
+                    best.div_ceil(2)
+                ),
+                None if over && bare => println!("there's nothing left to mate with"),
+                None if over => println!("the game is drawn"),
+                None => {}
+            }
+            if over {
+                println!("type undo, next for another position or quit");
+            } else {
+                println!("your move, or type hint, undo, next for another position or quit");
+            }
+
+            let mut input = String::new();
+            if std::io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+                return Ok(());
+            }
+            let input = input.trim();
+            let board = &game.board;
+            match input {
+                "quit" => return Ok(()),
+                "next" => break,
+                "hint" => {
+                    notes.push(match fastest(&table, board) {
+                        Some(mve) => format!("try {}", options.move_text(&board.san(&mve), &mve)),
+                        None => String::from("there's no win left, undo to get it back"),
+                    });
+                    continue;
+                }
+                "undo" => {
+                    // back to white's last move, black's answer goes with it
+                    if game.undo() && game.board.turn == Color::Black {
+                        game.undo();
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            if over {
+                continue;
+            }
+            let mve = board
+                .parse_san(&options.locale.delocalize(input))
+                .or_else(|| board.parse_uci(input).filter(|m| board.is_legal(m)));
+            let Some(mve) = mve else {
+                notes.push(format!("{} isn't a legal move", input));
+                continue;
+            };
+            let kept = fastest(&table, board);
+            let san = board.san(&mve);
+            game.play(&mve);
+            if game.result.is_some() {
+                continue;
+            }
+            match (kept, table.plies(&game.board)) {
+                (Some(kept), None) => {
+                    let before = &game.history[game.history.len() - 1].before;
+                    notes.push(format!(
+                        "{} throws away the win, {} kept it. type undo to take it back",
+                        options.move_text(&san, &mve),
+                        options.move_text(&before.san(&kept), &kept)
+                    ));
+                }
+                (_, Some(plies)) => notes.push(mate_text(plies)),
+                (None, None) => {}
+            }
+            if let Some(reply) = defend(&table, &game.board) {
+                let san = game.board.san(&reply);
+                game.play(&reply);
+                let text = options.move_text(&san, &reply);
+                notes.push(options.locale.plays(Color::Black, &text));
+            }
+        }
+    }
+}
+
+// the move that mates soonest, none when white can't win any more
+fn fastest(table: &Table, board: &ChessBoard) -> Option<Move> {
+    board
+        .legal_moves()
+        .into_iter()
+        .filter_map(|mve| {
+            let mut next = board.clone();
+            next.make_move(&mve);
+            Some((mve, table.plies(&next)?))
+        })
+        .min_by_key(|&(_, plies)| plies)
+        .map(|(mve, _)| mve)
+}
+
+// black's answer: a move that draws if there is one, else the one that holds
+// out longest
+fn defend(table: &Table, board: &ChessBoard) -> Option<Move> {
+    board.legal_moves().into_iter().max_by_key(|mve| {
+        let mut next = board.clone();
+        next.make_move(mve);
+        table.plies(&next).map_or(u16::MAX, u16::from)
+    })
+}
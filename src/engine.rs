@@ -0,0 +1,386 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use crate::searchstats;
+use crate::{BoardPos, ChessBoard, Color, Move, PieceType, RANKS};
+
+pub const MATE: i32 = 100_000;
+
+thread_local! {
+    // when a timed search on this thread has to give up, the depth it was
+    // searching is thrown away then
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+    // the positions searched on this thread so far, for bench
+    static NODES: Cell<u64> = const { Cell::new(0) };
+}
+
+// how many positions the searches on this thread went through
+pub fn nodes() -> u64 {
+    NODES.get()
+}
+
+fn out_of_time() -> bool {
+    DEADLINE
+        .get()
+        .is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+// bonus for standing on a central square, 0 on the rim up to 3 in the middle
+// of a standard board. wider boards have more files between rim and middle
+fn centrality(board: &ChessBoard, pos: BoardPos) -> i32 {
+    let dist = |x: u8, len: u8| -> i32 { (2 * x as i32 - (len as i32 - 1)).abs() / 2 };
+    3 - dist(pos.row, RANKS).max(dist(pos.col, board.width))
+}
+
+// the terms the static evaluation adds up, in centipawns for each side
+// indexed by Color::idx
+#[derive(Debug, Default)]
+pub struct Terms {
+    pub material: [i32; 2],
+    // knights and bishops in the center, and pawns as they near it
+    pub placement: [i32; 2],
+    // pawns for every rank they went forward
+    pub advance: [i32; 2],
+}
+
+impl Terms {
+    pub fn total(&self, color: Color) -> i32 {
+        let c = color.idx();
+        self.material[c] + self.placement[c] + self.advance[c]
+    }
+}
+
+pub fn terms(board: &ChessBoard) -> Terms {
+    let mut terms = Terms::default();
+    for color in [Color::White, Color::Black] {
+        let c = color.idx();
+        for p in board.pieces(color) {
+            terms.material[c] += p.piece.value() * 100;
+            match p.piece {
+                PieceType::Knight | PieceType::Bishop => {
+                    terms.placement[c] += 10 * centrality(board, p.pos)
+                }
+                PieceType::Pawn => {
+                    let advanced = match color {
+                        Color::White => 6 - p.pos.row as i32,
+                        Color::Black => p.pos.row as i32 - 1,
+                    };
+                    terms.advance[c] += 5 * advanced;
+                    terms.placement[c] += 5 * centrality(board, p.pos);
+                }
+                _ => {}
+            }
+        }
+    }
+    terms
+}
+
+// the terms for each side and the balance of them for white, in pawns, then
+// the evaluation itself when the variant's rules make something else of them
+pub fn breakdown(board: &ChessBoard) -> Vec<String> {
+    let terms = terms(board);
+    let pawns = |centipawns: i32| format!("{:.2}", centipawns as f64 / 100.0);
+    let row = |name: &str, of: [i32; 2]| {
+        format!(
+            "{:<10}{:>8}{:>8}{:>8}",
+            name,
+            pawns(of[0]),
+            pawns(of[1]),
+            format!("{:+.2}", (of[0] - of[1]) as f64 / 100.0)
+        )
+    };
+    let total = [terms.total(Color::White), terms.total(Color::Black)];
+    let mut lines = vec![
+        format!("{:<10}{:>8}{:>8}{:>8}", "", "White", "Black", "balance"),
+        row("material", terms.material),
+        row("placement", terms.placement),
+        row("advance", terms.advance),
+        row("total", total),
+    ];
+    let score = white_score(board.turn, evaluate(board));
+    if score != total[0] - total[1] {
+        lines.push(format!(
+            "the rules of {} make it {}",
+            board.variant.pgn_name().unwrap_or("standard chess"),
+            format_score(score)
+        ));
+    }
+    lines
+}
+
+// static evaluation in centipawns from the side to move's point of view
+pub fn evaluate(board: &ChessBoard) -> i32 {
+    let terms = terms(board);
+    board
+        .rules()
+        .evaluate(terms.total(board.turn) - terms.total(board.turn.other()))
+}
+
+// captures of valuable pieces by cheap ones are searched first
+fn order_moves(board: &ChessBoard, moves: &mut [Move]) {
+    moves.sort_by_key(|m| match board.piece_at(m.to) {
+        Some(victim) if board.is_capture(m) => {
+            let attacker = board.piece_at(m.from).map_or(0, |p| p.piece.value());
+            attacker - 10 * victim.piece.value()
+        }
+        _ => 0,
+    });
+}
+
+// pv receives the best line found from this position when the score is exact
+fn negamax(
+    board: &ChessBoard,
+    depth: u8,
+    ply: i32,
+    mut alpha: i32,
+    beta: i32,
+    pv: &mut Vec<Move>,
+) -> i32 {
+    NODES.set(NODES.get() + 1);
+    let leaf = depth == 0 || out_of_time();
+    searchstats::node(leaf);
+    if leaf {
+        return evaluate(board);
+    }
+    let mut moves = board.legal_moves();
+    if moves.is_empty() {
+        // mates closer to the root are worth more
+        return match board.rules().no_moves(board).winner() {
+            Some(winner) if winner == board.turn => MATE - ply,
+            Some(_) => -MATE + ply,
+            None => 0,
+        };
+    }
+    order_moves(board, &mut moves);
+    let mut line = Vec::new();
+    for (i, mve) in moves.iter().enumerate() {
+        let mut next = board.clone();
+        next.make_move(mve);
+        line.clear();
+        let traced =
+            searchstats::tracing(ply).then(|| searchstats::enter(board, mve, ply, alpha, beta));
+        let score = child_score(board, &next, depth - 1, ply + 1, alpha, beta, &mut line);
+        if let Some(at) = traced {
+            searchstats::leave(at, score);
+        }
+        if score >= beta {
+            searchstats::cutoff(board.is_capture(mve), i);
+            return beta;
+        }
+        if score > alpha {
+            alpha = score;
+            pv.clear();
+            pv.push(*mve);
+            pv.extend_from_slice(&line);
+        }
+    }
+    alpha
+}
+
+// the score of next, a move after board, from the point of view of board's
+// side to move. it is negated unless the same side moves again, like in the
+// middle of a turn of two moves
+fn child_score(
+    board: &ChessBoard,
+    next: &ChessBoard,
+    depth: u8,
+    ply: i32,
+    alpha: i32,
+    beta: i32,
+    pv: &mut Vec<Move>,
+) -> i32 {
+    if next.turn == board.turn {
+        negamax(next, depth, ply, alpha, beta, pv)
+    } else {
+        -negamax(next, depth, ply, -beta, -alpha, pv)
+    }
+}
+
+// searches depth plies ahead and returns the best move with its score,
+// or None if the side to move has no legal moves
+pub fn search(board: &ChessBoard, depth: u8) -> Option<(Move, i32)> {
+    let _span = tracing::debug_span!("depth", depth).entered();
+    let start = Instant::now();
+    searchstats::start();
+    let searched = search_root(board, depth, None, None);
+    searchstats::iteration(depth);
+    searchstats::finish(board);
+    let (pv, score) = searched?;
+    log_iteration(&pv, score, start);
+    Some((pv[0], score))
+}
+
+// the best move and its score when one move is left out, to tell whether
+// that one is the only good move. None if it was the only legal move
+pub fn search_without(board: &ChessBoard, depth: u8, left_out: Move) -> Option<(Move, i32)> {
+    searchstats::start();
+    let searched = search_root(board, depth, None, Some(left_out));
+    searchstats::iteration(depth);
+    searchstats::finish(board);
+    searched.map(|(pv, score)| (pv[0], score))
+}
+
+// searches one ply deeper at a time up to depth and reports every finished
+// depth with its main line and score, for showing the analysis while it grows
+pub fn deepen(
+    board: &ChessBoard,
+    depth: u8,
+    mut report: impl FnMut(u8, &[Move], i32),
+) -> Option<(Move, i32)> {
+    let mut best: Option<(Vec<Move>, i32)> = None;
+    searchstats::start();
+    for d in 1..=depth.max(1) {
+        let _span = tracing::debug_span!("depth", depth = d).entered();
+        let start = Instant::now();
+        best = search_root(board, d, best.map(|(pv, _)| pv[0]), None);
+        match &best {
+            Some((pv, score)) => {
+                searchstats::iteration(d);
+                log_iteration(pv, *score, start);
+                report(d, pv, *score)
+            }
+            None => break,
+        }
+    }
+    searchstats::finish(board);
+    best.map(|(pv, score)| (pv[0], score))
+}
+
+fn log_iteration(pv: &[Move], score: i32, start: Instant) {
+    let line: Vec<String> = pv.iter().map(Move::to_string).collect();
+    tracing::debug!(
+        "score {} in {:?}, line {}",
+        format_score(score),
+        start.elapsed(),
+        line.join(" ")
+    );
+}
+
+// searches one ply deeper at a time, up to depth, until the time is up, and
+// returns the best move and score of the last depth that finished with the
+// depth itself. the first depth always finishes
+pub fn think(board: &ChessBoard, limit: Duration, depth: u8) -> Option<(Move, i32, u8)> {
+    think_line(board, limit, depth).map(|(pv, score, d)| (pv[0], score, d))
+}
+
+// like think, with the whole main line of the last depth that finished
+pub fn think_line(board: &ChessBoard, limit: Duration, depth: u8) -> Option<(Vec<Move>, i32, u8)> {
+    let mut best: Option<(Vec<Move>, i32, u8)> = None;
+    let deadline = Instant::now() + limit;
+    searchstats::start();
+    for d in 1..=depth.max(1) {
+        let _span = tracing::debug_span!("depth", depth = d).entered();
+        let start = Instant::now();
+        DEADLINE.set(Some(deadline).filter(|_| d > 1));
+        let searched = search_root(board, d, best.as_ref().map(|(pv, _, _)| pv[0]), None);
+        DEADLINE.set(None);
+        if d > 1 && Instant::now() >= deadline {
+            tracing::debug!("out of time after {:?}", start.elapsed());
+            break;
+        }
+        match searched {
+            Some((pv, score)) => {
+                searchstats::iteration(d);
+                log_iteration(&pv, score, start);
+                // a found mate doesn't get any better deeper down
+                let mated = score.abs() > MATE - 1000;
+                best = Some((pv, score, d));
+                if mated {
+                    break;
+                }
+            }
+            None => break,
+        }
+    }
+    searchstats::finish(board);
+    best
+}
+
+// every legal move with its exact score searched to depth, best first. slower
+// than search, which only needs to know which one is best
+pub fn rank(board: &ChessBoard, depth: u8) -> Vec<(Move, i32)> {
+    let mut moves = board.legal_moves();
+    order_moves(board, &mut moves);
+    let mut line = Vec::new();
+    let mut ranked: Vec<(Move, i32)> = moves
+        .into_iter()
+        .map(|mve| {
+            let mut next = board.clone();
+            next.make_move(&mve);
+            line.clear();
+            let depth = depth.saturating_sub(1);
+            let score = child_score(board, &next, depth, 1, -MATE - 1, MATE + 1, &mut line);
+            (mve, score)
+        })
+        .collect();
+    ranked.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    ranked
+}
+
+// the main line starting with the best move, and its score. the best move of
+// the previous depth is tried first when given, a left out move not at all
+fn search_root(
+    board: &ChessBoard,
+    depth: u8,
+    first: Option<Move>,
+    left_out: Option<Move>,
+) -> Option<(Vec<Move>, i32)> {
+    let mut moves = board.legal_moves();
+    moves.retain(|&m| Some(m) != left_out);
+    order_moves(board, &mut moves);
+    if let Some(i) = first.and_then(|f| moves.iter().position(|&m| m == f)) {
+        moves[..=i].rotate_right(1);
+    }
+    let mut best: Option<(Vec<Move>, i32)> = None;
+    let mut alpha = -MATE - 1;
+    let mut line = Vec::new();
+    for mve in moves {
+        let mut next = board.clone();
+        next.make_move(&mve);
+        line.clear();
+        let traced =
+            searchstats::tracing(0).then(|| searchstats::enter(board, &mve, 0, alpha, MATE + 1));
+        let score = child_score(
+            board,
+            &next,
+            depth.saturating_sub(1),
+            1,
+            alpha,
+            MATE + 1,
+            &mut line,
+        );
+        if let Some(at) = traced {
+            searchstats::leave(at, score);
+        }
+        if best.is_none() || score > alpha {
+            let mut pv = vec![mve];
+            pv.extend_from_slice(&line);
+            best = Some((pv, score));
+            alpha = score;
+        }
+    }
+    best
+}
+
+// turns a score from the side to move's point of view into white's
+pub fn white_score(turn: Color, score: i32) -> i32 {
+    match turn {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+// pawns with two decimals like +0.35, or #3 / #-2 for mate in that many moves
+pub fn format_score(score: i32) -> String {
+    let plies = MATE - score.abs();
+    if plies < 1000 {
+        let moves = (plies + 1) / 2;
+        if score > 0 {
+            format!("#{}", moves)
+        } else {
+            format!("#-{}", moves)
+        }
+    } else {
+        format!("{:+.2}", score as f64 / 100.0)
+    }
+}
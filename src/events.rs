@@ -0,0 +1,146 @@
+// the --json protocol: one object per line on stdout for scripts and guis
+// driving the program, in place of the boards and messages meant for people.
+// every object has a type:
+//   state   the position after anything changed, with its legal moves
+//   move    a move the program played for a side
+//   reply   what a command answered
+//   note    anything else said, like explorer or oracle lines
+//   clock   the time left, every second while a clock runs
+//   result  how a finished game ended
+//   error   a line read that isn't a command
+// lines read are objects too, named after the command with its arguments:
+//   {"type":"move","move":"e4"}
+//   {"type":"new"}  {"type":"undo"}  {"type":"load","args":"game.toml"}
+// and plain lines are taken like typed ones
+use crate::clock::Clock;
+use crate::game::Game;
+use crate::json::{self, object, string};
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Color, Move};
+
+fn array(items: impl Iterator<Item = String>) -> String {
+    let items: Vec<String> = items.collect();
+    format!("[{}]", items.join(","))
+}
+
+fn lines(lines: &[String]) -> String {
+    array(lines.iter().map(|line| string(line)))
+}
+
+fn color(color: Color) -> String {
+    string(&color.name().to_lowercase())
+}
+
+// a move both ways it can be written, castling as the king's move outside
+// chess960 like engines expect
+fn mve(board: &ChessBoard, mve: &Move) -> String {
+    let uci = match board.variant {
+        VariantKind::Chess960 => mve.to_string(),
+        _ => board.standard_uci(mve),
+    };
+    object(&[("san", string(&board.san(mve))), ("uci", string(&uci))])
+}
+
+fn times(clock: &Clock) -> String {
+    let millis = |side: Color| clock.remaining(side).as_millis().to_string();
+    object(&[
+        ("white_ms", millis(Color::White)),
+        ("black_ms", millis(Color::Black)),
+    ])
+}
+
+// the board shown, which is the game's unless a side line or the editor is
+// open, and the status of the game
+pub fn state(game: &Game, board: &ChessBoard, status: &str) -> String {
+    // a finished game has no moves left, whatever the board would allow
+    let over = game.result.is_some() && std::ptr::eq(board, &game.board);
+    let moves = if over {
+        Vec::new()
+    } else {
+        board.legal_moves()
+    };
+    object(&[
+        ("type", string("state")),
+        ("fen", string(&board.to_fen())),
+        ("turn", color(board.turn)),
+        ("check", board.in_check(board.turn).to_string()),
+        ("status", string(status)),
+        (
+            "last_move",
+            // known with its san only for the game's own board
+            game.history
+                .last()
+                .filter(|_| std::ptr::eq(board, &game.board))
+                .map_or(String::from("null"), |played| {
+                    mve(&played.before, &played.mve)
+                }),
+        ),
+        ("moves", array(moves.iter().map(|m| mve(board, m)))),
+        ("ply", game.history.len().to_string()),
+        (
+            "clock",
+            game.clock.as_ref().map_or(String::from("null"), times),
+        ),
+    ])
+}
+
+// a move of side about to be played on board
+pub fn played(board: &ChessBoard, side: Color, played: &Move) -> String {
+    object(&[
+        ("type", string("move")),
+        ("color", color(side)),
+        ("move", mve(board, played)),
+    ])
+}
+
+pub fn reply(text: &[String]) -> String {
+    object(&[("type", string("reply")), ("lines", lines(text))])
+}
+
+pub fn note(text: &[String]) -> String {
+    object(&[("type", string("note")), ("lines", lines(text))])
+}
+
+pub fn clock_tick(clock: &Clock, running: Color) -> String {
+    object(&[
+        ("type", string("clock")),
+        ("running", color(running)),
+        ("clock", times(clock)),
+    ])
+}
+
+pub fn error(message: &str) -> String {
+    object(&[("type", string("error")), ("message", string(message))])
+}
+
+// the command line a line read stands for
+pub fn command(line: &str) -> Result<String, String> {
+    let line = line.trim();
+    if !line.starts_with('{') {
+        return Ok(line.to_string());
+    }
+    let message = json::parse(line).ok_or_else(|| format!("not json: {}", line))?;
+    let name = message
+        .str_at(&["type"])
+        .ok_or_else(|| format!("no type in {}", line))?;
+    let args = match name {
+        "move" => message
+            .str_at(&["move"])
+            .ok_or_else(|| format!("no move in {}", line))?,
+        _ => message.str_at(&["args"]).unwrap_or(""),
+    };
+    Ok(match name {
+        "move" => args.to_string(),
+        _ => format!("{} {}", name, args).trim_end().to_string(),
+    })
+}
+
+pub fn result(game: &Game) -> Option<String> {
+    let result = game.result?;
+    Some(object(&[
+        ("type", string("result")),
+        ("result", string(result.score())),
+        ("reason", string(&result.describe())),
+        ("summary", lines(&game.summary())),
+    ]))
+}
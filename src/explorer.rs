@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::display::DisplayOptions;
+use crate::pgn;
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Move};
+
+// the most moves listed for a position
+const SHOWN: usize = 10;
+
+// how often a move was played from a position and how those games ended
+struct Played {
+    mve: Move,
+    // white wins, draws and black wins
+    results: [u32; 3],
+}
+
+impl Played {
+    fn games(&self) -> u32 {
+        self.results.iter().sum()
+    }
+}
+
+// the moves played from every position of a folder of pgn games, by zobrist
+// key so games that transpose meet in the same position
+pub struct Explorer {
+    pub dir: PathBuf,
+    pub games: usize,
+    positions: HashMap<u64, Vec<Played>>,
+}
+
+impl Explorer {
+    // reads every pgn file in the folder, each may hold several games. games
+    // of other variants and ones that can't be replayed are left out, and so
+    // are the moves of unfinished ones
+    pub fn index(dir: &Path, variant: VariantKind) -> Result<Explorer, String> {
+        let files = std::fs::read_dir(dir)
+            .map_err(|e| format!("could not read {}: {}", dir.display(), e))?;
+        let mut paths: Vec<PathBuf> = files
+            .filter_map(|file| Some(file.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "pgn"))
+            .collect();
+        paths.sort();
+        let mut explorer = Explorer {
+            dir: dir.to_path_buf(),
+            games: 0,
+            positions: HashMap::new(),
+        };
+        for path in paths {
+            let Ok(text) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            for text in pgn::games(&text) {
+                let result = match pgn::result(&text) {
+                    Some("1-0") => 0,
+                    Some("1/2-1/2") => 1,
+                    Some("0-1") => 2,
+                    _ => continue,
+                };
+                let Ok(game) = pgn::read(&text, variant) else {
+                    continue;
+                };
+                if game.board.variant != variant {
+                    continue;
+                }
+                explorer.games += 1;
+                for played in &game.history {
+                    let moves = explorer
+                        .positions
+                        .entry(played.before.zobrist_key())
+                        .or_default();
+                    match moves.iter_mut().find(|p| p.mve == played.mve) {
+                        Some(p) => p.results[result] += 1,
+                        None => {
+                            let mut results = [0; 3];
+                            results[result] = 1;
+                            moves.push(Played {
+                                mve: played.mve,
+                                results,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(explorer)
+    }
+
+    // the moves played from the position, most often first, with how many
+    // games played them and how those ended in percent
+    pub fn lines(&self, board: &ChessBoard, options: &DisplayOptions) -> Vec<String> {
+        let Some(moves) = self.positions.get(&board.zobrist_key()) else {
+            return vec![String::from(
+                "explorer: no games went on from this position",
+            )];
+        };
+        let mut moves: Vec<&Played> = moves.iter().collect();
+        moves.sort_by_key(|p| std::cmp::Reverse(p.games()));
+        let total: u32 = moves.iter().map(|p| p.games()).sum();
+        let mut lines = vec![format!(
+            "explorer: {} {} from this position",
+            total,
+            if total == 1 { "game" } else { "games" }
+        )];
+        for played in moves.iter().take(SHOWN) {
+            let games = played.games();
+            let percent = |n: u32| n * 100 / games;
+            let [white, draws, black] = played.results;
+            lines.push(format!(
+                "  {:<8}{:>6}  white {:>3}%  draw {:>3}%  black {:>3}%",
+                options.move_text(&board.san(&played.mve), &played.mve),
+                games,
+                percent(white),
+                percent(draws),
+                percent(black)
+            ));
+        }
+        lines
+    }
+}
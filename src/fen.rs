@@ -0,0 +1,377 @@
+use std::num::{IntErrorKind, ParseIntError};
+
+use crate::castling::{self, LONG, SHORT};
+use crate::variant::VariantKind;
+use crate::{
+    BoardPos, ChessBoard, Color, Piece, PieceType, MAX_FILES, MAX_PIECES, MIN_FILES, RANKS,
+};
+
+// the most fields a fen has: placement, side to move, castling, en passant,
+// halfmove clock and move number
+const FIELDS: usize = 6;
+
+// "rank 8" for the first row of the placement
+fn rank_name(row: usize) -> String {
+    format!("rank {}", RANKS as usize - row)
+}
+
+// the halfmove clock or move number of a fen
+fn number(field: &str, name: &str) -> Result<u16, String> {
+    field.parse().map_err(|e: ParseIntError| match e.kind() {
+        IntErrorKind::PosOverflow => {
+            format!("{} {} is too large, at most {}", name, field, u16::MAX)
+        }
+        _ => format!("{} {} isn't a number", name, field),
+    })
+}
+
+// checks a fen the way tools want it checked: that it reads, and that the
+// position it describes could come up in a game
+pub fn validate_fen(fen: &str) -> Result<(), String> {
+    ChessBoard::parse_fen(fen)?.validate()
+}
+
+// the validate-fen subcommand: says valid, or why the fen isn't
+pub fn run(fen: &str) -> Result<(), String> {
+    validate_fen(fen).map_err(|e| format!("invalid fen: {}", e))?;
+    println!("valid");
+    Ok(())
+}
+
+impl ChessBoard {
+    pub fn from_fen(fen: &str) -> Option<Self> {
+        ChessBoard::parse_fen(fen).ok()
+    }
+
+    // reads the piece placement, side to move, castling rights, en passant
+    // square, halfmove clock and move number, saying what is wrong when it
+    // can't. castling is KQkq, or the rooks' files like HAha in chess960
+    pub fn parse_fen(fen: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() > FIELDS {
+            return Err(format!(
+                "{} fields, a fen has at most {}",
+                fields.len(),
+                FIELDS
+            ));
+        }
+        let field = |i: usize, missing: &'static str| fields.get(i).copied().unwrap_or(missing);
+        let placement = fields.first().ok_or("the fen is empty")?;
+        let turn = match field(1, "w") {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(format!("side to move {} isn't w or b", other)),
+        };
+        let castling = field(2, "-");
+        let en_passant = field(3, "-");
+        let halfmove = number(field(4, "0"), "halfmove clock")?;
+        let fullmove = number(field(5, "1"), "move number")?;
+        if fullmove == 0 {
+            return Err(String::from("move number 0 isn't a number above 0"));
+        }
+
+        let mut board = ChessBoard::empty();
+        board.turn = turn;
+        board.halfmove = halfmove;
+        board.fullmove = fullmove;
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != RANKS as usize {
+            return Err(format!(
+                "{} ranks in the placement, a board has {}",
+                ranks.len(),
+                RANKS
+            ));
+        }
+        // the first rank sets the width, the others have to match it
+        let mut width = None;
+        for (row, rank) in ranks.iter().enumerate() {
+            let too_long = || format!("{} is longer than {} files", rank_name(row), MAX_FILES);
+            let mut col: u8 = 0;
+            let mut chars = rank.chars().peekable();
+            while let Some(ch) = chars.next() {
+                if let Some(skip) = ch.to_digit(10) {
+                    if skip == 0 {
+                        return Err(format!(
+                            "{} has a run of empty squares starting with 0",
+                            rank_name(row)
+                        ));
+                    }
+                    // wider boards have runs of ten or more empty squares
+                    let mut skip = skip as u8;
+                    while let Some(digit) = chars.peek().and_then(|ch| ch.to_digit(10)) {
+                        skip = skip
+                            .checked_mul(10)
+                            .and_then(|skip| skip.checked_add(digit as u8))
+                            .ok_or_else(too_long)?;
+                        chars.next();
+                    }
+                    col = col.checked_add(skip).ok_or_else(too_long)?;
+                    continue;
+                }
+                if col >= MAX_FILES {
+                    return Err(too_long());
+                }
+                let piece = Piece::from_char(ch)
+                    .ok_or_else(|| format!("{} on {} isn't a piece", ch, rank_name(row)))?;
+                if board.lists[piece.color.idx()].len as usize == MAX_PIECES {
+                    return Err(format!(
+                        "{} has more than {} pieces",
+                        piece.color.name(),
+                        MAX_PIECES
+                    ));
+                }
+                board.put(Piece {
+                    pos: BoardPos {
+                        row: row as u8,
+                        col,
+                    },
+                    ..piece
+                });
+                col += 1;
+            }
+            if !(MIN_FILES..=MAX_FILES).contains(&col) {
+                return Err(format!(
+                    "{} has {} files, a board has {} to {}",
+                    rank_name(row),
+                    col,
+                    MIN_FILES,
+                    MAX_FILES
+                ));
+            }
+            let first = *width.get_or_insert(col);
+            if first != col {
+                return Err(format!(
+                    "{} has {} files but rank {} has {}",
+                    rank_name(row),
+                    col,
+                    RANKS,
+                    first
+                ));
+            }
+        }
+        board.width = width.ok_or("the placement is empty")?;
+
+        // pieces missing from the starting set are counted as captured, the
+        // capablanca one on a board ten files wide
+        let start = match board.width {
+            10 => VariantKind::Capablanca.rules().initial(),
+            _ => ChessBoard::new(),
+        };
+        for color in [Color::White, Color::Black] {
+            for piece in PieceType::ALL {
+                let count = |b: &ChessBoard| b.pieces(color).filter(|p| p.piece == piece).count();
+                let missing = count(&start).saturating_sub(count(&board));
+                board.captured[color.other().idx()][piece.idx()] = missing as u8;
+            }
+        }
+
+        if castling != "-" {
+            for ch in castling.chars() {
+                board.castle_flag(ch)?;
+            }
+        }
+        board.read_en_passant(en_passant)?;
+        Ok(board)
+    }
+
+    // whether a game can start from the position: one king a side where the
+    // king is royal, no pawns on the first or last rank, and the side that
+    // just moved not left in check. the en passant square is checked as the
+    // fen is read
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rules().royal_king() {
+            for color in [Color::White, Color::Black] {
+                let kings = self
+                    .pieces(color)
+                    .filter(|p| p.piece == PieceType::King)
+                    .count();
+                if kings != 1 {
+                    return Err(format!("{} needs exactly one king", color.name()));
+                }
+            }
+        }
+        if let Some(pawn) = [Color::White, Color::Black]
+            .into_iter()
+            .flat_map(|color| self.pieces(color))
+            .find(|p| p.piece == PieceType::Pawn && (p.pos.row == 0 || p.pos.row == RANKS - 1))
+        {
+            return Err(format!("a pawn can't stand on {}", pawn.pos));
+        }
+        if self.in_check(self.turn.other()) {
+            return Err(format!(
+                "{} is in check but it isn't its move",
+                self.turn.other().name()
+            ));
+        }
+        Ok(())
+    }
+
+    // the castling right of one letter of the castling field
+    fn castle_flag(&mut self, ch: char) -> Result<(), String> {
+        let color = match ch.is_ascii_uppercase() {
+            true => Color::White,
+            false => Color::Black,
+        };
+        let no_rook = || {
+            format!(
+                "castling flag {}: {} has no rook to castle with there",
+                ch,
+                color.name()
+            )
+        };
+        let king = self
+            .king_pos(color)
+            .filter(|pos| pos.row == castling::back_row(color))
+            .ok_or_else(|| {
+                format!(
+                    "castling flag {}: {}'s king isn't on its back rank",
+                    ch,
+                    color.name()
+                )
+            })?;
+        let (side, col) = match ch.to_ascii_lowercase() {
+            'k' => (SHORT, self.outer_rook(color, SHORT).ok_or_else(no_rook)?),
+            'q' => (LONG, self.outer_rook(color, LONG).ok_or_else(no_rook)?),
+            file @ 'a'..='l' if file as u8 - b'a' < self.width => {
+                let col = file as u8 - b'a';
+                match self.piece_at(BoardPos { row: king.row, col }) {
+                    Some(rook) if rook.color == color && rook.piece == PieceType::Rook => {}
+                    _ => return Err(no_rook()),
+                }
+                match col > king.col {
+                    true => (SHORT, col),
+                    false => (LONG, col),
+                }
+            }
+            _ => return Err(format!("invalid castling flag {}", ch)),
+        };
+        if self.castling[color.idx()][side].is_some() {
+            return Err(format!(
+                "castling flag {}: {} already castles to that side",
+                ch,
+                color.name()
+            ));
+        }
+        self.castling[color.idx()][side] = Some(col);
+        Ok(())
+    }
+
+    // the en passant field names the square a pawn skipped with its double
+    // step, so the pawn has to stand in front of it, with the square and the
+    // one the pawn came from empty. the board only keeps it while a pawn can
+    // take there
+    pub fn read_en_passant(&mut self, field: &str) -> Result<(), String> {
+        if field == "-" {
+            return Ok(());
+        }
+        let square = BoardPos::parse(field)
+            .filter(|pos| pos.col < self.width)
+            .ok_or_else(|| format!("en passant square {} isn't a square", field))?;
+        let mover = self.turn.other();
+        // the skipped square is on the third rank from the mover's side
+        let (skipped, landed, from) = match mover {
+            Color::White => (5, 4, 6),
+            Color::Black => (2, 3, 1),
+        };
+        if square.row != skipped {
+            return Err(format!(
+                "en passant square {} isn't possible with {} to move, it has to be on rank {}",
+                field,
+                self.turn.name(),
+                RANKS - skipped
+            ));
+        }
+        let at = |row| BoardPos {
+            row,
+            col: square.col,
+        };
+        let pawn = self
+            .piece_at(at(landed))
+            .is_some_and(|p| p.piece == PieceType::Pawn && p.color == mover);
+        if !pawn {
+            return Err(format!(
+                "en passant square {}: no {} pawn on {} that could have just moved past it",
+                field,
+                mover.name(),
+                at(landed)
+            ));
+        }
+        if self.piece_at(square).is_some() || self.piece_at(at(from)).is_some() {
+            return Err(format!(
+                "en passant square {}: the pawn on {} couldn't have come from {}",
+                field,
+                at(landed),
+                at(from)
+            ));
+        }
+        self.en_passant = self.en_passant_square(at(landed));
+        Ok(())
+    }
+
+    // the castling field: K and Q for the outermost rooks, the rook's file
+    // otherwise, which only happens in chess960
+    fn castling_fen(&self) -> String {
+        let mut field = String::new();
+        for color in [Color::White, Color::Black] {
+            for side in [SHORT, LONG] {
+                let Some(col) = self.castling[color.idx()][side] else {
+                    continue;
+                };
+                let ch = match (self.outer_rook(color, side) == Some(col), side) {
+                    (true, SHORT) => 'k',
+                    (true, _) => 'q',
+                    (false, _) => (b'a' + col) as char,
+                };
+                field.push(match color {
+                    Color::White => ch.to_ascii_uppercase(),
+                    Color::Black => ch,
+                });
+            }
+        }
+        if field.is_empty() {
+            field.push('-');
+        }
+        field
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for row in 0..RANKS {
+            let mut empty = 0;
+            for col in 0..self.width {
+                match self.piece_at(BoardPos { row, col }) {
+                    Some(p) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(p.to_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if row < RANKS - 1 {
+                placement.push('/');
+            }
+        }
+        let turn = match self.turn {
+            Color::White => 'w',
+            Color::Black => 'b',
+        };
+        let en_passant = self
+            .en_passant
+            .map_or(String::from("-"), |pos| pos.to_string());
+        format!(
+            "{} {} {} {} {} {}",
+            placement,
+            turn,
+            self.castling_fen(),
+            en_passant,
+            self.halfmove,
+            self.fullmove
+        )
+    }
+}
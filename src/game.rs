@@ -0,0 +1,450 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::Review;
+use crate::clock::{self, Clock};
+use crate::display::DisplayOptions;
+use crate::locale::Locale;
+use crate::variant;
+use crate::{ChessBoard, Color, Move, Piece};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameResult {
+    // the color is the winner
+    Checkmate(Color),
+    Resignation(Color),
+    Stalemate,
+    DrawAgreed,
+    // drawn because the side to move claimed it
+    DrawClaimed(DrawClaim),
+    // the color is the winner, the other side ran out of time
+    Timeout(Color),
+    // the color ran out of time, but the other side can't mate
+    TimeoutDraw(Color),
+    // ended early by the rules of an engine match
+    Adjudicated(Adjudication),
+    // a draw in an armageddon game, which black wins
+    ArmageddonDraw,
+    // the color is the winner, it has no pieces or moves left in antichess
+    NoMoves(Color),
+}
+
+// why an engine game was ended early
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Adjudication {
+    // the color is the winner, the other side's score stayed hopeless or the
+    // endgame tables have it lost
+    Win(Color),
+    // the score stayed close to even, or the endgame tables have it drawn
+    Draw,
+    // neither side has the pieces left to mate
+    Material,
+}
+
+// why the side to move may claim a draw
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DrawClaim {
+    Repetition,
+    FiftyMoves,
+}
+
+impl DrawClaim {
+    pub fn describe(self) -> &'static str {
+        match self {
+            DrawClaim::Repetition => "threefold repetition",
+            DrawClaim::FiftyMoves => "the fifty-move rule",
+        }
+    }
+}
+
+impl GameResult {
+    // the result as pgn writes it: 1-0, 0-1 or 1/2-1/2
+    pub fn score(self) -> &'static str {
+        match self.winner() {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+
+    pub fn winner(self) -> Option<Color> {
+        match self {
+            GameResult::Checkmate(c)
+            | GameResult::Resignation(c)
+            | GameResult::Timeout(c)
+            | GameResult::Adjudicated(Adjudication::Win(c))
+            | GameResult::NoMoves(c) => Some(c),
+            GameResult::ArmageddonDraw => Some(Color::Black),
+            GameResult::Stalemate
+            | GameResult::DrawAgreed
+            | GameResult::DrawClaimed(_)
+            | GameResult::TimeoutDraw(_)
+            | GameResult::Adjudicated(_) => None,
+        }
+    }
+
+    // in english, as pgn, logs and scripts get it
+    pub fn describe(self) -> String {
+        Locale::En.result(self)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayedMove {
+    pub mve: Move,
+    pub san: String,
+    // the position before the move, so it can be taken back
+    pub before: ChessBoard,
+    // how long the mover thought, when playing with clocks
+    pub elapsed: Option<Duration>,
+    // what the engine made of the move, once the game has been analyzed
+    pub review: Option<Review>,
+    // the engine's evaluation of the position after the move from white's
+    // side, when it was shown while playing
+    pub eval: Option<i32>,
+}
+
+// moves tried instead of the game's own, from the position after ply of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variation {
+    pub ply: usize,
+    pub moves: Vec<Move>,
+}
+
+// code that follows a game as it is played, like the announcer, instead of
+// looking for new moves in the history. each is told right after the move or
+// the end, with the game as it is then
+pub trait Observer: Send + Sync {
+    fn on_move(&self, _game: &Game, _played: &PlayedMove) {}
+    // after on_move, with the piece taken
+    fn on_capture(&self, _game: &Game, _played: &PlayedMove, _taken: Piece) {}
+    // after on_move, with the side in check
+    fn on_check(&self, _game: &Game, _color: Color) {}
+    fn on_game_end(&self, _game: &Game, _result: GameResult) {}
+}
+
+// the observers of a game. a copy of the game is followed by the same ones
+#[derive(Clone, Default)]
+pub struct Observers(Vec<Arc<dyn Observer>>);
+
+impl std::fmt::Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} observers", self.0.len())
+    }
+}
+
+// a board together with the moves that led to it
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub board: ChessBoard,
+    pub history: Vec<PlayedMove>,
+    // moves taken back, the last one is replayed first by redo
+    pub undone: Vec<Move>,
+    pub result: Option<GameResult>,
+    // only when playing with a time control
+    pub clock: Option<Clock>,
+    // a draw counts as a win for black
+    pub armageddon: bool,
+    // side lines explored off the game, kept with it
+    pub variations: Vec<Variation>,
+    pub observers: Observers,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Game::from_board(ChessBoard::new())
+    }
+
+    // chess960 starting position n
+    pub fn chess960(n: u16) -> Self {
+        Game::from_board(variant::chess960(n))
+    }
+
+    // the position the game started from
+    pub fn start(&self) -> &ChessBoard {
+        self.history
+            .first()
+            .map_or(&self.board, |played| &played.before)
+    }
+
+    pub fn from_board(board: ChessBoard) -> Self {
+        let mut game = Game {
+            board,
+            history: Vec::new(),
+            undone: Vec::new(),
+            result: None,
+            clock: None,
+            armageddon: false,
+            variations: Vec::new(),
+            observers: Observers::default(),
+        };
+        game.update_result();
+        game
+    }
+
+    pub fn observe(&mut self, observer: Arc<dyn Observer>) {
+        self.observers.0.push(observer);
+    }
+
+    // puts another game in this one's place, followed by the same observers
+    pub fn replace(&mut self, mut game: Game) {
+        game.observers = std::mem::take(&mut self.observers);
+        *self = game;
+    }
+
+    // ends the game, in armageddon with a win for black instead of a draw
+    pub fn end(&mut self, result: GameResult) {
+        self.finish(result);
+        self.tell_end();
+    }
+
+    // sets the result without telling the observers, a move that ends the
+    // game is told about first
+    fn finish(&mut self, result: GameResult) {
+        self.result = Some(match result.winner() {
+            None if self.armageddon => GameResult::ArmageddonDraw,
+            _ => result,
+        });
+    }
+
+    fn tell_end(&self) {
+        if let Some(result) = self.result {
+            for observer in &self.observers.0 {
+                observer.on_game_end(self, result);
+            }
+        }
+    }
+
+    // tells the observers about the last move played
+    fn tell_move(&self) {
+        let Some(played) = self.history.last() else {
+            return;
+        };
+        let taken = played
+            .before
+            .piece_at(played.mve.to)
+            .filter(|_| played.before.is_capture(&played.mve));
+        let check = self.board.in_check(self.board.turn);
+        for observer in &self.observers.0 {
+            observer.on_move(self, played);
+            if let Some(taken) = taken {
+                observer.on_capture(self, played, taken);
+            }
+            if check {
+                observer.on_check(self, self.board.turn);
+            }
+        }
+    }
+
+    // ends the game once the side to move has no time left, true if it did
+    pub fn check_time(&mut self) -> bool {
+        let turn = self.board.turn;
+        let Some(clock) = &mut self.clock else {
+            return false;
+        };
+        if self.result.is_some() || !clock.remaining(turn).is_zero() {
+            return false;
+        }
+        clock.stop();
+        self.end(if self.board.has_mating_material(turn.other()) {
+            GameResult::Timeout(turn.other())
+        } else {
+            GameResult::TimeoutDraw(turn)
+        });
+        true
+    }
+
+    // plays the move if it is legal and in time, and records it
+    pub fn play(&mut self, mve: &Move) -> bool {
+        if self.check_time() || self.result.is_some() || !self.board.is_legal(mve) {
+            return false;
+        }
+        let before = self.board.clone();
+        let san = self.board.san(mve);
+        self.board.make_move(mve);
+        self.undone.clear();
+        self.update_result();
+        let elapsed = self.clock.as_mut().and_then(|clock| match self.result {
+            Some(_) => clock.stop(),
+            // the clock runs on through a turn of several moves
+            None if self.board.turn == before.turn => None,
+            None => clock.switch(self.board.turn),
+        });
+        self.history.push(PlayedMove {
+            mve: *mve,
+            san,
+            before,
+            elapsed,
+            review: None,
+            eval: None,
+        });
+        self.tell_move();
+        self.tell_end();
+        true
+    }
+
+    // takes back the last move, false if there is nothing to take back
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(played) => {
+                self.board = played.before;
+                // side lines from the position taken back go with it
+                let plies = self.history.len();
+                self.variations.retain(|v| v.ply <= plies);
+                self.undone.push(played.mve);
+                self.result = None;
+                if let Some(clock) = &mut self.clock {
+                    clock.switch(self.board.turn);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    // plays the last taken back move again, false if there is none
+    pub fn redo(&mut self) -> bool {
+        let Some(mve) = self.undone.pop() else {
+            return false;
+        };
+        // playing clears what was undone, but the rest can still be redone
+        let undone = std::mem::take(&mut self.undone);
+        let played = self.play(&mve);
+        self.undone = undone;
+        played
+    }
+
+    // how often the position on the board appeared in the game, this time
+    // included
+    pub fn repetitions(&self) -> usize {
+        let key = self.board.zobrist_key();
+        1 + self
+            .history
+            .iter()
+            .filter(|played| played.before.zobrist_key() == key)
+            .count()
+    }
+
+    // the plies since the last capture or pawn move
+    pub fn halfmove_clock(&self) -> usize {
+        self.board.halfmove.into()
+    }
+
+    // a draw the side to move could claim: the position appeared three times,
+    // or fifty moves each went by without a capture or pawn move
+    pub fn draw_claim(&self) -> Option<DrawClaim> {
+        if self.repetitions() >= 3 {
+            return Some(DrawClaim::Repetition);
+        }
+        (self.halfmove_clock() >= 100).then_some(DrawClaim::FiftyMoves)
+    }
+
+    // whose turn it is and whether it is check, or how the game ended
+    pub fn status(&self) -> String {
+        Locale::En.status(self)
+    }
+
+    // ends the game when the side to move has no legal moves left
+    fn update_result(&mut self) {
+        if self.board.legal_moves().is_empty() {
+            self.finish(self.board.rules().no_moves(&self.board));
+        }
+    }
+
+    // the numbers behind the finished game: moves, captures, thinking times
+    // when there were clocks, and the material left
+    pub fn summary(&self) -> Vec<String> {
+        let by = |color: Color| self.history.iter().filter(move |p| p.before.turn == color);
+        let captures = |color: Color| by(color).filter(|p| p.before.is_capture(&p.mve)).count();
+        let mut lines = vec![
+            format!("moves: {}", self.full_moves().count()),
+            format!(
+                "captures: White {}, Black {}",
+                captures(Color::White),
+                captures(Color::Black)
+            ),
+        ];
+        if self.history.iter().any(|p| p.elapsed.is_some()) {
+            let average = |color: Color| {
+                let times: Vec<Duration> = by(color).filter_map(|p| p.elapsed).collect();
+                match times.len() {
+                    0 => String::from("-"),
+                    n => clock::format_elapsed(times.iter().sum::<Duration>() / n as u32),
+                }
+            };
+            lines.push(format!(
+                "average time per move: White {}, Black {}",
+                average(Color::White),
+                average(Color::Black)
+            ));
+            let longest = self
+                .history
+                .iter()
+                .enumerate()
+                .filter_map(|(i, p)| p.elapsed.map(|elapsed| (i, p, elapsed)))
+                .max_by_key(|&(_, _, elapsed)| elapsed);
+            if let Some((i, played, elapsed)) = longest {
+                lines.push(format!(
+                    "longest think: {} by {} on move {}, {}",
+                    clock::format_elapsed(elapsed),
+                    played.before.turn.name(),
+                    i / 2 + 1,
+                    played.san
+                ));
+            }
+        }
+        lines.push(format!("final {}", self.board.material_line()));
+        lines
+    }
+
+    // the time the mover had left after every move, replayed from the time
+    // controls and how long each move took. none without clocks, and for the
+    // moves in the middle of a turn
+    pub fn clock_times(&self) -> Vec<Option<Duration>> {
+        let Some(clock) = &self.clock else {
+            return vec![None; self.history.len()];
+        };
+        let mut left = clock.controls.map(|control| control.base);
+        self.history
+            .iter()
+            .map(|played| {
+                let side = played.before.turn.idx();
+                let elapsed = played.elapsed?;
+                left[side] = clock.controls[side].spend(left[side], elapsed);
+                Some(left[side])
+            })
+            .collect()
+    }
+
+    // the moves as numbered pairs, one line per full move: "1. e4 e5", with
+    // the time each took when playing with clocks: "1. e4 (2.1s) e5 (4.0s)"
+    pub fn history_lines(&self, options: &DisplayOptions) -> Vec<String> {
+        let san = |played: &PlayedMove| {
+            let mark = played.review.as_ref().map_or("", |r| r.quality.symbol());
+            let text = format!("{}{}", options.move_text(&played.san, &played.mve), mark);
+            match played.elapsed {
+                Some(elapsed) => format!("{} ({})", text, clock::format_elapsed(elapsed)),
+                None => text,
+            }
+        };
+        self.full_moves()
+            .enumerate()
+            .map(|(i, moves)| {
+                let moves: Vec<String> = moves.iter().map(san).collect();
+                format!("{}. {}", i + 1, moves.join(" "))
+            })
+            .collect()
+    }
+
+    // the moves grouped into full moves, each starting with a white turn
+    // unless the game started with black to move
+    fn full_moves(&self) -> impl Iterator<Item = &[PlayedMove]> {
+        self.history
+            .chunk_by(|_, next| next.before.turn == Color::Black || next.before.turn_moves > 0)
+    }
+}
@@ -0,0 +1,315 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tonic::{Request, Response, Status};
+
+use crate::engine;
+use crate::game::Game as Played;
+use crate::variant::VariantKind;
+use crate::ChessBoard;
+
+// the deepest the engine searches for a client, as every ply more multiplies
+// the time a request keeps a thread busy
+const MOST_DEPTH: u32 = 8;
+const DEFAULT_DEPTH: u32 = 4;
+
+// the messages of proto/chess.proto, written out as prost would generate them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+#[repr(i32)]
+pub enum Variant {
+    Standard = 0,
+    Chess960 = 1,
+    Antichess = 2,
+    Capablanca = 3,
+    Marseillais = 4,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Position {
+    #[prost(string, tag = "1")]
+    pub fen: String,
+    #[prost(enumeration = "Variant", tag = "2")]
+    pub variant: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Move {
+    #[prost(string, tag = "1")]
+    pub uci: String,
+    #[prost(string, tag = "2")]
+    pub san: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Game {
+    #[prost(message, optional, tag = "1")]
+    pub start: Option<Position>,
+    #[prost(message, repeated, tag = "2")]
+    pub moves: Vec<Move>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ValidateMoveRequest {
+    #[prost(message, optional, tag = "1")]
+    pub position: Option<Position>,
+    #[prost(message, optional, tag = "2")]
+    pub r#move: Option<Move>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ValidateMoveReply {
+    #[prost(bool, tag = "1")]
+    pub legal: bool,
+    #[prost(string, tag = "2")]
+    pub reason: String,
+    #[prost(message, optional, tag = "3")]
+    pub after: Option<Position>,
+    #[prost(message, optional, tag = "4")]
+    pub r#move: Option<Move>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MoveList {
+    #[prost(message, repeated, tag = "1")]
+    pub moves: Vec<Move>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AnalyseRequest {
+    #[prost(message, optional, tag = "1")]
+    pub position: Option<Position>,
+    #[prost(uint32, tag = "2")]
+    pub depth: u32,
+    #[prost(uint32, tag = "3")]
+    pub time_ms: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Analysis {
+    #[prost(message, optional, tag = "1")]
+    pub best: Option<Move>,
+    #[prost(int32, tag = "2")]
+    pub score: i32,
+    #[prost(string, tag = "3")]
+    pub score_text: String,
+    #[prost(uint32, tag = "4")]
+    pub depth: u32,
+    #[prost(message, repeated, tag = "5")]
+    pub line: Vec<Move>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GameState {
+    #[prost(message, optional, tag = "1")]
+    pub position: Option<Position>,
+    #[prost(message, repeated, tag = "2")]
+    pub legal_moves: Vec<Move>,
+    #[prost(string, tag = "3")]
+    pub result: String,
+    #[prost(string, tag = "4")]
+    pub status: String,
+}
+
+// the server trait and service that build.rs generates from the proto
+include!(concat!(env!("OUT_DIR"), "/chess.Chess.rs"));
+
+fn kind(variant: Variant) -> VariantKind {
+    match variant {
+        Variant::Standard => VariantKind::Standard,
+        Variant::Chess960 => VariantKind::Chess960,
+        Variant::Antichess => VariantKind::Antichess,
+        Variant::Capablanca => VariantKind::Capablanca,
+        Variant::Marseillais => VariantKind::Marseillais,
+    }
+}
+
+fn variant(kind: VariantKind) -> Variant {
+    match kind {
+        VariantKind::Standard => Variant::Standard,
+        VariantKind::Chess960 => Variant::Chess960,
+        VariantKind::Antichess => Variant::Antichess,
+        VariantKind::Capablanca => Variant::Capablanca,
+        VariantKind::Marseillais => Variant::Marseillais,
+    }
+}
+
+// the board of a position, the variant's starting one when the fen is empty
+fn board(position: Option<Position>) -> Result<ChessBoard, Status> {
+    let position = position.unwrap_or_default();
+    let kind = Variant::try_from(position.variant)
+        .map(kind)
+        .map_err(|_| Status::invalid_argument("unknown variant"))?;
+    if position.fen.is_empty() {
+        return Ok(kind.rules().initial());
+    }
+    ChessBoard::parse_fen(&position.fen)
+        .map(|board| board.with_variant(kind))
+        .and_then(|board| board.validate().map(|_| board))
+        .map_err(|e| Status::invalid_argument(format!("invalid fen: {}", e)))
+}
+
+fn position(board: &ChessBoard) -> Position {
+    Position {
+        fen: board.to_fen(),
+        variant: variant(board.variant).into(),
+    }
+}
+
+// a move both ways it can be written, castling as the king's move outside
+// chess960 like the rest of the program writes it for engines
+fn mve(board: &ChessBoard, mve: &crate::Move) -> Move {
+    let uci = match board.variant {
+        VariantKind::Chess960 => mve.to_string(),
+        _ => board.standard_uci(mve),
+    };
+    Move {
+        uci,
+        san: board.san(mve),
+    }
+}
+
+fn moves(board: &ChessBoard) -> Vec<Move> {
+    board.legal_moves().iter().map(|m| mve(board, m)).collect()
+}
+
+// the moves of a line, each written in the position it is played from
+fn line(board: &ChessBoard, pv: &[crate::Move]) -> Vec<Move> {
+    let mut board = board.clone();
+    pv.iter()
+        .map(|m| {
+            let written = mve(&board, m);
+            board.make_move(m);
+            written
+        })
+        .collect()
+}
+
+fn analyse(board: &ChessBoard, depth: u8, time_ms: u32) -> Option<Analysis> {
+    let (pv, score, depth) = if time_ms > 0 {
+        engine::think_line(board, Duration::from_millis(time_ms.into()), depth)?
+    } else {
+        let mut pv = Vec::new();
+        let (_, score) = engine::deepen(board, depth, |_, line, _| pv = line.to_vec())?;
+        (pv, score, depth)
+    };
+    let score = engine::white_score(board.turn, score);
+    let line = line(board, &pv);
+    Some(Analysis {
+        best: line.first().cloned(),
+        score,
+        score_text: engine::format_score(score),
+        depth: depth.into(),
+        line,
+    })
+}
+
+fn play(game: Game) -> Result<GameState, Status> {
+    let mut played = Played::from_board(board(game.start)?);
+    for (i, m) in game.moves.iter().enumerate() {
+        let board = &played.board;
+        let legal = board.parse_uci(&m.uci).filter(|m| board.is_legal(m));
+        if played.result.is_some() || !legal.is_some_and(|m| played.play(&m)) {
+            return Err(Status::invalid_argument(format!(
+                "move {}, {}, isn't legal",
+                i + 1,
+                m.uci
+            )));
+        }
+    }
+    Ok(GameState {
+        position: Some(position(&played.board)),
+        legal_moves: match played.result {
+            Some(_) => Vec::new(),
+            None => moves(&played.board),
+        },
+        result: played.result.map_or("*", |r| r.score()).to_string(),
+        status: played.status(),
+    })
+}
+
+struct Service;
+
+#[tonic::async_trait]
+impl chess_server::Chess for Service {
+    async fn validate_move(
+        &self,
+        request: Request<ValidateMoveRequest>,
+    ) -> Result<Response<ValidateMoveReply>, Status> {
+        let request = request.into_inner();
+        let board = board(request.position)?;
+        let uci = request.r#move.unwrap_or_default().uci;
+        let reply = match board.parse_uci(&uci) {
+            Some(m) if board.is_legal(&m) => {
+                let mut after = board.clone();
+                after.make_move(&m);
+                ValidateMoveReply {
+                    legal: true,
+                    reason: String::new(),
+                    after: Some(position(&after)),
+                    r#move: Some(mve(&board, &m)),
+                }
+            }
+            Some(_) => ValidateMoveReply {
+                reason: format!("{} isn't a legal move here", uci),
+                ..Default::default()
+            },
+            None => ValidateMoveReply {
+                reason: format!("{} isn't a move in uci notation, like e2e4", uci),
+                ..Default::default()
+            },
+        };
+        Ok(Response::new(reply))
+    }
+
+    async fn legal_moves(&self, request: Request<Position>) -> Result<Response<MoveList>, Status> {
+        let board = board(Some(request.into_inner()))?;
+        Ok(Response::new(MoveList {
+            moves: moves(&board),
+        }))
+    }
+
+    async fn analyse(
+        &self,
+        request: Request<AnalyseRequest>,
+    ) -> Result<Response<Analysis>, Status> {
+        let request = request.into_inner();
+        let board = board(request.position)?;
+        let depth = match request.depth {
+            0 => DEFAULT_DEPTH,
+            depth @ 1..=MOST_DEPTH => depth,
+            _ => {
+                return Err(Status::invalid_argument(format!(
+                    "the depth can be at most {}",
+                    MOST_DEPTH
+                )))
+            }
+        };
+        // the search is long work, kept off the threads serving requests
+        let time_ms = request.time_ms;
+        let analysis = tokio::task::spawn_blocking(move || analyse(&board, depth as u8, time_ms))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        analysis
+            .map(Response::new)
+            .ok_or_else(|| Status::failed_precondition("the side to move has no legal moves"))
+    }
+
+    async fn play_game(&self, request: Request<Game>) -> Result<Response<GameState>, Status> {
+        play(request.into_inner()).map(Response::new)
+    }
+}
+
+// serves the Chess service of proto/chess.proto until the process is stopped
+pub fn run(port: u16) -> Result<(), String> {
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| format!("could not start the grpc server: {}", e))?;
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    println!("serving grpc on 0.0.0.0:{}", port);
+    runtime
+        .block_on(
+            tonic::transport::Server::builder()
+                .add_service(chess_server::ChessServer::new(Service))
+                .serve(addr),
+        )
+        .map_err(|e| format!("could not serve grpc on port {}: {}", port, e))
+}
@@ -0,0 +1,151 @@
+use crate::game::Game;
+use crate::{BoardPos, Color, RANKS, SQUARES};
+
+// pixels per square of the svg, and the gap between the two boards
+const SQUARE: usize = 48;
+const GAP: usize = 32;
+
+// how often each side's pieces landed on every square, and how many of those
+// moves took something
+pub struct Heatmap {
+    width: u8,
+    moves: [[u16; SQUARES]; 2],
+    captures: [[u16; SQUARES]; 2],
+}
+
+impl Heatmap {
+    pub fn new(game: &Game) -> Heatmap {
+        let mut map = Heatmap {
+            width: game.board.width,
+            moves: [[0; SQUARES]; 2],
+            captures: [[0; SQUARES]; 2],
+        };
+        for played in &game.history {
+            let board = &played.before;
+            let side = board.turn.idx();
+            // castling counts for the square the king ends up on
+            let to = match board.castling_side(&played.mve) {
+                Some(side) => BoardPos {
+                    row: played.mve.to.row,
+                    col: board.castling_targets(side).0,
+                },
+                None => played.mve.to,
+            };
+            map.moves[side][to.to_idx()] += 1;
+            // the san tells en passant apart, where the target square is empty
+            if played.san.contains('x') {
+                map.captures[side][to.to_idx()] += 1;
+            }
+        }
+        map
+    }
+
+    fn squares(&self) -> impl Iterator<Item = BoardPos> {
+        let width = self.width;
+        (0..RANKS).flat_map(move |row| (0..width).map(move |col| BoardPos { row, col }))
+    }
+
+    // one board of counts, a dot where nothing went
+    fn grid(&self, counts: &[u16; SQUARES]) -> Vec<String> {
+        let mut lines: Vec<String> = (0..RANKS)
+            .map(|row| {
+                let cells: String = (0..self.width)
+                    .map(|col| match counts[BoardPos { row, col }.to_idx()] {
+                        0 => String::from("  ."),
+                        n => format!("{:>3}", n),
+                    })
+                    .collect();
+                format!("{} {}", RANKS - row, cells)
+            })
+            .collect();
+        let files: String = (0..self.width)
+            .map(|col| format!("  {}", (b'a' + col) as char))
+            .collect();
+        lines.push(format!("  {}", files));
+        lines
+    }
+
+    // for each side the moves and the captures on every square, side by side
+    pub fn text(&self) -> Vec<String> {
+        let column = 2 + 3 * self.width as usize + 4;
+        let mut lines = Vec::new();
+        for color in [Color::White, Color::Black] {
+            if color == Color::Black {
+                lines.push(String::new());
+            }
+            let heading = format!("{} moved to", color.name());
+            lines.push(format!("{:<column$}{} captured on", heading, color.name()));
+            let moves = self.grid(&self.moves[color.idx()]);
+            let captures = self.grid(&self.captures[color.idx()]);
+            for (left, right) in moves.iter().zip(&captures) {
+                lines.push(format!("{:<column$}{}", left, right));
+            }
+        }
+        lines
+    }
+
+    // the two sides' boards next to each other, each square tinted by how
+    // often the side landed there, with the count on it and a ring when there
+    // were captures
+    pub fn svg(&self) -> String {
+        let board = SQUARE * self.width as usize;
+        let top = SQUARE / 2;
+        let total = 2 * board + GAP;
+        let height = top + RANKS as usize * SQUARE;
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\">\n",
+            total, height
+        );
+        for color in [Color::White, Color::Black] {
+            let left = color.idx() * (board + GAP);
+            let moves = &self.moves[color.idx()];
+            let captures = &self.captures[color.idx()];
+            let most = *moves.iter().max().unwrap_or(&0);
+            out.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-size=\"16\">{}</text>\n",
+                left,
+                top - 8,
+                color.name()
+            ));
+            for pos in self.squares() {
+                let x = left + pos.col as usize * SQUARE;
+                let y = top + pos.row as usize * SQUARE;
+                let fill = match (pos.row + pos.col) % 2 {
+                    0 => "#f0d9b5",
+                    _ => "#b58863",
+                };
+                out.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\"/>\n",
+                    x, y, SQUARE, fill
+                ));
+                let count = moves[pos.to_idx()];
+                if count == 0 {
+                    continue;
+                }
+                out.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"#e03020\" fill-opacity=\"{3:.2}\"/>\n",
+                    x,
+                    y,
+                    SQUARE,
+                    0.15 + 0.7 * count as f32 / most as f32
+                ));
+                if captures[pos.to_idx()] > 0 {
+                    out.push_str(&format!(
+                        "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"#202020\" stroke-width=\"2\"/>\n",
+                        x + SQUARE / 2,
+                        y + SQUARE / 2,
+                        SQUARE / 2 - 4
+                    ));
+                }
+                out.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"16\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                    x + SQUARE / 2,
+                    y + SQUARE / 2,
+                    count
+                ));
+            }
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use crate::analysis;
+use crate::engine;
+use crate::rng::Rng;
+use crate::{ChessBoard, Color, Move, PieceType};
+
+// the built-in engine playing like a person of about this rating: it looks
+// less far ahead, picks among the moves a person would consider, weaker ones
+// the lower the rating, and takes its time over them
+#[derive(Debug, Clone, Copy)]
+pub struct Persona {
+    pub rating: u32,
+}
+
+impl Persona {
+    // a beginner doesn't look at the replies to its moves, a strong player
+    // looks a few moves further
+    fn depth(self) -> u8 {
+        match self.rating {
+            ..1000 => 1,
+            1000..1500 => 2,
+            1500..2000 => 3,
+            _ => 4,
+        }
+    }
+
+    // centipawns a move may be worse than the best and still be picked a
+    // third as often, if it looks as natural
+    fn spread(self) -> f64 {
+        5.0 + (2600.0 - self.rating.clamp(600, 2600) as f64) / 10.0
+    }
+
+    // the move to play after last, and how long to take over it with left
+    // on the clock, looking no further than depth. none when there is no
+    // legal move
+    pub fn choose(
+        self,
+        board: &ChessBoard,
+        last: Option<&Move>,
+        left: Option<Duration>,
+        depth: u8,
+        rng: &mut Rng,
+    ) -> Option<(Move, Duration)> {
+        let ranked = engine::rank(board, self.depth().min(depth));
+        // past a rook up every win looks alike, so a won position can be
+        // won the long way and a mate missed
+        let best = analysis::capped(ranked.first()?.1);
+        let spread = self.spread();
+        let weights: Vec<f64> = ranked
+            .iter()
+            .map(|(mve, score)| {
+                let worse = (best - analysis::capped(*score)) as f64;
+                (-worse / spread).exp() * natural(board, mve, last)
+            })
+            .collect();
+        let mut pick = rng.unit() * weights.iter().sum::<f64>();
+        let chosen = weights
+            .iter()
+            .position(|weight| {
+                pick -= weight;
+                pick <= 0.0
+            })
+            .unwrap_or(0);
+        // the moves about as good as the best are what there is to think about
+        let candidates = ranked
+            .iter()
+            .filter(|(_, score)| ((best - analysis::capped(*score)) as f64) < spread)
+            .count();
+        Some((ranked[chosen].0, think_time(candidates, left, rng)))
+    }
+}
+
+// how much more often a person considers the move than a quiet one: taking
+// back what was just taken above all, then other captures and checks.
+// retreats are easy to overlook, and nobody walks their king about while
+// there are queens to fear
+fn natural(board: &ChessBoard, mve: &Move, last: Option<&Move>) -> f64 {
+    let mut weight = 1.0;
+    if board.is_capture(mve) {
+        weight *= match last {
+            Some(last) if last.to == mve.to => 5.0,
+            _ => 3.0,
+        };
+    }
+    let mut next = board.clone();
+    next.make_move(mve);
+    if next.in_check(next.turn) {
+        weight *= 2.0;
+    }
+    let back = match board.turn {
+        Color::White => mve.to.row > mve.from.row,
+        Color::Black => mve.to.row < mve.from.row,
+    };
+    if back && !board.is_capture(mve) {
+        weight *= 0.5;
+    }
+    let king = board
+        .piece_at(mve.from)
+        .is_some_and(|p| p.piece == PieceType::King);
+    let queens = board
+        .pieces(board.turn.other())
+        .any(|p| p.piece == PieceType::Queen);
+    if king && queens && board.castling_side(mve).is_none() {
+        weight *= 0.3;
+    }
+    weight
+}
+
+// a few seconds a move without clocks, a fortieth of the time left with
+// them. an obvious move goes quicker and a choice between many slower, and
+// no move takes more than a tenth of the time left
+fn think_time(candidates: usize, left: Option<Duration>, rng: &mut Rng) -> Duration {
+    let usual = left.map_or(Duration::from_secs(3), |left| left / 40);
+    let hard = match candidates {
+        0 | 1 => 0.3,
+        2 | 3 => 0.8,
+        _ => 1.5,
+    };
+    let time = usual.mul_f64(hard * (0.5 + rng.unit()));
+    left.map_or(time, |left| time.min(left / 10))
+}
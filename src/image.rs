@@ -0,0 +1,344 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::game::Game;
+use crate::{BoardPos, ChessBoard, Color, PieceType, RANKS};
+
+type Rgb = [u8; 3];
+
+// square colors of the exported pictures
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Theme {
+    Brown,
+    Green,
+    Blue,
+    Gray,
+}
+
+impl Theme {
+    // light square, dark square, and the tint of the last move
+    fn colors(self) -> (Rgb, Rgb, Rgb) {
+        match self {
+            Theme::Brown => ([240, 217, 181], [181, 136, 99], [205, 210, 106]),
+            Theme::Green => ([238, 238, 210], [118, 150, 86], [246, 246, 105]),
+            Theme::Blue => ([222, 227, 230], [140, 162, 173], [155, 199, 0]),
+            Theme::Gray => ([220, 220, 220], [150, 150, 150], [190, 200, 120]),
+        }
+    }
+}
+
+const SPRITE: usize = 16;
+
+// 16x16 silhouettes, the outline is added around them when drawing
+fn sprite(piece: PieceType) -> [&'static str; SPRITE] {
+    match piece {
+        PieceType::Pawn => [
+            "................",
+            "................",
+            "................",
+            "................",
+            "......####......",
+            ".....######.....",
+            ".....######.....",
+            "......####......",
+            ".....######.....",
+            "......####......",
+            "......####......",
+            ".....######.....",
+            "....########....",
+            "...##########...",
+            "...##########...",
+            "................",
+        ],
+        PieceType::Knight => [
+            "................",
+            "................",
+            ".......##.......",
+            "......####......",
+            ".....#######....",
+            "....#########...",
+            "...####.#####...",
+            "...##########...",
+            ".......######...",
+            "......#######...",
+            ".....#######....",
+            ".....#######....",
+            "....#########...",
+            "...##########...",
+            "...##########...",
+            "................",
+        ],
+        PieceType::Bishop => [
+            "................",
+            ".......##.......",
+            "......####......",
+            ".....##.###.....",
+            ".....#.####.....",
+            ".....######.....",
+            "......####......",
+            ".......##.......",
+            "......####......",
+            ".....######.....",
+            "......####......",
+            "......####......",
+            "....########....",
+            "...##########...",
+            "...##########...",
+            "................",
+        ],
+        PieceType::Rook => [
+            "................",
+            "................",
+            "...##..##..##...",
+            "...##########...",
+            "....########....",
+            ".....######.....",
+            ".....######.....",
+            ".....######.....",
+            ".....######.....",
+            ".....######.....",
+            ".....######.....",
+            "....########....",
+            "...##########...",
+            "..############..",
+            "..############..",
+            "................",
+        ],
+        PieceType::Queen => [
+            "................",
+            "..#....##....#..",
+            "..##..####..##..",
+            "..###.####.###..",
+            "...##########...",
+            "...##########...",
+            "....########....",
+            ".....######.....",
+            ".....######.....",
+            "....########....",
+            "....########....",
+            "...##########...",
+            "..############..",
+            "..############..",
+            "................",
+            "................",
+        ],
+        PieceType::King => [
+            "................",
+            ".......##.......",
+            "......####......",
+            ".......##.......",
+            "....##.##.##....",
+            "...##########...",
+            "...##########...",
+            "....########....",
+            ".....######.....",
+            ".....######.....",
+            "....########....",
+            "....########....",
+            "...##########...",
+            "..############..",
+            "..############..",
+            "................",
+        ],
+        PieceType::Archbishop => [
+            "................",
+            "......##........",
+            ".....####.......",
+            "....##.###......",
+            "....#.#####.....",
+            "....#########...",
+            "...####.#####...",
+            "...##########...",
+            ".......######...",
+            "......#######...",
+            ".....#######....",
+            ".....#######....",
+            "....#########...",
+            "...##########...",
+            "...##########...",
+            "................",
+        ],
+        PieceType::Chancellor => [
+            "................",
+            "...##.##.##.....",
+            "...#########....",
+            "....#########...",
+            "....#########...",
+            "...####.#####...",
+            "...##########...",
+            ".......######...",
+            "......#######...",
+            ".....#######....",
+            ".....#######....",
+            ".....#######....",
+            "....#########...",
+            "...##########...",
+            "..############..",
+            "................",
+        ],
+    }
+}
+
+fn is_set(rows: &[&str; SPRITE], x: isize, y: isize) -> bool {
+    if x < 0 || y < 0 || x >= SPRITE as isize || y >= SPRITE as isize {
+        return false;
+    }
+    rows[y as usize].as_bytes()[x as usize] == b'#'
+}
+
+// what a sprite pixel shows: the piece, its outline, or the square beneath
+fn sprite_pixel(rows: &[&str; SPRITE], x: usize, y: usize) -> Option<bool> {
+    let (x, y) = (x as isize, y as isize);
+    if is_set(rows, x, y) {
+        return Some(true);
+    }
+    let near = (-1..=1).any(|dy| (-1..=1).any(|dx| is_set(rows, x + dx, y + dy)));
+    near.then_some(false)
+}
+
+// an rgb picture of the position, wider than high on boards with more than
+// eight files
+pub struct Picture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl ChessBoard {
+    // size is the height, the squares are as wide as high
+    pub fn picture(&self, size: u32, theme: Theme, flipped: bool) -> Picture {
+        let (light, dark, last) = theme.colors();
+        let (files, ranks) = (self.width as usize, RANKS as usize);
+        let height = size.max(8) as usize;
+        let width = height * files / ranks;
+        let mut pixels = vec![0; width * height * 3];
+        let marked = |pos: BoardPos| self.last_move.is_some_and(|m| m.from == pos || m.to == pos);
+        for y in 0..height {
+            for x in 0..width {
+                let (mut row, mut col) = (y * ranks / height, x * files / width);
+                if flipped {
+                    (row, col) = (ranks - 1 - row, files - 1 - col);
+                }
+                let pos = BoardPos {
+                    row: row as u8,
+                    col: col as u8,
+                };
+                let mut rgb = if marked(pos) {
+                    last
+                } else if (row + col).is_multiple_of(2) {
+                    light
+                } else {
+                    dark
+                };
+                if let Some(piece) = self.piece_at(pos) {
+                    // position inside the square, scaled down to the sprite
+                    let sx = (x * files % width) * SPRITE / width;
+                    let sy = (y * ranks % height) * SPRITE / height;
+                    let (fill, outline) = match piece.color {
+                        Color::White => ([250, 250, 250], [20, 20, 20]),
+                        Color::Black => ([30, 30, 30], [220, 220, 220]),
+                    };
+                    match sprite_pixel(&sprite(piece.piece), sx, sy) {
+                        Some(true) => rgb = fill,
+                        Some(false) => rgb = outline,
+                        None => {}
+                    }
+                }
+                let at = (y * width + x) * 3;
+                pixels[at..at + 3].copy_from_slice(&rgb);
+            }
+        }
+        Picture {
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        }
+    }
+}
+
+// the png subcommand: a picture of the position
+pub fn png(
+    board: &ChessBoard,
+    size: u32,
+    theme: Theme,
+    flipped: bool,
+    path: &Path,
+) -> Result<(), String> {
+    write_png(&board.picture(size, theme, flipped), path)
+}
+
+// the gif subcommand: every position of the game in turn, delay milliseconds
+// apart
+pub fn gif(
+    game: &Game,
+    size: u32,
+    theme: Theme,
+    flipped: bool,
+    delay: u32,
+    path: &Path,
+) -> Result<(), String> {
+    let pictures: Vec<Picture> = game
+        .history
+        .iter()
+        .map(|played| &played.before)
+        .chain([&game.board])
+        .map(|board| board.picture(size, theme, flipped))
+        .collect();
+    // gif delays count hundredths of a second
+    let delay = (delay / 10).min(u16::MAX as u32) as u16;
+    write_gif(&pictures, delay, path)
+}
+
+pub fn write_png(picture: &Picture, path: &Path) -> Result<(), String> {
+    let error = |e: &dyn std::fmt::Display| format!("could not write {}: {}", path.display(), e);
+    let file = File::create(path).map_err(|e| error(&e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), picture.width, picture.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+    writer
+        .write_image_data(&picture.pixels)
+        .map_err(|e| error(&e))
+}
+
+// one frame per position of the game, each shown for delay hundredths of a second
+pub fn write_gif(pictures: &[Picture], delay: u16, path: &Path) -> Result<(), String> {
+    let error = |e: &dyn std::fmt::Display| format!("could not write {}: {}", path.display(), e);
+    let Some((width, height)) = pictures.first().map(|p| (p.width as u16, p.height as u16)) else {
+        return Err(String::from("no positions to draw"));
+    };
+    // the pictures only use a handful of colors, so they share one exact palette
+    let mut palette: Vec<Rgb> = Vec::new();
+    let mut frames = Vec::new();
+    for picture in pictures {
+        let mut indices = Vec::with_capacity(picture.pixels.len() / 3);
+        for rgb in picture.pixels.chunks(3) {
+            let rgb = [rgb[0], rgb[1], rgb[2]];
+            let idx = match palette.iter().position(|&c| c == rgb) {
+                Some(idx) => idx,
+                None => {
+                    palette.push(rgb);
+                    palette.len() - 1
+                }
+            };
+            indices.push(idx as u8);
+        }
+        frames.push(indices);
+    }
+    let file = File::create(path).map_err(|e| error(&e))?;
+    let flat: Vec<u8> = palette.concat();
+    let mut encoder =
+        gif::Encoder::new(BufWriter::new(file), width, height, &flat).map_err(|e| error(&e))?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|e| error(&e))?;
+    for indices in frames {
+        let mut frame = gif::Frame::from_indexed_pixels(width, height, indices, None);
+        frame.delay = delay;
+        encoder.write_frame(&frame).map_err(|e| error(&e))?;
+    }
+    Ok(())
+}
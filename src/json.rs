@@ -0,0 +1,197 @@
+// just enough json for the network protocols: a reader for whole documents and
+// writers for the flat objects the program sends
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    // the field of an object, none for anything else
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    // the string at a path of object fields, like ["game", "id"]
+    pub fn str_at(&self, path: &[&str]) -> Option<&str> {
+        path.iter()
+            .try_fold(self, |value, key| value.get(key))?
+            .as_str()
+    }
+}
+
+// how deep arrays and objects may nest. the parser recurses, so without a
+// limit a line of brackets would overflow the stack
+const MAX_DEPTH: usize = 64;
+
+pub fn parse(text: &str) -> Option<Value> {
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip();
+    parser.chars.next().is_none().then_some(value)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    // the arrays and objects the parser is inside of
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip();
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let value = self.nested();
+        self.depth -= 1;
+        value
+    }
+
+    // a value, counted as one level deeper
+    fn nested(&mut self) -> Option<Value> {
+        match *self.chars.peek()? {
+            '{' => {
+                self.chars.next();
+                let mut fields = Vec::new();
+                self.skip();
+                if self.chars.peek() == Some(&'}') {
+                    self.chars.next();
+                    return Some(Value::Object(fields));
+                }
+                loop {
+                    self.skip();
+                    let key = self.string()?;
+                    self.skip();
+                    if self.chars.next()? != ':' {
+                        return None;
+                    }
+                    fields.push((key, self.value()?));
+                    self.skip();
+                    match self.chars.next()? {
+                        ',' => {}
+                        '}' => return Some(Value::Object(fields)),
+                        _ => return None,
+                    }
+                }
+            }
+            '[' => {
+                self.chars.next();
+                let mut items = Vec::new();
+                self.skip();
+                if self.chars.peek() == Some(&']') {
+                    self.chars.next();
+                    return Some(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip();
+                    match self.chars.next()? {
+                        ',' => {}
+                        ']' => return Some(Value::Array(items)),
+                        _ => return None,
+                    }
+                }
+            }
+            '"' => self.string().map(Value::String),
+            't' => self.word("true", Value::Bool(true)),
+            'f' => self.word("false", Value::Bool(false)),
+            'n' => self.word("null", Value::Null),
+            _ => {
+                let mut number = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                        break;
+                    }
+                    number.push(c);
+                    self.chars.next();
+                }
+                number.parse().ok().map(Value::Number)
+            }
+        }
+    }
+
+    fn word(&mut self, word: &str, value: Value) -> Option<Value> {
+        for expected in word.chars() {
+            if self.chars.next()? != expected {
+                return None;
+            }
+        }
+        Some(value)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.chars.next()? != '"' {
+            return None;
+        }
+        let mut out = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(out),
+                '\\' => match self.chars.next()? {
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    'r' => out.push('\r'),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'u' => {
+                        let code: String = self.chars.by_ref().take(4).collect();
+                        // characters outside the basic plane aren't needed here
+                        let c = char::from_u32(u32::from_str_radix(&code, 16).ok()?);
+                        out.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    c => out.push(c),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+}
+
+// an object from fields whose values are already json
+pub fn object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}:{}", string(key), value))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+pub fn string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
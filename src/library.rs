@@ -0,0 +1,160 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::clock::Bonus;
+use crate::config;
+use crate::game::Game;
+use crate::pgn;
+
+// the games directory in the data directory, one pgn file per game
+pub fn default_dir() -> Option<PathBuf> {
+    Some(config::data_dir()?.join("games"))
+}
+
+// a game found in the library
+pub struct Entry {
+    pub path: PathBuf,
+    pub tags: Vec<(String, String)>,
+    pub moves: usize,
+}
+
+impl Entry {
+    pub fn tag(&self, name: &str) -> &str {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map_or("?", |(_, value)| value)
+    }
+
+    // "2026.10.16 human vs engine depth 3: 1-0, 24 moves"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {} vs {}: {}, {} {}",
+            self.tag("Date"),
+            self.tag("White"),
+            self.tag("Black"),
+            self.tag("Result"),
+            self.moves,
+            if self.moves == 1 { "move" } else { "moves" }
+        )
+    }
+}
+
+// year, month and day of a count of days since 1970-01-01
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis())
+}
+
+// the tag pairs a game is written with, the date is today's
+pub fn tags(
+    game: &Game,
+    event: &str,
+    round: String,
+    players: [String; 2],
+) -> Vec<(&'static str, String)> {
+    let (year, month, day) = civil_date((now_millis() / 86_400_000) as i64);
+    let [white, black] = players;
+    let mut tags = vec![
+        ("Event", event.to_string()),
+        ("Site", String::from("?")),
+        ("Date", format!("{}.{:02}.{:02}", year, month, day)),
+        ("Round", round),
+        ("White", white),
+        ("Black", black),
+    ];
+    // pgn writes time controls in seconds, with an increment after a plus.
+    // it has no way to write a delay
+    if let Some(clock) = &game.clock {
+        let control = clock.controls[0];
+        let mut text = control.base.as_secs().to_string();
+        if let Some(Bonus::Increment(increment)) = control.bonus {
+            text.push_str(&format!("+{}", increment.as_secs()));
+        }
+        tags.push(("TimeControl", text));
+    }
+    tags
+}
+
+// the file every finished game is added to, games.pgn in the data directory
+pub fn default_log() -> Option<PathBuf> {
+    Some(config::data_dir()?.join("games.pgn"))
+}
+
+// past this size the log is moved to games.1.pgn, replacing the one before,
+// and a new one is started
+const LOG_LIMIT: u64 = 16 << 20;
+
+// adds the game to the end of the log
+pub fn append(path: &Path, game: &Game, tags: &[(&str, String)]) -> Result<(), String> {
+    let error = |e: std::io::Error| format!("could not write {}: {}", path.display(), e);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(error)?;
+    }
+    if std::fs::metadata(path).is_ok_and(|meta| meta.len() > LOG_LIMIT) {
+        std::fs::rename(path, path.with_extension("1.pgn")).map_err(error)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(error)?;
+    file.write_all(pgn::write(game, tags).as_bytes())
+        .map_err(error)
+}
+
+// writes the game as a new pgn file, named after when it was written so the
+// files sort from old to new
+pub fn log(dir: &Path, game: &Game, tags: &[(&str, String)]) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("could not create {}: {}", dir.display(), e))?;
+    let millis = now_millis();
+    let mut path = dir.join(format!("{}.pgn", millis));
+    let mut n = 1;
+    while path.exists() {
+        path = dir.join(format!("{}.pgn", millis + n));
+        n += 1;
+    }
+    std::fs::write(&path, pgn::write(game, tags))
+        .map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+// the games in the library from old to new. files that can't be read are left out
+pub fn list(dir: &Path) -> Vec<Entry> {
+    let Ok(files) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = files
+        .filter_map(|file| Some(file.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pgn"))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let text = std::fs::read_to_string(&path).ok()?;
+            Some(Entry {
+                tags: pgn::tags(&text),
+                moves: pgn::movetext(&text).len().div_ceil(2),
+                path,
+            })
+        })
+        .collect()
+}
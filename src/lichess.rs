@@ -0,0 +1,227 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+
+use crate::engine;
+use crate::json::{self, Value};
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Color};
+
+const SITE: &str = "https://lichess.org";
+
+// the page to analyse the position on at lichess, none for the variants it
+// doesn't have. the fen goes in the path with underscores for spaces
+pub fn analysis_url(board: &ChessBoard) -> Option<String> {
+    let path = match board.variant {
+        VariantKind::Standard => "",
+        VariantKind::Chess960 => "chess960/",
+        VariantKind::Antichess => "antichess/",
+        VariantKind::Capablanca | VariantKind::Marseillais => return None,
+    };
+    Some(format!(
+        "{}/analysis/{}{}",
+        SITE,
+        path,
+        board.to_fen().replace(' ', "_")
+    ))
+}
+
+// a get request of the public api, which needs no account
+pub fn fetch(path: &str) -> Result<Value, String> {
+    let output = Command::new("curl")
+        .args(["-sS", "--fail", "-H", "Accept: application/json"])
+        .arg(format!("{}{}", SITE, path))
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("could not run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    json::parse(&text).ok_or_else(|| format!("lichess answered {}", text))
+}
+
+// uploads a game through the import api, which needs no account, and returns
+// the address lichess gave it. the pgn goes to curl on stdin
+pub fn import(pgn: &str) -> Result<String, String> {
+    let mut child = Command::new("curl")
+        .args(["-sS", "--fail", "-H", "Accept: application/json"])
+        .args(["--data-urlencode", "pgn@-"])
+        .arg(format!("{}/api/import", SITE))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run curl: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(pgn.as_bytes());
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "lichess didn't take the game: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    json::parse(&text)
+        .and_then(|answer| answer.str_at(&["url"]).map(String::from))
+        .ok_or_else(|| format!("lichess answered {}", text))
+}
+
+// plays as a lichess bot account through the bot api. https goes through curl,
+// the token reaches it on stdin so it never shows up in the process list
+#[derive(Clone)]
+struct Client {
+    server: String,
+    token: String,
+}
+
+impl Client {
+    fn curl(&self, method: &str, path: &str, body: Option<&str>) -> Result<Child, String> {
+        let mut command = Command::new("curl");
+        command
+            .args(["-sS", "--fail", "-N", "-H", "@-", "-X", method])
+            .arg(format!("{}{}", self.server, path))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped());
+        if let Some(body) = body {
+            command.args(["--data", body]);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("could not run curl: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = writeln!(stdin, "Authorization: Bearer {}", self.token);
+        }
+        Ok(child)
+    }
+
+    // a request whose answer is one json document
+    fn request(&self, method: &str, path: &str, body: Option<&str>) -> Result<Value, String> {
+        let output = self
+            .curl(method, path, body)?
+            .wait_with_output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!("{} {} failed", method, path));
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        json::parse(&text).ok_or_else(|| format!("{} {} answered {}", method, path, text))
+    }
+
+    // one json document per line for as long as the server keeps the stream open,
+    // without the empty lines it sends to keep it alive
+    fn stream(&self, path: &str) -> Result<impl Iterator<Item = Value>, String> {
+        let mut child = self.curl("GET", path, None)?;
+        let stdout = child.stdout.take().ok_or("curl has no output")?;
+        Ok(BufReader::new(stdout)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| json::parse(&line)))
+    }
+}
+
+// the token is taken from LICHESS_TOKEN when it isn't given
+pub fn run(server: &str, token: Option<&str>, depth: u8) -> Result<(), String> {
+    let token = token
+        .map(str::to_string)
+        .or_else(|| std::env::var("LICHESS_TOKEN").ok())
+        .ok_or("a token is needed, give --token or set LICHESS_TOKEN")?;
+    let client = Client {
+        server: server.trim_end_matches('/').to_string(),
+        token,
+    };
+    let account = client.request("GET", "/api/account", None)?;
+    let name = account.str_at(&["username"]).unwrap_or("?");
+    println!("playing as {} at depth {}", name, depth);
+
+    for event in client.stream("/api/stream/event")? {
+        match event.str_at(&["type"]) {
+            Some("challenge") => {
+                let Some(id) = event.str_at(&["challenge", "id"]) else {
+                    continue;
+                };
+                // the board knows no other rules, and no position but the start
+                let variant = event.str_at(&["challenge", "variant", "key"]);
+                let result = if variant == Some("standard") {
+                    println!("accepting challenge {}", id);
+                    client.request("POST", &format!("/api/challenge/{}/accept", id), None)
+                } else {
+                    println!(
+                        "declining challenge {}, variant {}",
+                        id,
+                        variant.unwrap_or("unknown")
+                    );
+                    let path = format!("/api/challenge/{}/decline", id);
+                    client.request("POST", &path, Some("reason=variant"))
+                };
+                if let Err(e) = result {
+                    println!("{}", e);
+                }
+            }
+            Some("gameStart") => {
+                let id = event.str_at(&["game", "gameId"]).map(String::from);
+                let color = match event.str_at(&["game", "color"]) {
+                    Some("white") => Color::White,
+                    _ => Color::Black,
+                };
+                if let Some(id) = id {
+                    println!("game {} started, playing {}", id, color.name());
+                    let client = client.clone();
+                    std::thread::spawn(move || {
+                        if let Err(e) = play(&client, &id, color, depth) {
+                            println!("game {}: {}", id, e);
+                        }
+                    });
+                }
+            }
+            Some("gameFinish") => {
+                if let Some(id) = event.str_at(&["game", "gameId"]) {
+                    println!("game {} finished", id);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(String::from("lichess closed the event stream"))
+}
+
+// follows one game and answers every position where it's our turn
+fn play(client: &Client, id: &str, color: Color, depth: u8) -> Result<(), String> {
+    for event in client.stream(&format!("/api/bot/game/stream/{}", id))? {
+        let state = match event.str_at(&["type"]) {
+            Some("gameFull") => match event.get("state") {
+                Some(state) => state,
+                None => continue,
+            },
+            Some("gameState") => &event,
+            _ => continue,
+        };
+        if state.str_at(&["status"]) != Some("started") {
+            return Ok(());
+        }
+
+        let mut board = ChessBoard::new();
+        let moves = state.str_at(&["moves"]).unwrap_or("");
+        for uci in moves.split_whitespace() {
+            match board.parse_uci(uci) {
+                Some(mve) if board.is_legal(&mve) => board.make_move(&mve),
+                // a move that isn't legal here means the game can't be followed
+                _ => {
+                    client.request("POST", &format!("/api/bot/game/{}/resign", id), None)?;
+                    return Err(format!("can't follow {}, resigned", uci));
+                }
+            }
+        }
+        if board.turn != color {
+            continue;
+        }
+        let Some((mve, _)) = engine::search(&board, depth) else {
+            continue;
+        };
+        // promotions go out with the piece, like e7e8q
+        let uci = board.standard_uci(&mve);
+        client.request("POST", &format!("/api/bot/game/{}/move/{}", id, uci), None)?;
+    }
+    Ok(())
+}
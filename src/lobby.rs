@@ -0,0 +1,405 @@
+use std::collections::BTreeMap;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rustls::ServerConfig;
+
+use crate::clock::{Clock, TimeControl};
+use crate::game::{Game, GameResult};
+use crate::rng::{seed_from_time, Rng};
+use crate::transport::{Connection, Sender};
+use crate::Color;
+
+// a server where players meet and play. clients send one command per line:
+//   register <name>
+//   seek <time control> [white|black]    offers a game, any color if left out
+//   seeks                                lists the open seeks
+//   accept <seek id>
+//   move <uci>, draw, resign             in a game
+//   resume <game id> <key> [<seq>]       instead of register, after a lost
+//                                        connection, with the last move seen
+// and get back, besides ok and error <why>:
+//   seek <id> <name> <time control> <color>    one per open seek, then end
+//   game <id> <color> <opponent> <time control> <fen> <key>
+//   move <seq> <uci> <white ms> <black ms>     every move, seq counts from 1
+//   clock <white ms> <black ms>                after resuming
+//   draw <color>                               a draw offer
+//   result <score> <why>
+// the server plays by its own rules and clocks, clients only propose moves.
+// a player whose connection drops keeps their game, and their clock running,
+// until they resume it
+pub fn run(port: u16, tls: Option<Arc<ServerConfig>>) -> Result<(), String> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .map_err(|e| format!("could not listen on port {}: {}", port, e))?;
+    println!("lobby open on port {}", port);
+    let lobby = Arc::new(Mutex::new(Lobby {
+        players: BTreeMap::new(),
+        seeks: Vec::new(),
+        tables: BTreeMap::new(),
+        next_id: 0,
+        rng: Rng::new(seed_from_time()),
+    }));
+
+    // flags fall without anyone moving. a connection that panicked while
+    // holding the lobby doesn't take the others down, the lock is taken anyway
+    let ticking = Arc::clone(&lobby);
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_millis(100));
+        ticking
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .check_time();
+    });
+
+    for stream in listener.incoming().map_while(Result::ok) {
+        let lobby = Arc::clone(&lobby);
+        let tls = tls.clone();
+        std::thread::spawn(move || serve(stream, tls.as_ref(), &lobby));
+    }
+    Ok(())
+}
+
+// the longest line read, so a broken client can't fill the memory
+const MAX_LINE: usize = 256;
+
+struct Lobby {
+    players: BTreeMap<String, Client>,
+    seeks: Vec<Seek>,
+    tables: BTreeMap<u64, Table>,
+    // ids for seeks and tables
+    next_id: u64,
+    // for the keys that resume games
+    rng: Rng,
+}
+
+struct Client {
+    // none while a player in a game is disconnected
+    stream: Option<Sender>,
+    table: Option<u64>,
+}
+
+struct Seek {
+    id: u64,
+    name: String,
+    control: TimeControl,
+    color: Option<Color>,
+}
+
+struct Table {
+    game: Game,
+    // indexed by Color::idx
+    names: [String; 2],
+    // what a player needs to resume the game
+    keys: [String; 2],
+    draw_offer: Option<Color>,
+    // the move lines sent so far, to send again on resuming
+    moves: Vec<String>,
+}
+
+fn serve(stream: TcpStream, tls: Option<&Arc<ServerConfig>>, lobby: &Mutex<Lobby>) {
+    let Ok(mut connection) = Connection::accept(stream, tls) else {
+        return;
+    };
+    // handed to the lobby on register or resume
+    let mut writer = Some(connection.sender());
+    let mut name: Option<String> = None;
+    while let Some(line) = connection.read_line(MAX_LINE) {
+        let line = line.trim();
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        let mut lobby = lobby.lock().unwrap_or_else(|e| e.into_inner());
+        let reply = match (&name, command) {
+            (None, "register") => {
+                let wanted = args.trim();
+                if wanted.is_empty() || wanted.contains(char::is_whitespace) {
+                    Err(String::from("a name is one word"))
+                } else if lobby.players.contains_key(wanted) {
+                    Err(format!("{} is taken", wanted))
+                } else {
+                    let client = Client {
+                        stream: writer.take(),
+                        table: None,
+                    };
+                    lobby.players.insert(wanted.to_string(), client);
+                    name = Some(wanted.to_string());
+                    Ok(())
+                }
+            }
+            (None, "resume") => lobby.resume(args, &mut writer).map(|player| {
+                name = Some(player);
+            }),
+            (None, _) => Err(String::from("register first")),
+            (Some(_), "register" | "resume") => Err(String::from("already registered")),
+            (Some(name), "seek") => lobby.seek(name, args),
+            (Some(name), "seeks") => lobby.list(name),
+            (Some(name), "accept") => lobby.accept(name, args),
+            (Some(name), "move" | "draw" | "resign") => lobby.act(name, command, args),
+            (Some(_), _) => Err(format!("unknown command {}", command)),
+        };
+        let message = match reply {
+            Ok(()) => String::from("ok"),
+            Err(e) => format!("error {}", e),
+        };
+        match (&name, &mut writer) {
+            (Some(name), _) => lobby.send(name, &message),
+            (None, Some(sender)) => {
+                let _ = sender.send_line(&message);
+            }
+            (None, None) => {}
+        }
+    }
+    if let Some(name) = name {
+        lobby.lock().unwrap_or_else(|e| e.into_inner()).leave(&name);
+    }
+}
+
+impl Lobby {
+    fn send(&mut self, name: &str, line: &str) {
+        if let Some(stream) = self.players.get(name).and_then(|c| c.stream.as_ref()) {
+            let _ = stream.send_line(line);
+        }
+    }
+
+    fn seek(&mut self, name: &str, args: &str) -> Result<(), String> {
+        if self.players[name].table.is_some() {
+            return Err(String::from("finish your game first"));
+        }
+        let mut args = args.split_whitespace();
+        let control: TimeControl = args.next().ok_or("seek needs a time control")?.parse()?;
+        let color = match args.next() {
+            None => None,
+            Some("white") => Some(Color::White),
+            Some("black") => Some(Color::Black),
+            Some(other) => return Err(format!("no color {}", other)),
+        };
+        self.next_id += 1;
+        let id = self.next_id;
+        self.seeks.push(Seek {
+            id,
+            name: name.to_string(),
+            control,
+            color,
+        });
+        self.send(name, &format!("seek {}", id));
+        Ok(())
+    }
+
+    fn list(&mut self, name: &str) -> Result<(), String> {
+        let lines: Vec<String> = self
+            .seeks
+            .iter()
+            .map(|seek| {
+                let color = seek.color.map_or("any", |c| match c {
+                    Color::White => "white",
+                    Color::Black => "black",
+                });
+                format!("seek {} {} {} {}", seek.id, seek.name, seek.control, color)
+            })
+            .collect();
+        for line in lines {
+            self.send(name, &line);
+        }
+        self.send(name, "end");
+        Ok(())
+    }
+
+    // pairs the two players, the seeker gets the color asked for or white
+    fn accept(&mut self, name: &str, args: &str) -> Result<(), String> {
+        if self.players[name].table.is_some() {
+            return Err(String::from("finish your game first"));
+        }
+        let id: u64 = args.trim().parse().map_err(|_| "accept needs a seek id")?;
+        let at = self
+            .seeks
+            .iter()
+            .position(|seek| seek.id == id)
+            .ok_or_else(|| format!("no seek {}", id))?;
+        if self.seeks[at].name == name {
+            return Err(String::from("that is your own seek"));
+        }
+        let seek = self.seeks.remove(at);
+        // whoever starts a game has no other seeks left open
+        self.seeks.retain(|s| s.name != name && s.name != seek.name);
+
+        let seeker = seek.color.unwrap_or(Color::White);
+        let mut names = [name.to_string(), name.to_string()];
+        names[seeker.idx()] = seek.name.clone();
+        let mut game = Game::new();
+        game.clock = Some(Clock::new([seek.control; 2], game.board.turn));
+        self.next_id += 1;
+        let id = self.next_id;
+        let keys = [0; 2].map(|_| format!("{:016x}", self.rng.next_u64()));
+        let table = Table {
+            game,
+            names,
+            keys,
+            draw_offer: None,
+            moves: Vec::new(),
+        };
+        for color in [Color::White, Color::Black] {
+            let player = table.names[color.idx()].clone();
+            self.send(&player, &table.greeting(id, color));
+            if let Some(client) = self.players.get_mut(&player) {
+                client.table = Some(id);
+            }
+        }
+        self.tables.insert(id, table);
+        Ok(())
+    }
+
+    // puts a player whose connection dropped back at their table, and sends
+    // what they missed after the move numbered seq
+    fn resume(&mut self, args: &str, writer: &mut Option<Sender>) -> Result<String, String> {
+        let mut args = args.split_whitespace();
+        let usage = "resume needs a game id and a key";
+        let id: u64 = args.next().and_then(|id| id.parse().ok()).ok_or(usage)?;
+        let key = args.next().ok_or(usage)?;
+        let seen: usize = args.next().and_then(|seq| seq.parse().ok()).unwrap_or(0);
+        let table = self
+            .tables
+            .get(&id)
+            .ok_or_else(|| format!("no game {}", id))?;
+        let color = [Color::White, Color::Black]
+            .into_iter()
+            .find(|color| table.keys[color.idx()] == key)
+            .ok_or("wrong key")?;
+        let name = table.names[color.idx()].clone();
+        let client = self.players.get_mut(&name).expect("players sit at tables");
+        if client.stream.is_some() {
+            return Err(format!("{} is still connected", name));
+        }
+        client.stream = writer.take();
+
+        let mut lines = vec![table.greeting(id, color)];
+        lines.extend(table.moves.iter().skip(seen).cloned());
+        let clock = table.game.clock.as_ref().expect("lobby games have clocks");
+        lines.push(format!(
+            "clock {} {}",
+            clock.remaining(Color::White).as_millis(),
+            clock.remaining(Color::Black).as_millis()
+        ));
+        if let Some(offer) = table.draw_offer {
+            lines.push(format!("draw {}", offer.name().to_lowercase()));
+        }
+        for line in lines {
+            self.send(&name, &line);
+        }
+        Ok(name)
+    }
+
+    // a move, draw offer or resignation in the player's game
+    fn act(&mut self, name: &str, command: &str, args: &str) -> Result<(), String> {
+        let id = self.players[name].table.ok_or("not in a game")?;
+        let table = self.tables.get_mut(&id).expect("players sit at tables");
+        let color = if table.names[Color::White.idx()] == name {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let mut lines = Vec::new();
+        match command {
+            "move" => {
+                if table.game.board.turn != color {
+                    return Err(String::from("not your turn"));
+                }
+                let mve = (table.game.board)
+                    .parse_uci(args.trim())
+                    .ok_or("moves are like e2e4")?;
+                if !table.game.play(&mve) && table.game.result.is_none() {
+                    return Err(format!("illegal move {}", mve));
+                }
+                if let Some(played) = table.game.history.last().filter(|p| p.mve == mve) {
+                    let clock = table.game.clock.as_ref().expect("lobby games have clocks");
+                    let line = format!(
+                        "move {} {} {} {}",
+                        table.game.history.len(),
+                        played.mve,
+                        clock.remaining(Color::White).as_millis(),
+                        clock.remaining(Color::Black).as_millis()
+                    );
+                    table.moves.push(line.clone());
+                    lines.push(line);
+                }
+                if table.draw_offer == Some(color.other()) {
+                    table.draw_offer = None;
+                }
+            }
+            "draw" if table.draw_offer == Some(color.other()) => {
+                table.game.end(GameResult::DrawAgreed)
+            }
+            "draw" => {
+                table.draw_offer = Some(color);
+                lines.push(format!("draw {}", color.name().to_lowercase()));
+            }
+            _ => table.game.end(GameResult::Resignation(color.other())),
+        }
+        let names = table.names.clone();
+        for line in lines {
+            for player in &names {
+                self.send(player, &line);
+            }
+        }
+        self.finish(id);
+        Ok(())
+    }
+
+    // tells both players how a game that is over ended, and frees them
+    fn finish(&mut self, id: u64) {
+        let Some(result) = self.tables.get(&id).and_then(|table| table.game.result) else {
+            return;
+        };
+        let table = self.tables.remove(&id).expect("checked above");
+        let line = format!("result {} {}", result.score(), result.describe());
+        for player in &table.names {
+            self.send(player, &line);
+            if let Some(client) = self.players.get_mut(player) {
+                client.table = None;
+            }
+            // nobody is coming back for a finished game
+            if self.players.get(player).is_some_and(|c| c.stream.is_none()) {
+                self.players.remove(player);
+            }
+        }
+    }
+
+    fn check_time(&mut self) {
+        let flagged: Vec<u64> = self
+            .tables
+            .iter_mut()
+            .filter_map(|(id, table)| table.game.check_time().then_some(*id))
+            .collect();
+        for id in flagged {
+            self.finish(id);
+        }
+    }
+
+    // a player who leaves drops their seeks, but keeps their game to resume
+    fn leave(&mut self, name: &str) {
+        self.seeks.retain(|seek| seek.name != name);
+        match self.players.get_mut(name) {
+            Some(client) if client.table.is_some() => client.stream = None,
+            _ => {
+                self.players.remove(name);
+            }
+        }
+    }
+}
+
+impl Table {
+    fn greeting(&self, id: u64, color: Color) -> String {
+        let clock = self.game.clock.as_ref().expect("lobby games have clocks");
+        format!(
+            "game {} {} {} {} {} {}",
+            id,
+            color.name().to_lowercase(),
+            self.names[color.other().idx()],
+            clock.controls[color.idx()],
+            self.game
+                .history
+                .first()
+                .map_or(&self.game.board, |p| &p.before)
+                .to_fen(),
+            self.keys[color.idx()]
+        )
+    }
+}
@@ -0,0 +1,80 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::PieceType;
+
+// languages for the piece letters in algebraic notation
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+    Es,
+    It,
+    Nl,
+}
+
+const PIECES: [PieceType; 5] = [
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+impl Locale {
+    // letters for king, queen, rook, bishop and knight
+    fn letters(self) -> [char; 5] {
+        match self {
+            Locale::En => ['K', 'Q', 'R', 'B', 'N'],
+            Locale::De => ['K', 'D', 'T', 'L', 'S'],
+            Locale::Fr => ['R', 'D', 'T', 'F', 'C'],
+            Locale::Es | Locale::It => ['R', 'D', 'T', 'A', 'C'],
+            Locale::Nl => ['K', 'D', 'T', 'L', 'P'],
+        }
+    }
+
+    // some books put a letter in front of pawn moves too, it is accepted but never written
+    fn pawn_letter(self) -> Option<char> {
+        match self {
+            Locale::De => Some('B'),
+            Locale::Fr | Locale::Es | Locale::It => Some('P'),
+            Locale::En | Locale::Nl => None,
+        }
+    }
+
+    fn letter(self, piece: PieceType) -> Option<char> {
+        let i = PIECES.iter().position(|&p| p == piece)?;
+        Some(self.letters()[i])
+    }
+
+    fn piece(self, letter: char) -> Option<PieceType> {
+        let i = self.letters().iter().position(|&l| l == letter)?;
+        Some(PIECES[i])
+    }
+
+    // english san to this language. files are lowercase, so every uppercase
+    // letter in san is a piece letter
+    pub fn localize(self, san: &str) -> String {
+        san.chars()
+            .map(|ch| match Locale::En.piece(ch) {
+                Some(piece) => self.letter(piece).unwrap_or(ch),
+                None => ch,
+            })
+            .collect()
+    }
+
+    // san typed in this language back to english, all letters are swapped at
+    // once since the alphabets overlap
+    pub fn delocalize(self, san: &str) -> String {
+        san.chars()
+            .filter(|&ch| Some(ch) != self.pawn_letter())
+            .map(|ch| match self.piece(ch) {
+                Some(piece) => Locale::En.letter(piece).unwrap_or(ch),
+                None => ch,
+            })
+            .collect()
+    }
+}
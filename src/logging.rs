@@ -0,0 +1,69 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::time::Uptime;
+
+use crate::game::{Game, GameResult, Observer, PlayedMove};
+use crate::{Color, Piece};
+
+// starts logging to a file, or to stderr without one. nothing is logged when
+// neither a file nor a level is given. every line has the time since the
+// start, the level, the spans it was written in and the module:
+//   1.638638165s DEBUG round{round=1}:game{number=1}:depth{depth=2}: chess::engine: score -0.20 ..
+// spans are opened with tracing's own macros, and parallel::ordered carries
+// the one it was called in over to the threads doing the work
+pub fn init(file: Option<&Path>, level: Option<LevelFilter>) -> Result<(), String> {
+    if file.is_none() && level.is_none() {
+        return Ok(());
+    }
+    let out: Box<dyn Write + Send> = match file {
+        Some(path) => Box::new(
+            File::create(path).map_err(|e| format!("could not write {}: {}", path.display(), e))?,
+        ),
+        None => Box::new(std::io::stderr()),
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level.unwrap_or(LevelFilter::INFO))
+        .with_timer(Uptime::default())
+        .with_ansi(false)
+        .with_writer(Mutex::new(out))
+        .try_init()
+        .map_err(|e| e.to_string())
+}
+
+// logs the moves of a game and how it ends
+pub struct GameLog;
+
+impl Observer for GameLog {
+    fn on_move(&self, game: &Game, played: &PlayedMove) {
+        tracing::debug!(
+            target: "game",
+            "ply {}: {} {} ({})",
+            game.history.len(),
+            played.before.turn.name(),
+            played.san,
+            played.mve
+        );
+    }
+
+    fn on_capture(&self, _game: &Game, _played: &PlayedMove, taken: Piece) {
+        tracing::trace!(target: "game", "took {:?} on {}", taken.piece, taken.pos);
+    }
+
+    fn on_check(&self, _game: &Game, color: Color) {
+        tracing::trace!(target: "game", "{} in check", color.name());
+    }
+
+    fn on_game_end(&self, game: &Game, result: GameResult) {
+        tracing::info!(
+            target: "game",
+            "game over after {} plies: {} ({})",
+            game.history.len(),
+            result.score(),
+            result.describe()
+        );
+    }
+}
@@ -1,4 +1,96 @@
-#[derive(Debug, Clone, Copy)]
+mod analysis;
+mod announce;
+mod assess;
+mod batch;
+mod bench;
+mod book;
+mod cache;
+mod castling;
+mod cli;
+mod clipboard;
+mod clock;
+mod commands;
+mod config;
+mod correspondence;
+mod database;
+mod dedupe;
+mod describe;
+mod digest;
+mod display;
+mod dump;
+mod editor;
+mod endgame;
+mod engine;
+mod events;
+mod explorer;
+mod fen;
+mod game;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod heatmap;
+mod humanlike;
+mod image;
+mod json;
+mod library;
+mod lichess;
+mod lobby;
+mod locale;
+mod logging;
+mod messages;
+mod net;
+mod notation;
+mod odds;
+mod openings;
+mod options;
+mod parallel;
+mod pawns;
+mod perft;
+mod pgn;
+mod puzzle;
+mod rating;
+mod relay;
+mod repertoire;
+mod replay;
+mod rng;
+mod save;
+mod scoresheet;
+mod searchstats;
+mod selfplay;
+mod serve;
+mod similarity;
+mod solve;
+mod testsuite;
+mod theme;
+mod tournament;
+mod transport;
+mod tui;
+mod tutorial;
+mod uci;
+mod variant;
+mod watch;
+mod websocket;
+mod zobrist;
+
+use std::io::{IsTerminal, Write};
+use std::sync::mpsc::RecvTimeoutError;
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+use announce::Announcer;
+use cli::{BookAction, Cli, Command, Player};
+use clock::{Clock, TimeControl};
+use commands::{Flow, Session};
+use config::{Config, Renderer};
+use display::{clear_screen, Annotations, BoardStyle, DisplayOptions, Highlights};
+use game::Game;
+use humanlike::Persona;
+use locale::Locale;
+use options::EngineOptions;
+use theme::Theme;
+use variant::VariantKind;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum PieceType {
     Pawn,
     Bishop,
@@ -6,14 +98,89 @@ enum PieceType {
     Rook,
     Queen,
     King,
+    // moves like a bishop or a knight
+    Archbishop,
+    // moves like a rook or a knight
+    Chancellor,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+impl PieceType {
+    const ALL: [PieceType; 8] = [
+        PieceType::Pawn,
+        PieceType::Bishop,
+        PieceType::Knight,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+        PieceType::Archbishop,
+        PieceType::Chancellor,
+    ];
+
+    fn idx(self) -> usize {
+        match self {
+            PieceType::Pawn => 0,
+            PieceType::Bishop => 1,
+            PieceType::Knight => 2,
+            PieceType::Rook => 3,
+            PieceType::Queen => 4,
+            PieceType::King => 5,
+            PieceType::Archbishop => 6,
+            PieceType::Chancellor => 7,
+        }
+    }
+
+    // material value in pawns
+    fn value(self) -> i32 {
+        match self {
+            PieceType::Pawn => 1,
+            PieceType::Bishop | PieceType::Knight => 3,
+            PieceType::Rook => 5,
+            PieceType::Queen => 9,
+            PieceType::King => 0,
+            PieceType::Archbishop => 7,
+            PieceType::Chancellor => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum Color {
     White,
     Black,
 }
 
+impl Color {
+    fn other(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Color::White => "White",
+            Color::Black => "Black",
+        }
+    }
+
+    fn idx(self) -> usize {
+        match self {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+}
+
+// boards are eight ranks high and from eight up to twelve files wide. the
+// squares are numbered row by row with room for the widest board, so a square
+// keeps its number whatever the width
+const RANKS: u8 = 8;
+const MIN_FILES: u8 = 8;
+const MAX_FILES: u8 = 12;
+const SQUARES: usize = RANKS as usize * MAX_FILES as usize;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct BoardPos {
     row: u8,
@@ -22,26 +189,27 @@ struct BoardPos {
 
 impl BoardPos {
     fn to_idx(self) -> usize {
-        (self.col + self.row * 8).into()
+        usize::from(self.col) + usize::from(self.row) * usize::from(MAX_FILES)
     }
 
     fn from_idx(idx: usize) -> Option<Self> {
-        if idx >= 64 {
+        if idx >= SQUARES {
             return None;
         }
         Some(BoardPos {
-            row: (idx / 8).try_into().unwrap(),
-            col: (idx % 8).try_into().unwrap(),
+            row: (idx / usize::from(MAX_FILES)).try_into().unwrap(),
+            col: (idx % usize::from(MAX_FILES)).try_into().unwrap(),
         })
     }
 
     fn parse(string: &str) -> Option<BoardPos> {
-        if string.len() == 2 {
+        // the byte length only counts characters for ascii text
+        if string.len() == 2 && string.is_ascii() {
             let col: u8 = string.chars().nth(0).unwrap() as u8;
             let row: u8 = string.chars().nth(1).unwrap() as u8;
 
             return match (col, row) {
-                (b'a'..=b'h', b'1'..=b'8') => Some({
+                (b'a'..=b'l', b'1'..=b'8') => Some({
                     BoardPos {
                         row: b'8' - row,
                         col: col - b'a',
@@ -54,6 +222,12 @@ impl BoardPos {
     }
 }
 
+impl std::fmt::Display for BoardPos {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", (b'a' + self.col) as char, 8 - self.row)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct Piece {
     color: Color,
@@ -70,6 +244,8 @@ impl Piece {
             PieceType::Rook => 'r',
             PieceType::Queen => 'q',
             PieceType::King => 'k',
+            PieceType::Archbishop => 'a',
+            PieceType::Chancellor => 'c',
         };
         match self.color {
             Color::White => ch.to_ascii_uppercase(),
@@ -77,6 +253,27 @@ impl Piece {
         }
     }
 
+    fn to_unicode(self) -> char {
+        match (self.color, self.piece) {
+            (Color::White, PieceType::Pawn) => '♙',
+            (Color::White, PieceType::Bishop) => '♗',
+            (Color::White, PieceType::Knight) => '♘',
+            (Color::White, PieceType::Rook) => '♖',
+            (Color::White, PieceType::Queen) => '♕',
+            (Color::White, PieceType::King) => '♔',
+            (Color::Black, PieceType::Pawn) => '♟',
+            (Color::Black, PieceType::Bishop) => '♝',
+            (Color::Black, PieceType::Knight) => '♞',
+            (Color::Black, PieceType::Rook) => '♜',
+            (Color::Black, PieceType::Queen) => '♛',
+            (Color::Black, PieceType::King) => '♚',
+            (Color::White, PieceType::Archbishop) => '\u{1fa50}',
+            (Color::White, PieceType::Chancellor) => '\u{1fa4f}',
+            (Color::Black, PieceType::Archbishop) => '\u{1fa53}',
+            (Color::Black, PieceType::Chancellor) => '\u{1fa52}',
+        }
+    }
+
     fn from_char(ch: char) -> Option<Self> {
         let color = match ch.is_uppercase() {
             true => Color::White,
@@ -90,6 +287,8 @@ impl Piece {
             'r' => PieceType::Rook,
             'q' => PieceType::Queen,
             'k' => PieceType::King,
+            'a' => PieceType::Archbishop,
+            'c' => PieceType::Chancellor,
             _ => {
                 return None;
             }
@@ -102,6 +301,29 @@ impl Piece {
         })
     }
 
+    // whether the piece could capture on pos. pawns only take diagonally
+    fn attacks(&self, pos: BoardPos, board: &ChessBoard) -> bool {
+        if pos == self.pos {
+            return false;
+        }
+        if self.piece == PieceType::Pawn {
+            let forward: i8 = match self.color {
+                Color::White => -1,
+                Color::Black => 1,
+            };
+            return pos.row as i8 - self.pos.row as i8 == forward
+                && (pos.col as i8 - self.pos.col as i8).abs() == 1;
+        }
+        self.is_move_valid(
+            &Move {
+                from: self.pos,
+                to: pos,
+                promotion: None,
+            },
+            board,
+        )
+    }
+
     fn is_move_valid(&self, mve: &Move, board: &ChessBoard) -> bool {
         match self.piece {
             PieceType::Pawn => {
@@ -131,58 +353,26 @@ impl Piece {
                     return false;
                 }
 
-                let attacked: Option<Piece> = board.pieces[(mve.to.row * 8 + mve.to.col) as usize];
+                let attacked: Option<Piece> = board.piece_at(mve.to);
 
                 if mve.from.col != mve.to.col {
                     let col_diff: u8 = (mve.from.col as i8 - mve.to.col as i8).unsigned_abs();
-                    if actual_len != 1
-                        || col_diff != 1
-                        || attacked.is_none()
-                        || attacked.unwrap().color == self.color
-                    {
+                    if actual_len != 1 || col_diff != 1 {
                         return false;
                     }
+                    // a diagonal step onto an empty square only takes en passant
+                    match attacked {
+                        Some(attacked) => attacked.color != self.color,
+                        None => board.en_passant_capture(mve).is_some(),
+                    }
                 } else {
-                    let start: usize =
-                        (8 * std::cmp::min(mve.from.row, mve.to.row) + mve.from.col) as usize;
-                    return board
-                        .pieces
-                        .iter()
-                        .skip(start + if self.color == Color::Black { 8 } else { 0 })
-                        .step_by(8)
-                        .take(actual_len.into())
-                        .all(Option::is_none);
+                    attacked.is_none() && board.is_path_clear(mve.from, mve.to)
                 }
-                true
             }
             PieceType::Rook => {
                 match (mve.from.row == mve.to.row, mve.from.col == mve.to.col) {
                     (false, false) => false,
-                    (true, false) => {
-                        let start: usize =
-                            (8 * mve.from.row + std::cmp::min(mve.from.col, mve.to.col)).into();
-                        let end: usize =
-                            (8 * mve.from.row + std::cmp::max(mve.from.col, mve.to.col) - 1).into();
-                        board
-                            .pieces
-                            .iter()
-                            .skip(start + 1)
-                            .take(end - start)
-                            .all(Option::is_none)
-                    }
-                    (false, true) => {
-                        let start: usize =
-                            (8 * std::cmp::min(mve.from.row, mve.to.row) + mve.from.col).into();
-                        let end: usize =
-                            (8 * std::cmp::max(mve.from.row, mve.to.row) + mve.from.col - 8).into();
-                        board
-                            .pieces
-                            .iter()
-                            .skip(start + 8)
-                            .take(end - start)
-                            .step_by(8)
-                            .all(Option::is_none)
-                    }
+                    (true, false) | (false, true) => board.is_path_clear(mve.from, mve.to),
                     (true, true) => panic!("something went wrong"), // this means the rook didnt move/captured itself, (wrong)
                 }
             }
@@ -193,22 +383,7 @@ impl Piece {
                     return false;
                 }
 
-                let sign = col_offset.signum() * row_offset.signum();
-                assert!(sign != 0, "both column and row offset must be non-zero");
-                let step = (sign + 8) as usize;
-
-                let start: usize = std::cmp::min(mve.from.to_idx(), mve.to.to_idx());
-                let end: usize = std::cmp::max(mve.from.to_idx(), mve.to.to_idx());
-
-                let down_skip = if mve.from.row < mve.to.row { step } else { 0 };
-
-                board
-                    .pieces
-                    .iter()
-                    .skip(start + down_skip + step)
-                    .take(end - start - step)
-                    .step_by(step)
-                    .all(Option::is_none)
+                board.is_path_clear(mve.from, mve.to)
             }
             PieceType::Knight => {
                 let mut diff = vec![
@@ -228,17 +403,7 @@ impl Piece {
                     return false;
                 }
 
-                let start = std::cmp::min(mve.from.to_idx(), mve.to.to_idx());
-                let end = std::cmp::max(mve.from.to_idx(), mve.to.to_idx());
-
-                let step: usize = if straight {
-                    if col_offset == 0 { 8 } else { 1 }     
-                } else {
-                    let sign = col_offset.signum() * row_offset.signum(); 
-                    (8 + sign) as usize
-                };
-
-                board.pieces.iter().skip(start + step).take(end - start - step).step_by(step).all(|e| {println!("{:#?}", e); e.is_none()})
+                board.is_path_clear(mve.from, mve.to)
             }
             PieceType::King => {
                 let col_offset = mve.from.col as i8 - mve.to.col as i8;
@@ -246,172 +411,1213 @@ impl Piece {
 
                 col_offset.abs() <= 1 && row_offset.abs() <= 1
             }
+            PieceType::Archbishop => {
+                self.moves_like(&[PieceType::Bishop, PieceType::Knight], mve, board)
+            }
+            PieceType::Chancellor => {
+                self.moves_like(&[PieceType::Rook, PieceType::Knight], mve, board)
+            }
         }
     }
+
+    // whether one of the given pieces could make the move in this one's place
+    fn moves_like(&self, pieces: &[PieceType], mve: &Move, board: &ChessBoard) -> bool {
+        pieces
+            .iter()
+            .any(|&piece| Piece { piece, ..*self }.is_move_valid(mve, board))
+    }
 }
 
-const NONE_PIECE: Option<Piece> = None;
+// the most pieces a side can have, two full ranks of the widest board
+const MAX_PIECES: usize = 2 * MAX_FILES as usize;
 
-#[derive(Debug)]
-struct ChessBoard {
-    pieces: [Option<Piece>; 64],
-    turn: Color,
-    winner: Option<Color>,
+// the squares of one side's pieces, in no particular order. a side never has
+// more than MAX_PIECES pieces, so this fits in a fixed array and copies cheaply
+#[derive(Debug, Clone, Copy)]
+struct PieceList {
+    squares: [u8; MAX_PIECES],
+    len: u8,
 }
 
-fn row_to_display(row: u8) -> u8 {
-    8 - row
+impl PieceList {
+    fn new() -> Self {
+        PieceList {
+            squares: [0; MAX_PIECES],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, idx: usize) {
+        self.squares[self.len as usize] = idx as u8;
+        self.len += 1;
+    }
+
+    fn remove(&mut self, idx: usize) {
+        let len = self.len as usize;
+        if let Some(i) = self.squares[..len]
+            .iter()
+            .position(|&sq| sq as usize == idx)
+        {
+            self.squares[i] = self.squares[len - 1];
+            self.len -= 1;
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.squares[..self.len as usize]
+            .iter()
+            .map(|&sq| sq as usize)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ChessBoard {
+    // piece type per square, the color is looked up in the bitboards
+    kinds: [Option<PieceType>; SQUARES],
+    // one bit per square occupied by that color, indexed by Color::idx
+    colors: [u128; 2],
+    // the number of files, the columns past it are off the board
+    width: u8,
+    lists: [PieceList; 2],
+    turn: Color,
+    last_move: Option<Move>,
+    // the square a pawn skipped with the double step it just made, while an
+    // enemy pawn could take it en passant there
+    en_passant: Option<BoardPos>,
+    // number of enemy pieces of each type taken by a side, indexed by Color::idx
+    // and PieceType::idx
+    captured: [[u8; PieceType::ALL.len()]; 2],
+    // the plies since the last capture or pawn move, for the fifty move rule
+    halfmove: u16,
+    // starts at 1 and goes up after every black turn, like in fen
+    fullmove: u16,
+    // the moves the side to move has made so far in its turn, in variants
+    // where a turn is more than one move
+    turn_moves: u8,
+    // the columns of the rooks each side may still castle with, indexed by
+    // Color::idx and then castling::SHORT or castling::LONG. in chess960 the
+    // rooks can start on any column, so a flag wouldn't do
+    castling: [[Option<u8>; 2]; 2],
+    // the rules the position is played by
+    variant: VariantKind,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 struct Move {
     from: BoardPos,
     to: BoardPos,
+    // the piece a pawn becomes on the far row
+    promotion: Option<PieceType>,
+}
+
+// uci notation, with the piece promoted to in lowercase like e7e8q
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        match self.promotion {
+            Some(piece) => {
+                let piece = Piece {
+                    color: Color::Black,
+                    piece,
+                    pos: self.to,
+                };
+                write!(f, "{}", piece.to_char())
+            }
+            None => Ok(()),
+        }
+    }
 }
 
 impl Move {
     fn parse(string: &str) -> Option<Self> {
-        if string.len() == 4 {
+        if (string.len() == 4 || string.len() == 5) && string.is_ascii() {
             let from = BoardPos::parse(&string[0..2]);
             let to = BoardPos::parse(&string[2..4]);
-            if from.is_some() && to.is_some() {
-                let (from, to) = (from.unwrap(), to.unwrap());
-                return Some(Move { from, to });
+            let promotion = match string[4..].chars().next() {
+                Some(ch) => Some(
+                    Piece::from_char(ch)
+                        .map(|p| p.piece)
+                        .filter(|&p| p != PieceType::Pawn)?,
+                ),
+                None => None,
+            };
+            if let (Some(from), Some(to)) = (from, to) {
+                return Some(Move {
+                    from,
+                    to,
+                    promotion,
+                });
             }
             return None;
         }
         None
     }
-    fn is_valid(&self, board: &ChessBoard) -> bool {
-        if board.pieces[self.from.to_idx()].is_none()
-            || board.pieces[self.from.to_idx()].unwrap().color != board.turn
-        {
-            return false;
+}
+
+impl ChessBoard {
+    fn empty() -> Self {
+        ChessBoard {
+            kinds: [None; SQUARES],
+            colors: [0; 2],
+            width: MIN_FILES,
+            lists: [PieceList::new(); 2],
+            turn: Color::White,
+            last_move: None,
+            en_passant: None,
+            captured: [[0; PieceType::ALL.len()]; 2],
+            halfmove: 0,
+            fullmove: 1,
+            turn_moves: 0,
+            castling: [[None; 2]; 2],
+            variant: VariantKind::Standard,
         }
-        if board.pieces[self.to.to_idx()].is_some()
-            && board.pieces[self.to.to_idx()].unwrap().color == board.turn
-        {
+    }
+
+    fn new() -> Self {
+        ChessBoard::with_back_rank(&[
+            PieceType::Rook,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Queen,
+            PieceType::King,
+            PieceType::Bishop,
+            PieceType::Knight,
+            PieceType::Rook,
+        ])
+    }
+
+    // the pawns on their rows and the given pieces behind them, mirrored for
+    // black, with castling allowed with the rooks on either side of the king.
+    // the board is as wide as the rank
+    fn with_back_rank(rank: &[PieceType]) -> Self {
+        let mut board = ChessBoard::empty();
+        board.width = rank.len() as u8;
+        for color in [Color::White, Color::Black] {
+            let row = castling::back_row(color);
+            let pawn_row = match color {
+                Color::White => 6,
+                Color::Black => 1,
+            };
+            for (col, &piece) in rank.iter().enumerate() {
+                let col = col as u8;
+                board.put(Piece {
+                    color,
+                    piece: PieceType::Pawn,
+                    pos: BoardPos { row: pawn_row, col },
+                });
+                board.put(Piece {
+                    color,
+                    piece,
+                    pos: BoardPos { row, col },
+                });
+            }
+            for side in [castling::SHORT, castling::LONG] {
+                board.castling[color.idx()][side] = board.outer_rook(color, side);
+            }
+        }
+        board
+    }
+
+    fn color_at(&self, pos: BoardPos) -> Option<Color> {
+        let bit = 1u128 << pos.to_idx();
+        if self.colors[Color::White.idx()] & bit != 0 {
+            Some(Color::White)
+        } else if self.colors[Color::Black.idx()] & bit != 0 {
+            Some(Color::Black)
+        } else {
+            None
+        }
+    }
+
+    fn piece_at(&self, pos: BoardPos) -> Option<Piece> {
+        let piece = self.kinds[pos.to_idx()]?;
+        let color = self.color_at(pos)?;
+        Some(Piece { color, piece, pos })
+    }
+
+    fn is_empty(&self, pos: BoardPos) -> bool {
+        (self.colors[0] | self.colors[1]) & (1u128 << pos.to_idx()) == 0
+    }
+
+    // places a piece on its square, the square has to be empty
+    fn put(&mut self, piece: Piece) {
+        let idx = piece.pos.to_idx();
+        self.kinds[idx] = Some(piece.piece);
+        self.colors[piece.color.idx()] |= 1u128 << idx;
+        self.lists[piece.color.idx()].push(idx);
+    }
+
+    fn take(&mut self, pos: BoardPos) -> Option<Piece> {
+        let piece = self.piece_at(pos)?;
+        let idx = pos.to_idx();
+        self.kinds[idx] = None;
+        self.colors[piece.color.idx()] &= !(1u128 << idx);
+        self.lists[piece.color.idx()].remove(idx);
+        Some(piece)
+    }
+
+    // every square of the board, row by row from a8
+    fn squares(&self) -> impl Iterator<Item = BoardPos> {
+        let width = self.width;
+        (0..RANKS).flat_map(move |row| (0..width).map(move |col| BoardPos { row, col }))
+    }
+
+    fn pieces(&self, color: Color) -> impl Iterator<Item = Piece> + '_ {
+        self.lists[color.idx()]
+            .iter()
+            .map(move |idx| self.piece_at(BoardPos::from_idx(idx).unwrap()).unwrap())
+    }
+
+    // false once color is down to a lone king, or a king and a single bishop
+    // or knight, which can't force mate. without mate any piece can still win
+    fn has_mating_material(&self, color: Color) -> bool {
+        if !self.rules().royal_king() {
+            return true;
+        }
+        let mut rest = self.pieces(color).filter(|p| p.piece != PieceType::King);
+        match (rest.next(), rest.next()) {
+            (None, _) => false,
+            (Some(p), None) => !matches!(p.piece, PieceType::Bishop | PieceType::Knight),
+            _ => true,
+        }
+    }
+
+    // true if every square strictly between from and to is empty. the squares
+    // have to be on the same row, column or diagonal
+    fn is_path_clear(&self, from: BoardPos, to: BoardPos) -> bool {
+        let row_step = (to.row as i8 - from.row as i8).signum();
+        let col_step = (to.col as i8 - from.col as i8).signum();
+        let mut row = from.row as i8 + row_step;
+        let mut col = from.col as i8 + col_step;
+        while (row, col) != (to.row as i8, to.col as i8) {
+            let pos = BoardPos {
+                row: row as u8,
+                col: col as u8,
+            };
+            if !self.is_empty(pos) {
+                return false;
+            }
+            row += row_step;
+            col += col_step;
+        }
+        true
+    }
+
+    // plays a move without checking it, the caller has to make sure it is legal
+    fn make_move(&mut self, mve: &Move) {
+        let castles = self.castling_side(mve);
+        // a pawn taken en passant doesn't stand on the target square
+        let taken = self.en_passant_capture(mve).unwrap_or(mve.to);
+        // a double step can only be taken en passant right away
+        self.en_passant = None;
+        self.update_castling(mve);
+        self.halfmove = self.halfmove.saturating_add(1);
+        if let Some(side) = castles {
+            self.castle(mve, side);
+        } else if let Some(piece) = self.take(mve.from) {
+            if let Some(taken) = self.take(taken) {
+                self.captured[piece.color.idx()][taken.piece.idx()] += 1;
+                self.halfmove = 0;
+            }
+            if piece.piece == PieceType::Pawn {
+                self.halfmove = 0;
+            }
+            self.put(Piece {
+                pos: mve.to,
+                piece: mve.promotion.unwrap_or(piece.piece),
+                ..piece
+            });
+            if piece.piece == PieceType::Pawn && mve.from.row.abs_diff(mve.to.row) == 2 {
+                self.en_passant = self.en_passant_square(mve.to);
+            }
+        }
+        self.last_move = Some(*mve);
+        // a turn ends after as many moves as the variant gives, or as soon as
+        // one of them checks
+        self.turn_moves += 1;
+        if self.turn_moves < self.rules().moves_per_turn() && !self.in_check(self.turn.other()) {
+            return;
+        }
+        self.turn_moves = 0;
+        if self.turn == Color::Black {
+            self.fullmove = self.fullmove.saturating_add(1);
+        }
+        self.turn = self.turn.other();
+    }
+
+    fn king_pos(&self, color: Color) -> Option<BoardPos> {
+        self.pieces(color)
+            .find(|p| p.piece == PieceType::King)
+            .map(|p| p.pos)
+    }
+
+    // whether a piece of by could capture on pos, whatever stands there
+    fn is_attacked(&self, pos: BoardPos, by: Color) -> bool {
+        self.attackers(pos, by).next().is_some()
+    }
+
+    fn attackers(&self, pos: BoardPos, by: Color) -> impl Iterator<Item = Piece> + '_ {
+        self.pieces(by).filter(move |p| p.attacks(pos, self))
+    }
+
+    fn in_check(&self, color: Color) -> bool {
+        if !self.rules().royal_king() {
             return false;
         }
-        let piece = board.pieces[self.from.to_idx()].unwrap();
-        piece.is_move_valid(self, board)
+        match self.king_pos(color) {
+            Some(pos) => self.is_attacked(pos, color.other()),
+            None => false,
+        }
+    }
+
+    // a move is legal if the piece can make it, it doesn't leave the own king
+    // in check and the variant allows it. what the variant rules out can depend
+    // on the other moves, like compulsory captures, so it is looked up among them
+    fn is_legal(&self, mve: &Move) -> bool {
+        self.legal_moves().contains(mve)
+    }
+
+    fn keeps_king_safe(&self, mve: &Move) -> bool {
+        if !self.rules().royal_king() {
+            return true;
+        }
+        let mut next = self.clone();
+        next.make_move(mve);
+        !next.in_check(self.turn)
+    }
+
+    fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for from in self.pieces(self.turn) {
+            for to in self
+                .squares()
+                .filter(|&pos| self.color_at(pos) != Some(self.turn))
+            {
+                let mve = Move {
+                    from: from.pos,
+                    to,
+                    promotion: None,
+                };
+                if !from.is_move_valid(&mve, self) || !self.keeps_king_safe(&mve) {
+                    continue;
+                }
+                // a pawn reaching the far row is one move for each piece it
+                // can become
+                match self.promotes(&mve) {
+                    true => moves.extend(self.rules().promotions().iter().map(|&piece| Move {
+                        promotion: Some(piece),
+                        ..mve
+                    })),
+                    false => moves.push(mve),
+                }
+            }
+        }
+        moves.extend(self.castling_moves().filter(|m| self.keeps_king_safe(m)));
+        self.rules().restrict(self, &mut moves);
+        moves
     }
 }
 
-impl ChessBoard {
-    fn new() -> Self {
-        let mut pieces: Vec<Piece> = Vec::new();
-        let mut board: ChessBoard = ChessBoard {
-            pieces: [NONE_PIECE; 64],
-            turn: Color::White,
-            winner: None,
-        };
-        //add pawns
-        for col in 0..8 {
-            //add white pawns
-            pieces.push(Piece {
-                color: Color::White,
-                piece: PieceType::Pawn,
-                pos: BoardPos { row: 6, col },
-            });
-            //add black pawns
-            pieces.push(Piece {
-                color: Color::Black,
-                piece: PieceType::Pawn,
-                pos: BoardPos { row: 1, col },
-            });
+// sets up the game from --fen, --opening, --odds or --pgn, or the initial
+// position of the variant
+fn start_game(cli: &Cli, rng: &mut rng::Rng) -> Result<Game, String> {
+    if cli.variant != VariantKind::Standard && (cli.opening.is_some() || cli.odds.is_some()) {
+        return Err(String::from("--opening and --odds are for standard chess"));
+    }
+    if cli.position.is_some() && cli.variant != VariantKind::Chess960 {
+        return Err(String::from("--position is for --variant chess960"));
+    }
+    if let Some(fen) = &cli.fen {
+        let board = ChessBoard::parse_fen(fen)
+            .map(|board| board.with_variant(cli.variant))
+            .and_then(|board| board.validate().map(|_| board))
+            .map_err(|e| format!("invalid fen: {}", e))?;
+        return Ok(Game::from_board(board));
+    }
+    if let Some(opening) = &cli.opening {
+        return openings::play(opening);
+    }
+    if let Some(odds) = cli.odds {
+        return Ok(Game::from_board(odds.board()));
+    }
+    match &cli.pgn {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+            pgn::read(&text, cli.variant)
+        }
+        None if cli.variant == VariantKind::Chess960 => {
+            let n = cli
+                .position
+                .unwrap_or_else(|| rng.below(variant::CHESS960_POSITIONS.into()) as u16);
+            println!("chess960 position {}", n);
+            Ok(Game::chess960(n))
         }
+        None => Ok(Game::from_board(cli.variant.rules().setup(rng))),
+    }
+}
 
-        for (col, piece) in "rnbqkbnr"
-            .chars()
-            .map(|c| Piece::from_char(c).unwrap().piece)
-            .enumerate()
-        {
-            let col = col.try_into().unwrap();
-            //add white pawns
-            pieces.push(Piece {
-                color: Color::White,
-                piece,
-                pos: BoardPos { row: 7, col },
-            });
-            //add black pawns
-            pieces.push(Piece {
-                color: Color::Black,
-                piece,
-                pos: BoardPos { row: 0, col },
-            });
+// every new move is read out in words before the position in accessible mode
+fn announce_moves(session: &Session, announced: &mut usize) {
+    let history = &session.game.history;
+    for played in history.iter().skip(*announced) {
+        let words = played.before.describe_move(&played.mve);
+        if session.options.style == BoardStyle::Accessible {
+            println!("{}.", words);
         }
+    }
+    *announced = history.len();
+}
 
-        for piece in pieces {
-            board.pieces[piece.pos.to_idx()] = Some(piece);
+// searches the position and prints the evaluation after every depth. the
+// line is rewritten in place, in accessible mode only the final one is read out
+fn print_eval(session: &mut Session) -> Option<(Move, i32)> {
+    let board = match &session.sideline {
+        Some(sideline) => &sideline.board,
+        None => &session.game.board,
+    };
+    let options = &session.options;
+    let accessible = options.style == BoardStyle::Accessible;
+    let mut last = None;
+    let report = |depth, pv: &[Move], score| {
+        let score = engine::white_score(board.turn, score);
+        let line = options.line_text(board, pv);
+        if !accessible {
+            // the main line changes length, the rest of the old one is erased
+            print!("\r{} {}\x1b[K", display::eval_line(score, depth), line);
+            let _ = std::io::stdout().flush();
+        }
+        last = Some((depth, score, line));
+    };
+    let best = match &mut session.uci {
+        Some(uci) => uci.search(board, session.depth, report),
+        None => engine::deepen(board, session.depth, report),
+    };
+    match &last {
+        Some((depth, score, line)) if accessible => println!(
+            "Evaluation {} at depth {}, main line {}.",
+            engine::format_score(*score),
+            depth,
+            line
+        ),
+        Some(_) => println!(),
+        None => {}
+    }
+    // the evaluation after the last move of the game goes into its pgn
+    if let (None, Some((_, score, _))) = (&session.sideline, last) {
+        if let Some(played) = session.game.history.last_mut() {
+            played.eval.get_or_insert(score);
         }
-        board
     }
+    best
+}
+
+// runs the subcommands that do their work and end without a board to show,
+// none for the others
+fn run_command(cli: &Cli, game: &Game, rng: &mut rng::Rng) -> Option<Result<(), String>> {
+    let result = match cli.command.as_ref()? {
+        Command::Perft {
+            depth,
+            hash,
+            parallel,
+            threads,
+        } => perft::run(&game.board, *depth, *hash, *parallel, *threads),
+        Command::Bench { depth } => {
+            bench::run(*depth);
+            Ok(())
+        }
+        Command::PerftSuite => perft::suite(),
+        Command::Difftest { games, plies } => perft::difftest(
+            &game.board,
+            cli.uci.as_deref(),
+            &cli.uci_options,
+            *games,
+            *plies,
+            rng,
+        ),
+        Command::Batch { file } => batch::run(game.clone(), file.as_deref()),
+        Command::ValidateFen { fen } => fen::run(fen),
+        Command::Png {
+            output,
+            size,
+            theme,
+        } => image::png(&game.board, *size, *theme, cli.flip, output),
+        Command::Gif {
+            output,
+            size,
+            theme,
+            delay,
+        } => image::gif(game, *size, *theme, cli.flip, *delay, output),
+        Command::Tournament {
+            schedule,
+            output,
+            rules,
+            resume,
+        } => {
+            let log = (!cli.no_library).then(library::default_log).flatten();
+            tournament::run(
+                &game.board,
+                schedule,
+                *rules,
+                output,
+                log.as_deref(),
+                *resume,
+            )
+        }
+        Command::Search {
+            path,
+            filter,
+            output,
+        } => database::run(path, filter, output.as_deref()),
+        Command::Dedupe {
+            path,
+            output,
+            merge,
+        } => dedupe::run(path, output.as_deref(), *merge).map(print_lines),
+        Command::Similarity {
+            path,
+            player,
+            depth,
+            top,
+        } => similarity::run(path, player.as_deref(), *depth, *top).map(print_lines),
+        Command::Book {
+            action:
+                BookAction::Build {
+                    pgn,
+                    output,
+                    limits,
+                },
+        } => book::run(pgn, output, *limits),
+        Command::Tactics { output, depth } => puzzle::tactics(output, cli.variant, *depth),
+        Command::Testsuite { file, time } => {
+            testsuite::run(file, cli.variant, std::time::Duration::from_millis(*time))
+        }
+        Command::Serve {
+            port,
+            engine,
+            certificate,
+        } => certificate
+            .server_config()
+            .and_then(|tls| serve::run(*port, game.clone(), *engine, tls)),
+        Command::Relay { port, certificate } => certificate
+            .server_config()
+            .and_then(|tls| relay::run(*port, tls)),
+        Command::Lobby { port, certificate } => certificate
+            .server_config()
+            .and_then(|tls| lobby::run(*port, tls)),
+        #[cfg(feature = "grpc")]
+        Command::Grpc { port } => grpc::run(*port),
+        Command::Lichess {
+            token,
+            depth,
+            server,
+        } => lichess::run(server, token.as_deref(), *depth),
+        Command::Watch { .. }
+        | Command::Puzzle { .. }
+        | Command::Train { .. }
+        | Command::Tutorial
+        | Command::Selfplay { .. }
+        | Command::Repertoire { .. }
+        | Command::Host { .. }
+        | Command::Join { .. } => return None,
+    };
+    Some(result)
+}
 
-    fn print(&self) {
-        println!(
-            "{}'s turn",
-            match self.turn {
-                Color::White => "White",
-                Color::Black => "Black",
-            }
-        );
-        println!("   a  b  c  d  e  f  g  h");
-        for (idx, piece) in self.pieces.iter().enumerate() {
-            let pos = BoardPos::from_idx(idx).unwrap();
-            if pos.col == 0 {
-                print!("{} ", row_to_display(pos.row));
-            }
-            print!(
-                "[{}]",
-                match piece {
-                    Some(p) => p.to_char(),
-                    None => ' ',
+// runs the subcommands that show boards in the terminal and end, none for
+// the others
+fn run_display_command(
+    cli: &Cli,
+    game: &Game,
+    options: &DisplayOptions,
+    rng: &mut rng::Rng,
+) -> Option<Result<(), String>> {
+    let result = match cli.command.as_ref()? {
+        Command::Watch {
+            pgn,
+            interval,
+            follow,
+        } => {
+            let interval = std::time::Duration::from_millis(*interval);
+            watch::run(pgn, &game.board, options, interval, *follow)
+        }
+        Command::Tutorial => tutorial::run(options),
+        Command::Train { ending } => endgame::run(*ending, options, rng),
+        Command::Selfplay {
+            output,
+            settings,
+            rules,
+        } => selfplay::run(&game.board, *settings, *rules, output, rng),
+        Command::Repertoire {
+            file,
+            color,
+            export: Some(output),
+            images,
+        } => repertoire::export(file, cli.variant, color.color(), output, images.as_deref()),
+        Command::Repertoire {
+            file,
+            color,
+            export: None,
+            ..
+        } => repertoire::run(file, cli.variant, color.color(), options, rng),
+        Command::Puzzle {
+            file: Some(file),
+            daily: false,
+        } => puzzle::run(file, cli.variant, options),
+        Command::Puzzle { .. } => puzzle::daily(options),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn print_lines(lines: Vec<String>) {
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = logging::init(cli.log_file.as_deref(), cli.log_level) {
+        println!("{}", e);
+        std::process::exit(1);
+    }
+    if let Some(path) = &cli.search_stats {
+        if let Err(e) = searchstats::init(path, cli.search_trace) {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    let mut seed = cli.seed.unwrap_or_else(rng::seed_from_time);
+    let journal = match (&cli.record, &cli.replay) {
+        (Some(path), _) => replay::Journal::record(path, seed).map(Some),
+        (_, Some(path)) => replay::Journal::replay(path).map(|(journal, recorded)| {
+            seed = recorded;
+            Some(journal)
+        }),
+        _ => Ok(None),
+    };
+    let journal = match journal {
+        Ok(journal) => journal,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut rng = rng::Rng::new(seed);
+
+    let mut game = match start_game(&cli, &mut rng) {
+        Ok(game) => game,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(result) = run_command(&cli, &game, &mut rng) {
+        if let Err(e) = result {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let config = match cli.config.clone().or_else(config::default_path) {
+        Some(path) => match Config::load(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None => Config::default(),
+    };
+
+    let mut options = DisplayOptions {
+        unicode: cli.unicode || config.unicode,
+        color: DisplayOptions::color_supported() && !cli.no_color && config.color != Some(false),
+        flipped: cli.flip || config.flip,
+        auto_flip: cli.auto_flip || config.auto_flip,
+        locale: cli
+            .locale
+            .or(config.locale)
+            .or_else(Locale::from_env)
+            .unwrap_or_default(),
+        notation: cli.notation.unwrap_or(config.notation),
+        eval: cli.eval || config.eval,
+        style: if cli.accessible {
+            BoardStyle::Accessible
+        } else if cli.large {
+            BoardStyle::Large
+        } else if cli.compact {
+            BoardStyle::Compact
+        } else {
+            match config.renderer {
+                Renderer::Accessible => BoardStyle::Accessible,
+                Renderer::Large => BoardStyle::Large,
+                Renderer::Compact => BoardStyle::Compact,
+                Renderer::Plain | Renderer::Tui if DisplayOptions::terminal_is_small() => {
+                    BoardStyle::Compact
                 }
-            );
-            if pos.col == 7 {
-                println!(" {}", row_to_display(pos.row));
+                Renderer::Plain | Renderer::Tui => BoardStyle::Grid,
+            }
+        },
+        coordinates: !cli.no_coords && config.coordinates != Some(false),
+        redraw: false,
+        theme: Theme::default(),
+    };
+    // screen readers follow new output better than a cleared screen
+    options.redraw = std::io::stdout().is_terminal()
+        && options.style != BoardStyle::Accessible
+        && !cli.no_redraw
+        && config.redraw != Some(false);
+    // the config file can add themes and replace the built in ones
+    let mut themes = theme::built_in();
+    for (name, theme) in &config.themes {
+        match themes.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = *theme,
+            None => themes.push((name.clone(), *theme)),
+        }
+    }
+    if let Some(name) = &config.theme {
+        match themes.iter().find(|(n, _)| n == name) {
+            Some((_, theme)) => options.theme = *theme,
+            None => {
+                println!("unknown theme in config: {}", name);
+                std::process::exit(1);
             }
         }
-        println!("   a  b  c  d  e  f  g  h");
     }
 
-    fn execute(&mut self, mve: &Move) -> bool {
-        let from_idx = mve.from.to_idx();
-        let from_piece = self.pieces[from_idx];
-        let to_idx = mve.to.to_idx();
-        if from_piece.is_some() && mve.is_valid(self) {
-            self.pieces[to_idx] = from_piece;
-            self.pieces[from_idx] = None;
-            self.turn = match self.turn {
-                Color::White => Color::Black,
-                Color::Black => Color::White,
-            };
-            true
+    if let Some(result) = run_display_command(&cli, &game, &options, &mut rng) {
+        if let Err(e) = result {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let autosave = if cli.no_autosave || config.autosave == Some(false) {
+        None
+    } else {
+        save::autosave_path()
+    };
+    save::install_panic_hook();
+    let mut resumed = false;
+    if let Some(path) = &autosave {
+        // only asked on a terminal, piped input is meant for the game itself
+        let fresh = cli.command.is_none()
+            && cli.fen.is_none()
+            && cli.pgn.is_none()
+            && cli.opening.is_none()
+            && cli.odds.is_none()
+            && cli.variant == VariantKind::Standard
+            && journal.is_none();
+        if fresh && path.exists() && std::io::stdin().is_terminal() {
+            print!("resume the unfinished game from last time? [y/n] ");
+            let _ = std::io::stdout().flush();
+            let mut answer = String::new();
+            let _ = std::io::stdin().read_line(&mut answer);
+            if answer.trim().eq_ignore_ascii_case("y") {
+                match save::load(path) {
+                    Ok(saved) => {
+                        game = saved;
+                        resumed = true;
+                    }
+                    Err(e) => println!("{}", e),
+                }
+            }
+        }
+    }
+
+    // a side without a time control of its own plays with the --tc one, or
+    // the other side's when there is none
+    let control = cli.tc.or(cli.white_tc).or(cli.black_tc);
+    if let Some(control) = control.filter(|_| !resumed) {
+        let controls = [
+            cli.white_tc.unwrap_or(control),
+            cli.black_tc.unwrap_or(control),
+        ];
+        game.clock = Some(Clock::new(controls, game.board.turn));
+    }
+    if cli.armageddon && !resumed {
+        game.armageddon = true;
+        // black gets less time for the draw odds, 5 against 4 minutes unless
+        // the time control says otherwise
+        let white = cli.tc.unwrap_or(TimeControl {
+            base: std::time::Duration::from_secs(300),
+            bonus: None,
+        });
+        let black = cli.black_tc.unwrap_or(TimeControl {
+            base: white.base * 4 / 5,
+            ..white
+        });
+        game.clock = Some(Clock::new(
+            [cli.white_tc.unwrap_or(white), black],
+            game.board.turn,
+        ));
+    }
+    // in a network game one side is played here by a person, the other remotely
+    let mut players = [cli.white, cli.black];
+    let mut remote = None;
+    let network = match &cli.command {
+        Some(Command::Host {
+            port,
+            color,
+            certificate,
+            relay,
+            code,
+            trust,
+        }) => net::host(
+            *port,
+            color.color(),
+            &game.board,
+            certificate,
+            relay.as_deref(),
+            code.as_deref(),
+            trust,
+        )
+        .map(|r| (r, Some(color.color()))),
+        Some(Command::Join { addr, relay, trust }) => {
+            net::join(addr, relay.as_deref(), trust).map(|(r, color, board)| {
+                game = Game::from_board(board);
+                (r, color)
+            })
+        }
+        _ => Err(String::new()),
+    };
+    match network {
+        Ok((r, None)) => {
+            if let Err(e) = watch::follow(r, &game.board, &options) {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok((r, Some(color))) => {
+            players[color.idx()] = Player::Human;
+            players[color.other().idx()] = Player::Remote;
+            options.flipped = color == Color::Black;
+            // clocks on two machines would drift apart
+            game.clock = None;
+            remote = Some(r);
+        }
+        Err(e) if !e.is_empty() => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+        Err(_) => {}
+    }
+    if players.contains(&Player::Uci) && cli.uci.is_none() {
+        println!("the uci player needs an engine, give it with --uci");
+        std::process::exit(1);
+    }
+    let engine_options = EngineOptions::from_config(&config.engine).and_then(|mut options| {
+        for option in &cli.engine_options {
+            options.parse(option)?;
+        }
+        Ok(options)
+    });
+    let engine_options = match engine_options {
+        Ok(options) => options,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let uci = match &cli.uci {
+        Some(command) => match uci::UciEngine::start(command, &cli.uci_options)
+            .and_then(|mut engine| engine_options.apply(&mut engine).map(|_| engine))
+        {
+            Ok(engine) => Some(engine),
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let span = tracing::info_span!("game", number = 1).entered();
+    tracing::info!("started from {}", game.start().to_fen());
+    let mut session = Session {
+        game,
+        games: 1,
+        span,
+        options,
+        highlights: Highlights::default(),
+        annotations: Annotations::default(),
+        players,
+        depth: cli.depth.or(config.depth).unwrap_or(3),
+        human_like: cli.human_like.map(|rating| Persona { rating }),
+        draw_offer: None,
+        takeback_request: None,
+        choices: Vec::new(),
+        themes,
+        uci,
+        engine_options,
+        secret: cli.secret.clone().or(config.secret).unwrap_or_default(),
+        autosave,
+        viewing: None,
+        library: if cli.no_library {
+            None
+        } else {
+            library::default_dir()
+        },
+        logged: false,
+        game_log: if cli.no_library {
+            None
+        } else {
+            library::default_log()
+        },
+        rng,
+        ratings: if cli.unrated {
+            None
         } else {
-            false
+            rating::default_path()
+        },
+        rated: false,
+        reviewed: false,
+        analysis_cache: if cli.no_cache {
+            None
+        } else {
+            cache::default_path()
+        },
+        editor: None,
+        explorer: None,
+        oracle: None,
+        sideline: None,
+        premove: None,
+        remote,
+        sent: 0,
+    };
+    session.game.observe(std::sync::Arc::new(logging::GameLog));
+    if let Some(command) = cli.announce.clone().or(config.announce) {
+        let announcer = Announcer::new(command);
+        session.game.observe(std::sync::Arc::new(announcer));
+    }
+
+    // the terminal interface doesn't wait for the network, and reads keys
+    // where a recording or a script has lines
+    let network = session.remote.is_some() || journal.is_some() || cli.json;
+    if !network && cli.tui
+        || !network
+            && (config.renderer == Renderer::Tui && !cli.accessible && !cli.large && !cli.compact)
+    {
+        if let Err(e) = tui::run(session) {
+            println!("terminal error: {}", e);
         }
+        return;
     }
+
+    play(session, journal, cli.json);
 }
 
-fn main() {
-    let mut board = ChessBoard::new();
+// the game in the terminal line by line, or with --json for scripts, until
+// quit or the end of the input
+fn play(mut session: Session, mut journal: Option<replay::Journal>, json: bool) {
+    // scripts get each position once, after the messages that led to it.
+    // their lines are read on a thread of their own so the clock can be told
+    // about while waiting
+    let mut lines = None;
+    if json {
+        session.options.redraw = true;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        lines = Some(receiver);
+    }
+    // when redrawing, messages wait to be printed under the next board
+    let mut notes: Vec<String> = Vec::new();
+    for line in session.game.history_lines(&session.options) {
+        if session.options.redraw {
+            notes.push(line);
+        } else {
+            println!("{}", line);
+        }
+    }
+
     let mut input = String::new();
-    while board.winner.is_none() {
-        board.print();
+    let mut show_board = true;
+    // moves already read out in accessible mode
+    let mut announced = session.game.history.len();
+    // the summary of a finished game is only printed under its first board
+    let mut summarized = false;
+    // after a game ends new and rematch can start another one
+    loop {
+        // the evaluation search doubles as the engine's search for its move
+        let mut searched = None;
+        let over = session.game.result.is_some();
+        if show_board && json {
+            if !notes.is_empty() {
+                println!("{}", events::note(&notes));
+                notes.clear();
+            }
+            let mut lines = session.explorer_lines();
+            lines.extend(session.oracle_lines());
+            if !lines.is_empty() {
+                println!("{}", events::note(&lines));
+            }
+            let board = session.shown_board();
+            println!("{}", events::state(&session.game, board, &session.status()));
+            if over && !summarized {
+                if let Some(result) = events::result(&session.game) {
+                    println!("{}", result);
+                }
+            }
+            summarized = over;
+        } else if show_board {
+            clear_screen(&session.options);
+            announce_moves(&session, &mut announced);
+            session
+                .shown_board()
+                .print(&session.options, &session.highlights);
+            println!("{}", session.status());
+            if let Some(opening) = session.opening() {
+                println!("{}", opening);
+            }
+            if let Some(clock) = &session.game.clock {
+                println!("{}", clock.describe());
+            }
+            for line in session.explorer_lines() {
+                println!("{}", line);
+            }
+            for line in session.oracle_lines() {
+                println!("{}", line);
+            }
+            for note in notes.drain(..) {
+                println!("{}", note);
+            }
+            session.highlights = Highlights::default();
+            if over && !summarized {
+                for line in session.game.summary() {
+                    println!("{}", line);
+                }
+            }
+            summarized = over;
+            // a side line of a finished game is explored with the evaluation
+            let exploring = session.sideline.is_some();
+            if over && !exploring && session.remote.is_some() {
+                println!("type quit to leave");
+            } else if over && !exploring {
+                println!("type new or rematch to play again, or quit");
+            } else if session.options.eval && session.editor.is_none() {
+                searched = print_eval(&mut session);
+            }
+        }
+
+        let turn = session.game.board.turn;
+        if !over && session.players[turn.idx()] == Player::Remote {
+            let happened = session.wait_for_remote();
+            if session.options.redraw {
+                notes.extend(happened);
+            } else {
+                for line in happened {
+                    println!("{}", line);
+                }
+            }
+            show_board = true;
+            continue;
+        }
+        // nothing is played while a position is set up in the editor
+        if !over && session.editor.is_none() && session.players[turn.idx()] != Player::Human {
+            // what was searched belongs to the side line when one is open
+            let searched = searched.filter(|_| session.sideline.is_none());
+            if let Some(mve) = session.computer_move(searched.map(|(mve, _)| mve)) {
+                if json {
+                    println!("{}", events::played(&session.game.board, turn, &mve));
+                }
+                if let Some(note) = journal.as_mut().and_then(|j| j.decided(&mve)) {
+                    if session.options.redraw {
+                        notes.push(note);
+                    } else {
+                        println!("{}", note);
+                    }
+                }
+                if session.options.style != BoardStyle::Accessible && !json {
+                    let san = session.game.board.san(&mve);
+                    let text = session.options.move_text(&san, &mve);
+                    let note = session.options.locale.plays(turn, &text);
+                    if session.options.redraw {
+                        notes.push(note);
+                    } else {
+                        println!("{}", note);
+                    }
+                }
+                session.game.play(&mve);
+                session.record();
+            }
+            show_board = true;
+            continue;
+        }
+
+        if let Some(note) = session.play_premove() {
+            session.record();
+            match json {
+                true => println!("{}", events::note(&[note])),
+                false if session.options.redraw => notes.push(note),
+                false => println!("{}", note),
+            }
+            show_board = true;
+            continue;
+        }
+
         input.clear();
-        std::io::stdin().read_line(&mut input).unwrap();
-        input = input.as_str().trim().to_string();
-        let player_move: Move = match Move::parse(&input) {
-            Some(m) => m,
-            None => {
-                println!("invalid move format. example: e2e4");
-                continue;
+        if let Some(line) = journal.as_mut().and_then(replay::Journal::next_input) {
+            if !json {
+                println!("> {}", line);
             }
-        };
-        let result = board.execute(&player_move);
-        if !result {
-            println!("move is invalid");
+            input = line;
+        } else if let Some(lines) = &lines {
+            match lines.recv_timeout(std::time::Duration::from_secs(1)) {
+                Ok(line) => input = line,
+                Err(RecvTimeoutError::Timeout) => {
+                    let running = session.game.clock.as_ref().and_then(Clock::running);
+                    if session.game.check_time() {
+                        session.record();
+                        show_board = true;
+                    } else if let (Some(clock), Some(running)) = (&session.game.clock, running) {
+                        println!("{}", events::clock_tick(clock, running));
+                        show_board = false;
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if let Some(journal) = &mut journal {
+                journal.input(&input);
+            }
+            match events::command(&input) {
+                Ok(command) => input = command,
+                Err(e) => {
+                    println!("{}", events::error(&e));
+                    show_board = false;
+                    continue;
+                }
+            }
+        } else if std::io::stdin().read_line(&mut input).unwrap() == 0 {
+            break;
+        } else if let Some(journal) = &mut journal {
+            journal.input(&input);
+        }
+        let reply = commands::execute(&mut session, &input);
+        if json {
+            println!("{}", events::reply(&reply.lines));
+        } else if session.options.redraw && reply.flow == Flow::ShowBoard {
+            notes.extend(reply.lines);
+        } else {
+            for line in &reply.lines {
+                println!("{}", line);
+            }
+        }
+        match reply.flow {
+            Flow::Quit => return,
+            Flow::ShowBoard => show_board = true,
+            Flow::Continue => show_board = false,
         }
     }
 }
@@ -0,0 +1,330 @@
+use crate::game::{Adjudication, DrawClaim, Game, GameResult};
+use crate::locale::Locale;
+use crate::Color;
+
+// the words of one language that the status line, the results and the answers
+// to moves are pieced together from. {} stands for what is filled in, in order
+struct Words {
+    // the sides as they are named inside a sentence
+    white: &'static str,
+    black: &'static str,
+    to_move: &'static str,
+    check: &'static str,
+    plays: &'static str,
+    wins: &'static str,
+    draw: &'static str,
+    // a draw by a claim, and the claims
+    draw_by: &'static str,
+    repetition: &'static str,
+    fifty_moves: &'static str,
+    claimable: &'static str,
+    checkmate: &'static str,
+    resigns: &'static str,
+    stalemate: &'static str,
+    agreed: &'static str,
+    out_of_time: &'static str,
+    cant_mate: &'static str,
+    adjudicated: &'static str,
+    adjudicated_draw: &'static str,
+    no_material: &'static str,
+    armageddon: &'static str,
+    no_moves: &'static str,
+    invalid_move: &'static str,
+    compulsory: &'static str,
+    unknown: &'static str,
+}
+
+const EN: Words = Words {
+    white: "White",
+    black: "Black",
+    to_move: "{} to move",
+    check: "check",
+    plays: "{} plays {}",
+    wins: "{} wins",
+    draw: "draw",
+    draw_by: "draw by {}",
+    repetition: "threefold repetition",
+    fifty_moves: "the fifty-move rule",
+    claimable: "draw can be claimed by {}",
+    checkmate: "checkmate",
+    resigns: "{} resigns",
+    stalemate: "stalemate",
+    agreed: "draw agreed",
+    out_of_time: "{} ran out of time",
+    cant_mate: "but {} can't mate",
+    adjudicated: "adjudicated",
+    adjudicated_draw: "adjudicated draw",
+    no_material: "neither side can mate",
+    armageddon: "draw, which wins the armageddon for {}",
+    no_moves: "{} has no moves left and wins",
+    invalid_move: "move is invalid",
+    compulsory: "a capture is compulsory",
+    unknown: "unknown command or move. type help for a list of commands",
+};
+
+const DE: Words = Words {
+    white: "Weiß",
+    black: "Schwarz",
+    to_move: "{} am Zug",
+    check: "Schach",
+    plays: "{} spielt {}",
+    wins: "{} gewinnt",
+    draw: "remis",
+    draw_by: "remis durch {}",
+    repetition: "dreifache Stellungswiederholung",
+    fifty_moves: "die 50-Züge-Regel",
+    claimable: "Remis kann beansprucht werden durch {}",
+    checkmate: "schachmatt",
+    resigns: "{} gibt auf",
+    stalemate: "patt",
+    agreed: "remis vereinbart",
+    out_of_time: "{} hat die Zeit überschritten",
+    cant_mate: "aber {} kann nicht mattsetzen",
+    adjudicated: "durch Adjudikation",
+    adjudicated_draw: "remis durch Adjudikation",
+    no_material: "keine Seite kann mattsetzen",
+    armageddon: "remis, damit gewinnt {} das Armageddon",
+    no_moves: "{} hat keine Züge mehr und gewinnt",
+    invalid_move: "ungültiger Zug",
+    compulsory: "es herrscht Schlagzwang",
+    unknown: "unbekannter Befehl oder Zug. help listet die Befehle auf",
+};
+
+const FR: Words = Words {
+    white: "les Blancs",
+    black: "les Noirs",
+    to_move: "{} jouent",
+    check: "échec",
+    plays: "{} jouent {}",
+    wins: "{} gagnent",
+    draw: "nulle",
+    draw_by: "nulle par {}",
+    repetition: "triple répétition",
+    fifty_moves: "la règle des 50 coups",
+    claimable: "nulle réclamable par {}",
+    checkmate: "échec et mat",
+    resigns: "{} abandonnent",
+    stalemate: "pat",
+    agreed: "nulle par accord mutuel",
+    out_of_time: "{} ont dépassé le temps",
+    cant_mate: "mais {} ne peuvent pas mater",
+    adjudicated: "par arbitrage",
+    adjudicated_draw: "nulle par arbitrage",
+    no_material: "aucun camp ne peut mater",
+    armageddon: "nulle, donc {} gagnent l'armageddon",
+    no_moves: "{} n'ont plus de coups et gagnent",
+    invalid_move: "coup invalide",
+    compulsory: "la prise est obligatoire",
+    unknown: "commande ou coup inconnu. help liste les commandes",
+};
+
+const ES: Words = Words {
+    white: "las blancas",
+    black: "las negras",
+    to_move: "juegan {}",
+    check: "jaque",
+    plays: "{} juegan {}",
+    wins: "ganan {}",
+    draw: "tablas",
+    draw_by: "tablas por {}",
+    repetition: "triple repetición",
+    fifty_moves: "la regla de los 50 movimientos",
+    claimable: "se pueden reclamar tablas por {}",
+    checkmate: "jaque mate",
+    resigns: "{} abandonan",
+    stalemate: "ahogado",
+    agreed: "tablas de mutuo acuerdo",
+    out_of_time: "{} se quedaron sin tiempo",
+    cant_mate: "pero {} no pueden dar mate",
+    adjudicated: "por adjudicación",
+    adjudicated_draw: "tablas por adjudicación",
+    no_material: "ningún bando puede dar mate",
+    armageddon: "tablas, así que {} ganan el armagedón",
+    no_moves: "{} no tienen jugadas y ganan",
+    invalid_move: "jugada no válida",
+    compulsory: "la captura es obligatoria",
+    unknown: "orden o jugada desconocida. help lista las órdenes",
+};
+
+const IT: Words = Words {
+    white: "il Bianco",
+    black: "il Nero",
+    to_move: "muove {}",
+    check: "scacco",
+    plays: "{} gioca {}",
+    wins: "vince {}",
+    draw: "patta",
+    draw_by: "patta per {}",
+    repetition: "triplice ripetizione",
+    fifty_moves: "la regola delle 50 mosse",
+    claimable: "si può reclamare la patta per {}",
+    checkmate: "scacco matto",
+    resigns: "{} abbandona",
+    stalemate: "stallo",
+    agreed: "patta d'accordo",
+    out_of_time: "{} ha esaurito il tempo",
+    cant_mate: "ma {} non può dare matto",
+    adjudicated: "per aggiudicazione",
+    adjudicated_draw: "patta per aggiudicazione",
+    no_material: "nessuno dei due può dare matto",
+    armageddon: "patta, quindi {} vince l'armageddon",
+    no_moves: "{} non ha più mosse e vince",
+    invalid_move: "mossa non valida",
+    compulsory: "la presa è obbligatoria",
+    unknown: "comando o mossa sconosciuti. help elenca i comandi",
+};
+
+const NL: Words = Words {
+    white: "Wit",
+    black: "Zwart",
+    to_move: "{} aan zet",
+    check: "schaak",
+    plays: "{} speelt {}",
+    wins: "{} wint",
+    draw: "remise",
+    draw_by: "remise door {}",
+    repetition: "drievoudige herhaling",
+    fifty_moves: "de vijftigzettenregel",
+    claimable: "remise claimbaar door {}",
+    checkmate: "schaakmat",
+    resigns: "{} geeft op",
+    stalemate: "pat",
+    agreed: "remise overeengekomen",
+    out_of_time: "{} heeft de tijd overschreden",
+    cant_mate: "maar {} kan niet matzetten",
+    adjudicated: "door arbitrage",
+    adjudicated_draw: "remise door arbitrage",
+    no_material: "geen van beide kan matzetten",
+    armageddon: "remise, daarmee wint {} de armageddon",
+    no_moves: "{} heeft geen zetten meer en wint",
+    invalid_move: "ongeldige zet",
+    compulsory: "slaan is verplicht",
+    unknown: "onbekende opdracht of zet. help toont de opdrachten",
+};
+
+// template with every {} replaced by the next of args
+fn fill(template: &str, args: &[&str]) -> String {
+    let mut text = String::new();
+    let mut args = args.iter();
+    let mut parts = template.split("{}").peekable();
+    while let Some(part) = parts.next() {
+        text.push_str(part);
+        if parts.peek().is_some() {
+            text.push_str(args.next().unwrap_or(&""));
+        }
+    }
+    text
+}
+
+impl Locale {
+    fn words(self) -> &'static Words {
+        match self {
+            Locale::En => &EN,
+            Locale::De => &DE,
+            Locale::Fr => &FR,
+            Locale::Es => &ES,
+            Locale::It => &IT,
+            Locale::Nl => &NL,
+        }
+    }
+
+    // the language of LC_ALL, LC_MESSAGES or LANG, like de from de_DE.UTF-8
+    pub fn from_env() -> Option<Locale> {
+        let value = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())?;
+        let language = value.split(['_', '.', '@']).next()?;
+        clap::ValueEnum::from_str(language, true).ok()
+    }
+
+    pub fn side(self, color: Color) -> &'static str {
+        match color {
+            Color::White => self.words().white,
+            Color::Black => self.words().black,
+        }
+    }
+
+    fn claim(self, claim: DrawClaim) -> &'static str {
+        match claim {
+            DrawClaim::Repetition => self.words().repetition,
+            DrawClaim::FiftyMoves => self.words().fifty_moves,
+        }
+    }
+
+    // a side playing a move, given as it is shown
+    pub fn plays(self, color: Color, mve: &str) -> String {
+        fill(self.words().plays, &[self.side(color), mve])
+    }
+
+    pub fn invalid_move(self) -> String {
+        self.words().invalid_move.to_string()
+    }
+
+    // the move typed isn't a capture but one is possible
+    pub fn capture_compulsory(self) -> String {
+        format!("{}, {}", self.words().invalid_move, self.words().compulsory)
+    }
+
+    pub fn unknown_input(self) -> String {
+        self.words().unknown.to_string()
+    }
+
+    pub fn result(self, result: GameResult) -> String {
+        let words = self.words();
+        let wins = |color: Color| fill(words.wins, &[self.side(color)]);
+        match result {
+            GameResult::Checkmate(c) => format!("{}, {}", words.checkmate, wins(c)),
+            GameResult::Resignation(c) => {
+                format!(
+                    "{}, {}",
+                    fill(words.resigns, &[self.side(c.other())]),
+                    wins(c)
+                )
+            }
+            GameResult::Stalemate => format!("{}, {}", words.stalemate, words.draw),
+            GameResult::DrawAgreed => words.agreed.to_string(),
+            GameResult::DrawClaimed(claim) => fill(words.draw_by, &[self.claim(claim)]),
+            GameResult::Timeout(c) => format!(
+                "{}, {}",
+                fill(words.out_of_time, &[self.side(c.other())]),
+                wins(c)
+            ),
+            GameResult::Adjudicated(Adjudication::Win(c)) => {
+                format!("{}, {}", words.adjudicated, wins(c))
+            }
+            GameResult::Adjudicated(Adjudication::Draw) => words.adjudicated_draw.to_string(),
+            GameResult::Adjudicated(Adjudication::Material) => {
+                format!("{}, {}", words.adjudicated_draw, words.no_material)
+            }
+            GameResult::ArmageddonDraw => fill(words.armageddon, &[self.side(Color::Black)]),
+            GameResult::NoMoves(c) => fill(words.no_moves, &[self.side(c)]),
+            GameResult::TimeoutDraw(c) => format!(
+                "{}, {}, {}",
+                fill(words.out_of_time, &[self.side(c)]),
+                fill(words.cant_mate, &[self.side(c.other())]),
+                words.draw
+            ),
+        }
+    }
+
+    // the result of a finished game, or whose move it is and what can be done
+    pub fn status(self, game: &Game) -> String {
+        if let Some(result) = game.result {
+            return self.result(result);
+        }
+        let words = self.words();
+        let turn = game.board.turn;
+        let mut status = fill(words.to_move, &[self.side(turn)]);
+        if game.board.in_check(turn) {
+            status.push_str(&format!(", {}", words.check));
+        }
+        if let Some(claim) = game.draw_claim() {
+            status.push_str(&format!(
+                ", {}",
+                fill(words.claimable, &[self.claim(claim)])
+            ));
+        }
+        status
+    }
+}
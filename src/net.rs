@@ -0,0 +1,227 @@
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use rustls::ServerConfig;
+
+use crate::relay;
+use crate::rng::{self, Rng};
+use crate::transport::{Certificate, Connection, Sender, Trust};
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Color};
+
+// the other player of a game over tcp. every message is one line: a move in
+// uci notation, draw to offer or accept a draw, claim for a claimed draw, or
+// resign. the host starts with "chess <color of the joining side> <fen>",
+// with the variant after the fen unless it is standard chess. anyone
+// connecting after the opponent gets "chess watch <fen>", every line sent so
+// far and then every line either player sends
+pub struct Remote {
+    connection: Connection,
+    sender: Sender,
+    // on the host, what spectators are sent
+    feed: Option<Arc<Mutex<Feed>>>,
+}
+
+// the fen, followed by the variant unless it is standard chess
+fn position(board: &ChessBoard) -> String {
+    match board.variant.pgn_name() {
+        Some(variant) => format!("{} {}", board.to_fen(), variant),
+        None => board.to_fen(),
+    }
+}
+
+struct Feed {
+    start: String,
+    lines: Vec<String>,
+    spectators: Vec<Sender>,
+}
+
+impl Feed {
+    fn push(&mut self, line: &str) {
+        self.lines.push(line.to_string());
+        // spectators that went away are dropped
+        self.spectators
+            .retain(|spectator| spectator.send_line(line).is_ok());
+    }
+
+    fn add(&mut self, spectator: Sender) {
+        let mut sent = spectator.send_line(&format!("chess watch {}", self.start));
+        for line in &self.lines {
+            sent = sent.and_then(|_| spectator.send_line(line));
+        }
+        if sent.is_ok() {
+            self.spectators.push(spectator);
+        }
+    }
+}
+
+// the feed even if a spectator's thread panicked while adding to it, so the
+// game goes on without that spectator
+fn lock(feed: &Mutex<Feed>) -> MutexGuard<'_, Feed> {
+    feed.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// the longest line read, so a broken peer can't fill the memory
+const MAX_LINE: usize = 256;
+
+impl Remote {
+    fn new(connection: Connection) -> Self {
+        Remote {
+            sender: connection.sender(),
+            connection,
+            feed: None,
+        }
+    }
+
+    // waits for the other side to join, then tells it its color and the position.
+    // with tls everyone connecting has to speak it
+    pub fn host(
+        port: u16,
+        color: Color,
+        board: &ChessBoard,
+        tls: Option<Arc<ServerConfig>>,
+    ) -> Result<Self, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .map_err(|e| format!("could not listen on port {}: {}", port, e))?;
+        println!("waiting for the other player on port {}", port);
+        // someone who fails the tls handshake isn't the opponent
+        let connection = loop {
+            let (stream, addr) = listener
+                .accept()
+                .map_err(|e| format!("could not accept a connection: {}", e))?;
+            match Connection::accept(stream, tls.as_ref()) {
+                Ok(connection) => {
+                    println!("{} joined", addr);
+                    break connection;
+                }
+                Err(e) => println!("{}: {}", addr, e),
+            }
+        };
+        let mut remote = Remote::greet(connection, color, board);
+        let feed = Arc::new(Mutex::new(Feed {
+            start: position(board),
+            lines: Vec::new(),
+            spectators: Vec::new(),
+        }));
+        remote.feed = Some(Arc::clone(&feed));
+        // each spectator's handshake gets its own thread, so one that never
+        // finishes it doesn't keep the others out
+        std::thread::spawn(move || {
+            for stream in listener.incoming().map_while(Result::ok) {
+                let feed = Arc::clone(&feed);
+                let tls = tls.clone();
+                std::thread::spawn(move || {
+                    if let Ok(connection) = Connection::accept(stream, tls.as_ref()) {
+                        lock(&feed).add(connection.sender());
+                    }
+                });
+            }
+        });
+        Ok(remote)
+    }
+
+    // tells the player who joined their color and the position. this is all
+    // hosting does over a relay, where nobody else can connect to watch
+    pub fn greet(connection: Connection, color: Color, board: &ChessBoard) -> Self {
+        let mut remote = Remote::new(connection);
+        let name = color.other().name().to_lowercase();
+        remote.send(&format!("chess {} {}", name, position(board)));
+        remote
+    }
+
+    // learns from the host, directly or through a relay, the color to play, none
+    // when both players are there and this is a spectator, and the position
+    pub fn join(
+        connection: Connection,
+        host: &str,
+    ) -> Result<(Self, Option<Color>, ChessBoard), String> {
+        let mut remote = Remote::new(connection);
+        let greeting = remote
+            .receive()
+            .ok_or_else(|| format!("{} closed the connection", host))?;
+        let invalid = || format!("{} is not a chess host: {}", host, greeting);
+        let rest = greeting.strip_prefix("chess ").ok_or_else(invalid)?;
+        let (color, fen) = rest.split_once(' ').ok_or_else(invalid)?;
+        let color = match color {
+            "white" => Some(Color::White),
+            "black" => Some(Color::Black),
+            "watch" => None,
+            _ => return Err(invalid()),
+        };
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let variant = match fields.get(6) {
+            Some(name) => VariantKind::from_pgn(name).ok_or_else(invalid)?,
+            None => VariantKind::Standard,
+        };
+        let board = ChessBoard::from_fen(&fields[..fields.len().min(6)].join(" "))
+            .ok_or_else(invalid)?
+            .with_variant(variant);
+        Ok((remote, color, board))
+    }
+
+    // a lost connection shows up on the next receive
+    pub fn send(&mut self, line: &str) {
+        self.mirror(line);
+        let _ = self.sender.send_line(line);
+    }
+
+    // the next message, none once the connection is gone
+    pub fn receive(&mut self) -> Option<String> {
+        let line = self.connection.read_line(MAX_LINE)?.trim().to_string();
+        self.mirror(&line);
+        Some(line)
+    }
+
+    // passes a line between the players on to the spectators
+    fn mirror(&mut self, line: &str) {
+        if let Some(feed) = &self.feed {
+            lock(feed).push(line);
+        }
+    }
+}
+
+// the host subcommand: waits for the other player on port, or meets them at
+// a relay under the code, one nobody else is likely to pick unless given
+pub fn host(
+    port: u16,
+    color: Color,
+    board: &ChessBoard,
+    certificate: &Certificate,
+    relay: Option<&str>,
+    code: Option<&str>,
+    trust: &Trust,
+) -> Result<Remote, String> {
+    match relay {
+        Some(relay) => {
+            let code = code.map_or_else(
+                || {
+                    let mut rng = Rng::new(rng::seed_from_time());
+                    format!("{:06}", rng.below(1_000_000))
+                },
+                str::to_string,
+            );
+            let tls = trust.client_config()?;
+            let connection = relay::connect(relay, &code, tls.as_ref())?;
+            Ok(Remote::greet(connection, color, board))
+        }
+        None if trust.tls || trust.pin.is_some() => Err(String::from(
+            "--tls and --pin are for connecting to a --relay",
+        )),
+        None => Remote::host(port, color, board, certificate.server_config()?),
+    }
+}
+
+// the join subcommand: connects to the host at addr, or through a relay to
+// the host that got the code addr
+pub fn join(
+    addr: &str,
+    relay: Option<&str>,
+    trust: &Trust,
+) -> Result<(Remote, Option<Color>, ChessBoard), String> {
+    let tls = trust.client_config()?;
+    let connection = match relay {
+        Some(relay) => relay::connect(relay, addr, tls.as_ref())?,
+        None => Connection::connect(addr, tls.as_ref())?,
+    };
+    Remote::join(connection, addr)
+}
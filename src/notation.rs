@@ -0,0 +1,187 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::castling;
+use crate::{BoardPos, ChessBoard, Move, PieceType};
+
+// how moves are shown, whatever way they were typed
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Notation {
+    // standard algebraic notation like Nf3, with the piece letters of the locale
+    #[default]
+    San,
+    // the start and target square like g1f3
+    Uci,
+    // san with chess symbols for the pieces like ♘f3
+    Figurine,
+}
+
+fn piece_letter(piece: PieceType) -> &'static str {
+    match piece {
+        PieceType::Pawn => "",
+        PieceType::Bishop => "B",
+        PieceType::Knight => "N",
+        PieceType::Rook => "R",
+        PieceType::Queen => "Q",
+        PieceType::King => "K",
+        PieceType::Archbishop => "A",
+        PieceType::Chancellor => "C",
+    }
+}
+
+impl ChessBoard {
+    // standard algebraic notation for a legal move in this position, e.g. Nbd7,
+    // exd5+ or e8=Q
+    pub fn san(&self, mve: &Move) -> String {
+        let piece = match self.piece_at(mve.from) {
+            Some(p) => p,
+            None => return mve.to_string(),
+        };
+        if let Some(side) = self.castling_side(mve) {
+            let san = match side {
+                castling::SHORT => "O-O",
+                _ => "O-O-O",
+            };
+            return format!("{}{}", san, self.check_mark(mve));
+        }
+        let capture = self.is_capture(mve);
+        let mut san = String::from(piece_letter(piece.piece));
+
+        if piece.piece == PieceType::Pawn {
+            if capture {
+                san.push(mve.from.to_string().as_bytes()[0] as char);
+            }
+        } else {
+            // other pieces of the same kind that could go to the same square
+            let rivals: Vec<Move> = self
+                .legal_moves()
+                .into_iter()
+                .filter(|m| {
+                    m.to == mve.to
+                        && m.from != mve.from
+                        && self.piece_at(m.from).map(|p| p.piece) == Some(piece.piece)
+                })
+                .collect();
+            if !rivals.is_empty() {
+                let from = mve.from.to_string();
+                if rivals.iter().all(|m| m.from.col != mve.from.col) {
+                    san.push_str(&from[0..1]);
+                } else if rivals.iter().all(|m| m.from.row != mve.from.row) {
+                    san.push_str(&from[1..2]);
+                } else {
+                    san.push_str(&from);
+                }
+            }
+        }
+
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&mve.to.to_string());
+        if let Some(promotion) = mve.promotion {
+            san.push('=');
+            san.push_str(piece_letter(promotion));
+        }
+        san.push_str(self.check_mark(mve));
+        san
+    }
+
+    // + if the move gives check, # if it mates
+    fn check_mark(&self, mve: &Move) -> &'static str {
+        let mut next = self.clone();
+        next.make_move(mve);
+        if !next.in_check(next.turn) {
+            ""
+        } else if next.legal_moves().is_empty() {
+            "#"
+        } else {
+            "+"
+        }
+    }
+}
+
+// swaps the piece letters of english san for symbols, the white ones for both
+// sides like in print
+pub fn figurine(san: &str) -> String {
+    san.chars()
+        .map(|ch| match ch {
+            'K' => '♔',
+            'Q' => '♕',
+            'R' => '♖',
+            'B' => '♗',
+            'N' => '♘',
+            'A' => '\u{1fa50}',
+            'C' => '\u{1fa4f}',
+            _ => ch,
+        })
+        .collect()
+}
+
+// check marks and annotations like + # ! ? don't change which move is meant
+fn strip_suffix(san: &str) -> &str {
+    san.trim_end_matches(['+', '#', '!', '?'])
+}
+
+impl ChessBoard {
+    // finds the legal move written in standard algebraic notation
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        // castling is written with zeros as well
+        let wanted = strip_suffix(san.trim()).replace('0', "O");
+        self.legal_moves()
+            .into_iter()
+            .find(|m| strip_suffix(&self.san(m)) == wanted)
+    }
+
+    // a line of moves played one after the other from this position
+    pub fn san_line(&self, line: &[Move]) -> Vec<String> {
+        let mut board = self.clone();
+        line.iter()
+            .map(|mve| {
+                let san = board.san(mve);
+                board.make_move(mve);
+                san
+            })
+            .collect()
+    }
+
+    // legal moves that fit a partial or ambiguous move like Nf3 or e4: the
+    // target square, the piece letter if given, and any file or rank of the
+    // start square that was typed in between
+    pub fn san_candidates(&self, san: &str) -> Vec<Move> {
+        let text: String = strip_suffix(san.trim())
+            .chars()
+            .filter(|&c| c != 'x' && c != '-')
+            .collect();
+        if text.len() < 2 || !text.is_ascii() {
+            return Vec::new();
+        }
+        let (hints, square) = text.split_at(text.len() - 2);
+        let Some(to) = BoardPos::parse(square) else {
+            return Vec::new();
+        };
+        let (piece, hints) = match hints.chars().next() {
+            Some(ch) if ch.is_ascii_uppercase() => {
+                match PieceType::ALL
+                    .into_iter()
+                    .find(|&p| piece_letter(p).starts_with(ch))
+                {
+                    Some(piece) => (Some(piece), &hints[1..]),
+                    None => return Vec::new(),
+                }
+            }
+            // a start file without a letter, like the e in exd5, is a pawn
+            Some(_) => (Some(PieceType::Pawn), hints),
+            None => (None, hints),
+        };
+        self.legal_moves()
+            .into_iter()
+            .filter(|m| {
+                let from = m.from.to_string();
+                m.to == to
+                    && piece.is_none_or(|p| self.piece_at(m.from).map(|f| f.piece) == Some(p))
+                    && hints.chars().all(|c| from.contains(c))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,43 @@
+use clap::ValueEnum;
+
+use crate::castling;
+use crate::{BoardPos, ChessBoard, Color};
+
+// material given up by the stronger player, who plays white, except with
+// pawn and move where they give up the move as well and play black
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Odds {
+    // without the f2 pawn
+    Pawn,
+    // black without the f7 pawn
+    PawnAndMove,
+    // without the queen's knight
+    Knight,
+    // without the queen's rook
+    Rook,
+    Queen,
+}
+
+impl Odds {
+    fn square(self) -> &'static str {
+        match self {
+            Odds::Pawn => "f2",
+            Odds::PawnAndMove => "f7",
+            Odds::Knight => "b1",
+            Odds::Rook => "a1",
+            Odds::Queen => "d1",
+        }
+    }
+
+    // the initial position without the piece. without the rook white can't
+    // castle long any more
+    pub fn board(self) -> ChessBoard {
+        let mut board = ChessBoard::new();
+        let square = BoardPos::parse(self.square()).unwrap();
+        board.take(square);
+        if self == Odds::Rook {
+            board.castling[Color::White.idx()][castling::LONG] = None;
+        }
+        board
+    }
+}
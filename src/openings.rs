@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::game::Game;
+use crate::variant::VariantKind;
+use crate::ChessBoard;
+
+// eco code, name and the moves of the line
+const OPENINGS: &[(&str, &str, &str)] = &[
+    ("A00", "Polish Opening", "b4"),
+    ("A01", "Nimzo-Larsen Attack", "b3"),
+    ("A02", "Bird's Opening", "f4"),
+    ("A04", "Reti Opening", "Nf3"),
+    ("A10", "English Opening", "c4"),
+    ("A40", "Queen's Pawn Game", "d4"),
+    ("A45", "Indian Defense", "d4 Nf6"),
+    ("A80", "Dutch Defense", "d4 f5"),
+    ("B00", "King's Pawn Game", "e4"),
+    ("B01", "Scandinavian Defense", "e4 d5"),
+    ("B02", "Alekhine's Defense", "e4 Nf6"),
+    ("B06", "Modern Defense", "e4 g6"),
+    ("B07", "Pirc Defense", "e4 d6 d4 Nf6 Nc3"),
+    ("B10", "Caro-Kann Defense", "e4 c6"),
+    ("B12", "Caro-Kann Advance Variation", "e4 c6 d4 d5 e5"),
+    ("B20", "Sicilian Defense", "e4 c5"),
+    ("B22", "Sicilian Alapin", "e4 c5 c3"),
+    ("B23", "Sicilian Closed", "e4 c5 Nc3"),
+    (
+        "B33",
+        "Sicilian Sveshnikov",
+        "e4 c5 Nf3 Nc6 d4 cxd4 Nxd4 Nf6 Nc3 e5",
+    ),
+    (
+        "B35",
+        "Sicilian Accelerated Dragon",
+        "e4 c5 Nf3 Nc6 d4 cxd4 Nxd4 g6",
+    ),
+    ("B40", "Sicilian Kan", "e4 c5 Nf3 e6 d4 cxd4 Nxd4 a6"),
+    (
+        "B70",
+        "Sicilian Dragon",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 g6",
+    ),
+    (
+        "B80",
+        "Sicilian Scheveningen",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 e6",
+    ),
+    (
+        "B90",
+        "Sicilian Najdorf",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6",
+    ),
+    ("C00", "French Defense", "e4 e6"),
+    ("C02", "French Advance Variation", "e4 e6 d4 d5 e5"),
+    ("C11", "French Classical Variation", "e4 e6 d4 d5 Nc3 Nf6"),
+    ("C15", "French Winawer Variation", "e4 e6 d4 d5 Nc3 Bb4"),
+    ("C20", "King's Pawn Opening", "e4 e5"),
+    ("C23", "Bishop's Opening", "e4 e5 Bc4"),
+    ("C25", "Vienna Game", "e4 e5 Nc3"),
+    ("C30", "King's Gambit", "e4 e5 f4"),
+    ("C33", "King's Gambit Accepted", "e4 e5 f4 exf4"),
+    ("C40", "King's Knight Opening", "e4 e5 Nf3"),
+    ("C41", "Philidor Defense", "e4 e5 Nf3 d6"),
+    ("C42", "Petrov's Defense", "e4 e5 Nf3 Nf6"),
+    ("C44", "Scotch Game", "e4 e5 Nf3 Nc6 d4 exd4"),
+    ("C46", "Three Knights Game", "e4 e5 Nf3 Nc6 Nc3"),
+    ("C47", "Four Knights Game", "e4 e5 Nf3 Nc6 Nc3 Nf6"),
+    ("C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"),
+    ("C51", "Evans Gambit", "e4 e5 Nf3 Nc6 Bc4 Bc5 b4"),
+    ("C53", "Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5 c3"),
+    ("C55", "Two Knights Defense", "e4 e5 Nf3 Nc6 Bc4 Nf6"),
+    ("C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"),
+    (
+        "C68",
+        "Ruy Lopez Exchange Variation",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6",
+    ),
+    (
+        "C70",
+        "Ruy Lopez Morphy Defense",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4",
+    ),
+    ("D00", "Queen's Pawn Game", "d4 d5"),
+    ("D02", "London System", "d4 d5 Nf3 Nf6 Bf4"),
+    ("D06", "Queen's Gambit", "d4 d5 c4"),
+    ("D10", "Slav Defense", "d4 d5 c4 c6"),
+    ("D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"),
+    ("D30", "Queen's Gambit Declined", "d4 d5 c4 e6"),
+    ("D43", "Semi-Slav Defense", "d4 d5 c4 c6 Nf3 Nf6 Nc3 e6"),
+    ("D70", "Grunfeld Defense", "d4 Nf6 c4 g6 Nc3 d5"),
+    ("E00", "Catalan Opening", "d4 Nf6 c4 e6 g3"),
+    ("E12", "Queen's Indian Defense", "d4 Nf6 c4 e6 Nf3 b6"),
+    ("E20", "Nimzo-Indian Defense", "d4 Nf6 c4 e6 Nc3 Bb4"),
+    ("E60", "King's Indian Defense", "d4 Nf6 c4 g6"),
+];
+
+// the opening with that eco code or name, or the only one whose name contains
+// the text, ignoring case
+fn find(query: &str) -> Result<&'static (&'static str, &'static str, &'static str), String> {
+    let query = query.trim().to_lowercase();
+    if let Some(opening) = OPENINGS
+        .iter()
+        .find(|(eco, name, _)| eco.to_lowercase() == query || name.to_lowercase() == query)
+    {
+        return Ok(opening);
+    }
+    let matches: Vec<_> = OPENINGS
+        .iter()
+        .filter(|(_, name, _)| name.to_lowercase().contains(&query))
+        .collect();
+    match matches.as_slice() {
+        [opening] => Ok(opening),
+        [] => Err(format!("unknown opening: {}", query)),
+        _ => Err(format!(
+            "{} could be: {}",
+            query,
+            matches
+                .iter()
+                .map(|(eco, name, _)| format!("{} {}", eco, name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+// the game after the moves of the opening
+pub fn play(query: &str) -> Result<Game, String> {
+    let (eco, name, moves) = find(query)?;
+    let mut game = Game::new();
+    for san in moves.split_whitespace() {
+        match game.board.parse_san(san) {
+            Some(mve) => {
+                game.play(&mve);
+            }
+            None => return Err(format!("illegal move {} in {} {}", san, eco, name)),
+        }
+    }
+    Ok(game)
+}
+
+// every position on the way through the lines by its zobrist key, with the
+// opening whose line ends there if one does
+fn book() -> &'static HashMap<u64, Option<usize>> {
+    static BOOK: OnceLock<HashMap<u64, Option<usize>>> = OnceLock::new();
+    BOOK.get_or_init(|| {
+        let mut book = HashMap::new();
+        for (i, (_, _, moves)) in OPENINGS.iter().enumerate() {
+            let mut board = ChessBoard::new();
+            for san in moves.split_whitespace() {
+                let Some(mve) = board.parse_san(san) else {
+                    break;
+                };
+                board.make_move(&mve);
+                book.entry(board.zobrist_key()).or_insert(None);
+            }
+            // the first line to reach a position names it
+            book.entry(board.zobrist_key())
+                .or_insert(None)
+                .get_or_insert(i);
+        }
+        book
+    })
+}
+
+// the eco code and name of the last line the game reached, by position so
+// transpositions count. it stays once the game leaves the book, even if it
+// comes back to a known position later
+pub fn name(game: &Game) -> Option<String> {
+    let start = game.start();
+    if start.variant != VariantKind::Standard
+        || start.zobrist_key() != ChessBoard::new().zobrist_key()
+    {
+        return None;
+    }
+    let book = book();
+    let mut named = None;
+    let boards = game.history.iter().skip(1).map(|played| &played.before);
+    for board in boards.chain(std::iter::once(&game.board).filter(|_| !game.history.is_empty())) {
+        match book.get(&board.zobrist_key()) {
+            Some(Some(i)) => named = Some(*i),
+            Some(None) => {}
+            None => break,
+        }
+    }
+    named.map(|i| format!("{} {}", OPENINGS[i].0, OPENINGS[i].1))
+}
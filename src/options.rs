@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use crate::uci::UciEngine;
+
+// what values an option takes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    // a whole number between min and max
+    Spin(i64, i64),
+    // true or false
+    Check,
+    // a file the engine reads
+    Path,
+}
+
+// an engine option known by a short name, and the names engines give it over
+// uci, the first one the engine offers is used. they only reach an engine
+// started with --uci, the built-in engine has none of them
+struct Spec {
+    name: &'static str,
+    uci: &'static [&'static str],
+    kind: Kind,
+    help: &'static str,
+}
+
+const OPTIONS: &[Spec] = &[
+    Spec {
+        name: "hash",
+        uci: &["Hash"],
+        kind: Kind::Spin(1, 1 << 20),
+        help: "memory for the hash table in megabytes",
+    },
+    Spec {
+        name: "threads",
+        uci: &["Threads"],
+        kind: Kind::Spin(1, 1024),
+        help: "how many threads the engine searches with",
+    },
+    Spec {
+        name: "skill",
+        uci: &["Skill Level", "Skill"],
+        kind: Kind::Spin(0, 20),
+        help: "how well the engine plays, from 0 up to its full strength at 20",
+    },
+    Spec {
+        name: "contempt",
+        uci: &["Contempt"],
+        kind: Kind::Spin(-100, 100),
+        help: "centipawns the engine gives up to avoid a draw",
+    },
+    Spec {
+        name: "nnue",
+        uci: &["EvalFile"],
+        kind: Kind::Path,
+        help: "the network file the engine evaluates with",
+    },
+    Spec {
+        name: "book",
+        uci: &["OwnBook"],
+        kind: Kind::Check,
+        help: "whether the engine plays from its own opening book",
+    },
+];
+
+fn spec(name: &str) -> Result<&'static Spec, String> {
+    OPTIONS
+        .iter()
+        .find(|spec| spec.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            let names: Vec<&str> = OPTIONS.iter().map(|spec| spec.name).collect();
+            format!(
+                "unknown engine option {}, there are {}",
+                name,
+                names.join(", ")
+            )
+        })
+}
+
+// the value if the option takes it, written the way uci wants it
+fn check(spec: &Spec, value: &str) -> Result<String, String> {
+    let value = value.trim();
+    match spec.kind {
+        Kind::Spin(min, max) => match value.parse::<i64>() {
+            Ok(n) if (min..=max).contains(&n) => Ok(n.to_string()),
+            _ => Err(format!(
+                "{} takes a number from {} to {}, not {}",
+                spec.name, min, max, value
+            )),
+        },
+        Kind::Check => match value.to_lowercase().as_str() {
+            "true" | "on" | "yes" => Ok(String::from("true")),
+            "false" | "off" | "no" => Ok(String::from("false")),
+            _ => Err(format!("{} is on or off, not {}", spec.name, value)),
+        },
+        Kind::Path if value.is_empty() => Err(format!("{} needs a file", spec.name)),
+        Kind::Path => Ok(value.to_string()),
+    }
+}
+
+// the engine options set so far, from the config file, the command line and
+// the option command in that order, each checked when it is set
+#[derive(Debug, Default)]
+pub struct EngineOptions {
+    values: BTreeMap<&'static str, String>,
+}
+
+impl EngineOptions {
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let spec = spec(name)?;
+        let value = check(spec, value)?;
+        self.values.insert(spec.name, value);
+        Ok(())
+    }
+
+    // an option written like hash=64, as given on the command line
+    pub fn parse(&mut self, option: &str) -> Result<(), String> {
+        let (name, value) = option
+            .split_once('=')
+            .ok_or_else(|| format!("invalid engine option: {}. example: hash=64", option))?;
+        self.set(name.trim(), value)
+    }
+
+    // from the [engine] table of the config file, where numbers and on or
+    // off don't need quotes
+    pub fn from_config(table: &BTreeMap<String, toml::Value>) -> Result<Self, String> {
+        let mut options = EngineOptions::default();
+        for (name, value) in table {
+            let value = match value {
+                toml::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            options
+                .set(name, &value)
+                .map_err(|e| format!("in the config: {}", e))?;
+        }
+        Ok(options)
+    }
+
+    // sends every option set to the engine under the name it knows it by.
+    // the config file may be meant for other engines too, so options this
+    // one doesn't have are only logged
+    pub fn apply(&self, engine: &mut UciEngine) -> Result<(), String> {
+        for (name, value) in &self.values {
+            match uci_name(engine, spec(name)?) {
+                Some(uci) => engine.set_option(uci, value)?,
+                None => tracing::warn!("{} has no {} option, leaving it out", engine.name, name),
+            }
+        }
+        Ok(())
+    }
+
+    // sets one option and hands it to the running engine, which takes it
+    // before its next search
+    pub fn change(
+        &mut self,
+        engine: &mut UciEngine,
+        name: &str,
+        value: &str,
+    ) -> Result<(), String> {
+        let spec = spec(name)?;
+        let value = check(spec, value)?;
+        let uci = uci_name(engine, spec)
+            .ok_or_else(|| format!("{} has no {} option", engine.name, spec.name))?;
+        engine.set_option(uci, &value)?;
+        self.values.insert(spec.name, value);
+        Ok(())
+    }
+
+    // every option with its value, or what it takes when it isn't set, and
+    // whether the engine has it
+    pub fn describe(&self, engine: &UciEngine) -> Vec<String> {
+        OPTIONS
+            .iter()
+            .map(|spec| {
+                let value = match (self.values.get(spec.name), spec.kind) {
+                    (Some(value), _) => value.clone(),
+                    (None, Kind::Spin(min, max)) => format!("unset, {} to {}", min, max),
+                    (None, Kind::Check) => String::from("unset, on or off"),
+                    (None, Kind::Path) => String::from("unset, a file"),
+                };
+                let offered = match uci_name(engine, spec) {
+                    Some(_) => String::new(),
+                    None => format!(", {} doesn't have it", engine.name),
+                };
+                format!("  {:<10}{} ({}){}", spec.name, spec.help, value, offered)
+            })
+            .collect()
+    }
+}
+
+fn uci_name(engine: &UciEngine, spec: &Spec) -> Option<&'static str> {
+    spec.uci.iter().copied().find(|name| engine.offers(name))
+}
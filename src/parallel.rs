@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+use std::sync::mpsc;
+
+use rayon::prelude::*;
+
+// does the work of every job on a pool of threads, one per core unless told
+// how many, and hands the results to done in the order of the jobs, each as
+// soon as the ones before it are in. the first error done gives stops it,
+// the jobs already running are left to finish unseen. the work is logged
+// under the span ordered was called in, whichever thread does it
+pub fn ordered<J, R>(
+    threads: Option<usize>,
+    jobs: Vec<J>,
+    work: impl Fn(J) -> R + Send + Sync + 'static,
+    mut done: impl FnMut(R) -> Result<(), String>,
+) -> Result<(), String>
+where
+    J: Send + 'static,
+    R: Send + 'static,
+{
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads.unwrap_or(0))
+        .build()
+        .map_err(|e| format!("could not set up the thread pool: {}", e))?;
+    let span = tracing::Span::current();
+    let (sender, receiver) = mpsc::channel();
+    // the results are waited for here, outside the pool, so even a pool of
+    // one thread gets on with the work
+    pool.spawn(move || {
+        jobs.into_par_iter()
+            .enumerate()
+            .for_each_with(sender, |sender, (i, job)| {
+                let _ = sender.send((i, span.in_scope(|| work(job))));
+            });
+    });
+    let mut waiting = BTreeMap::new();
+    let mut next = 0;
+    for (i, result) in receiver {
+        waiting.insert(i, result);
+        while let Some(result) = waiting.remove(&next) {
+            done(result)?;
+            next += 1;
+        }
+    }
+    Ok(())
+}
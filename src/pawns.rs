@@ -0,0 +1,64 @@
+use crate::{BoardPos, ChessBoard, Color, Move, PieceType, RANKS};
+
+// the row a side's pawns promote on, the other side's back row
+pub fn promotion_row(color: Color) -> u8 {
+    match color {
+        Color::White => 0,
+        Color::Black => RANKS - 1,
+    }
+}
+
+impl ChessBoard {
+    // the square skipped by the pawn that just made a double step to landed,
+    // if an enemy pawn beside it could take it en passant. it is only kept
+    // then, so positions that differ in nothing else have the same key and fen
+    pub fn en_passant_square(&self, landed: BoardPos) -> Option<BoardPos> {
+        let pawn = self
+            .piece_at(landed)
+            .filter(|p| p.piece == PieceType::Pawn)?;
+        let row = match pawn.color {
+            Color::White => landed.row + 1,
+            Color::Black => landed.row.checked_sub(1)?,
+        };
+        let taker = [landed.col.checked_sub(1), landed.col.checked_add(1)]
+            .into_iter()
+            .flatten()
+            .filter(|&col| col < self.width)
+            .any(|col| {
+                self.piece_at(BoardPos {
+                    row: landed.row,
+                    col,
+                })
+                .is_some_and(|p| p.piece == PieceType::Pawn && p.color != pawn.color)
+            });
+        taker.then_some(BoardPos {
+            row,
+            col: landed.col,
+        })
+    }
+
+    // the square of the pawn the move takes en passant, if it does: a pawn
+    // stepping diagonally onto the square an enemy pawn just skipped
+    pub fn en_passant_capture(&self, mve: &Move) -> Option<BoardPos> {
+        if self.en_passant != Some(mve.to) || mve.from.col == mve.to.col {
+            return None;
+        }
+        let mover = self
+            .piece_at(mve.from)
+            .filter(|p| p.piece == PieceType::Pawn)?;
+        let taken = BoardPos {
+            row: mve.from.row,
+            col: mve.to.col,
+        };
+        self.piece_at(taken)
+            .is_some_and(|p| p.piece == PieceType::Pawn && p.color != mover.color)
+            .then_some(taken)
+    }
+
+    // whether the move takes a pawn to the far row, where it has to become
+    // one of the pieces the variant allows
+    pub fn promotes(&self, mve: &Move) -> bool {
+        self.piece_at(mve.from)
+            .is_some_and(|p| p.piece == PieceType::Pawn && mve.to.row == promotion_row(p.color))
+    }
+}
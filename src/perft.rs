@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use rayon::prelude::*;
+
+use crate::rng::Rng;
+use crate::uci::UciEngine;
+use crate::variant::VariantKind;
+use crate::ChessBoard;
+
+// counts the leaf nodes of the legal move tree, used to verify move generation
+pub fn perft(board: &ChessBoard, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = board.legal_moves();
+    // bulk counting: one ply from the leaves every legal move is exactly one node,
+    // so there is no need to play them out
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves
+        .iter()
+        .map(|mve| {
+            let mut next = board.clone();
+            next.make_move(mve);
+            perft(&next, depth - 1)
+        })
+        .sum()
+}
+
+// same as perft, but subtree counts are remembered by (zobrist key, depth) so
+// transpositions are only counted once
+pub fn perft_hashed(board: &ChessBoard, depth: u8, table: &mut HashMap<(u64, u8), u64>) -> u64 {
+    if depth <= 1 {
+        return perft(board, depth);
+    }
+    let key = (board.zobrist_key(), depth);
+    if let Some(&nodes) = table.get(&key) {
+        return nodes;
+    }
+    let nodes = board
+        .legal_moves()
+        .iter()
+        .map(|mve| {
+            let mut next = board.clone();
+            next.make_move(mve);
+            perft_hashed(&next, depth - 1, table)
+        })
+        .sum();
+    table.insert(key, nodes);
+    nodes
+}
+
+// splits the root moves over the rayon thread pool. with hashing every root
+// move gets its own table, so the threads never have to share one
+pub fn perft_parallel(board: &ChessBoard, depth: u8, hashed: bool) -> u64 {
+    if depth <= 1 {
+        return perft(board, depth);
+    }
+    board
+        .legal_moves()
+        .par_iter()
+        .map(|mve| {
+            let mut next = board.clone();
+            next.make_move(mve);
+            if hashed {
+                perft_hashed(&next, depth - 1, &mut HashMap::new())
+            } else {
+                perft(&next, depth - 1)
+            }
+        })
+        .sum()
+}
+
+// positions known for catching move generation bugs, each with its published
+// count at a depth that keeps the whole suite to seconds
+const SUITE: [(&str, &str, u8, u64); 8] = [
+    (
+        "initial position",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        4,
+        197_281,
+    ),
+    // castling both ways, pins, checks and en passant
+    (
+        "kiwipete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        3,
+        97_862,
+    ),
+    // rook and pawn ending with discovered checks, en passant among them
+    (
+        "position 3",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        4,
+        43_238,
+    ),
+    // pawns about to promote on both sides and castling out of check
+    (
+        "position 4",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        3,
+        9_467,
+    ),
+    // a promotion by capture and a knight checking from f2
+    (
+        "position 5",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        3,
+        62_379,
+    ),
+    (
+        "position 6",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        3,
+        89_890,
+    ),
+    // taking en passant uncovers a check from the bishop
+    (
+        "en passant check",
+        "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+        6,
+        1_440_467,
+    ),
+    // taking en passant would expose the king to the bishop
+    (
+        "en passant pin",
+        "8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1",
+        6,
+        824_064,
+    ),
+];
+
+// counts every position of the suite and compares with the known counts, a
+// line each. an error when any differs, so scripts can stop on it
+pub fn suite() -> Result<(), String> {
+    let start = Instant::now();
+    let mut failed = 0;
+    for (name, fen, depth, expected) in SUITE {
+        let board = ChessBoard::from_fen(fen).expect("the suite's fens are valid");
+        let nodes = perft_parallel(&board, depth, false);
+        if nodes == expected {
+            println!("{:<18} perft({}) = {} ok", name, depth, nodes);
+        } else {
+            failed += 1;
+            println!(
+                "{:<18} perft({}) = {}, expected {}",
+                name, depth, nodes, expected
+            );
+        }
+    }
+    println!("{:.3}s", start.elapsed().as_secs_f64());
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{} of {} counts differ", n, SUITE.len())),
+    }
+}
+
+// starts the reference engine, given with --uci, and compares against it
+pub fn difftest(
+    board: &ChessBoard,
+    command: Option<&str>,
+    options: &[String],
+    games: u32,
+    plies: u32,
+    rng: &mut Rng,
+) -> Result<(), String> {
+    let command = command.ok_or("difftest needs a reference engine, given with --uci")?;
+    let mut reference = UciEngine::start(command, options)?;
+    compare(board, &mut reference, games, plies, rng)
+}
+
+// plays random games from board and compares the legal moves of every
+// position with those of a uci engine that answers go perft 1, stopping at
+// the first position where they differ
+pub fn compare(
+    board: &ChessBoard,
+    reference: &mut UciEngine,
+    games: u32,
+    plies: u32,
+    rng: &mut Rng,
+) -> Result<(), String> {
+    if !matches!(board.variant, VariantKind::Standard | VariantKind::Chess960) {
+        return Err(String::from(
+            "only standard chess and chess960 can be compared",
+        ));
+    }
+    let mut positions = 0;
+    for game in 1..=games {
+        let mut board = board.clone();
+        let mut played = Vec::new();
+        for _ in 0..plies {
+            positions += 1;
+            let moves = board.legal_moves();
+            let mut ours: Vec<String> = moves
+                .iter()
+                .map(|m| reference.move_text(&board, m))
+                .collect();
+            let mut theirs = reference.perft_moves(&board)?;
+            ours.sort();
+            theirs.sort();
+            if ours != theirs {
+                let only = |a: &[String], b: &[String]| -> String {
+                    let only: Vec<&str> = a
+                        .iter()
+                        .filter(|m| !b.contains(m))
+                        .map(String::as_str)
+                        .collect();
+                    match only.is_empty() {
+                        true => String::from("none"),
+                        false => only.join(" "),
+                    }
+                };
+                return Err(format!(
+                    "the moves differ in game {} after {}\nfen {}\nonly here: {}\nonly in {}: {}",
+                    game,
+                    match played.is_empty() {
+                        true => String::from("no moves"),
+                        false => played.join(" "),
+                    },
+                    board.to_fen(),
+                    only(&ours, &theirs),
+                    reference.name,
+                    only(&theirs, &ours)
+                ));
+            }
+            if moves.is_empty() {
+                break;
+            }
+            let mve = moves[rng.below(moves.len())];
+            played.push(reference.move_text(&board, &mve));
+            board.make_move(&mve);
+        }
+    }
+    println!(
+        "{} {}, {} positions, the moves agree with {}",
+        games,
+        if games == 1 { "game" } else { "games" },
+        positions,
+        reference.name
+    );
+    Ok(())
+}
+
+pub fn run(
+    board: &ChessBoard,
+    depth: u8,
+    hashed: bool,
+    parallel: bool,
+    threads: Option<usize>,
+) -> Result<(), String> {
+    if let Some(n) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build_global()
+            .map_err(|e| format!("could not set up the thread pool: {}", e))?;
+    }
+
+    let start = Instant::now();
+    let nodes = if parallel || threads.is_some() {
+        perft_parallel(board, depth, hashed)
+    } else if hashed {
+        perft_hashed(board, depth, &mut HashMap::new())
+    } else {
+        perft(board, depth)
+    };
+    let elapsed = start.elapsed();
+
+    println!("perft({}) = {}", depth, nodes);
+    println!(
+        "{:.3}s, {:.0} nodes/s",
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn suite() {
+        assert_eq!(super::suite(), Ok(()));
+    }
+}
@@ -0,0 +1,290 @@
+use crate::analysis::Quality;
+use crate::clock;
+use crate::engine;
+use crate::game::Game;
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Color, Move};
+
+// a piece of movetext that matters for the moves
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    // a move in san
+    Move(String),
+    // the start of a variation, another move instead of the last one
+    Open,
+    Close,
+}
+
+// the san moves of the main line of a pgn game, leaving out tag pairs,
+// comments, variations, move numbers, annotation glyphs and the result
+pub fn movetext(pgn: &str) -> Vec<String> {
+    let mut moves = Vec::new();
+    let mut variation = 0;
+    for token in tokens(pgn) {
+        match token {
+            Token::Move(mve) if variation == 0 => moves.push(mve),
+            Token::Move(_) => {}
+            Token::Open => variation += 1,
+            Token::Close => variation -= 1,
+        }
+    }
+    moves
+}
+
+// the moves of a pgn game with its variations, leaving out tag pairs,
+// comments, move numbers, annotation glyphs and the result
+pub fn tokens(pgn: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut comment = false;
+    for line in pgn.lines() {
+        if !comment && (line.starts_with('[') || line.starts_with('%')) {
+            continue;
+        }
+        let mut token = String::new();
+        for ch in line.chars().chain(std::iter::once(' ')) {
+            if comment {
+                comment = ch != '}';
+                continue;
+            }
+            if !(ch.is_whitespace() || "{}();".contains(ch)) {
+                token.push(ch);
+                continue;
+            }
+            if let Some(mve) = san_token(&token) {
+                tokens.push(Token::Move(mve));
+            }
+            token.clear();
+            match ch {
+                '{' => comment = true,
+                '(' => tokens.push(Token::Open),
+                ')' => tokens.push(Token::Close),
+                // the rest of the line is a comment
+                ';' => break,
+                _ => {}
+            }
+        }
+    }
+    tokens
+}
+
+// the tag pairs at the top of a pgn game, like ("White", "human")
+pub fn tags(pgn: &str) -> Vec<(String, String)> {
+    pgn.lines()
+        .filter_map(|line| {
+            let inner = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+            let (name, value) = inner.split_once(' ')?;
+            let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+// replays a pgn game from its fen tag or the initial position, by the rules
+// of its variant tag or else the given variant
+pub fn read(pgn: &str, variant: VariantKind) -> Result<Game, String> {
+    let mut game = Game::from_board(start(pgn, variant)?);
+    for san in movetext(pgn) {
+        match game.board.parse_san(&san) {
+            Some(mve) => {
+                game.play(&mve);
+            }
+            None => return Err(format!("illegal move in pgn: {}", san)),
+        }
+    }
+    Ok(game)
+}
+
+// the position a pgn game starts from, by its fen and variant tags
+pub fn start(pgn: &str, variant: VariantKind) -> Result<ChessBoard, String> {
+    let tags = tags(pgn);
+    let tag = |wanted: &str| {
+        tags.iter()
+            .find(|(name, _)| name == wanted)
+            .map(|(_, value)| value.clone())
+    };
+    let variant = match tag("Variant") {
+        Some(name) => VariantKind::from_pgn(&name)
+            .ok_or_else(|| format!("unknown variant in pgn: {}", name))?,
+        None => variant,
+    };
+    match tag("FEN") {
+        Some(fen) => ChessBoard::parse_fen(&fen)
+            .map(|board| board.with_variant(variant))
+            .and_then(|board| board.validate().map(|_| board))
+            .map_err(|e| format!("invalid fen in pgn: {}", e)),
+        None => Ok(variant.rules().initial().with_variant(variant)),
+    }
+}
+
+// the games of a pgn file that holds several, each from its tag pairs to the
+// end of its movetext
+pub fn games(text: &str) -> Vec<String> {
+    let mut games = Vec::new();
+    let mut game = String::new();
+    let mut moves = false;
+    for line in text.lines() {
+        if line.starts_with('[') && moves {
+            games.push(std::mem::take(&mut game));
+            moves = false;
+        }
+        moves |= !line.starts_with('[') && !line.trim().is_empty();
+        game.push_str(line);
+        game.push('\n');
+    }
+    if moves {
+        games.push(game);
+    }
+    games
+}
+
+// the number in front of a move: white's moves always have one, black's or
+// the later ones of a turn only when asked for, with dots
+fn move_number(before: &ChessBoard, start: &ChessBoard, always: bool) -> String {
+    let number = before.fullmove - start.fullmove + 1;
+    if before.turn == Color::White && before.turn_moves == 0 {
+        format!("{}. ", number)
+    } else if always {
+        format!("{}... ", number)
+    } else {
+        String::new()
+    }
+}
+
+// a line of moves from before as a variation in parentheses
+fn variation(before: &ChessBoard, start: &ChessBoard, line: &[Move]) -> Vec<String> {
+    let mut board = before.clone();
+    let mut tokens: Vec<String> = line
+        .iter()
+        .enumerate()
+        .map(|(i, mve)| {
+            let mut token = move_number(&board, start, i == 0);
+            token.push_str(&board.san(mve));
+            board.make_move(mve);
+            token
+        })
+        .collect();
+    tokens[0].insert(0, '(');
+    tokens.last_mut().expect("the line has moves").push(')');
+    tokens
+}
+
+// a score for an eval comment: pawns like 0.35 or -1.20, or mates like #3
+// and #-2
+fn eval_text(score: i32) -> String {
+    let text = engine::format_score(score);
+    match text.starts_with('#') {
+        true => text,
+        false => format!("{:.2}", score as f64 / 100.0),
+    }
+}
+
+// the result at the end of the movetext, unless the game is still going on
+pub fn result(pgn: &str) -> Option<&'static str> {
+    let last = pgn
+        .lines()
+        .filter(|line| !line.starts_with('['))
+        .flat_map(str::split_whitespace)
+        .last()?;
+    ["1-0", "0-1", "1/2-1/2"].into_iter().find(|&r| r == last)
+}
+
+// strips a leading move number like 12. or 12... and drops tokens that aren't moves
+fn san_token(token: &str) -> Option<String> {
+    if matches!(token, "*" | "1-0" | "0-1" | "1/2-1/2") || token.starts_with('$') {
+        return None;
+    }
+    let mve = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if mve.is_empty() {
+        None
+    } else {
+        Some(mve.to_string())
+    }
+}
+
+// the game as pgn with the given tag pairs first, in the order given. the
+// variant is added unless it is standard chess, and the position it started
+// from as a fen tag unless it is the usual initial one. chess960 always has
+// the fen, there is no usual initial position
+pub fn write(game: &Game, tags: &[(&str, String)]) -> String {
+    let mut text = String::new();
+    for (name, value) in tags {
+        text.push_str(&format!("[{} \"{}\"]\n", name, value.replace('"', "'")));
+    }
+    let start = game.start();
+    if let Some(name) = start.variant.pgn_name() {
+        text.push_str(&format!("[Variant \"{}\"]\n", name));
+    }
+    let fen = start.to_fen();
+    let initial = start.variant.rules().initial();
+    if fen != initial.to_fen() || start.variant == VariantKind::Chess960 {
+        text.push_str(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", fen));
+    }
+    let result = game.result.map_or("*", |r| r.score());
+    text.push_str(&format!("[Result \"{}\"]\n\n", result));
+    let mut tokens = Vec::new();
+    // black's first move gets a number, and so does any after a comment or
+    // variation
+    let mut renumber = true;
+    let clocks = game.clock_times();
+    for (ply, played) in game.history.iter().enumerate() {
+        let mut token = move_number(&played.before, start, renumber);
+        token.push_str(&played.san);
+        tokens.push(token);
+        renumber = false;
+        // analyzed moves get a glyph for how good they were, the score after
+        // them and the engine's line when it had a better move, as lichess
+        // reads them. the score shown while playing stands in for an
+        // analysis, and the time left follows it when there were clocks
+        let review = played.review.as_ref();
+        if let Some(review) = review {
+            let glyph = match review.quality {
+                Quality::Inaccuracy => Some("$6"),
+                Quality::Mistake => Some("$2"),
+                Quality::Blunder => Some("$4"),
+                Quality::Best | Quality::Good => None,
+            };
+            tokens.extend(glyph.map(String::from));
+        }
+        let mut commands = Vec::new();
+        if let Some(score) = review.map_or(played.eval, |review| review.score) {
+            commands.push(format!("[%eval {}]", eval_text(score)));
+        }
+        if let Some(left) = clocks[ply] {
+            commands.push(format!("[%clk {}]", clock::format_clk(left)));
+        }
+        if !commands.is_empty() {
+            tokens.push(format!("{{ {} }}", commands.join(" ")));
+            renumber = true;
+        }
+        if let Some(review) = review.filter(|review| !review.line.is_empty()) {
+            tokens.extend(variation(&played.before, start, &review.line));
+            renumber = true;
+        }
+        // the side lines explored instead of the move. ones from the final
+        // position have no move to follow
+        for explored in game.variations.iter().filter(|v| v.ply == ply) {
+            tokens.extend(variation(&played.before, start, &explored.moves));
+            renumber = true;
+        }
+    }
+    // movetext lines are kept under 80 characters
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + token.len() + 1 > 79 {
+            text.push_str(&line);
+            text.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    if !line.is_empty() {
+        line.push(' ');
+    }
+    line.push_str(result);
+    text.push_str(&line);
+    text.push_str("\n\n");
+    text
+}
@@ -0,0 +1,409 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::analysis;
+use crate::config;
+use crate::display::{clear_screen, DisplayOptions, Highlights};
+use crate::engine;
+use crate::game::{Game, GameResult};
+use crate::json::Value;
+use crate::library;
+use crate::lichess;
+use crate::pgn;
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Color, Move};
+
+// a tactic wins at least this many centipawns, and that much more than any
+// other move
+const WINNING: i32 = 300;
+
+// a position and the line that solves it, the solver's moves and the
+// opponent's replies in turn
+pub struct Puzzle {
+    pub id: Option<String>,
+    pub board: ChessBoard,
+    pub solution: Vec<Move>,
+}
+
+// the opcode and operands of every operation of a line of epd
+pub type Operations<'a> = Vec<(&'a str, &'a str)>;
+
+// reads a line of epd: the first four fields of a fen, or all six, then
+// operations ending in semicolons, each an opcode and its operands, like
+// 6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - bm Ra8#; id "back rank";
+pub fn epd(line: &str, variant: VariantKind) -> Result<(ChessBoard, Operations<'_>), String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 4 {
+        return Err(format!("not a position: {}", line));
+    }
+    // the halfmove clock and move number of a full fen are numbers, which
+    // no operation is
+    let numbers = fields[4..]
+        .iter()
+        .take(2)
+        .take_while(|f| f.parse::<u32>().is_ok())
+        .count();
+    let fen = fields[..4 + numbers].join(" ");
+    let board = ChessBoard::parse_fen(&fen)
+        .map(|board| board.with_variant(variant))
+        .and_then(|board| board.validate().map(|_| board))
+        .map_err(|e| format!("invalid position {}: {}", fen, e))?;
+
+    // the operations start after the fields, which may be spaced any way
+    let mut rest = line.trim_start();
+    for _ in 0..4 + numbers {
+        rest = rest.trim_start();
+        rest = &rest[rest.find(char::is_whitespace).unwrap_or(rest.len())..];
+    }
+    let operations = rest
+        .split(';')
+        .map(str::trim)
+        .filter(|operation| !operation.is_empty())
+        .map(|operation| {
+            let (opcode, operands) = operation.split_once(' ').unwrap_or((operation, ""));
+            (opcode, operands.trim())
+        })
+        .collect();
+    Ok((board, operations))
+}
+
+impl Puzzle {
+    // reads a line of epd. the solution is the pv operation, or the bm one
+    // when it is a single move
+    pub fn parse(line: &str, variant: VariantKind) -> Result<Puzzle, String> {
+        let (board, operations) = epd(line, variant)?;
+        let mut id = None;
+        let mut moves = None;
+        for (opcode, operands) in operations {
+            match opcode {
+                "id" => id = Some(operands.trim_matches('"').to_string()),
+                "pv" => moves = Some(operands),
+                // a best move only counts when there's no line, and only when
+                // there is a single one
+                "bm" if moves.is_none() && !operands.contains(' ') => moves = Some(operands),
+                _ => {}
+            }
+        }
+        let moves = moves.ok_or_else(|| format!("puzzle without a solution: {}", line))?;
+
+        let mut after = board.clone();
+        let mut solution = Vec::new();
+        for text in moves.split_whitespace() {
+            let mve = after
+                .parse_san(text)
+                .or_else(|| after.parse_uci(text).filter(|m| after.is_legal(m)))
+                .ok_or_else(|| format!("illegal move {} in the solution: {}", text, line))?;
+            after.make_move(&mve);
+            solution.push(mve);
+        }
+        Ok(Puzzle {
+            id,
+            board,
+            solution,
+        })
+    }
+
+    // the puzzle as a line of epd that parse reads back
+    pub fn to_epd(&self) -> String {
+        let fen = self.board.to_fen();
+        let fields: Vec<&str> = fen.split_whitespace().take(4).collect();
+        let mut line = format!(
+            "{} pv {};",
+            fields.join(" "),
+            self.board.san_line(&self.solution).join(" ")
+        );
+        if let Some(id) = &self.id {
+            line.push_str(&format!(" id \"{}\";", id));
+        }
+        line
+    }
+}
+
+// the positions of a game where the side to move had a single clearly winning
+// move, whether it was played or missed. name goes into the id of each
+pub fn find(game: &Game, depth: u8, name: &str) -> Vec<Puzzle> {
+    let mut puzzles = Vec::new();
+    for played in &game.history {
+        let board = &played.before;
+        let Some((best, score)) = engine::search(board, depth) else {
+            break;
+        };
+        let score = analysis::capped(score);
+        if score < WINNING {
+            continue;
+        }
+        let only = match engine::search_without(board, depth, best) {
+            Some((_, second)) => score - analysis::capped(second) >= WINNING,
+            // a forced move is no puzzle
+            None => false,
+        };
+        if !only {
+            continue;
+        }
+        let dots = match board.turn {
+            Color::White => ".",
+            Color::Black => "...",
+        };
+        let found = if played.mve == best {
+            "played"
+        } else {
+            "missed"
+        };
+        puzzles.push(Puzzle {
+            id: Some(format!("{}, {}{} {}", name, board.fullmove, dots, found)),
+            board: board.clone(),
+            solution: vec![best],
+        });
+    }
+    puzzles
+}
+
+// looks through the games of the library for tactics and writes them to
+// output as puzzles, returning how many were found. games of other variants
+// are left out, the puzzle command sets its positions in one variant
+pub fn export(dir: &Path, output: &Path, variant: VariantKind, depth: u8) -> Result<usize, String> {
+    let mut lines = Vec::new();
+    for entry in library::list(dir) {
+        let text = std::fs::read_to_string(&entry.path)
+            .map_err(|e| format!("could not read {}: {}", entry.path.display(), e))?;
+        let Ok(game) = pgn::read(&text, variant) else {
+            continue;
+        };
+        if game.board.variant != variant {
+            continue;
+        }
+        let name = format!(
+            "{} vs {} {}",
+            entry.tag("White"),
+            entry.tag("Black"),
+            entry.tag("Date")
+        );
+        lines.extend(find(&game, depth, &name).iter().map(Puzzle::to_epd));
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    std::fs::write(output, text)
+        .map_err(|e| format!("could not write {}: {}", output.display(), e))?;
+    Ok(lines.len())
+}
+
+// the tactics subcommand: exports the puzzles of the library's games
+pub fn tactics(output: &Path, variant: VariantKind, depth: u8) -> Result<(), String> {
+    let dir = library::default_dir().ok_or("no data directory for the game library")?;
+    let found = export(&dir, output, variant, depth)?;
+    println!(
+        "wrote {} {} to {}",
+        found,
+        if found == 1 { "puzzle" } else { "puzzles" },
+        output.display()
+    );
+    Ok(())
+}
+
+// reads every puzzle of an epd file, skipping empty lines and # comments
+pub fn load(path: &Path, variant: VariantKind) -> Result<Vec<Puzzle>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| Puzzle::parse(line, variant))
+        .collect()
+}
+
+// the daily puzzle as lichess gives it: the moves of the game that led to it
+// in san, and the solution in uci from the position after the last of them
+fn from_lichess(daily: &Value) -> Result<Puzzle, String> {
+    let moves = daily
+        .str_at(&["game", "pgn"])
+        .ok_or("the daily puzzle has no game")?;
+    let mut board = ChessBoard::new();
+    for san in moves.split_whitespace() {
+        let mve = board.parse_san(san).ok_or_else(|| {
+            format!(
+                "the game of the daily puzzle plays {}, which this board can't",
+                san
+            )
+        })?;
+        board.make_move(&mve);
+    }
+    let Some(Value::Array(solution)) = daily.get("puzzle").and_then(|p| p.get("solution")) else {
+        return Err(String::from("the daily puzzle has no solution"));
+    };
+    let mut after = board.clone();
+    let mut line = Vec::new();
+    for uci in solution.iter().filter_map(Value::as_str) {
+        let mve = after
+            .parse_uci(uci)
+            .filter(|m| after.is_legal(m))
+            .ok_or_else(|| {
+                format!(
+                    "the solution of the daily puzzle plays {}, which this board can't",
+                    uci
+                )
+            })?;
+        after.make_move(&mve);
+        line.push(mve);
+    }
+    let mut id = format!(
+        "lichess daily {}",
+        daily.str_at(&["puzzle", "id"]).unwrap_or("puzzle")
+    );
+    if let Some(Value::Number(rating)) = daily.get("puzzle").and_then(|p| p.get("rating")) {
+        id.push_str(&format!(", rated {}", rating));
+    }
+    Ok(Puzzle {
+        id: Some(id),
+        board,
+        solution: line,
+    })
+}
+
+// sets the lichess daily puzzle. it is kept as epd once fetched, and the one
+// kept is set when lichess can't be reached
+pub fn daily(options: &DisplayOptions) -> Result<(), String> {
+    let path = config::data_dir().map(|dir| dir.join("daily.epd"));
+    let puzzle = match lichess::fetch("/api/puzzle/daily").and_then(|daily| from_lichess(&daily)) {
+        Ok(puzzle) => {
+            if let Some(path) = &path {
+                let _ = std::fs::create_dir_all(path.parent().unwrap_or(path))
+                    .and_then(|_| std::fs::write(path, puzzle.to_epd() + "\n"));
+            }
+            puzzle
+        }
+        Err(e) => {
+            let kept = path
+                .as_deref()
+                .filter(|path| path.exists())
+                .ok_or_else(|| format!("could not get the daily puzzle: {}", e))?;
+            println!(
+                "could not get the daily puzzle, setting the last one fetched: {}",
+                e
+            );
+            let mut puzzles = load(kept, VariantKind::Standard)?;
+            puzzles
+                .pop()
+                .ok_or_else(|| format!("no puzzle in {}", kept.display()))?
+        }
+    };
+    set(&[puzzle], options);
+    Ok(())
+}
+
+// how one puzzle went
+enum Outcome {
+    Solved(Duration),
+    Failed,
+    Quit,
+}
+
+// sets the puzzles one after the other. only the moves of the solution are
+// taken, the opponent's replies are played for the solver, and a wrong move
+// ends the puzzle. the time and result of each are summed up at the end
+pub fn run(path: &Path, variant: VariantKind, options: &DisplayOptions) -> Result<(), String> {
+    let puzzles = load(path, variant)?;
+    if puzzles.is_empty() {
+        return Err(format!("no puzzles in {}", path.display()));
+    }
+    set(&puzzles, options);
+    Ok(())
+}
+
+fn set(puzzles: &[Puzzle], options: &DisplayOptions) {
+    let mut solved = Vec::new();
+    let mut tried = 0;
+    // how the last puzzle went, shown under the board of the next one
+    let mut notes = Vec::new();
+    for (i, puzzle) in puzzles.iter().enumerate() {
+        notes.push(match &puzzle.id {
+            Some(id) => format!("puzzle {} of {}: {}", i + 1, puzzles.len(), id),
+            None => format!("puzzle {} of {}", i + 1, puzzles.len()),
+        });
+        match solve(puzzle, options, &mut notes) {
+            Outcome::Solved(time) => {
+                tried += 1;
+                solved.push(time);
+                notes.push(format!("solved in {:.1}s", time.as_secs_f64()));
+            }
+            Outcome::Failed => tried += 1,
+            Outcome::Quit => break,
+        }
+    }
+    for note in notes {
+        println!("{}", note);
+    }
+    println!();
+    println!("solved {} of {} puzzles", solved.len(), tried);
+    if !solved.is_empty() {
+        let total: Duration = solved.iter().sum();
+        println!(
+            "average time {:.1}s",
+            total.as_secs_f64() / solved.len() as f64
+        );
+    }
+}
+
+fn solve(puzzle: &Puzzle, options: &DisplayOptions, notes: &mut Vec<String>) -> Outcome {
+    let mut game = Game::from_board(puzzle.board.clone());
+    let solver = game.board.turn;
+    let started = Instant::now();
+    let mut found = 0;
+    notes.push(format!("{} to move", solver.name()));
+    while found < puzzle.solution.len() {
+        clear_screen(options);
+        game.board.print(options, &Highlights::default());
+        for note in notes.drain(..) {
+            println!("{}", note);
+        }
+        println!("find the best move, or type skip to see the solution or quit to stop");
+
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+            return Outcome::Quit;
+        }
+        let input = input.trim();
+        let board = &game.board;
+        let mve = match input {
+            "quit" => return Outcome::Quit,
+            "skip" => {
+                notes.push(solution_text(puzzle, options));
+                return Outcome::Failed;
+            }
+            _ => board
+                .parse_san(&options.locale.delocalize(input))
+                .or_else(|| board.parse_uci(input).filter(|m| board.is_legal(m))),
+        };
+        let Some(mve) = mve else {
+            notes.push(format!("{} isn't a legal move", input));
+            continue;
+        };
+        let expected = puzzle.solution[found];
+        game.play(&mve);
+        // any mate finishes the puzzle, not only the one in the solution
+        let mated = matches!(game.result, Some(GameResult::Checkmate(c)) if c == solver);
+        if mated {
+            break;
+        }
+        if mve != expected {
+            let san = &game.history[game.history.len() - 1].san;
+            notes.push(format!("{} isn't it", options.move_text(san, &mve)));
+            notes.push(solution_text(puzzle, options));
+            return Outcome::Failed;
+        }
+        found += 1;
+        // the opponent's answer from the solution, if there is one
+        if let Some(reply) = puzzle.solution.get(found) {
+            let san = game.board.san(reply);
+            game.play(reply);
+            let text = options.move_text(&san, reply);
+            notes.push(options.locale.plays(solver.other(), &text));
+            found += 1;
+        }
+    }
+    Outcome::Solved(started.elapsed())
+}
+
+fn solution_text(puzzle: &Puzzle, options: &DisplayOptions) -> String {
+    let line = options.line_text(&puzzle.board, &puzzle.solution);
+    format!("the solution was {}", line)
+}
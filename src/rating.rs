@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+// how far one game moves a rating
+const K: f64 = 32.0;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ratings {
+    // by name: "human", or "engine depth 3" for a bot level
+    pub players: BTreeMap<String, Rating>,
+    // by the same names, the accuracy in percent of every analyzed game
+    pub accuracy: BTreeMap<String, Vec<f64>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rating {
+    // the rating after every rated game, the first one is where it started
+    pub history: Vec<i32>,
+}
+
+impl Rating {
+    pub fn current(&self) -> i32 {
+        self.history.last().copied().unwrap_or(1500)
+    }
+}
+
+// ratings.toml in the data directory
+pub fn default_path() -> Option<PathBuf> {
+    Some(config::data_dir()?.join("ratings.toml"))
+}
+
+// the bots start from a guess that grows with how deep they look, or from
+// the rating they play like, so the first games against them don't swing
+// the human's rating too far
+fn initial(name: &str) -> i32 {
+    let number = |prefix: &str| name.strip_prefix(prefix)?.parse::<i32>().ok();
+    match (number("engine depth "), number("human-like ")) {
+        (Some(depth), _) => 1000 + 200 * depth,
+        (_, Some(rating)) => rating,
+        _ => 1500,
+    }
+}
+
+impl Ratings {
+    // a missing file just means nobody is rated yet
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Ratings::default()),
+            Err(e) => return Err(format!("could not read {}: {}", path.display(), e)),
+        };
+        toml::from_str(&text).map_err(|e| format!("invalid ratings {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let text = toml::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| format!("could not write {}: {}", path.display(), e))
+    }
+
+    pub fn current(&self, name: &str) -> i32 {
+        self.players
+            .get(name)
+            .map_or_else(|| initial(name), Rating::current)
+    }
+
+    // elo update after a game between a and b, score is a's: 1, 0.5 or 0
+    pub fn record(&mut self, a: &str, b: &str, score: f64) {
+        let (ra, rb) = (self.current(a), self.current(b));
+        let expected = 1.0 / (1.0 + 10f64.powf((rb - ra) as f64 / 400.0));
+        let change = (K * (score - expected)).round() as i32;
+        for (name, rating) in [(a, ra + change), (b, rb - change)] {
+            let start = initial(name);
+            let entry = self.players.entry(name.to_string()).or_insert(Rating {
+                history: vec![start],
+            });
+            entry.history.push(rating);
+        }
+    }
+
+    pub fn record_accuracy(&mut self, name: &str, accuracy: f64) {
+        // one decimal is all that is shown
+        let accuracy = (accuracy * 10.0).round() / 10.0;
+        self.accuracy
+            .entry(name.to_string())
+            .or_default()
+            .push(accuracy);
+    }
+
+    // one line per player with the rating and the last few it went through,
+    // then one per player with the average accuracy and the last few
+    pub fn describe(&self) -> Vec<String> {
+        let ratings = self.players.iter().map(|(name, rating)| {
+            let games = rating.history.len() - 1;
+            let change = rating.current() - rating.history[0];
+            let recent: Vec<String> = rating
+                .history
+                .iter()
+                .rev()
+                .take(10)
+                .rev()
+                .map(i32::to_string)
+                .collect();
+            format!(
+                "{} {} after {} {} ({:+}): {}",
+                name,
+                rating.current(),
+                games,
+                if games == 1 { "game" } else { "games" },
+                change,
+                recent.join(" ")
+            )
+        });
+        let accuracy = self.accuracy.iter().map(|(name, history)| {
+            let games = history.len();
+            let average = history.iter().sum::<f64>() / games as f64;
+            let recent: Vec<String> = history
+                .iter()
+                .rev()
+                .take(10)
+                .rev()
+                .map(|a| format!("{:.1}", a))
+                .collect();
+            format!(
+                "{} accuracy {:.1}% over {} analyzed {}: {}",
+                name,
+                average,
+                games,
+                if games == 1 { "game" } else { "games" },
+                recent.join(" ")
+            )
+        });
+        ratings.chain(accuracy).collect()
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use rustls::{ClientConfig, ServerConfig};
+
+use crate::transport::{Connection, Sender};
+
+// meets two players who can't take connections themselves. both connect out
+// and send "relay <game code>". the first is told waiting, and once the second
+// arrives both are told paired, after which every line one sends reaches the
+// other unchanged. when either goes, the other's connection is closed
+pub fn run(port: u16, tls: Option<Arc<ServerConfig>>) -> Result<(), String> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .map_err(|e| format!("could not listen on port {}: {}", port, e))?;
+    println!("relay open on port {}", port);
+    let waiting = Arc::new(Mutex::new(BTreeMap::new()));
+    for stream in listener.incoming().map_while(Result::ok) {
+        let waiting = Arc::clone(&waiting);
+        let tls = tls.clone();
+        std::thread::spawn(move || serve(stream, tls.as_ref(), &waiting));
+    }
+    Ok(())
+}
+
+// the longest line passed on, so a broken client can't fill the memory
+const MAX_LINE: usize = 256;
+
+// a player waiting for the other one, with where to put them once they come
+struct Waiting {
+    sender: Sender,
+    partner: Arc<Mutex<Option<Sender>>>,
+}
+
+// the waiting list and partners are taken even if another connection
+// panicked while holding them, so one bad client doesn't end the relay
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn serve(
+    stream: TcpStream,
+    tls: Option<&Arc<ServerConfig>>,
+    waiting: &Mutex<BTreeMap<String, Waiting>>,
+) {
+    let Ok(mut connection) = Connection::accept(stream, tls) else {
+        return;
+    };
+    let sender = connection.sender();
+    let Some(line) = connection.read_line(MAX_LINE) else {
+        return;
+    };
+    let code = match line.strip_prefix("relay ").map(str::trim) {
+        Some(code) if !code.is_empty() && !code.contains(char::is_whitespace) => code,
+        _ => {
+            let _ = sender.send_line("error the first line is relay <game code>");
+            return;
+        }
+    };
+
+    let partner = Arc::new(Mutex::new(None));
+    {
+        let mut waiting = lock(waiting);
+        match waiting.remove(code) {
+            Some(first) => {
+                *lock(&first.partner) = Some(sender.clone());
+                *lock(&partner) = Some(first.sender.clone());
+                let _ = first.sender.send_line("paired");
+                let _ = sender.send_line("paired");
+            }
+            None => {
+                let _ = sender.send_line("waiting");
+                waiting.insert(
+                    code.to_string(),
+                    Waiting {
+                        sender: sender.clone(),
+                        partner: Arc::clone(&partner),
+                    },
+                );
+            }
+        }
+    }
+
+    while let Some(line) = connection.read_line(MAX_LINE) {
+        // nobody hears what is sent before the other player is there
+        if let Some(partner) = lock(&partner).as_ref() {
+            let _ = partner.send_line(&line);
+        }
+    }
+
+    let mut waiting = lock(waiting);
+    let paired = lock(&partner).take();
+    match paired {
+        Some(partner) => partner.close(),
+        None => {
+            if waiting
+                .get(code)
+                .is_some_and(|w| Arc::ptr_eq(&w.partner, &partner))
+            {
+                waiting.remove(code);
+            }
+        }
+    }
+}
+
+// connects to a relay and waits there until the other player with the same
+// code is connected too
+pub fn connect(
+    relay: &str,
+    code: &str,
+    tls: Option<&Arc<ClientConfig>>,
+) -> Result<Connection, String> {
+    let mut connection = Connection::connect(relay, tls)?;
+    connection
+        .sender()
+        .send_line(&format!("relay {}", code))
+        .map_err(|e| e.to_string())?;
+    loop {
+        match connection.read_line(MAX_LINE).as_deref() {
+            Some("paired") => return Ok(connection),
+            Some("waiting") => println!("waiting for the other player, game code {}", code),
+            Some(line) => match line.strip_prefix("error ") {
+                Some(e) => return Err(format!("the relay refused: {}", e)),
+                None => return Err(format!("{} is not a relay", relay)),
+            },
+            None => return Err(format!("{} closed the connection", relay)),
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+// height of the pictures on the flashcards, in pixels
+const CARD_SIZE: u32 = 320;
+
+use crate::display::{clear_screen, DisplayOptions, Highlights};
+use crate::game::Game;
+use crate::image::{self, Theme};
+use crate::pgn::{self, Token};
+use crate::rng::Rng;
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Color, Move};
+
+// how many lines go by before a missed one comes back, doubling every time it
+// is then played right
+const SOON: usize = 3;
+
+// how often a missed line has to be played right in a row before it is done
+const REVIEWS: u32 = 2;
+
+// one way through the repertoire, from the start of its game to a move of the
+// side trained
+struct Line {
+    start: ChessBoard,
+    moves: Vec<Move>,
+}
+
+// a line waiting its turn in the quiz, and how it went so far
+struct Card {
+    line: usize,
+    missed: bool,
+    // the times played right since it was last missed
+    streak: u32,
+}
+
+// every line of the games of a pgn file, variations included. lines end on
+// the last move of side, the opponent's moves after it have nothing to quiz,
+// and a line that is the start of another isn't kept apart
+fn lines(text: &str, variant: VariantKind, side: Color) -> Result<Vec<Line>, String> {
+    let mut lines: Vec<Line> = Vec::new();
+    for text in pgn::games(text) {
+        // the main line is checked on the way, the variations are read here
+        let start = pgn::read(&text, variant)?.start().clone();
+        // the moves so far, each with the position it was played from
+        let mut path: Vec<(ChessBoard, Move)> = Vec::new();
+        let mut board = start.clone();
+        let mut stack = Vec::new();
+        let mut ends = Vec::new();
+        for token in pgn::tokens(&text) {
+            match token {
+                Token::Move(san) => {
+                    let mve = board
+                        .parse_san(&san)
+                        .ok_or_else(|| format!("illegal move in repertoire: {}", san))?;
+                    path.push((board.clone(), mve));
+                    board.make_move(&mve);
+                    ends.push(path.clone());
+                }
+                // a variation replaces the move before it
+                Token::Open => {
+                    stack.push((path.clone(), board.clone()));
+                    if let Some((before, _)) = path.pop() {
+                        board = before;
+                    }
+                }
+                Token::Close => {
+                    let (outer, after) = stack
+                        .pop()
+                        .ok_or_else(|| String::from("unbalanced variation in repertoire"))?;
+                    path = outer;
+                    board = after;
+                }
+            }
+        }
+        for mut end in ends {
+            while end.last().is_some_and(|(before, _)| before.turn != side) {
+                end.pop();
+            }
+            if end.is_empty() {
+                continue;
+            }
+            let moves: Vec<Move> = end.iter().map(|&(_, mve)| mve).collect();
+            // a shorter line that this one goes on from is replaced by it
+            lines.retain(|line| {
+                line.start.zobrist_key() != start.zobrist_key() || !moves.starts_with(&line.moves)
+            });
+            let covered = lines.iter().any(|line| {
+                line.start.zobrist_key() == start.zobrist_key() && line.moves.starts_with(&moves)
+            });
+            if !covered {
+                lines.push(Line {
+                    start: start.clone(),
+                    moves,
+                });
+            }
+        }
+    }
+    Ok(lines)
+}
+
+// how one line went
+enum Outcome {
+    Right,
+    Missed,
+    Quit,
+}
+
+// quizzes the lines of a repertoire in random order, playing the opponent's
+// moves and asking for side's. a missed line comes back a few lines later,
+// and again further on each time it is played right, until it sticks
+pub fn run(
+    path: &Path,
+    variant: VariantKind,
+    side: Color,
+    options: &DisplayOptions,
+    rng: &mut Rng,
+) -> Result<(), String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let lines = lines(&text, variant, side)?;
+    if lines.is_empty() {
+        return Err(format!(
+            "no moves for {} in {}",
+            side.name(),
+            path.display()
+        ));
+    }
+    let mut options = *options;
+    options.flipped = side == Color::Black;
+    options.auto_flip = false;
+
+    let mut order: Vec<usize> = (0..lines.len()).collect();
+    for i in (1..order.len()).rev() {
+        order.swap(i, rng.below(i + 1));
+    }
+    let mut queue: VecDeque<Card> = order
+        .into_iter()
+        .map(|line| Card {
+            line,
+            missed: false,
+            streak: 0,
+        })
+        .collect();
+    let mut notes = vec![format!("{} lines for {}", lines.len(), side.name())];
+    let mut first_time = 0;
+    let mut missed = 0;
+    while let Some(mut card) = queue.pop_front() {
+        match quiz(&lines, card.line, side, &options, &mut notes) {
+            Outcome::Quit => break,
+            Outcome::Missed => {
+                if !card.missed {
+                    missed += 1;
+                }
+                card.missed = true;
+                card.streak = 0;
+                let at = SOON.min(queue.len());
+                queue.insert(at, card);
+            }
+            Outcome::Right if !card.missed => first_time += 1,
+            Outcome::Right => {
+                card.streak += 1;
+                if card.streak < REVIEWS {
+                    let at = (SOON << card.streak).min(queue.len());
+                    queue.insert(at, card);
+                }
+            }
+        }
+        notes.push(format!("{} lines left", queue.len()));
+    }
+    for note in notes {
+        println!("{}", note);
+    }
+    println!();
+    println!(
+        "{} of {} lines right the first time, {} missed",
+        first_time,
+        lines.len(),
+        missed
+    );
+    Ok(())
+}
+
+// plays one line through: the opponent's moves are made for the user, and a
+// move of side that isn't the line's is shown right and played for them, so
+// the line is seen to its end either way. a move of another line from the
+// same position only asks for this line's
+fn quiz(
+    lines: &[Line],
+    i: usize,
+    side: Color,
+    options: &DisplayOptions,
+    notes: &mut Vec<String>,
+) -> Outcome {
+    let line = &lines[i];
+    let mut game = Game::from_board(line.start.clone());
+    let mut outcome = Outcome::Right;
+    for (ply, &expected) in line.moves.iter().enumerate() {
+        if game.board.turn != side {
+            let san = game.board.san(&expected);
+            game.play(&expected);
+            let text = options.move_text(&san, &expected);
+            notes.push(options.locale.plays(side.other(), &text));
+            continue;
+        }
+        loop {
+            clear_screen(options);
+            game.board.print(options, &Highlights::default());
+            for note in notes.drain(..) {
+                println!("{}", note);
+            }
+            println!("your repertoire move, or type show to see it or quit to stop");
+
+            let mut input = String::new();
+            if std::io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+                return Outcome::Quit;
+            }
+            let input = input.trim();
+            let board = &game.board;
+            let text = options.move_text(&board.san(&expected), &expected);
+            match input {
+                "quit" => return Outcome::Quit,
+                "show" => {
+                    notes.push(format!("the repertoire plays {}", text));
+                    outcome = Outcome::Missed;
+                }
+                _ => {
+                    let mve = board
+                        .parse_san(&options.locale.delocalize(input))
+                        .or_else(|| board.parse_uci(input).filter(|m| board.is_legal(m)));
+                    let Some(mve) = mve else {
+                        notes.push(format!("{} isn't a legal move", input));
+                        continue;
+                    };
+                    let played = options.move_text(&board.san(&mve), &mve);
+                    let elsewhere = lines.iter().any(|other| {
+                        other.start.zobrist_key() == line.start.zobrist_key()
+                            && other.moves.get(ply) == Some(&mve)
+                            && other.moves[..ply] == line.moves[..ply]
+                    });
+                    if mve == expected {
+                        notes.push(format!("{}, right", text));
+                    } else if elsewhere {
+                        notes.push(format!(
+                            "{} is in the repertoire too, this line plays another move",
+                            played
+                        ));
+                        continue;
+                    } else {
+                        notes.push(format!(
+                            "{} isn't it, the repertoire plays {}",
+                            played, text
+                        ));
+                        outcome = Outcome::Missed;
+                    }
+                }
+            }
+            game.play(&expected);
+            break;
+        }
+    }
+    notes.push(match outcome {
+        Outcome::Right => String::from("line done"),
+        _ => String::from("line missed, it will come back"),
+    });
+    outcome
+}
+
+// a field of a csv file, quoted when it holds a comma, a quote or a line break
+fn csv_field(text: &str) -> String {
+    match text.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
+
+// the moves from start as they are written in a game: "1. e4 e5 2. Nf3"
+fn numbered(start: &ChessBoard, moves: &[Move]) -> String {
+    let mut board = start.clone();
+    let mut words = Vec::new();
+    for (i, mve) in moves.iter().enumerate() {
+        match board.turn {
+            Color::White if board.turn_moves == 0 => words.push(format!("{}.", board.fullmove)),
+            Color::Black if i == 0 => words.push(format!("{}...", board.fullmove)),
+            _ => {}
+        }
+        words.push(board.san(mve));
+        board.make_move(mve);
+    }
+    words.join(" ")
+}
+
+// writes a flashcard for every position of the repertoire where side is to
+// move, as csv that anki imports: the front, the back with the repertoire's
+// moves there, and a tag. the front has the moves that led there and the fen,
+// and with images a picture of the board, written to that folder for anki's
+// media folder, and says how many cards it wrote
+pub fn export(
+    path: &Path,
+    variant: VariantKind,
+    side: Color,
+    output: &Path,
+    images: Option<&Path>,
+) -> Result<(), String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let lines = lines(&text, variant, side)?;
+    if let Some(dir) = images {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("could not create {}: {}", dir.display(), e))?;
+    }
+    // every position once, however many lines go through it, with the
+    // moves that reach it first and every answer the lines give
+    let mut cards: Vec<(ChessBoard, String, Vec<String>)> = Vec::new();
+    for line in &lines {
+        let mut board = line.start.clone();
+        for (ply, mve) in line.moves.iter().enumerate() {
+            if board.turn == side {
+                let san = board.san(mve);
+                let key = board.zobrist_key();
+                match cards
+                    .iter_mut()
+                    .find(|(known, _, _)| known.zobrist_key() == key)
+                {
+                    Some((_, _, answers)) if answers.contains(&san) => {}
+                    Some((_, _, answers)) => answers.push(san),
+                    None => {
+                        let before = numbered(&line.start, &line.moves[..ply]);
+                        cards.push((board.clone(), before, vec![san]));
+                    }
+                }
+            }
+            board.make_move(mve);
+        }
+    }
+    let tag = format!("repertoire_{}", side.name().to_lowercase());
+    let stem = output.file_stem().map_or(String::from("card"), |stem| {
+        stem.to_string_lossy().to_string()
+    });
+    let mut csv = String::new();
+    for (i, (board, before, answers)) in cards.iter().enumerate() {
+        let mut front = match before.is_empty() {
+            true => format!("{} to move at the start", side.name()),
+            false => format!("{} to move after {}", side.name(), before),
+        };
+        if let Some(dir) = images {
+            let name = format!("{}-{}.png", stem, i + 1);
+            let picture = board.picture(CARD_SIZE, Theme::Brown, side == Color::Black);
+            image::write_png(&picture, &dir.join(&name))?;
+            front = format!("<img src=\"{}\"><br>{}", name, front);
+        }
+        front.push_str(&format!("<br>{}", board.to_fen()));
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            csv_field(&front),
+            csv_field(&answers.join(" or ")),
+            tag
+        ));
+    }
+    std::fs::write(output, csv)
+        .map_err(|e| format!("could not write {}: {}", output.display(), e))?;
+    println!(
+        "wrote {} {} to {}",
+        cards.len(),
+        if cards.len() == 1 {
+            "flashcard"
+        } else {
+            "flashcards"
+        },
+        output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use crate::Move;
+
+// a line of a replay file: the seed comes first, then every line typed and
+// every move the program chose, in the order they happened
+//   seed 1234
+//   input e4
+//   engine e7e5
+pub enum Entry {
+    Input(String),
+    Engine(String),
+}
+
+// a session being written down with --record, or one read back with --replay.
+// the line interface only, the terminal one reads keys instead of lines
+pub enum Journal {
+    Record(File),
+    Replay {
+        entries: VecDeque<Entry>,
+        // set once a move differs from the recorded one, only the first is told
+        diverged: bool,
+    },
+}
+
+impl Journal {
+    // starts a replay file with the seed and the command line it was run with,
+    // the second only for whoever reads the file
+    pub fn record(path: &Path, seed: u64) -> Result<Journal, String> {
+        let error = |e: std::io::Error| format!("could not write {}: {}", path.display(), e);
+        let mut file = File::create(path).map_err(error)?;
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        writeln!(file, "# chess {}", args.join(" ")).map_err(error)?;
+        writeln!(file, "seed {}", seed).map_err(error)?;
+        Ok(Journal::Record(file))
+    }
+
+    // reads a replay file, returning it with the seed to start from
+    pub fn replay(path: &Path) -> Result<(Journal, u64), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        let mut seed = None;
+        let mut entries = VecDeque::new();
+        for (n, line) in text.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match kind {
+                "seed" => {
+                    seed =
+                        Some(rest.parse().map_err(|_| {
+                            format!("bad seed on line {} of {}", n + 1, path.display())
+                        })?)
+                }
+                "input" => entries.push_back(Entry::Input(rest.to_string())),
+                "engine" => entries.push_back(Entry::Engine(rest.to_string())),
+                _ => {
+                    return Err(format!(
+                        "unknown entry {} on line {} of {}",
+                        kind,
+                        n + 1,
+                        path.display()
+                    ))
+                }
+            }
+        }
+        let seed = seed.ok_or_else(|| format!("no seed in {}", path.display()))?;
+        let journal = Journal::Replay {
+            entries,
+            diverged: false,
+        };
+        Ok((journal, seed))
+    }
+
+    // the next line typed in the recording, None once they run out so the
+    // session goes on from the keyboard. engine moves the replay didn't get
+    // to are dropped on the way
+    pub fn next_input(&mut self) -> Option<String> {
+        let Journal::Replay { entries, .. } = self else {
+            return None;
+        };
+        while let Some(entry) = entries.pop_front() {
+            if let Entry::Input(line) = entry {
+                return Some(line);
+            }
+        }
+        None
+    }
+
+    // writes down a line typed
+    pub fn input(&mut self, line: &str) {
+        if let Journal::Record(file) = self {
+            let _ = writeln!(file, "input {}", line.trim_end_matches(['\r', '\n']));
+        }
+    }
+
+    // writes down a move the program chose, or checks it against the
+    // recording. the note says where a replay first went another way
+    pub fn decided(&mut self, mve: &Move) -> Option<String> {
+        let text = mve.to_string();
+        match self {
+            Journal::Record(file) => {
+                let _ = writeln!(file, "engine {}", text);
+                None
+            }
+            Journal::Replay { entries, diverged } => {
+                let recorded = match entries.front() {
+                    Some(Entry::Engine(recorded)) => {
+                        let recorded = recorded.clone();
+                        entries.pop_front();
+                        Some(recorded)
+                    }
+                    _ => None,
+                };
+                if *diverged || recorded.as_deref() == Some(text.as_str()) {
+                    return None;
+                }
+                *diverged = true;
+                Some(match recorded {
+                    Some(recorded) => format!(
+                        "replay diverged: {} was played, the recording has {}",
+                        text, recorded
+                    ),
+                    None => format!(
+                        "replay diverged: {} was played, the recording has none",
+                        text
+                    ),
+                })
+            }
+        }
+    }
+}
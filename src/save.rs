@@ -0,0 +1,174 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{Clock, TimeControl};
+use crate::config;
+use crate::game::{Game, GameResult};
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Color};
+
+// a game as written by save: where it started, the moves since, and what the
+// moves alone can't tell, like a resignation or the time left
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SavedGame {
+    start: String,
+    // in uci notation so the file doesn't depend on the notation setting
+    moves: Vec<String>,
+    result: Option<GameResult>,
+    // milliseconds left for white and black
+    clock: Option<[u64; 2]>,
+    // the time controls of the clocks, like "5+3"
+    #[serde(default)]
+    controls: Option<[String; 2]>,
+    #[serde(default)]
+    armageddon: bool,
+    #[serde(default)]
+    variant: VariantKind,
+    // milliseconds each move took, when played with clocks
+    #[serde(default)]
+    elapsed: Vec<u64>,
+}
+
+// the game in progress as save writes it and where the panic hook puts it, so
+// a crash never costs more than the move being thought about
+static RESCUE: Mutex<Option<(PathBuf, String)>> = Mutex::new(None);
+
+// autosave.toml in the data directory
+pub fn autosave_path() -> Option<PathBuf> {
+    Some(config::data_dir()?.join("autosave.toml"))
+}
+
+// where a crash leaves the game when it isn't autosaved
+pub fn crash_path() -> Option<PathBuf> {
+    Some(config::data_dir()?.join("crash.toml"))
+}
+
+pub fn save(game: &Game, path: &Path) -> Result<(), String> {
+    write(path, &to_text(game)?)
+}
+
+// writes a file next to path and renames it over path once it is on disk, so
+// a crash or a full disk halfway leaves the old file whole
+pub fn write(path: &Path, text: &str) -> Result<(), String> {
+    write_bytes(path, text.as_bytes())
+}
+
+pub fn write_bytes(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let error = |e: std::io::Error| format!("could not write {}: {}", path.display(), e);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let temporary = path.with_file_name(name);
+    let mut file = File::create(&temporary).map_err(error)?;
+    file.write_all(bytes)
+        .and_then(|_| file.sync_all())
+        .and_then(|_| std::fs::rename(&temporary, path))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&temporary);
+            error(e)
+        })?;
+    // the rename itself only lasts once the directory is on disk too
+    if let Some(dir) = path.parent().and_then(|dir| File::open(dir).ok()) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+// the game for the panic hook to write to path, or nothing once it is over
+pub fn keep_for_crash(kept: Option<(PathBuf, String)>) {
+    *RESCUE.lock().unwrap_or_else(|e| e.into_inner()) = kept;
+}
+
+// writes the kept game when the program panics, after whatever the hook
+// before did, like printing the message
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let kept = RESCUE.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some((path, text)) = kept {
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            match write(&path, &text) {
+                Ok(()) => eprintln!(
+                    "the game in progress was saved to {}, load {} continues it",
+                    path.display(),
+                    path.display()
+                ),
+                Err(e) => eprintln!("the game in progress was lost: {}", e),
+            }
+        }
+    }));
+}
+
+pub fn to_text(game: &Game) -> Result<String, String> {
+    let start = game
+        .history
+        .first()
+        .map_or(&game.board, |played| &played.before);
+    let saved = SavedGame {
+        start: start.to_fen(),
+        moves: game.history.iter().map(|p| p.mve.to_string()).collect(),
+        result: game.result,
+        clock: game.clock.as_ref().map(|clock| {
+            [Color::White, Color::Black].map(|color| clock.remaining(color).as_millis() as u64)
+        }),
+        armageddon: game.armageddon,
+        variant: start.variant,
+        controls: game
+            .clock
+            .as_ref()
+            .map(|clock| clock.controls.map(|control| control.to_string())),
+        elapsed: game
+            .history
+            .iter()
+            .map_while(|played| played.elapsed)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .collect(),
+    };
+    toml::to_string(&saved).map_err(|e| e.to_string())
+}
+
+pub fn load(path: &Path) -> Result<Game, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let saved: SavedGame = toml::from_str(&text)
+        .map_err(|e| format!("invalid saved game {}: {}", path.display(), e))?;
+    let board = ChessBoard::from_fen(&saved.start)
+        .ok_or_else(|| format!("invalid fen in saved game: {}", saved.start))?;
+    let mut game = Game::from_board(board.with_variant(saved.variant));
+    game.armageddon = saved.armageddon;
+    for uci in &saved.moves {
+        match game.board.parse_uci(uci) {
+            Some(mve) if game.play(&mve) => {}
+            _ => return Err(format!("illegal move in saved game: {}", uci)),
+        }
+    }
+    for (played, millis) in game.history.iter_mut().zip(&saved.elapsed) {
+        played.elapsed = Some(Duration::from_millis(*millis));
+    }
+    if saved.result.is_some() {
+        game.result = saved.result;
+    }
+    if let Some(millis) = saved.clock {
+        let remaining = millis.map(Duration::from_millis);
+        let controls = match &saved.controls {
+            Some([white, black]) => [white.parse()?, black.parse()?],
+            // without them the clocks just get no bonus
+            None => remaining.map(|base| TimeControl { base, bonus: None }),
+        };
+        game.clock = Some(Clock::resume(
+            controls,
+            remaining,
+            game.board.turn,
+            game.result.is_none(),
+        ));
+    }
+    Ok(game)
+}
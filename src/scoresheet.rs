@@ -0,0 +1,130 @@
+use crate::display::DisplayOptions;
+use crate::game::Game;
+use crate::Color;
+
+// moves on one sheet, half of them in each pair of columns like the paper
+// forms at tournaments
+const MOVES: usize = 60;
+const HALF: usize = MOVES / 2;
+// wide enough for the longest san, like Qxe8+ or O-O-O#
+const CELL: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Form {
+    // columns lined up with spaces, to read or paste
+    Text,
+    // boxed columns at 72 characters with a page per sheet and room for the
+    // signatures, for a printer or a text to pdf tool
+    Print,
+}
+
+// the moves of the game by move number, white's and black's. in variants
+// with more than one move a turn they share the cell
+fn numbered(game: &Game, options: &DisplayOptions) -> (u16, Vec<[String; 2]>) {
+    let first = game.start().fullmove;
+    let mut rows: Vec<[String; 2]> = Vec::new();
+    for played in &game.history {
+        let row = (played.before.fullmove - first) as usize;
+        if rows.len() <= row {
+            rows.resize(row + 1, [String::new(), String::new()]);
+        }
+        let cell = &mut rows[row][played.before.turn.idx()];
+        if !cell.is_empty() {
+            cell.push_str(", ");
+        }
+        cell.push_str(&options.move_text(&played.san, &played.mve));
+    }
+    // a game that black starts leaves white's first cell empty
+    if game.start().turn == Color::Black && !rows.is_empty() {
+        rows[0][0] = String::from("...");
+    }
+    (first, rows)
+}
+
+fn row(form: Form, number: u16, moves: Option<&[String; 2]>) -> String {
+    let empty = [String::new(), String::new()];
+    let [white, black] = moves.unwrap_or(&empty);
+    match form {
+        Form::Text => format!("{:>4}  {:<CELL$} {:<CELL$}", number, white, black),
+        Form::Print => format!("| {:>3} | {:<CELL$} | {:<CELL$} ", number, white, black),
+    }
+}
+
+// two tags side by side on a line of the header
+fn header(left: (&str, &str), right: (&str, &str)) -> String {
+    let left = format!("{}: {}", left.0, left.1);
+    format!("{:<36}{}: {}", left, right.0, right.1)
+}
+
+// the game as a score sheet: the tags on top, the moves in two halves of
+// columns side by side and the result under them, a sheet for every sixty
+// moves
+pub fn sheet(game: &Game, tags: &[(&str, String)], options: &DisplayOptions, form: Form) -> String {
+    let tag = |wanted: &str| {
+        tags.iter()
+            .find(|(name, _)| *name == wanted)
+            .map_or("", |(_, value)| value.as_str())
+    };
+    let (first, rows) = numbered(game, options);
+    let sheets = rows.len().div_ceil(MOVES).max(1);
+    let rule = format!("+-----+{0}+{0}+-----+{0}+{0}+", "-".repeat(CELL + 2));
+    let mut lines = Vec::new();
+    for page in 0..sheets {
+        if page > 0 && form == Form::Print {
+            lines.push(String::from("\x0c"));
+        } else if page > 0 {
+            lines.push(String::new());
+        }
+        lines.push(header(("Event", tag("Event")), ("Date", tag("Date"))));
+        lines.push(header(("Site", tag("Site")), ("Round", tag("Round"))));
+        lines.push(header(("White", tag("White")), ("Black", tag("Black"))));
+        if sheets > 1 {
+            lines.push(format!("sheet {} of {}", page + 1, sheets));
+        }
+        lines.push(String::new());
+        match form {
+            Form::Text => {
+                let heading = format!("{:>4}  {:<CELL$} {:<CELL$}", "No.", "White", "Black");
+                lines.push(format!("{}    {}", heading, heading));
+            }
+            Form::Print => {
+                let heading = format!("| No. | {:<CELL$} | {:<CELL$} ", "White", "Black");
+                lines.push(rule.clone());
+                lines.push(format!("{}{}|", heading, heading));
+                lines.push(rule.clone());
+            }
+        }
+        for i in 0..HALF {
+            let left = page * MOVES + i;
+            let right = left + HALF;
+            let number = |index: usize| first + index as u16;
+            let (left, right) = (
+                row(form, number(left), rows.get(left)),
+                row(form, number(right), rows.get(right)),
+            );
+            lines.push(match form {
+                Form::Text => format!("{}    {}", left, right).trim_end().to_string(),
+                Form::Print => format!("{}{}|", left, right),
+            });
+        }
+        if form == Form::Print {
+            lines.push(rule.clone());
+        }
+    }
+    lines.push(String::new());
+    lines.push(match game.result {
+        Some(result) => format!(
+            "Result: {}  {}",
+            result.score(),
+            options.locale.result(result)
+        ),
+        None => String::from("Result: *"),
+    });
+    if form == Form::Print {
+        lines.push(String::new());
+        lines.push(String::from(
+            "White's signature: ________________    Black's signature: ________________",
+        ));
+    }
+    lines.join("\n") + "\n"
+}
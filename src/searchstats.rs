@@ -0,0 +1,206 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::{ChessBoard, Move};
+
+// the file every search is reported to with --search-stats, and how many
+// plies of the tree each report shows
+struct Settings {
+    out: Mutex<File>,
+    trace: u8,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+// the stages moves are tried in below the root: captures ordered by the
+// victim's value, then the quiet moves
+const STAGES: [&str; 2] = ["captures", "quiet moves"];
+
+// what one search went through so far
+struct Stats {
+    start: Instant,
+    nodes: u64,
+    leaves: u64,
+    // the nodes where a move failed high, and by the stage of that move
+    cutoffs: u64,
+    by_stage: [u64; STAGES.len()],
+    // those where it was the first move tried
+    first: u64,
+    // the nodes searched by every depth once it was done
+    iterations: Vec<(u8, u64)>,
+    // the top of the tree: ply, move, window and score once known
+    trace: Vec<(i32, String, i32, i32, Option<i32>)>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Stats>> = const { RefCell::new(None) };
+}
+
+// from now on every search is reported to the file, with trace plies of its
+// tree
+pub fn init(path: &Path, trace: u8) -> Result<(), String> {
+    let file =
+        File::create(path).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    let _ = SETTINGS.set(Settings {
+        out: Mutex::new(file),
+        trace,
+    });
+    Ok(())
+}
+
+fn with(f: impl FnOnce(&mut Stats)) {
+    if SETTINGS.get().is_none() {
+        return;
+    }
+    CURRENT.with_borrow_mut(|stats| {
+        if let Some(stats) = stats {
+            f(stats)
+        }
+    });
+}
+
+// a search starts on this thread
+pub fn start() {
+    if SETTINGS.get().is_none() {
+        return;
+    }
+    CURRENT.set(Some(Stats {
+        start: Instant::now(),
+        nodes: 0,
+        leaves: 0,
+        cutoffs: 0,
+        by_stage: [0; STAGES.len()],
+        first: 0,
+        iterations: Vec::new(),
+        trace: Vec::new(),
+    }));
+}
+
+pub fn node(leaf: bool) {
+    with(|stats| {
+        stats.nodes += 1;
+        stats.leaves += u64::from(leaf);
+    });
+}
+
+// the move at index in the ordered moves failed high
+pub fn cutoff(capture: bool, index: usize) {
+    with(|stats| {
+        stats.cutoffs += 1;
+        stats.by_stage[usize::from(!capture)] += 1;
+        stats.first += u64::from(index == 0);
+    });
+}
+
+pub fn iteration(depth: u8) {
+    with(|stats| stats.iterations.push((depth, stats.nodes)));
+}
+
+// whether moves at this ply go in the trace
+pub fn tracing(ply: i32) -> bool {
+    SETTINGS
+        .get()
+        .is_some_and(|settings| ply < i32::from(settings.trace))
+}
+
+// a move about to be searched at ply with the window, the place its score
+// goes once it is known
+pub fn enter(board: &ChessBoard, mve: &Move, ply: i32, alpha: i32, beta: i32) -> usize {
+    let mut at = 0;
+    with(|stats| {
+        at = stats.trace.len();
+        stats.trace.push((ply, board.san(mve), alpha, beta, None));
+    });
+    at
+}
+
+pub fn leave(at: usize, score: i32) {
+    with(|stats| {
+        if let Some(entry) = stats.trace.get_mut(at) {
+            entry.4 = Some(score);
+        }
+    });
+}
+
+// a bound as the trace writes it, with the infinite ones as such
+fn bound(score: i32) -> String {
+    match score.abs() > crate::engine::MATE {
+        true if score > 0 => String::from("inf"),
+        true => String::from("-inf"),
+        false => score.to_string(),
+    }
+}
+
+// the search from board is over: its report goes to the file
+pub fn finish(board: &ChessBoard) {
+    let Some(settings) = SETTINGS.get() else {
+        return;
+    };
+    let Some(stats) = CURRENT.take() else {
+        return;
+    };
+    let percent = |n: u64, of: u64| match of {
+        0 => 0.0,
+        _ => 100.0 * n as f64 / of as f64,
+    };
+    let elapsed = stats.start.elapsed();
+    let mut lines = vec![
+        format!("search from {}", board.to_fen()),
+        format!(
+            "  nodes {}, leaves {}, {:.3}s, {:.0} nps",
+            stats.nodes,
+            stats.leaves,
+            elapsed.as_secs_f64(),
+            stats.nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+        ),
+        format!(
+            "  cutoffs {} in {:.1}% of the inner nodes, {:.1}% on the first move",
+            stats.cutoffs,
+            percent(stats.cutoffs, stats.nodes - stats.leaves),
+            percent(stats.first, stats.cutoffs)
+        ),
+    ];
+    for (stage, count) in STAGES.iter().zip(stats.by_stage) {
+        lines.push(format!(
+            "  cutoffs by {:<12}{} ({:.1}%)",
+            stage,
+            count,
+            percent(count, stats.cutoffs)
+        ));
+    }
+    lines.push(String::from(
+        "  hash table hits: none, the search keeps no transposition table",
+    ));
+    let mut previous = 0;
+    for (depth, nodes) in &stats.iterations {
+        let searched = nodes - previous;
+        let branching = match previous {
+            0 => String::from("-"),
+            _ => format!("{:.2}", searched as f64 / previous as f64),
+        };
+        lines.push(format!(
+            "  depth {:<3} nodes {:<10} branching factor {}",
+            depth, searched, branching
+        ));
+        previous = searched;
+    }
+    for (ply, san, alpha, beta, score) in &stats.trace {
+        lines.push(format!(
+            "  {}{} [{}, {}] {}",
+            "  ".repeat(*ply as usize),
+            san,
+            bound(*alpha),
+            bound(*beta),
+            score.map_or(String::from("?"), |score| score.to_string())
+        ));
+    }
+    let mut out = settings.out.lock().unwrap();
+    for line in lines {
+        let _ = writeln!(out, "{}", line);
+    }
+    let _ = writeln!(out);
+}
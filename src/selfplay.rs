@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use clap::{Args, ValueEnum};
+
+use crate::game::Game;
+use crate::json::{object, string};
+use crate::parallel;
+use crate::rng::Rng;
+use crate::tournament::{self, Rules};
+use crate::variant::VariantKind;
+use crate::ChessBoard;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    // a line of epd per position: bm the move played, ce its score and c0
+    // the result
+    Epd,
+    // fen,move,score,result with a header line
+    Csv,
+    // an object per line with the same fields
+    Json,
+}
+
+// how many games are played and how they start
+#[derive(Debug, Clone, Copy, Args)]
+pub struct Settings {
+    /// How many games to play
+    #[arg(long, default_value_t = 10)]
+    pub games: u32,
+
+    /// Search depth of the engine on both sides
+    #[arg(long, default_value_t = 3)]
+    pub depth: u8,
+
+    /// Random moves played before the engine takes over, so the games differ
+    #[arg(long, default_value_t = 8)]
+    pub random_plies: u32,
+
+    /// How every position is written
+    #[arg(long, value_enum, default_value_t = Format::Epd)]
+    pub format: Format,
+
+    /// How many games are played at once, one per core if not given
+    #[arg(long)]
+    pub threads: Option<usize>,
+}
+
+// one searched position of a game, once the game is over and its result known
+struct Sample<'a> {
+    board: &'a ChessBoard,
+    uci: String,
+    san: String,
+    // centipawns for the side to move, as the engine searched it
+    score: i32,
+    // 1-0, 0-1 or 1/2-1/2
+    result: &'a str,
+}
+
+impl Format {
+    fn header(self) -> Option<&'static str> {
+        match self {
+            Format::Csv => Some("fen,move,score,result"),
+            _ => None,
+        }
+    }
+
+    fn line(self, sample: &Sample) -> String {
+        let fen = sample.board.to_fen();
+        match self {
+            Format::Epd => {
+                let fields: Vec<&str> = fen.split_whitespace().take(4).collect();
+                format!(
+                    "{} bm {}; ce {}; c0 \"{}\";",
+                    fields.join(" "),
+                    sample.san,
+                    sample.score,
+                    sample.result
+                )
+            }
+            Format::Csv => format!("{},{},{},{}", fen, sample.uci, sample.score, sample.result),
+            Format::Json => object(&[
+                ("fen", string(&fen)),
+                ("move", string(&sample.uci)),
+                ("san", string(&sample.san)),
+                ("score", sample.score.to_string()),
+                ("result", string(sample.result)),
+            ]),
+        }
+    }
+}
+
+// plays random moves from start for the opening, so the games of an engine
+// that always picks the same move still differ. None when they end the game
+fn opening(start: &ChessBoard, plies: u32, rng: &mut Rng) -> Option<ChessBoard> {
+    let mut board = start.clone();
+    for _ in 0..plies {
+        let moves = board.legal_moves();
+        if moves.is_empty() {
+            return None;
+        }
+        board.make_move(&moves[rng.below(moves.len())]);
+    }
+    (!board.legal_moves().is_empty()).then_some(board)
+}
+
+// the positions of a finished game the engine searched, with what it played
+fn samples<'a>(game: &'a Game, scores: &[i32], result: &'a str) -> Vec<Sample<'a>> {
+    game.history
+        .iter()
+        .zip(scores)
+        .map(|(played, &score)| Sample {
+            board: &played.before,
+            uci: match played.before.variant {
+                VariantKind::Chess960 => played.mve.to_string(),
+                _ => played.before.standard_uci(&played.mve),
+            },
+            san: played.san.clone(),
+            score,
+            result,
+        })
+        .collect()
+}
+
+// plays games of the engine against itself as settings say and writes every position
+// it searched to output, for tuning the evaluation or training a network on.
+// unfinished games are left out, their positions have no result to learn
+pub fn run(
+    start: &ChessBoard,
+    settings: Settings,
+    rules: Rules,
+    output: &Path,
+    rng: &mut Rng,
+) -> Result<(), String> {
+    let format = settings.format;
+    let error = |e: std::io::Error| format!("could not write {}: {}", output.display(), e);
+    let mut out = BufWriter::new(File::create(output).map_err(error)?);
+    if let Some(header) = format.header() {
+        writeln!(out, "{}", header).map_err(error)?;
+    }
+    // the openings are picked one after another so a seed always gives the
+    // same games, then the games are played side by side and written in order
+    let jobs: Vec<(u32, Option<ChessBoard>)> = (0..settings.games)
+        .map(|i| (i + 1, opening(start, settings.random_plies, rng)))
+        .collect();
+    let work = move |(number, board): (u32, Option<ChessBoard>)| {
+        let _span = tracing::info_span!("game", number).entered();
+        let played = board.map(|board| tournament::play(&board, [settings.depth; 2], rules, None));
+        (number, played)
+    };
+    let mut positions = 0;
+    parallel::ordered(settings.threads, jobs, work, |(number, played)| {
+        let Some((game, scores)) = played else {
+            println!("game {}: the random opening ended it, left out", number);
+            return Ok(());
+        };
+        let Some(result) = game.result else {
+            println!("game {}: unfinished, left out", number);
+            return Ok(());
+        };
+        let found = samples(&game, &scores, result.score());
+        for sample in &found {
+            writeln!(out, "{}", format.line(sample)).map_err(error)?;
+        }
+        positions += found.len();
+        println!(
+            "game {}: {} ({}), {} positions",
+            number,
+            result.score(),
+            result.describe(),
+            found.len()
+        );
+        Ok(())
+    })?;
+    out.flush().map_err(error)?;
+    println!("wrote {} positions to {}", positions, output.display());
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use rustls::ServerConfig;
+
+use crate::engine;
+use crate::game::Game;
+use crate::json::{self, object, string};
+use crate::logging::GameLog;
+use crate::transport::Sender;
+use crate::variant::VariantKind;
+use crate::websocket::{self, WebSocket};
+use crate::{ChessBoard, Color, Move};
+
+// the board a browser gets when it asks for the page instead of a websocket
+const PAGE: &str = include_str!("serve.html");
+
+// the seat id of the engine, no client gets it
+const ENGINE: usize = usize::MAX;
+
+// one game served over websocket. every message is a json object with a type:
+//   client: {"type":"join","color":"white"}   color is optional
+//           {"type":"move","move":"e2e4"}
+//   server: {"type":"state","fen":..,"turn":..,"moves":[..],"legal":[..],"you":..}
+//           {"type":"result","result":"1-0","reason":..}
+//           {"type":"error","message":..}
+// everyone connected gets the state after every move, clients that haven't
+// joined, or joined when both colors were taken, only watch. with an engine
+// depth the engine takes the other color of whoever joins first. browsers get
+// a board to play on at the same address
+struct Table {
+    game: Game,
+    engine: Option<u8>,
+    // the client playing each color, indexed by Color::idx
+    seats: [Option<usize>; 2],
+    clients: Vec<(usize, Sender)>,
+}
+
+pub fn run(
+    port: u16,
+    mut game: Game,
+    engine: Option<u8>,
+    tls: Option<Arc<ServerConfig>>,
+) -> Result<(), String> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .map_err(|e| format!("could not listen on port {}: {}", port, e))?;
+    let scheme = if tls.is_some() { "wss" } else { "ws" };
+    println!("serving the game on {}://0.0.0.0:{}", scheme, port);
+    let page = if tls.is_some() { "https" } else { "http" };
+    println!("open {}://localhost:{} to play in a browser", page, port);
+    game.observe(Arc::new(GameLog));
+    let table = Arc::new(Mutex::new(Table {
+        game,
+        engine,
+        seats: [None, None],
+        clients: Vec::new(),
+    }));
+    for (id, stream) in listener.incoming().enumerate() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let table = Arc::clone(&table);
+        let tls = tls.clone();
+        std::thread::spawn(move || {
+            if let Ok(socket) = WebSocket::accept(stream, tls.as_ref(), Some(PAGE)) {
+                serve(id, socket, &table);
+            }
+        });
+    }
+    Ok(())
+}
+
+// the table is taken even if another client panicked while holding it, so
+// one bad message doesn't end the game for everyone
+fn lock(table: &Mutex<Table>) -> MutexGuard<'_, Table> {
+    table.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn serve(id: usize, mut socket: WebSocket, table: &Mutex<Table>) {
+    let _span = tracing::info_span!("client", id).entered();
+    let sender = socket.sender();
+    {
+        let mut table = lock(table);
+        table.clients.push((id, sender));
+        let state = table.state(id);
+        table.send(id, &state);
+    }
+    while let Some(text) = socket.receive() {
+        {
+            let mut table = lock(table);
+            let Some(message) = json::parse(&text) else {
+                table.send(id, &error("messages are json objects"));
+                continue;
+            };
+            let field = |name: &str| message.get(name).and_then(|value| value.as_str());
+            match field("type") {
+                Some("join") => table.join(id, field("color")),
+                Some("move") => table.play(id, field("move").unwrap_or("")),
+                _ => table.send(id, &error("the type is join or move")),
+            }
+        }
+        engine_moves(table);
+    }
+    let mut table = lock(table);
+    table.clients.retain(|(client, _)| *client != id);
+    // a color someone left can be taken again, and the engine's once nobody
+    // plays it any more
+    for seat in table.seats.iter_mut() {
+        if *seat == Some(id) {
+            *seat = None;
+        }
+    }
+    if !table
+        .seats
+        .iter()
+        .any(|seat| seat.is_some_and(|s| s != ENGINE))
+    {
+        table.seats = [None, None];
+    }
+}
+
+impl Table {
+    fn join(&mut self, id: usize, color: Option<&str>) {
+        if self.seats.contains(&Some(id)) {
+            return self.send(id, &error("already playing"));
+        }
+        let color = match color {
+            Some("white") => Color::White,
+            Some("black") => Color::Black,
+            Some(other) => return self.send(id, &error(&format!("no color {}", other))),
+            None if self.seats[Color::White.idx()].is_none() => Color::White,
+            None => Color::Black,
+        };
+        if self.seats[color.idx()].is_some() {
+            return self.send(id, &error(&format!("{} is taken", color.name())));
+        }
+        self.seats[color.idx()] = Some(id);
+        if self.engine.is_some() && self.seats[color.other().idx()].is_none() {
+            self.seats[color.other().idx()] = Some(ENGINE);
+        }
+        let state = self.state(id);
+        self.send(id, &state);
+    }
+
+    fn play(&mut self, id: usize, uci: &str) {
+        let turn = self.game.board.turn;
+        if self.game.result.is_some() {
+            return self.send(id, &error("the game is over"));
+        }
+        if self.seats[turn.idx()] != Some(id) {
+            return self.send(id, &error("not your turn"));
+        }
+        match self.game.board.parse_uci(uci) {
+            Some(mve) if self.game.board.is_legal(&mve) => {
+                self.game.play(&mve);
+            }
+            _ => return self.send(id, &error(&format!("illegal move {}", uci))),
+        }
+        self.played();
+    }
+
+    // the position the engine has to answer and how deep, none unless it is
+    // the engine's move
+    fn engine_turn(&self) -> Option<(ChessBoard, u8)> {
+        let depth = self.engine?;
+        let turn = self.game.board.turn;
+        (self.game.result.is_none() && self.seats[turn.idx()] == Some(ENGINE))
+            .then(|| (self.game.board.clone(), depth))
+    }
+
+    // tells everyone the position after a move, and the result once the game
+    // is over
+    fn played(&mut self) {
+        let ids: Vec<usize> = self.clients.iter().map(|(client, _)| *client).collect();
+        for client in ids {
+            let state = self.state(client);
+            self.send(client, &state);
+        }
+        if let Some(result) = self.game.result {
+            let message = object(&[
+                ("type", string("result")),
+                ("result", string(result.score())),
+                ("reason", string(&result.describe())),
+            ]);
+            for (_, sender) in &self.clients {
+                let _ = websocket::send(sender, &message);
+            }
+        }
+    }
+
+    // the position as one client sees it
+    fn state(&self, id: usize) -> String {
+        let you = match self.seats.iter().position(|seat| *seat == Some(id)) {
+            Some(0) => "white",
+            Some(_) => "black",
+            None => "spectator",
+        };
+        let moves: Vec<String> = self
+            .game
+            .history
+            .iter()
+            .map(|played| string(&uci(&played.before, &played.mve)))
+            .collect();
+        let board = &self.game.board;
+        let legal: Vec<String> = board
+            .legal_moves()
+            .iter()
+            .map(|mve| string(&uci(board, mve)))
+            .collect();
+        object(&[
+            ("type", string("state")),
+            ("fen", string(&self.game.board.to_fen())),
+            ("turn", string(&self.game.board.turn.name().to_lowercase())),
+            ("moves", format!("[{}]", moves.join(","))),
+            ("legal", format!("[{}]", legal.join(","))),
+            ("you", string(you)),
+        ])
+    }
+
+    // a client that can't be reached is dropped when its reading thread ends
+    fn send(&mut self, id: usize, message: &str) {
+        if let Some((_, sender)) = self.clients.iter().find(|(client, _)| *client == id) {
+            let _ = websocket::send(sender, message);
+        }
+    }
+}
+
+// the engine answers for as long as it is to move, a turn can be more than
+// one move. it searches with the table unlocked so the clients are still
+// served, and the move is only played if nobody moved meanwhile
+fn engine_moves(table: &Mutex<Table>) {
+    loop {
+        let (plies, turn) = {
+            let table = lock(table);
+            (table.game.history.len(), table.engine_turn())
+        };
+        let Some((board, depth)) = turn else {
+            return;
+        };
+        let Some((mve, _)) = engine::search(&board, depth) else {
+            return;
+        };
+        let mut table = lock(table);
+        // another client's message may have set off the same search, which
+        // played first
+        if table.game.history.len() != plies || table.engine_turn().is_none() {
+            return;
+        }
+        table.game.play(&mve);
+        table.played();
+    }
+}
+
+// a move in uci, castling as the king's move outside chess960, like a board
+// is clicked
+fn uci(board: &ChessBoard, mve: &Move) -> String {
+    match board.variant {
+        VariantKind::Chess960 => mve.to_string(),
+        _ => board.standard_uci(mve),
+    }
+}
+
+fn error(message: &str) -> String {
+    object(&[("type", string("error")), ("message", string(message))])
+}
@@ -0,0 +1,171 @@
+use std::path::Path;
+
+use crate::analysis;
+use crate::database;
+use crate::engine;
+use crate::library::Entry;
+use crate::pgn;
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Color, PieceType};
+
+const PHASES: [&str; 3] = ["opening", "middlegame", "endgame"];
+
+// the first ten moves are the opening, unless the pieces came off early
+// enough for an endgame: no more than two rooks and a minor piece a side
+fn phase(board: &ChessBoard) -> usize {
+    let pieces: i32 = [Color::White, Color::Black]
+        .iter()
+        .flat_map(|&color| board.pieces(color))
+        .filter(|p| p.piece != PieceType::Pawn)
+        .map(|p| p.piece.value())
+        .sum();
+    match board.fullmove {
+        _ if pieces <= 26 => 2,
+        ..=10 => 0,
+        _ => 1,
+    }
+}
+
+// how a side's moves compared with the engine's
+#[derive(Debug, Clone, Copy, Default)]
+struct Tally {
+    moves: u32,
+    // the moves that were the engine's first choice, or among its top ones
+    first: u32,
+    top: u32,
+    // centipawns given away compared to the engine's first choice
+    loss: i64,
+}
+
+impl Tally {
+    fn add(&mut self, other: &Tally) {
+        self.moves += other.moves;
+        self.first += other.first;
+        self.top += other.top;
+        self.loss += other.loss;
+    }
+
+    // "opening     9 moves, first choice 67%, top 3 89%, average loss 12"
+    fn line(&self, name: &str, top: usize) -> String {
+        if self.moves == 0 {
+            return format!("  {:<12}no moves", name);
+        }
+        let percent = |n: u32| 100.0 * n as f64 / self.moves as f64;
+        format!(
+            "  {:<12}{} {}, first choice {:.0}%, top {} {:.0}%, average loss {:.0}",
+            name,
+            self.moves,
+            if self.moves == 1 { "move" } else { "moves" },
+            percent(self.first),
+            top,
+            percent(self.top),
+            self.loss as f64 / self.moves as f64
+        )
+    }
+}
+
+// the phases of one side and all of them together
+fn phase_lines(tallies: &[Tally; 3], top: usize) -> Vec<String> {
+    let mut total = Tally::default();
+    let mut lines = Vec::new();
+    for (name, tally) in PHASES.iter().zip(tallies) {
+        lines.push(tally.line(name, top));
+        total.add(tally);
+    }
+    lines.push(total.line("whole game", top));
+    lines
+}
+
+// compares every move of the player in the games of a pgn file, or the pgn
+// files of a directory, with the built-in engine's ranking of the moves
+// searched to depth: how often it was the engine's first choice, how often
+// among its top ones and how many centipawns it gave away, by the phase of
+// the game. without a player both sides of every game are compared. moves
+// without an alternative tell nothing and are left out
+pub fn run(
+    path: &Path,
+    player: Option<&str>,
+    depth: u8,
+    top: usize,
+) -> Result<Vec<String>, String> {
+    let top = top.max(1);
+    let wanted = player.map(str::to_lowercase);
+    let mut lines = Vec::new();
+    let mut games = 0;
+    let mut overall = [Tally::default(); 3];
+    for file in database::files(path)? {
+        let Ok(text) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        for (i, text) in pgn::games(&text).into_iter().enumerate() {
+            let entry = Entry {
+                path: file.clone(),
+                tags: pgn::tags(&text),
+                moves: pgn::movetext(&text).len().div_ceil(2),
+            };
+            let sides: Vec<Color> = [Color::White, Color::Black]
+                .into_iter()
+                .filter(|color| {
+                    wanted.as_ref().is_none_or(|wanted| {
+                        entry.tag(color.name()).to_lowercase().contains(wanted)
+                    })
+                })
+                .collect();
+            if sides.is_empty() {
+                continue;
+            }
+            let name = format!(
+                "{} #{}",
+                file.file_name().unwrap_or_default().to_string_lossy(),
+                i + 1
+            );
+            let game = match pgn::read(&text, VariantKind::Standard) {
+                Ok(game) => game,
+                Err(e) => {
+                    lines.push(format!("{}: left out, {}", name, e));
+                    continue;
+                }
+            };
+            let mut tallies = [[Tally::default(); 3]; 2];
+            for played in game.history.iter() {
+                let mover = played.before.turn;
+                if !sides.contains(&mover) {
+                    continue;
+                }
+                let ranked = engine::rank(&played.before, depth);
+                let Some(at) = ranked.iter().position(|&(mve, _)| mve == played.mve) else {
+                    continue;
+                };
+                if ranked.len() < 2 {
+                    continue;
+                }
+                // moves as good as the engine's choices count as them
+                let score = ranked[at].1;
+                let tally = &mut tallies[mover.idx()][phase(&played.before)];
+                tally.moves += 1;
+                tally.first += u32::from(score >= ranked[0].1);
+                tally.top += u32::from(score >= ranked[top.min(ranked.len()) - 1].1);
+                tally.loss +=
+                    i64::from((analysis::capped(ranked[0].1) - analysis::capped(score)).max(0));
+            }
+            games += 1;
+            lines.push(format!("{}: {}", name, entry.describe()));
+            for color in &sides {
+                lines.push(format!("{}, {}:", color.name(), entry.tag(color.name())));
+                lines.extend(phase_lines(&tallies[color.idx()], top));
+                for (all, tally) in overall.iter_mut().zip(&tallies[color.idx()]) {
+                    all.add(tally);
+                }
+            }
+        }
+    }
+    match (player, games) {
+        (_, 0) => lines.push(String::from("no games to compare")),
+        (Some(player), 2..) => {
+            lines.push(format!("{} over {} games:", player, games));
+            lines.extend(phase_lines(&overall, top));
+        }
+        _ => {}
+    }
+    Ok(lines)
+}
@@ -0,0 +1,140 @@
+use crate::display::DisplayOptions;
+use crate::{ChessBoard, Color, Move};
+
+// the search for a forced mate, counting the positions it went through
+struct Solver {
+    attacker: Color,
+    nodes: u64,
+}
+
+impl Solver {
+    // whether the attacker wins from board within turns of its own, the
+    // defender moving however it likes. it wins once the side to move has no
+    // moves and the variant gives the game to the attacker, which is mate in
+    // standard chess
+    fn wins(&mut self, board: &ChessBoard, turns: u32) -> bool {
+        self.nodes += 1;
+        let moves = board.legal_moves();
+        if moves.is_empty() {
+            return board.rules().no_moves(board).winner() == Some(self.attacker);
+        }
+        if turns == 0 {
+            return false;
+        }
+        if board.turn == self.attacker {
+            ordered(board, moves)
+                .iter()
+                .any(|mve| self.wins_after(board, mve, turns))
+        } else {
+            moves.iter().all(|mve| self.wins_after(board, mve, turns))
+        }
+    }
+
+    // the same after mve is played, a turn of the attacker used up once the
+    // move hands the turn over
+    fn wins_after(&mut self, board: &ChessBoard, mve: &Move, turns: u32) -> bool {
+        let mut after = board.clone();
+        after.make_move(mve);
+        let left = match board.turn == self.attacker && after.turn != self.attacker {
+            true => turns - 1,
+            false => turns,
+        };
+        self.wins(&after, left)
+    }
+
+    // the defender's moves after the attacker's first that keep it from
+    // winning in time
+    fn refutations(&mut self, board: &ChessBoard, turns: u32) -> Vec<Move> {
+        board
+            .legal_moves()
+            .into_iter()
+            .filter(|mve| !self.wins_after(board, mve, turns))
+            .collect()
+    }
+}
+
+// checks first, then captures, since mates mostly come from those
+fn ordered(board: &ChessBoard, moves: Vec<Move>) -> Vec<Move> {
+    let mut moves = moves;
+    moves.sort_by_key(|mve| {
+        let mut after = board.clone();
+        after.make_move(mve);
+        let check = after.in_check(after.turn);
+        (!check, !board.is_capture(mve))
+    });
+    moves
+}
+
+// every first move that forces mate in at most n moves of the side to move,
+// each with the defences and the answer that keeps the mate on, and the tries
+// that only a single defence refutes, the way compositions are checked
+pub fn mate(board: &ChessBoard, n: u32, options: &DisplayOptions) -> Vec<String> {
+    let mut solver = Solver {
+        attacker: board.turn,
+        nodes: 0,
+    };
+    let text = |board: &ChessBoard, mve: &Move| options.move_text(&board.san(mve), mve);
+    let mut keys = Vec::new();
+    let mut tries = Vec::new();
+    for mve in board.legal_moves() {
+        let mut after = board.clone();
+        after.make_move(&mve);
+        // a variant with more moves a turn goes on with the attacker's next one
+        let left = match after.turn == board.turn {
+            true => n,
+            false => n - 1,
+        };
+        if solver.wins(&after, left) {
+            keys.push((mve, after, left));
+            continue;
+        }
+        if after.turn != board.turn {
+            if let [refutation] = solver.refutations(&after, left)[..] {
+                tries.push(format!(
+                    "try {}, refuted only by {}",
+                    text(board, &mve),
+                    text(&after, &refutation)
+                ));
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    if keys.is_empty() {
+        lines.push(format!("{} has no forced mate in {}", board.turn.name(), n));
+    }
+    for (mve, after, left) in &keys {
+        let defences = after.legal_moves();
+        if defences.is_empty() || after.turn == board.turn {
+            lines.push(format!("key {}", text(board, mve)));
+            continue;
+        }
+        lines.push(format!(
+            "key {}, {} defences",
+            text(board, mve),
+            defences.len()
+        ));
+        for defence in defences {
+            let mut reply = after.clone();
+            reply.make_move(&defence);
+            let answer = reply
+                .legal_moves()
+                .into_iter()
+                .find(|answer| solver.wins_after(&reply, answer, *left));
+            lines.push(match answer {
+                Some(answer) => format!("  {} {}", text(after, &defence), text(&reply, &answer)),
+                // the defence ends the game by itself, like a stalemate
+                // that loses in some variants
+                None => format!("  {}", text(after, &defence)),
+            });
+        }
+    }
+    lines.extend(tries);
+    lines.push(format!(
+        "{} key{}, {} positions searched",
+        keys.len(),
+        if keys.len() == 1 { "" } else { "s" },
+        solver.nodes
+    ));
+    lines
+}
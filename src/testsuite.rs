@@ -0,0 +1,157 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::engine;
+use crate::puzzle;
+use crate::variant::VariantKind;
+use crate::{ChessBoard, Move};
+
+// the deepest the engine goes however much time is left
+const MAX_DEPTH: u8 = 64;
+
+// one position of a suite and what makes an answer right
+struct Test {
+    id: String,
+    board: ChessBoard,
+    // bm: one of these has to be played, when there are any
+    best: Vec<Move>,
+    // am: none of these may be
+    avoid: Vec<Move>,
+    // c0 of the strategic test suite, like "f5=10, Be5+=2": the points each
+    // move gets, the best is worth 10
+    points: Vec<(Move, u32)>,
+}
+
+// the moves of an operand list like "Qg6 Rxf7+", any that don't parse on the
+// board make the line unusable
+fn moves(board: &ChessBoard, operands: &str) -> Result<Vec<Move>, String> {
+    operands
+        .split_whitespace()
+        .map(|text| {
+            board
+                .parse_san(text)
+                .or_else(|| board.parse_uci(text).filter(|m| board.is_legal(m)))
+                .ok_or_else(|| format!("illegal move {}", text))
+        })
+        .collect()
+}
+
+impl Test {
+    fn parse(line: &str, n: usize, variant: VariantKind) -> Result<Test, String> {
+        let (board, operations) = puzzle::epd(line, variant)?;
+        let mut test = Test {
+            id: format!("#{}", n),
+            board,
+            best: Vec::new(),
+            avoid: Vec::new(),
+            points: Vec::new(),
+        };
+        for (opcode, operands) in operations {
+            let error = |e: String| format!("{} in {}", e, line);
+            match opcode {
+                "id" => test.id = operands.trim_matches('"').to_string(),
+                "bm" => test.best = moves(&test.board, operands).map_err(error)?,
+                "am" => test.avoid = moves(&test.board, operands).map_err(error)?,
+                "c0" => {
+                    // c0 is a free comment elsewhere, it only counts when every
+                    // part of it is a move and its points
+                    let points: Option<Vec<(Move, u32)>> = operands
+                        .trim_matches('"')
+                        .split(',')
+                        .map(|part| {
+                            let (text, points) = part.trim().split_once('=')?;
+                            let mve = moves(&test.board, text).ok()?;
+                            Some((*mve.first()?, points.parse().ok()?))
+                        })
+                        .collect();
+                    test.points = points.unwrap_or_default();
+                }
+                _ => {}
+            }
+        }
+        if test.best.is_empty() && test.avoid.is_empty() {
+            return Err(format!("no bm or am operation in {}", line));
+        }
+        Ok(test)
+    }
+
+    fn passed(&self, mve: Move) -> bool {
+        (self.best.is_empty() || self.best.contains(&mve)) && !self.avoid.contains(&mve)
+    }
+}
+
+// runs the engine over every position of an epd suite for the given time
+// each, printing a line per position as it goes, and then how many it got
+// right. suites with points per move, like the strategic test suite, get
+// their points summed up too
+pub fn run(path: &Path, variant: VariantKind, time: Duration) -> Result<(), String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let tests: Vec<Test> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .enumerate()
+        .map(|(i, line)| Test::parse(line, i + 1, variant))
+        .collect::<Result<_, _>>()?;
+    if tests.is_empty() {
+        return Err(format!("no positions in {}", path.display()));
+    }
+
+    let mut solved = 0;
+    let mut points = 0;
+    let mut most = 0;
+    for test in &tests {
+        let board = &test.board;
+        let Some((mve, score, depth)) = engine::think(board, time, MAX_DEPTH) else {
+            println!("{:<20} no legal moves", test.id);
+            continue;
+        };
+        let passed = test.passed(mve);
+        solved += usize::from(passed);
+        let sans = |moves: &[Move]| -> String {
+            let sans: Vec<String> = moves.iter().map(|m| board.san(m)).collect();
+            sans.join(" ")
+        };
+        let mut expected = Vec::new();
+        if !test.best.is_empty() {
+            expected.push(format!("bm {}", sans(&test.best)));
+        }
+        if !test.avoid.is_empty() {
+            expected.push(format!("am {}", sans(&test.avoid)));
+        }
+        let mut line = format!(
+            "{:<20} {:<8} {:<6} {:>7} depth {:<3} {}",
+            test.id,
+            board.san(&mve),
+            if passed { "ok" } else { "failed" },
+            engine::format_score(score),
+            depth,
+            expected.join(", ")
+        );
+        if !test.points.is_empty() {
+            let got = test
+                .points
+                .iter()
+                .find(|&&(m, _)| m == mve)
+                .map_or(0, |&(_, p)| p);
+            let best = test.points.iter().map(|&(_, p)| p).max().unwrap_or(0);
+            points += got;
+            most += best;
+            line.push_str(&format!(", {} of {} points", got, best));
+        }
+        println!("{}", line);
+    }
+    println!();
+    println!(
+        "solved {} of {} ({:.1}%) at {:.1}s a position",
+        solved,
+        tests.len(),
+        solved as f64 * 100.0 / tests.len() as f64,
+        time.as_secs_f64()
+    );
+    if most > 0 {
+        println!("{} of {} points", points, most);
+    }
+    Ok(())
+}
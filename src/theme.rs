@@ -0,0 +1,90 @@
+use serde::Deserialize;
+
+use crate::display::Mark;
+use crate::Color;
+
+// colors of the board in the terminal, as xterm 256 color numbers. fields
+// left out of a theme in the config file keep the default colors
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub light: u8,
+    pub dark: u8,
+    pub selected: u8,
+    pub target: u8,
+    pub last_move: u8,
+    pub threat: u8,
+    pub hanging: u8,
+    pub white_piece: u8,
+    pub black_piece: u8,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            light: 180,
+            dark: 137,
+            selected: 185,
+            target: 107,
+            last_move: 143,
+            threat: 174,
+            hanging: 160,
+            white_piece: 15,
+            black_piece: 0,
+        }
+    }
+}
+
+pub fn built_in() -> Vec<(String, Theme)> {
+    let brown = Theme::default();
+    let green = Theme {
+        light: 230,
+        dark: 101,
+        last_move: 186,
+        ..brown
+    };
+    let blue = Theme {
+        light: 153,
+        dark: 67,
+        selected: 117,
+        target: 73,
+        last_move: 110,
+        ..brown
+    };
+    let gray = Theme {
+        light: 250,
+        dark: 244,
+        selected: 254,
+        target: 108,
+        last_move: 187,
+        ..brown
+    };
+    vec![
+        (String::from("brown"), brown),
+        (String::from("green"), green),
+        (String::from("blue"), blue),
+        (String::from("gray"), gray),
+    ]
+}
+
+impl Theme {
+    // background of a square
+    pub fn square(&self, mark: Option<Mark>, light: bool) -> u8 {
+        match mark {
+            Some(Mark::Selected) => self.selected,
+            Some(Mark::Target) => self.target,
+            Some(Mark::Hanging) => self.hanging,
+            Some(Mark::Threat) => self.threat,
+            Some(Mark::LastMove) => self.last_move,
+            None if light => self.light,
+            None => self.dark,
+        }
+    }
+
+    pub fn piece(&self, color: Color) -> u8 {
+        match color {
+            Color::White => self.white_piece,
+            Color::Black => self.black_piece,
+        }
+    }
+}
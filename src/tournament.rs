@@ -0,0 +1,570 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::endgame::{Oracle, Wdl};
+use crate::engine;
+use crate::game::{Adjudication, Game, GameResult};
+use crate::library;
+use crate::logging::GameLog;
+use crate::parallel;
+use crate::pgn;
+use crate::save;
+use crate::{ChessBoard, Color};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    // everyone plays everyone
+    RoundRobin,
+    // the first player plays everyone else
+    Gauntlet,
+    // every round pairs players with as many points as each other, for
+    // events with too many players for everyone to meet
+    Swiss,
+}
+
+// who plays whom in a round of a round robin or a gauntlet, as indices into
+// the players. every pairing is played once with each color
+fn pairings(players: usize, format: Format) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for a in 0..players {
+        for b in a + 1..players {
+            if format == Format::Gauntlet && a != 0 {
+                continue;
+            }
+            pairs.push((a, b));
+            pairs.push((b, a));
+        }
+    }
+    pairs
+}
+
+// the standings as the games come in, indexed like the players
+struct Table {
+    // points are counted in halves so draws stay exact
+    halves: Vec<u32>,
+    played: Vec<u32>,
+    // who each player met and with which color, in the order of the games
+    opponents: Vec<Vec<usize>>,
+    colors: Vec<Vec<Color>>,
+    byes: Vec<bool>,
+}
+
+impl Table {
+    fn new(players: usize) -> Self {
+        Table {
+            halves: vec![0; players],
+            played: vec![0; players],
+            opponents: vec![Vec::new(); players],
+            colors: vec![Vec::new(); players],
+            byes: vec![false; players],
+        }
+    }
+
+    fn record(&mut self, white: usize, black: usize, result: &str) {
+        match result {
+            "1-0" => self.halves[white] += 2,
+            "0-1" => self.halves[black] += 2,
+            "1/2-1/2" => {
+                self.halves[white] += 1;
+                self.halves[black] += 1;
+            }
+            _ => {}
+        }
+        for (player, opponent, color) in
+            [(white, black, Color::White), (black, white, Color::Black)]
+        {
+            self.played[player] += 1;
+            self.opponents[player].push(opponent);
+            self.colors[player].push(color);
+        }
+    }
+
+    // a swiss round without an opponent counts as a win
+    fn bye(&mut self, player: usize) {
+        self.halves[player] += 2;
+        self.played[player] += 1;
+        self.byes[player] = true;
+    }
+
+    // the points of the opponents met, to tell apart swiss players with as
+    // many points
+    fn buchholz(&self, player: usize) -> u32 {
+        self.opponents[player].iter().map(|&o| self.halves[o]).sum()
+    }
+
+    // how many more games the player had white than black
+    fn balance(&self, player: usize) -> i32 {
+        self.colors[player]
+            .iter()
+            .map(|&color| if color == Color::White { 1 } else { -1 })
+            .sum()
+    }
+
+    // the players from first place to last by points, then by buchholz when
+    // asked for, then in the order they were given
+    fn ranking(&self, buchholz: bool) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.halves.len()).collect();
+        order.sort_by_key(|&p| {
+            let tiebreak = if buchholz { self.buchholz(p) } else { 0 };
+            std::cmp::Reverse((self.halves[p], tiebreak))
+        });
+        order
+    }
+}
+
+// the pairings of the next swiss round, and the player sitting it out when
+// there are an odd number of them: the lowest placed one who hasn't yet
+fn swiss(table: &Table) -> (Vec<(usize, usize)>, Option<usize>) {
+    let mut ranking = table.ranking(false);
+    let bye = (ranking.len() % 2 == 1).then(|| {
+        let at = ranking
+            .iter()
+            .rposition(|&p| !table.byes[p])
+            .unwrap_or(ranking.len() - 1);
+        ranking.remove(at)
+    });
+    // players only meet again once there is no other way to pair them all,
+    // or none was found in the tries allowed. with repeats the first try
+    // always pairs everyone
+    let (mut tries, mut again) = (MATCH_TRIES, MATCH_TRIES);
+    let pairs = match_up(table, &ranking, false, &mut tries)
+        .or_else(|| match_up(table, &ranking, true, &mut again))
+        .unwrap_or_default();
+    let pairs = pairs
+        .into_iter()
+        .map(|(a, b)| colors(table, a, b))
+        .collect();
+    (pairs, bye)
+}
+
+// how many pairings match_up tries before giving up, going back on them can
+// otherwise take exponentially long when few pairings are left
+const MATCH_TRIES: u32 = 100_000;
+
+// pairs the players, given from first place to last, within their score
+// group where they can with its top half against its bottom half, and going
+// back on a pairing when the rest can't be paired after it. none when that
+// isn't possible within the tries
+fn match_up(
+    table: &Table,
+    players: &[usize],
+    repeat: bool,
+    tries: &mut u32,
+) -> Option<Vec<(usize, usize)>> {
+    let Some((&first, rest)) = players.split_first() else {
+        return Some(Vec::new());
+    };
+    let group = rest
+        .iter()
+        .take_while(|&&p| table.halves[p] == table.halves[first])
+        .count();
+    // 1 meets 3 and 2 meets 4 in a group of four
+    let middle = group.saturating_sub(1) / 2;
+    let candidates = (middle..group)
+        .chain((0..middle).rev())
+        .chain(group..rest.len());
+    for i in candidates {
+        let opponent = rest[i];
+        if !repeat && table.opponents[first].contains(&opponent) {
+            continue;
+        }
+        *tries = tries.checked_sub(1)?;
+        let mut left = rest.to_vec();
+        left.remove(i);
+        if let Some(mut pairs) = match_up(table, &left, repeat, tries) {
+            pairs.insert(0, (first, opponent));
+            return Some(pairs);
+        }
+    }
+    None
+}
+
+// white and black of a swiss pairing: white goes to whoever had it less
+// often, then to whoever had black last, then to the higher placed a
+fn colors(table: &Table, a: usize, b: usize) -> (usize, usize) {
+    let owed = |p: usize| {
+        (
+            table.balance(p),
+            table.colors[p].last() == Some(&Color::White),
+        )
+    };
+    match owed(b) < owed(a) {
+        true => (b, a),
+        false => (a, b),
+    }
+}
+
+// who plays in the event and how often they meet
+#[derive(Debug, Clone, Args)]
+pub struct Schedule {
+    /// Search depth of one player, given once per player
+    #[arg(long = "engine", required = true)]
+    pub engines: Vec<u8>,
+
+    /// Who plays whom
+    #[arg(long, value_enum, default_value_t = Format::RoundRobin)]
+    pub format: Format,
+
+    /// How many times every pairing is played with each color, once unless
+    /// given. In a swiss how many rounds are played, by default enough for
+    /// a single winner: log2 of the players rounded up
+    #[arg(long)]
+    pub rounds: Option<u32>,
+
+    /// How many games are played at once, one per core if not given
+    #[arg(long)]
+    pub threads: Option<usize>,
+}
+
+// when a game is decided before it is over. scores are in centipawns from
+// the point of view of the engine that searched them
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Args)]
+pub struct Rules {
+    /// Let an engine resign once its score stays at or below minus this many
+    /// centipawns
+    #[arg(long)]
+    pub resign_score: Option<i32>,
+
+    /// How many of its own moves in a row the score has to stay that low
+    #[arg(long, default_value_t = 3)]
+    pub resign_moves: u32,
+
+    /// Call a draw once both engines' scores stay within this many centipawns of even
+    #[arg(long)]
+    pub draw_score: Option<i32>,
+
+    /// How many moves of both sides in a row the score has to stay that close
+    #[arg(long, default_value_t = 10)]
+    pub draw_moves: u32,
+}
+
+// the scores seen so far in the game, against the rules
+struct Adjudicator<'a> {
+    rules: Rules,
+    // the endgame tables, that know the result of the endings they cover
+    oracle: Option<&'a Oracle>,
+    // own moves in a row with a hopeless score, indexed by Color::idx
+    losing: [u32; 2],
+    // plies in a row with a score near even
+    even: u32,
+}
+
+impl Adjudicator<'_> {
+    // the result the game is decided with after the side to move found score
+    fn update(&mut self, board: &ChessBoard, score: i32) -> Option<GameResult> {
+        // nobody can mate
+        if !board.has_mating_material(Color::White) && !board.has_mating_material(Color::Black) {
+            return Some(GameResult::Adjudicated(Adjudication::Material));
+        }
+        let turn = board.turn;
+        if let Some(wdl) = self.oracle.and_then(|oracle| oracle.probe(board)) {
+            return Some(GameResult::Adjudicated(match wdl {
+                Wdl::Win(_) => Adjudication::Win(turn),
+                Wdl::Draw => Adjudication::Draw,
+                Wdl::Loss(_) => Adjudication::Win(turn.other()),
+            }));
+        }
+        let rules = self.rules;
+        match rules.resign_score {
+            Some(limit) if score <= -limit => self.losing[turn.idx()] += 1,
+            _ => self.losing[turn.idx()] = 0,
+        }
+        match rules.draw_score {
+            Some(limit) if score.abs() <= limit => self.even += 1,
+            _ => self.even = 0,
+        }
+        if rules.resign_score.is_some() && self.losing[turn.idx()] >= rules.resign_moves.max(1) {
+            return Some(GameResult::Adjudicated(Adjudication::Win(turn.other())));
+        }
+        // both sides count, so n moves are 2n plies
+        if rules.draw_score.is_some() && self.even >= 2 * rules.draw_moves.max(1) {
+            return Some(GameResult::Adjudicated(Adjudication::Draw));
+        }
+        None
+    }
+}
+
+// plays the game out between two engines, with the score each move was
+// searched to. a claimable draw is claimed right away, so the fifty-move rule
+// ends every game. with the oracle an ending it covers is decided at once
+pub fn play(
+    start: &ChessBoard,
+    depths: [u8; 2],
+    rules: Rules,
+    oracle: Option<&Oracle>,
+) -> (Game, Vec<i32>) {
+    let mut game = Game::from_board(start.clone());
+    game.observe(Arc::new(GameLog));
+    let mut adjudicator = Adjudicator {
+        rules,
+        oracle,
+        losing: [0; 2],
+        even: 0,
+    };
+    let mut scores = Vec::new();
+    while game.result.is_none() {
+        if let Some(claim) = game.draw_claim() {
+            game.end(GameResult::DrawClaimed(claim));
+            break;
+        }
+        let depth = depths[game.board.turn.idx()];
+        let Some((mve, score)) = engine::search(&game.board, depth) else {
+            break;
+        };
+        if let Some(result) = adjudicator.update(&game.board, score) {
+            game.end(result);
+            break;
+        }
+        game.play(&mve);
+        scores.push(score);
+    }
+    (game, scores)
+}
+
+// what decides the games of an event and their order, which a resumed one
+// has to be started with again
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Event {
+    start: String,
+    engines: Vec<u8>,
+    format: Format,
+    rounds: u32,
+    rules: Rules,
+}
+
+// the event and the results of the games played so far, in the order of the
+// pairings, written after every game so an interrupted event can go on
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Progress {
+    event: Event,
+    results: Vec<String>,
+}
+
+// next to the pgn: tournament.progress.toml for tournament.pgn
+fn progress_path(output: &Path) -> PathBuf {
+    output.with_extension("progress.toml")
+}
+
+// the results and the games of the interrupted event, checked against the
+// one being started
+fn resume(event: &Event, output: &Path) -> Result<(Vec<String>, String), String> {
+    let path = progress_path(output);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let progress: Progress =
+        toml::from_str(&text).map_err(|e| format!("invalid {}: {}", path.display(), e))?;
+    if progress.event != *event {
+        return Err(format!(
+            "{} was played with other engines, rounds, rules or start position",
+            path.display()
+        ));
+    }
+    let text = std::fs::read_to_string(output)
+        .map_err(|e| format!("could not read {}: {}", output.display(), e))?;
+    let games = pgn::games(&text);
+    let played = progress.results.len();
+    if games.len() < played {
+        return Err(format!(
+            "{} has {} games, {} were played",
+            output.display(),
+            games.len(),
+            played
+        ));
+    }
+    // a game written just before the interruption but not yet counted is
+    // played again
+    let text = games[..played]
+        .iter()
+        .map(|game| format!("{}\n\n", game.trim_end()))
+        .collect();
+    Ok((progress.results, text))
+}
+
+// plays all games of the event between engines of the given depths, printing
+// each result and the standings, and writes the games to output as pgn.
+// after every game the results so far are kept next to it, for resuming the
+// event from the next game once it was interrupted
+pub fn run(
+    start: &ChessBoard,
+    schedule: &Schedule,
+    rules: Rules,
+    output: &Path,
+    log: Option<&Path>,
+    resumed: bool,
+) -> Result<(), String> {
+    let Schedule {
+        engines: depths,
+        format,
+        rounds,
+        threads,
+    } = schedule.clone();
+    if depths.len() < 2 {
+        return Err(String::from("a tournament needs at least two engines"));
+    }
+    let players = depths.len();
+    let swiss_rounds = usize::BITS - (players - 1).leading_zeros();
+    let rounds = match (rounds, format) {
+        (Some(rounds), _) => rounds,
+        (None, Format::Swiss) => swiss_rounds,
+        (None, _) => 1,
+    };
+    let games = rounds as usize
+        * match format {
+            Format::Swiss => players / 2,
+            _ => pairings(players, format).len(),
+        };
+    // engines of the same depth are told apart by the order they were given in
+    let names: Vec<String> = depths
+        .iter()
+        .enumerate()
+        .map(
+            |(i, &depth)| match depths.iter().filter(|&&other| other == depth).count() {
+                1 => format!("depth {}", depth),
+                _ => {
+                    let nth = depths[..i].iter().filter(|&&other| other == depth).count() + 1;
+                    format!("depth {} #{}", depth, nth)
+                }
+            },
+        )
+        .collect();
+    let mut progress = Progress {
+        event: Event {
+            start: start.to_fen(),
+            engines: depths.clone(),
+            format,
+            rounds,
+            rules,
+        },
+        results: Vec::new(),
+    };
+    let mut text = String::new();
+    if resumed {
+        (progress.results, text) = resume(&progress.event, output)?;
+        println!(
+            "resuming after game {} of {}",
+            progress.results.len(),
+            games
+        );
+    }
+    let path = progress_path(output);
+    let oracle = Arc::new(Oracle::build());
+    let mut table = Table::new(players);
+    // the number of the next game, counting from 0 over all rounds
+    let mut i = 0;
+    for round in 1..=rounds {
+        // the games of the round log under it on whichever thread plays them
+        let _span = tracing::info_span!("round", round).entered();
+        // swiss rounds are paired by the results so far, the same again when
+        // resuming
+        let (pairs, bye) = match format {
+            Format::Swiss => swiss(&table),
+            _ => (pairings(players, format), None),
+        };
+        let resumed = i + pairs.len() <= progress.results.len();
+        if format == Format::Swiss && !resumed {
+            println!("round {} pairings:", round);
+            for &(white, black) in &pairs {
+                println!("  {} - {}", names[white], names[black]);
+            }
+            if let Some(bye) = bye {
+                println!("  {} has a bye", names[bye]);
+            }
+        }
+        if let Some(bye) = bye {
+            table.bye(bye);
+        }
+        // the games of the round played before an interruption count as
+        // they went, the others are played side by side and written in order
+        let first = i;
+        i += pairs.len();
+        let before = progress.results.len().clamp(first, i) - first;
+        for (&(white, black), result) in pairs
+            .iter()
+            .zip(progress.results.get(first..).unwrap_or(&[]))
+        {
+            table.record(white, black, result);
+        }
+        let jobs: Vec<(usize, usize, usize)> = pairs
+            .iter()
+            .enumerate()
+            .skip(before)
+            .map(|(k, &(white, black))| (first + k + 1, white, black))
+            .collect();
+        let board = start.clone();
+        let engines = depths.clone();
+        let oracle = oracle.clone();
+        let players = names.clone();
+        let work = move |(number, white, black): (usize, usize, usize)| {
+            let _span = tracing::info_span!("game", number).entered();
+            tracing::info!("{} against {}", players[white], players[black]);
+            let (game, _) = play(
+                &board,
+                [engines[white], engines[black]],
+                rules,
+                Some(&oracle),
+            );
+            (white, black, game)
+        };
+        parallel::ordered(threads, jobs, work, |(white, black, game)| {
+            let result = game.result.map_or("*", |r| r.score());
+            println!(
+                "round {}: {} - {} {} ({})",
+                round,
+                names[white],
+                names[black],
+                result,
+                game.result
+                    .map_or(String::from("unfinished"), |r| r.describe())
+            );
+            let tags = library::tags(
+                &game,
+                "chess tournament",
+                round.to_string(),
+                [names[white].clone(), names[black].clone()],
+            );
+            text.push_str(&pgn::write(&game, &tags));
+            if let Some(log) = log {
+                library::append(log, &game, &tags)?;
+            }
+            // the games go first, a game written but not counted is only
+            // played again
+            save::write(output, &text)?;
+            table.record(white, black, result);
+            progress.results.push(result.to_string());
+            save::write(
+                &path,
+                &toml::to_string(&progress).map_err(|e| e.to_string())?,
+            )
+        })?;
+    }
+    // once every game is played there is nothing left to resume
+    let _ = std::fs::remove_file(&path);
+
+    let points = |halves: u32| match halves % 2 {
+        0 => format!("{}", halves / 2),
+        _ => format!("{}.5", halves / 2),
+    };
+    let swiss = format == Format::Swiss;
+    println!();
+    println!("standings:");
+    for (place, &p) in table.ranking(swiss).iter().enumerate() {
+        let mut line = format!(
+            "{}. {}  {}/{}",
+            place + 1,
+            names[p],
+            points(table.halves[p]),
+            table.played[p]
+        );
+        if swiss {
+            line.push_str(&format!(", buchholz {}", points(table.buchholz(p))));
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
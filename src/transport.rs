@@ -0,0 +1,365 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use clap::Args;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{
+    ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, ServerConfig,
+    ServerConnection, SignatureScheme, StreamOwned,
+};
+
+use crate::digest::hex;
+
+// what the network modes talk over: a tcp socket, with or without tls
+trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+// a connection one thread reads from while others write to it. tls can't be
+// split into a reading and a writing half like a socket, so reading waits in
+// short turns and lets writers in between
+pub struct Connection {
+    stream: Arc<Mutex<Box<dyn Stream>>>,
+    // the socket under it, for closing it from another thread
+    socket: Arc<TcpStream>,
+    // read but not yet taken
+    buffer: Vec<u8>,
+}
+
+// the writing side of a connection, for any thread
+#[derive(Clone)]
+pub struct Sender {
+    stream: Arc<Mutex<Box<dyn Stream>>>,
+    socket: Arc<TcpStream>,
+}
+
+// how long reading holds on to the connection at a time
+const TURN: Duration = Duration::from_millis(50);
+
+// how long a peer gets to finish the tls handshake
+const HANDSHAKE: Duration = Duration::from_secs(10);
+
+impl Connection {
+    fn new(socket: &TcpStream, stream: Box<dyn Stream>) -> Result<Self, String> {
+        socket
+            .set_read_timeout(Some(TURN))
+            .map_err(|e| e.to_string())?;
+        Ok(Connection {
+            stream: Arc::new(Mutex::new(stream)),
+            socket: Arc::new(socket.try_clone().map_err(|e| e.to_string())?),
+            buffer: Vec::new(),
+        })
+    }
+
+    // the server side of a connection that was just accepted
+    pub fn accept(socket: TcpStream, tls: Option<&Arc<ServerConfig>>) -> Result<Self, String> {
+        tracing::info!("connection from {}", peer(&socket));
+        let Some(config) = tls else {
+            let plain = socket.try_clone().map_err(|e| e.to_string())?;
+            return Connection::new(&socket, Box::new(plain));
+        };
+        let mut connection =
+            ServerConnection::new(Arc::clone(config)).map_err(|e| e.to_string())?;
+        let mut handshaking = socket.try_clone().map_err(|e| e.to_string())?;
+        handshake(&mut handshaking, |socket| connection.complete_io(socket))?;
+        tracing::debug!("tls handshake done with {}", peer(&socket));
+        Connection::new(&socket, Box::new(StreamOwned::new(connection, handshaking)))
+    }
+
+    // connects to addr, like host:port
+    pub fn connect(addr: &str, tls: Option<&Arc<ClientConfig>>) -> Result<Self, String> {
+        let socket = TcpStream::connect(addr).map_err(|e| {
+            tracing::warn!("could not connect to {}: {}", addr, e);
+            format!("could not connect to {}: {}", addr, e)
+        })?;
+        tracing::info!(
+            "connected to {}{}",
+            addr,
+            if tls.is_some() { " with tls" } else { "" }
+        );
+        let Some(config) = tls else {
+            let plain = socket.try_clone().map_err(|e| e.to_string())?;
+            return Connection::new(&socket, Box::new(plain));
+        };
+        let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let name = ServerName::try_from(host.to_string())
+            .map_err(|_| format!("{} is not a host name", host))?;
+        let mut connection =
+            ClientConnection::new(Arc::clone(config), name).map_err(|e| e.to_string())?;
+        let mut handshaking = socket.try_clone().map_err(|e| e.to_string())?;
+        handshake(&mut handshaking, |socket| connection.complete_io(socket))
+            .map_err(|e| format!("could not connect to {}: {}", addr, e))?;
+        Connection::new(&socket, Box::new(StreamOwned::new(connection, handshaking)))
+    }
+
+    pub fn sender(&self) -> Sender {
+        Sender {
+            stream: Arc::clone(&self.stream),
+            socket: Arc::clone(&self.socket),
+        }
+    }
+
+    // reads some more, none once the connection is closed or broken
+    fn fill(&mut self) -> Option<()> {
+        let mut chunk = [0; 4096];
+        loop {
+            let read = self.stream.lock().unwrap().read(&mut chunk);
+            match read {
+                Ok(0) => return None,
+                Ok(n) => {
+                    self.buffer.extend_from_slice(&chunk[..n]);
+                    return Some(());
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    // a writer waiting for the lock gets its turn
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return None,
+            }
+        }
+    }
+
+    // the next line without its line break, none once the connection is gone
+    // or the peer sends a line longer than max
+    pub fn read_line(&mut self, max: usize) -> Option<String> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line).trim_end().to_string();
+                tracing::trace!("received {}", line);
+                return Some(line);
+            }
+            if self.buffer.len() > max {
+                tracing::warn!(
+                    "{} sent a line longer than {} bytes",
+                    peer(&self.socket),
+                    max
+                );
+                return None;
+            }
+            if self.fill().is_none() {
+                tracing::info!("{} closed the connection", peer(&self.socket));
+                return None;
+            }
+        }
+    }
+
+    pub fn read_exact(&mut self, len: usize) -> Option<Vec<u8>> {
+        while self.buffer.len() < len {
+            self.fill()?;
+        }
+        Some(self.buffer.drain(..len).collect())
+    }
+}
+
+impl Sender {
+    pub fn send(&self, bytes: &[u8]) -> std::io::Result<()> {
+        let mut stream = self.stream.lock().unwrap();
+        stream.write_all(bytes)?;
+        stream.flush()
+    }
+
+    pub fn send_line(&self, line: &str) -> std::io::Result<()> {
+        tracing::trace!("sent {}", line);
+        self.send(format!("{}\n", line).as_bytes())
+    }
+
+    // ends the connection, the reading side sees it closed
+    pub fn close(&self) {
+        let _ = self.socket.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+// who is on the other end, for the log
+fn peer(socket: &TcpStream) -> String {
+    socket
+        .peer_addr()
+        .map_or(String::from("an unknown peer"), |addr| addr.to_string())
+}
+
+fn handshake(
+    socket: &mut TcpStream,
+    mut complete: impl FnMut(&mut TcpStream) -> std::io::Result<(usize, usize)>,
+) -> Result<(), String> {
+    socket
+        .set_read_timeout(Some(HANDSHAKE))
+        .map_err(|e| e.to_string())?;
+    complete(socket).map_err(|e| format!("tls handshake failed: {}", e))?;
+    Ok(())
+}
+
+// the certificate a server offers tls with, none for plain tcp
+#[derive(Debug, Clone, Args)]
+pub struct Certificate {
+    /// PEM certificate chain to serve over TLS with, plain TCP if not given
+    #[arg(long, requires = "key")]
+    pub cert: Option<PathBuf>,
+
+    /// PEM private key of the certificate
+    #[arg(long, requires = "cert")]
+    pub key: Option<PathBuf>,
+}
+
+impl Certificate {
+    // the tls setup, after telling the fingerprint clients can pin
+    pub fn server_config(&self) -> Result<Option<Arc<ServerConfig>>, String> {
+        let (Some(cert), Some(key)) = (&self.cert, &self.key) else {
+            return Ok(None);
+        };
+        let (config, fingerprint) = server_config(cert, key)?;
+        println!("tls certificate fingerprint {}", fingerprint);
+        Ok(Some(config))
+    }
+}
+
+// how a client connects: plain tcp, or tls trusting the usual authorities or
+// one pinned certificate
+#[derive(Debug, Clone, Args)]
+pub struct Trust {
+    /// Connect over TLS, to a server started with --cert
+    #[arg(long)]
+    pub tls: bool,
+
+    /// Only trust the server certificate with this SHA-256 fingerprint, which
+    /// the server prints. Implies --tls
+    #[arg(long)]
+    pub pin: Option<String>,
+}
+
+impl Trust {
+    pub fn client_config(&self) -> Result<Option<Arc<ClientConfig>>, String> {
+        match (self.tls, &self.pin) {
+            (false, None) => Ok(None),
+            (_, pin) => client_config(pin.as_deref()).map(Some),
+        }
+    }
+}
+
+// the tls setup of a server from a pem certificate chain and private key,
+// with the sha-256 fingerprint of the certificate for clients to pin
+fn server_config(cert: &Path, key: &Path) -> Result<(Arc<ServerConfig>, String), String> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| {
+            format!(
+                "could not read a certificate from {}: {}",
+                cert.display(),
+                e
+            )
+        })?;
+    let first = certs
+        .first()
+        .ok_or_else(|| format!("{} holds no certificate", cert.display()))?;
+    let fingerprint = hex(ring::digest::digest(&ring::digest::SHA256, first).as_ref());
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| format!("could not read a private key from {}: {}", key.display(), e))?;
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| e.to_string())?;
+    Ok((Arc::new(config), fingerprint))
+}
+
+// the tls setup of a client. with a pin only the certificate with that sha-256
+// fingerprint is accepted, which suits a host's self-signed one. without, the
+// certificate has to be signed by one of the usual authorities
+fn client_config(pin: Option<&str>) -> Result<Arc<ClientConfig>, String> {
+    let config = match pin {
+        Some(pin) => {
+            let fingerprint: String = pin.chars().filter(|&c| c != ':').collect();
+            let fingerprint = fingerprint.to_lowercase();
+            if fingerprint.len() != 64 || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("invalid pin: {}. it is a sha-256 fingerprint", pin));
+            }
+            let provider = CryptoProvider::get_default()
+                .cloned()
+                .unwrap_or_else(|| Arc::new(rustls::crypto::ring::default_provider()));
+            ClientConfig::builder()
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(Pinned {
+                    fingerprint,
+                    provider,
+                }))
+                .with_no_client_auth()
+        }
+        None => {
+            let roots = RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            };
+            ClientConfig::builder()
+                .with_root_certificates(roots)
+                .with_no_client_auth()
+        }
+    };
+    Ok(Arc::new(config))
+}
+
+// trusts exactly one certificate, whoever signed it
+#[derive(Debug)]
+struct Pinned {
+    fingerprint: String,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for Pinned {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let seen = hex(ring::digest::digest(&ring::digest::SHA256, end_entity).as_ref());
+        if seen == self.fingerprint {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "the certificate's fingerprint is {}, not the pinned one",
+                seen
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
@@ -0,0 +1,553 @@
+use std::cell::Cell;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEventKind,
+};
+use ratatui::crossterm::execute;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color as TermColor, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::cli::Player;
+use crate::commands::{self, Flow, Session};
+use crate::display::{self, piece_char, row_to_display, Annotations, Brush, Highlights, Mark};
+use crate::engine;
+use crate::{BoardPos, ChessBoard, Color, Move, MAX_FILES, RANKS};
+
+struct App {
+    session: Session,
+    input: String,
+    // output of the last command
+    messages: Vec<String>,
+    // tab switches between typing and moving pieces with the cursor
+    cursor_mode: bool,
+    cursor: BoardPos,
+    // the piece picked up with the cursor
+    selected: Option<BoardPos>,
+    // where the board panel was last drawn, to map mouse clicks to squares
+    board_area: Cell<Rect>,
+    // white's evaluation and the depth it was found at
+    eval: Option<(u8, i32)>,
+    // the engine's main line from the evaluated position
+    pv: Vec<Move>,
+    // whether the summary of the finished game was shown
+    summarized: bool,
+    // key of the position the evaluation belongs to
+    evaluated: Option<u64>,
+    quit: bool,
+}
+
+pub fn run(session: Session) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+    let mut app = App {
+        session,
+        input: String::new(),
+        messages: vec![String::from(
+            "type a move like e2e4, help for commands, tab for the cursor, esc to quit",
+        )],
+        cursor_mode: false,
+        cursor: BoardPos { row: 6, col: 4 },
+        selected: None,
+        board_area: Cell::new(Rect::default()),
+        eval: None,
+        pv: Vec::new(),
+        evaluated: None,
+        summarized: false,
+        quit: false,
+    };
+    let result = execute!(std::io::stdout(), EnableMouseCapture)
+        .and_then(|_| app.run(&mut terminal))
+        .and(execute!(std::io::stdout(), DisableMouseCapture));
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        while !self.quit {
+            if self.session.game.check_time() {
+                self.session.record();
+            }
+            let over = self.session.game.result.is_some();
+            if over && !self.summarized {
+                self.messages.extend(self.session.game.summary());
+            }
+            self.summarized = over;
+            terminal.draw(|frame| self.draw(frame))?;
+            if self.engine_to_move() || self.needs_eval() {
+                self.think(terminal);
+                continue;
+            }
+            // with clocks running the screen is redrawn every tenth of a second
+            if self.session.game.clock.is_some() && !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => self.key(key),
+                Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                    self.click(mouse.column, mouse.row)
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return;
+        }
+        if self.cursor_mode {
+            self.cursor_key(key.code);
+            return;
+        }
+        match key.code {
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Tab => self.cursor_mode = true,
+            KeyCode::Left => self.move_cursor(0, -1),
+            KeyCode::Right => self.move_cursor(0, 1),
+            KeyCode::Up => self.move_cursor(-1, 0),
+            KeyCode::Down => self.move_cursor(1, 0),
+            KeyCode::Char(ch) => self.input.push(ch),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => self.submit(),
+            _ => {}
+        }
+    }
+
+    fn cursor_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Tab => self.cursor_mode = false,
+            KeyCode::Esc if self.selected.is_some() => self.selected = None,
+            KeyCode::Esc => self.cursor_mode = false,
+            KeyCode::Left | KeyCode::Char('h') => self.move_cursor(0, -1),
+            KeyCode::Right | KeyCode::Char('l') => self.move_cursor(0, 1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1, 0),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1, 0),
+            KeyCode::Enter | KeyCode::Char(' ') => self.pick(),
+            _ => {}
+        }
+    }
+
+    // moves the cursor the way it looks on screen, so up stays up on a flipped board
+    fn move_cursor(&mut self, down: i8, right: i8) {
+        self.cursor_mode = true;
+        let (down, right) = if self
+            .session
+            .options
+            .is_flipped(self.session.game.board.turn)
+        {
+            (-down, -right)
+        } else {
+            (down, right)
+        };
+        let last_col = self.session.game.board.width as i8 - 1;
+        self.cursor.row = (self.cursor.row as i8 + down).clamp(0, RANKS as i8 - 1) as u8;
+        self.cursor.col = (self.cursor.col as i8 + right).clamp(0, last_col) as u8;
+    }
+
+    fn click(&mut self, x: u16, y: u16) {
+        let area = self.board_area.get();
+        // inside the border the first line holds the file letters and every
+        // rank starts with its number and a space, squares are 3 cells wide
+        let (left, top) = (area.x + 3, area.y + 2);
+        let board = &self.session.game.board;
+        let width = u16::from(board.width);
+        if x < left || y < top || x >= left + 3 * width || y >= top + u16::from(RANKS) {
+            return;
+        }
+        let options = &self.session.options;
+        self.cursor = BoardPos {
+            row: options.draw_order(board.turn, RANKS)[(y - top) as usize],
+            col: options.draw_order(board.turn, board.width)[((x - left) / 3) as usize],
+        };
+        self.pick();
+    }
+
+    // picks up the piece under the cursor, or moves the picked up piece there
+    fn pick(&mut self) {
+        let board = self.live_board();
+        let own_piece = board.color_at(self.cursor) == Some(board.turn);
+        match self.selected {
+            Some(from) if from == self.cursor => self.selected = None,
+            Some(from) if !own_piece => {
+                let mve = format!("{}{}", from, self.cursor);
+                let reply = commands::execute(&mut self.session, &mve);
+                self.messages = reply.lines;
+                self.selected = None;
+            }
+            _ if own_piece => self.selected = Some(self.cursor),
+            _ => {}
+        }
+    }
+
+    // the game's position, or the side line's while one is explored
+    fn live_board(&self) -> &ChessBoard {
+        match &self.session.sideline {
+            Some(sideline) => &sideline.board,
+            None => &self.session.game.board,
+        }
+    }
+
+    fn needs_eval(&self) -> bool {
+        let game = &self.session.game;
+        let board = self.live_board();
+        self.session.options.eval
+            && self.session.editor.is_none()
+            && (game.result.is_none() || self.session.sideline.is_some())
+            && self.evaluated != Some(board.zobrist_key())
+    }
+
+    // searches the position when the evaluation is shown, redrawing it after
+    // every depth, and plays the program's move if it is its turn
+    fn think(&mut self, terminal: &mut DefaultTerminal) {
+        let board = self.live_board().clone();
+        let depth = self.session.depth;
+        let mut searched = None;
+        if self.session.options.eval {
+            // the engine is taken out while it runs, the board is drawn meanwhile
+            let mut uci = self.session.uci.take();
+            let report = |d, pv: &[Move], score| {
+                self.eval = Some((d, engine::white_score(board.turn, score)));
+                self.pv = pv.to_vec();
+                let _ = terminal.draw(|frame| self.draw(frame));
+            };
+            searched = match &mut uci {
+                Some(engine) => engine.search(&board, depth, report),
+                None => engine::deepen(&board, depth, report),
+            };
+            self.session.uci = uci;
+            self.evaluated = Some(board.zobrist_key());
+        }
+        if self.engine_to_move() {
+            // what was searched belongs to the side line when one is open
+            let searched = searched.filter(|_| self.session.sideline.is_none());
+            if let Some(mve) = self.session.computer_move(searched.map(|(mve, _)| mve)) {
+                self.session.game.play(&mve);
+                self.session.annotations = Annotations::default();
+                self.session.record();
+                if let Some(note) = self.session.play_premove() {
+                    self.session.record();
+                    self.messages = vec![note];
+                }
+            }
+        }
+    }
+
+    fn engine_to_move(&self) -> bool {
+        let game = &self.session.game;
+        self.session.players[game.board.turn.idx()] != Player::Human
+            && game.result.is_none()
+            && self.session.editor.is_none()
+    }
+
+    fn submit(&mut self) {
+        let input = std::mem::take(&mut self.input);
+        let reply = commands::execute(&mut self.session, &input);
+        self.messages = reply.lines;
+        if reply.flow == Flow::Quit {
+            self.quit = true;
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, input] =
+            Layout::vertical([Constraint::Min(12), Constraint::Length(3)]).areas(frame.area());
+        let bar_width = if self.session.options.eval { 3 } else { 0 };
+        let [board, bar, side] = Layout::horizontal([
+            // three cells a square, the rank numbers and the border
+            Constraint::Length(3 * u16::from(self.session.game.board.width) + 8),
+            Constraint::Length(bar_width),
+            Constraint::Min(20),
+        ])
+        .areas(main);
+        // the status grows with the explorer's and oracle's moves, the history gives way
+        let status_lines = self.status_lines();
+        let [status, history, messages] = Layout::vertical([
+            Constraint::Length((status_lines.len() as u16 + 2).max(8)),
+            Constraint::Min(3),
+            Constraint::Length(8),
+        ])
+        .areas(side);
+
+        self.board_area.set(board);
+        frame.render_widget(
+            Paragraph::new(self.board_lines()).block(Block::bordered().title(" board ")),
+            board,
+        );
+        frame.render_widget(
+            Paragraph::new(status_lines).block(Block::bordered().title(" status ")),
+            status,
+        );
+        if self.session.options.eval {
+            self.draw_eval_bar(frame, bar);
+        }
+        self.draw_history(frame, history);
+        frame.render_widget(
+            Paragraph::new(self.messages.join("\n")).block(Block::bordered()),
+            messages,
+        );
+        frame.render_widget(
+            Paragraph::new(format!("> {}", self.input)).block(Block::bordered().title(
+                if self.cursor_mode {
+                    " cursor: arrows/hjkl, enter picks, tab to type "
+                } else {
+                    " move "
+                },
+            )),
+            input,
+        );
+        frame.set_cursor_position((input.x + 3 + self.input.chars().count() as u16, input.y + 1));
+    }
+
+    // the piece under the cursor or the first square of a typed move are
+    // selected, otherwise whatever show picked is used
+    fn highlights(&self) -> Highlights {
+        let typed = self.input.get(0..2).and_then(BoardPos::parse);
+        match self.selected.or(typed) {
+            Some(pos) => Highlights::legal_targets(self.live_board(), pos),
+            None => self.session.highlights.clone(),
+        }
+    }
+
+    // the engine's main line while it belongs to the position on the board, its
+    // own moves blue and the replies red, under the arrows the player drew
+    fn arrows(&self) -> Vec<(Move, Brush)> {
+        let board = self.live_board();
+        let mut arrows = Vec::new();
+        let live = self.session.viewing.is_none() || self.session.sideline.is_some();
+        if live && self.session.options.eval && self.evaluated == Some(board.zobrist_key()) {
+            for (i, &mve) in self.pv.iter().enumerate() {
+                let brush = if i % 2 == 0 { Brush::Blue } else { Brush::Red };
+                arrows.push((mve, brush));
+            }
+        }
+        arrows.extend_from_slice(&self.session.annotations.arrows);
+        arrows
+    }
+
+    fn board_lines(&self) -> Vec<Line<'static>> {
+        let board = self.session.shown_board();
+        let highlights = self.highlights();
+        let turn = self.session.game.board.turn;
+        let order = self.session.options.draw_order(turn, RANKS);
+        let columns = self.session.options.draw_order(turn, board.width);
+        let files: String = columns
+            .iter()
+            .map(|col| format!(" {} ", (b'a' + col) as char))
+            .collect();
+
+        let annotations = &self.session.annotations;
+        let glyphs = arrow_glyphs(&self.arrows(), &order, &columns);
+        let mut lines = vec![Line::from(format!("  {}", files))];
+        for (y, &row) in order.iter().enumerate() {
+            let mut spans = vec![Span::raw(format!("{} ", row_to_display(row)))];
+            for (x, &col) in columns.iter().enumerate() {
+                let pos = BoardPos { row, col };
+                let piece = board.piece_at(pos);
+                // marks drawn by the player cover the last move but not a selection
+                let drawn = annotations.marks.iter().find(|&&(p, _)| p == pos);
+                let theme = &self.session.options.theme;
+                let background = match (highlights.mark(pos, board), drawn) {
+                    (None | Some(Mark::LastMove), Some(&(_, brush))) => mark_color(brush),
+                    (mark, _) => {
+                        TermColor::Indexed(theme.square(mark, (row + col).is_multiple_of(2)))
+                    }
+                };
+                let foreground =
+                    TermColor::Indexed(theme.piece(piece.map_or(Color::White, |p| p.color)));
+                let mut style = Style::new()
+                    .bg(background)
+                    .fg(foreground)
+                    .add_modifier(Modifier::BOLD);
+                if self.cursor_mode && pos == self.cursor {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                let ch = piece_char(piece, &self.session.options);
+                match glyphs[y][x] {
+                    // an arrow runs through the middle of empty squares and
+                    // points at pieces from their left
+                    Some((glyph, brush)) => {
+                        let arrow = style.fg(arrow_color(brush));
+                        if piece.is_some() {
+                            spans.push(Span::styled(glyph.to_string(), arrow));
+                            spans.push(Span::styled(format!("{} ", ch), style));
+                        } else {
+                            spans.push(Span::styled(format!(" {} ", glyph), arrow));
+                        }
+                    }
+                    None => spans.push(Span::styled(format!(" {} ", ch), style)),
+                }
+            }
+            spans.push(Span::raw(format!(" {}", row_to_display(row))));
+            lines.push(Line::from(spans));
+        }
+        lines.push(Line::from(format!("  {}", files)));
+        lines
+    }
+
+    fn status_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(self.session.status()),
+            Line::from(self.session.game.board.material_line()),
+            Line::from(format!(
+                "White: {}",
+                self.session
+                    .game
+                    .board
+                    .capture_tray(Color::White, &self.session.options)
+            )),
+            Line::from(format!(
+                "Black: {}",
+                self.session
+                    .game
+                    .board
+                    .capture_tray(Color::Black, &self.session.options)
+            )),
+        ];
+        if let Some(opening) = self.session.opening() {
+            lines.insert(1, Line::from(opening));
+        }
+        if let Some(clock) = &self.session.game.clock {
+            lines.push(Line::from(clock.describe()));
+        }
+        lines.extend(self.session.explorer_lines().into_iter().map(Line::from));
+        lines.extend(self.session.oracle_lines().into_iter().map(Line::from));
+        if let (true, Some((depth, score))) = (self.session.options.eval, self.eval) {
+            let board = self.live_board();
+            // until the new position is searched the old line doesn't fit the board
+            let line = match self.evaluated == Some(board.zobrist_key()) {
+                true => self.session.options.line_text(board, &self.pv),
+                false => String::new(),
+            };
+            lines.push(Line::from(format!(
+                "eval {} depth {}: {}",
+                engine::format_score(score),
+                depth,
+                line
+            )));
+        }
+        lines
+    }
+
+    // white's share of the board height, filled from white's side of the board
+    fn draw_eval_bar(&self, frame: &mut Frame, area: Rect) {
+        // the same height as the ranks and file letters of the board panel
+        let height = 10.min(area.height.saturating_sub(2)) as usize;
+        let white = self
+            .eval
+            .map_or(height / 2, |(_, score)| display::eval_share(score, height));
+        let white_at_top = self
+            .session
+            .options
+            .is_flipped(self.session.game.board.turn);
+        let lines: Vec<Line> = (0..height)
+            .map(|i| {
+                let from_white = if white_at_top { i } else { height - 1 - i };
+                let color = if from_white < white {
+                    TermColor::White
+                } else {
+                    TermColor::DarkGray
+                };
+                Line::from(Span::styled("█", Style::new().fg(color)))
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered()), area);
+    }
+
+    fn draw_history(&self, frame: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = self
+            .session
+            .game
+            .history_lines(&self.session.options)
+            .into_iter()
+            .map(Line::from)
+            .collect();
+        // keep the latest moves in view once the list outgrows the panel
+        let visible = area.height.saturating_sub(2) as usize;
+        let scroll = lines.len().saturating_sub(visible) as u16;
+        frame.render_widget(
+            Paragraph::new(lines)
+                .scroll((scroll, 0))
+                .block(Block::bordered().title(" moves ")),
+            area,
+        );
+    }
+}
+
+fn mark_color(brush: Brush) -> TermColor {
+    match brush {
+        Brush::Green => TermColor::Indexed(71),
+        Brush::Red => TermColor::Indexed(167),
+        Brush::Blue => TermColor::Indexed(68),
+        Brush::Yellow => TermColor::Indexed(179),
+    }
+}
+
+fn arrow_color(brush: Brush) -> TermColor {
+    match brush {
+        Brush::Green => TermColor::Indexed(28),
+        Brush::Red => TermColor::Indexed(160),
+        Brush::Blue => TermColor::Indexed(27),
+        Brush::Yellow => TermColor::Indexed(220),
+    }
+}
+
+type Glyphs = [[Option<(char, Brush)>; MAX_FILES as usize]; RANKS as usize];
+
+// the character of every arrow on each square in screen order, so arrows
+// point the way they look on a flipped board. an arrow goes diagonally until
+// it lines up with its target and straight from there, which draws knight
+// moves as a bent line, and ends in an arrowhead
+fn arrow_glyphs(arrows: &[(Move, Brush)], rows: &[u8], columns: &[u8]) -> Glyphs {
+    let mut glyphs = [[None; MAX_FILES as usize]; RANKS as usize];
+    let screen = |pos: BoardPos| -> (i8, i8) {
+        let y = rows.iter().position(|&r| r == pos.row).unwrap_or(0);
+        let x = columns.iter().position(|&c| c == pos.col).unwrap_or(0);
+        (y as i8, x as i8)
+    };
+    for &(mve, brush) in arrows {
+        let (mut y, mut x) = screen(mve.from);
+        let target = screen(mve.to);
+        while (y, x) != target {
+            let step = ((target.0 - y).signum(), (target.1 - x).signum());
+            if (y, x) != screen(mve.from) {
+                glyphs[y as usize][x as usize] = Some((line_glyph(step), brush));
+            }
+            (y, x) = (y + step.0, x + step.1);
+            if (y, x) == target {
+                glyphs[y as usize][x as usize] = Some((head_glyph(step), brush));
+            }
+        }
+    }
+    glyphs
+}
+
+fn line_glyph(step: (i8, i8)) -> char {
+    match step {
+        (0, _) => '─',
+        (_, 0) => '│',
+        (dy, dx) if dy == dx => '╲',
+        _ => '╱',
+    }
+}
+
+fn head_glyph(step: (i8, i8)) -> char {
+    match step {
+        (-1, 0) => '↑',
+        (1, 0) => '↓',
+        (0, -1) => '←',
+        (0, 1) => '→',
+        (-1, 1) => '↗',
+        (-1, -1) => '↖',
+        (1, 1) => '↘',
+        _ => '↙',
+    }
+}
@@ -0,0 +1,359 @@
+use crate::castling::{back_row, LONG, SHORT};
+use crate::describe::piece_name;
+use crate::display::{clear_screen, DisplayOptions, Highlights};
+use crate::{BoardPos, ChessBoard, Color, Move, PieceType};
+
+// what an exercise asks for. white is the only side that moves, black's king
+// just stands there so the position is legal
+#[derive(Clone, Copy)]
+enum Goal {
+    // a piece of the type gets to the square in at most that many moves
+    Reach(PieceType, &'static str, u8),
+    // anything takes what stands on the square
+    Capture(&'static str),
+    Castle(usize),
+    // any move out of check
+    Escape,
+    Mate,
+}
+
+struct Exercise {
+    title: &'static str,
+    lesson: &'static [&'static str],
+    fen: &'static str,
+    goal: Goal,
+}
+
+const EXERCISES: &[Exercise] = &[
+    Exercise {
+        title: "the rook",
+        lesson: &[
+            "the rook moves along a rank or a file, as far as it likes, but can't jump over anything",
+            "move the rook to the marked square",
+        ],
+        fen: "7k/8/8/8/8/8/8/R3K3 w - - 0 1",
+        goal: Goal::Reach(PieceType::Rook, "a7", 1),
+    },
+    Exercise {
+        title: "the bishop",
+        lesson: &[
+            "the bishop moves diagonally, as far as it likes, and so stays on squares of one color",
+            "move the bishop to the marked square",
+        ],
+        fen: "7k/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        goal: Goal::Reach(PieceType::Bishop, "h6", 1),
+    },
+    Exercise {
+        title: "the queen",
+        lesson: &[
+            "the queen moves like a rook and a bishop together",
+            "move the queen to the marked square",
+        ],
+        fen: "7k/8/8/8/8/8/8/3QK3 w - - 0 1",
+        goal: Goal::Reach(PieceType::Queen, "a4", 1),
+    },
+    Exercise {
+        title: "the king",
+        lesson: &[
+            "the king moves one square in any direction, and never onto a square the opponent attacks",
+            "walk the king to the marked square in three moves",
+        ],
+        fen: "7k/8/8/8/8/8/8/4K3 w - - 0 1",
+        goal: Goal::Reach(PieceType::King, "e4", 3),
+    },
+    Exercise {
+        title: "the knight",
+        lesson: &[
+            "the knight jumps in an L: two squares one way and then one to the side, over anything in between",
+            "bring the knight to the marked square in two moves",
+        ],
+        fen: "7k/8/8/8/8/8/8/1N2K3 w - - 0 1",
+        goal: Goal::Reach(PieceType::Knight, "e4", 2),
+    },
+    Exercise {
+        title: "the pawn",
+        lesson: &[
+            "the pawn moves straight ahead one square, or two from its starting square, and never backwards",
+            "move the pawn to the marked square in one move",
+        ],
+        fen: "7k/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        goal: Goal::Reach(PieceType::Pawn, "e4", 1),
+    },
+    Exercise {
+        title: "pawn captures",
+        lesson: &[
+            "the pawn is the only piece that captures differently from how it moves: one square diagonally forward",
+            "take the black pawn",
+        ],
+        fen: "7k/8/8/8/3p4/4P3/8/4K3 w - - 0 1",
+        goal: Goal::Capture("d4"),
+    },
+    Exercise {
+        title: "en passant",
+        lesson: &[
+            "a pawn that moves two squares past an enemy pawn on its fifth rank can be taken as if it had moved one, right away or never",
+            "black just played e7-e5. take that pawn en passant",
+        ],
+        fen: "7k/8/8/3Pp3/8/8/8/4K3 w - e6 0 1",
+        goal: Goal::Capture("e5"),
+    },
+    Exercise {
+        title: "promotion",
+        lesson: &[
+            "a pawn that reaches the last rank becomes a queen, rook, bishop or knight, whichever its side wants",
+            "promote the pawn, type e8=Q or e7e8q",
+        ],
+        fen: "7k/4P3/8/8/8/8/8/4K3 w - - 0 1",
+        goal: Goal::Reach(PieceType::Pawn, "e8", 1),
+    },
+    Exercise {
+        title: "castling",
+        lesson: &[
+            "once a game, the king can move two squares toward a rook that has never moved, which jumps to its other side",
+            "the squares between them have to be empty, and the king may not be in check, pass an attacked square or land on one",
+            "castle on the side where it is allowed, type O-O or O-O-O or move the king two squares",
+        ],
+        fen: "5r1k/8/8/8/8/8/8/R3K2R w KQ - 0 1",
+        goal: Goal::Castle(LONG),
+    },
+    Exercise {
+        title: "check",
+        lesson: &[
+            "a king that is attacked is in check, and the next move has to end the attack",
+            "move the king away, block the line or take the attacker. get out of check",
+        ],
+        fen: "4r2k/8/8/8/8/8/3B4/4K3 w - - 0 1",
+        goal: Goal::Escape,
+    },
+    Exercise {
+        title: "checkmate",
+        lesson: &[
+            "a king in check that can't get out of it is checkmated, and that wins the game",
+            "mate the black king in one move",
+        ],
+        fen: "6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1",
+        goal: Goal::Mate,
+    },
+];
+
+// how the piece moves, said when a move of it breaks the rule
+fn rule(piece: PieceType) -> &'static str {
+    match piece {
+        PieceType::Pawn => "a pawn moves straight ahead, two squares only from its starting square, and takes diagonally",
+        PieceType::Rook => "a rook moves along a rank or a file and can't jump",
+        PieceType::Bishop => "a bishop moves diagonally and can't jump",
+        PieceType::Queen => "a queen moves along ranks, files and diagonals and can't jump",
+        PieceType::King => "a king moves one square at a time, or two when castling",
+        PieceType::Knight => "a knight jumps two squares one way and one to the side",
+        PieceType::Archbishop => "an archbishop moves like a bishop or a knight",
+        PieceType::Chancellor => "a chancellor moves like a rook or a knight",
+    }
+}
+
+// why input isn't a legal move on board, as precisely as can be told
+fn why_not(board: &ChessBoard, input: &str) -> String {
+    if input.starts_with("O-O") || input.starts_with("0-0") {
+        return String::from(
+            "castling isn't allowed there: the king and rook must not have moved, the squares between them must be empty and the king may not pass an attacked square",
+        );
+    }
+    if let Some(promotion) = board
+        .san_candidates(input)
+        .into_iter()
+        .find(|m| m.promotion.is_some())
+    {
+        return format!(
+            "say which piece the pawn becomes, like {}",
+            board.san(&promotion)
+        );
+    }
+    let Some(mve) = board.parse_uci(input) else {
+        return format!("{} isn't a move here, type one like e2e4 or Nf3", input);
+    };
+    let Some(piece) = board.piece_at(mve.from).filter(|p| p.color == board.turn) else {
+        return format!("there is no piece of yours on {}", mve.from);
+    };
+    let home = back_row(board.turn);
+    let two_over =
+        mve.from.row == home && mve.to.row == home && mve.from.col.abs_diff(mve.to.col) == 2;
+    if piece.piece == PieceType::King && two_over {
+        return why_not(board, "O-O");
+    }
+    if board.color_at(mve.to) == Some(board.turn) {
+        return format!("{} holds a piece of your own", mve.to);
+    }
+    if !piece.is_move_valid(&mve, board) {
+        return rule(piece.piece).to_string();
+    }
+    String::from("that would leave your king in check")
+}
+
+// whether mve, legal on board, does what the goal asks. what is wrong if not
+fn check(board: &ChessBoard, mve: &Move, goal: Goal) -> Result<(), String> {
+    let mut after = board.clone();
+    after.make_move(mve);
+    let piece = board.piece_at(mve.from).map(|p| p.piece);
+    match goal {
+        Goal::Reach(wanted, _, _) if piece != Some(wanted) => Err(format!(
+            "that moves the {}, this one is about the {}",
+            piece.map_or("piece", piece_name),
+            piece_name(wanted)
+        )),
+        Goal::Reach(..) => Ok(()),
+        // a pawn taken en passant doesn't stand where the capture lands
+        Goal::Capture(on)
+            if board.is_capture(mve)
+                && BoardPos::parse(on)
+                    .is_some_and(|on| board.en_passant_capture(mve).unwrap_or(mve.to) == on) =>
+        {
+            Ok(())
+        }
+        Goal::Capture(on) => Err(format!(
+            "a legal move, but the piece on {} is still there",
+            on
+        )),
+        Goal::Castle(side) if board.castling_side(mve) == Some(side) => Ok(()),
+        Goal::Castle(_) => Err(String::from("a legal move, but this one is about castling")),
+        Goal::Escape => Ok(()),
+        Goal::Mate if after.legal_moves().is_empty() && after.in_check(after.turn) => Ok(()),
+        Goal::Mate => Err(String::from(
+            "a legal move, but the black king could still escape",
+        )),
+    }
+}
+
+// the square the goal marks on the board
+fn marked(board: &ChessBoard, goal: Goal) -> Option<BoardPos> {
+    match goal {
+        Goal::Reach(_, to, _) | Goal::Capture(to) => BoardPos::parse(to),
+        Goal::Castle(side) => {
+            let king = board.king_pos(board.turn)?;
+            let col = match side {
+                SHORT => king.col + 2,
+                _ => king.col - 2,
+            };
+            Some(BoardPos { row: king.row, col })
+        }
+        Goal::Escape | Goal::Mate => None,
+    }
+}
+
+// a first move that reaches the goal in the moves left, for a hint
+fn solution(board: &ChessBoard, goal: Goal, left: u8) -> Option<Move> {
+    let moves = board.legal_moves();
+    let Goal::Reach(piece, to, _) = goal else {
+        return moves
+            .into_iter()
+            .find(|mve| check(board, mve, goal).is_ok());
+    };
+    let to = BoardPos::parse(to)?;
+    moves
+        .into_iter()
+        .filter(|mve| board.piece_at(mve.from).is_some_and(|p| p.piece == piece))
+        .find(|mve| {
+            if mve.to == to {
+                return true;
+            }
+            let mut after = board.clone();
+            after.make_move(mve);
+            after.turn = board.turn;
+            left > 1 && solution(&after, goal, left - 1).is_some()
+        })
+}
+
+// goes through the rules a piece at a time, each with a small exercise on a
+// nearly empty board. every attempt that fails says why
+pub fn run(options: &DisplayOptions) -> Result<(), String> {
+    let mut done = 0;
+    // said above the next lesson, the screen is cleared before it
+    let mut praise = None;
+    for (i, exercise) in EXERCISES.iter().enumerate() {
+        let start = ChessBoard::from_fen(exercise.fen)
+            .ok_or_else(|| format!("invalid fen in the tutorial: {}", exercise.fen))?;
+        let mut board = start.clone();
+        // moves made toward a goal of several
+        let mut made = 0;
+        let mut notes: Vec<String> = praise.take().into_iter().collect();
+        notes.extend(exercise.lesson.iter().map(|line| line.to_string()));
+        let solved = loop {
+            clear_screen(options);
+            println!(
+                "lesson {} of {}: {}",
+                i + 1,
+                EXERCISES.len(),
+                exercise.title
+            );
+            let highlights = Highlights {
+                targets: marked(&board, exercise.goal).into_iter().collect(),
+                ..Highlights::default()
+            };
+            board.print(options, &highlights);
+            for note in notes.drain(..) {
+                println!("{}", note);
+            }
+            println!("your move, or type hint, skip or quit");
+
+            let mut input = String::new();
+            if std::io::stdin().read_line(&mut input).unwrap_or(0) == 0 {
+                return Ok(());
+            }
+            let input = input.trim();
+            let left = match exercise.goal {
+                Goal::Reach(_, _, moves) => moves - made,
+                _ => 1,
+            };
+            match input {
+                "quit" => return Ok(()),
+                "skip" => break false,
+                "hint" => {
+                    notes.push(match solution(&board, exercise.goal, left) {
+                        Some(mve) => format!("try {}", options.move_text(&board.san(&mve), &mve)),
+                        None => String::from(
+                            "there's no way left, type skip or start again with a wrong move",
+                        ),
+                    });
+                    continue;
+                }
+                _ => {}
+            }
+            let mve = board
+                .parse_san(&options.locale.delocalize(input))
+                .or_else(|| board.parse_uci(input).filter(|m| board.is_legal(m)));
+            let Some(mve) = mve else {
+                notes.push(why_not(&board, input));
+                continue;
+            };
+            if let Err(e) = check(&board, &mve, exercise.goal) {
+                notes.push(e);
+                continue;
+            }
+            let Goal::Reach(_, to, moves) = exercise.goal else {
+                break true;
+            };
+            board.make_move(&mve);
+            // black never moves, the turn comes straight back
+            board.turn = Color::White;
+            made += 1;
+            if BoardPos::parse(to) == Some(mve.to) {
+                break true;
+            }
+            if made == moves {
+                notes.push(format!(
+                    "that took all {} moves without reaching {}, try again from the start",
+                    moves, to
+                ));
+                board = start.clone();
+                made = 0;
+            }
+        };
+        if solved {
+            done += 1;
+            praise = Some(String::from("well done"));
+        }
+    }
+    if let Some(praise) = praise {
+        println!("{}", praise);
+    }
+    println!("finished {} of {} exercises", done, EXERCISES.len());
+    Ok(())
+}
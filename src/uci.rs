@@ -0,0 +1,273 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::engine::MATE;
+use crate::{ChessBoard, Move};
+
+// an external engine like stockfish, spoken to over the uci protocol on its
+// stdin and stdout. it runs for as long as this lives
+pub struct UciEngine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    pub name: String,
+    // whether it writes castling as the king taking its rook, as needed for
+    // chess960, instead of as the king's move
+    chess960: bool,
+    // the options it offers by name, with the range of the numeric ones
+    offered: Vec<(String, Option<(i64, i64)>)>,
+}
+
+// an option line like "option name Hash type spin default 16 min 1 max 33554432"
+// as the option's name and its range when it has one
+fn offered(line: &str) -> Option<(String, Option<(i64, i64)>)> {
+    let (name, rest) = line.strip_prefix("option name ")?.split_once(" type ")?;
+    let words: Vec<&str> = rest.split_whitespace().collect();
+    let after = |key: &str| {
+        let at = words.iter().position(|w| *w == key)?;
+        words.get(at + 1)?.parse::<i64>().ok()
+    };
+    let range = match words.first() {
+        Some(&"spin") => after("min").zip(after("max")),
+        _ => None,
+    };
+    Some((name.trim().to_string(), range))
+}
+
+impl UciEngine {
+    // starts the command with sh, so it can carry arguments, and sets the options
+    // given as name=value
+    pub fn start(command: &str, options: &[String]) -> Result<Self, String> {
+        let mut child = Command::new("sh")
+            .args(["-c", command])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("could not start {}: {}", command, e))?;
+        let stdin = child.stdin.take().ok_or("the engine has no stdin")?;
+        let stdout = child.stdout.take().ok_or("the engine has no stdout")?;
+        let mut engine = UciEngine {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            name: command.to_string(),
+            chess960: false,
+            offered: Vec::new(),
+        };
+
+        engine.send("uci");
+        loop {
+            let line = engine
+                .read()
+                .ok_or_else(|| format!("{} is not a uci engine", command))?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            }
+            if line.starts_with("option name UCI_Chess960 ") {
+                engine.chess960 = true;
+            }
+            if let Some(option) = offered(&line) {
+                engine.offered.push(option);
+            }
+            if line.trim() == "uciok" {
+                break;
+            }
+        }
+        if engine.chess960 {
+            engine.send("setoption name UCI_Chess960 value true");
+        }
+        for option in options {
+            let (name, value) = option
+                .split_once('=')
+                .ok_or_else(|| format!("invalid engine option: {}. example: Hash=64", option))?;
+            engine.send(&format!(
+                "setoption name {} value {}",
+                name.trim(),
+                value.trim()
+            ));
+        }
+        engine.ready()?;
+        Ok(engine)
+    }
+
+    pub fn offers(&self, name: &str) -> bool {
+        self.offered.iter().any(|(offered, _)| offered == name)
+    }
+
+    // sets an option the engine offers, numbers only within the range it
+    // gave, and waits until it has taken it
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let range = self
+            .offered
+            .iter()
+            .find(|(offered, _)| offered == name)
+            .ok_or_else(|| format!("{} has no option {}", self.name, name))?
+            .1;
+        if let (Some((min, max)), Ok(n)) = (range, value.parse::<i64>()) {
+            if !(min..=max).contains(&n) {
+                return Err(format!(
+                    "{} takes {} from {} to {}, not {}",
+                    self.name, name, min, max, n
+                ));
+            }
+        }
+        self.send(&format!("setoption name {} value {}", name, value));
+        self.ready()
+    }
+
+    fn send(&mut self, line: &str) {
+        tracing::trace!("to {}: {}", self.name, line);
+        let _ = writeln!(self.stdin, "{}", line).and_then(|_| self.stdin.flush());
+    }
+
+    // the next line, none once the engine is gone
+    fn read(&mut self) -> Option<String> {
+        let mut line = String::new();
+        match self.stdout.read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                tracing::warn!("{} is gone", self.name);
+                None
+            }
+            Ok(_) => {
+                tracing::trace!("from {}: {}", self.name, line.trim_end());
+                Some(line)
+            }
+        }
+    }
+
+    fn ready(&mut self) -> Result<(), String> {
+        self.send("isready");
+        loop {
+            match self.read() {
+                Some(line) if line.trim() == "readyok" => return Ok(()),
+                Some(_) => {}
+                None => return Err(format!("{} stopped", self.name)),
+            }
+        }
+    }
+
+    // the move in the notation the engine speaks
+    pub fn move_text(&self, board: &ChessBoard, mve: &Move) -> String {
+        match self.chess960 {
+            true => mve.to_string(),
+            false => board.standard_uci(mve),
+        }
+    }
+
+    // the legal moves the engine sees in the board, from the lines like
+    // "e2e4: 1" that stockfish and its forks answer go perft 1 with
+    pub fn perft_moves(&mut self, board: &ChessBoard) -> Result<Vec<String>, String> {
+        self.send(&format!("position fen {}", board.to_fen()));
+        self.send("go perft 1");
+        let mut moves = Vec::new();
+        loop {
+            let line = self
+                .read()
+                .ok_or_else(|| format!("{} stopped", self.name))?;
+            let line = line.trim();
+            if line.starts_with("Nodes searched") {
+                return Ok(moves);
+            }
+            if let Some((mve, "1")) = line.split_once(": ") {
+                if !mve.contains(' ') {
+                    moves.push(mve.to_string());
+                }
+            }
+        }
+    }
+
+    // searches to depth like engine::deepen, reporting every depth the engine
+    // tells about. the engine only gets to pick among the moves legal here
+    pub fn search(
+        &mut self,
+        board: &ChessBoard,
+        depth: u8,
+        mut report: impl FnMut(u8, &[Move], i32),
+    ) -> Option<(Move, i32)> {
+        let moves = board.legal_moves();
+        if moves.is_empty() {
+            return None;
+        }
+        let allowed: Vec<String> = moves.iter().map(|m| self.move_text(board, m)).collect();
+        self.send(&format!("position fen {}", board.to_fen()));
+        self.send(&format!(
+            "go depth {} searchmoves {}",
+            depth.max(1),
+            allowed.join(" ")
+        ));
+
+        let mut score = 0;
+        loop {
+            let line = self.read()?;
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("info") => {
+                    let words: Vec<&str> = words.collect();
+                    let after = |key: &str| {
+                        let at = words.iter().position(|w| *w == key)?;
+                        words.get(at + 1).copied()
+                    };
+                    let Some(reached) = after("depth").and_then(|d| d.parse().ok()) else {
+                        continue;
+                    };
+                    score = match (after("cp"), after("mate")) {
+                        (Some(cp), _) => cp.parse().unwrap_or(score),
+                        (_, Some(mate)) => match mate.parse::<i32>() {
+                            Ok(n) if n > 0 => MATE - (2 * n - 1),
+                            Ok(n) => -(MATE - 2 * -n),
+                            Err(_) => score,
+                        },
+                        _ => continue,
+                    };
+                    let pv = match words.iter().position(|w| *w == "pv") {
+                        Some(at) => main_line(board, &words[at + 1..]),
+                        None => continue,
+                    };
+                    if !pv.is_empty() {
+                        report(reached, &pv, score);
+                    }
+                }
+                Some("bestmove") => {
+                    let best = words.next().and_then(|uci| board.parse_uci(uci));
+                    // whatever else it says, the move has to be legal here
+                    let best = best.filter(|m| moves.contains(m)).unwrap_or(moves[0]);
+                    return Some((best, score));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// the part of an engine's main line this board can follow
+fn main_line(board: &ChessBoard, words: &[&str]) -> Vec<Move> {
+    let mut board = board.clone();
+    let mut pv = Vec::new();
+    for word in words {
+        match board.parse_uci(word) {
+            Some(mve) if board.is_legal(&mve) => {
+                board.make_move(&mve);
+                pv.push(mve);
+            }
+            _ => break,
+        }
+    }
+    pv
+}
+
+impl Drop for UciEngine {
+    // asks the engine to quit, and stops it when it doesn't in time
+    fn drop(&mut self) {
+        self.send("quit");
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt::Debug;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameResult;
+use crate::rng::Rng;
+use crate::{ChessBoard, Move, PieceType};
+
+// the rules a game is played by, apart from how the pieces move, which is
+// the same in every variant. standard chess is what the defaults do
+pub trait Variant: Debug + Sync {
+    fn kind(&self) -> VariantKind;
+
+    // the usual initial position, the one pgn leaves out
+    fn initial(&self) -> ChessBoard {
+        ChessBoard::new().with_variant(self.kind())
+    }
+
+    // the position a new game starts from, with rng for variants that shuffle it
+    fn setup(&self, _rng: &mut Rng) -> ChessBoard {
+        self.initial()
+    }
+
+    // whether the king may not be left in check, and with it whether there is
+    // check, mate and castling
+    fn royal_king(&self) -> bool {
+        true
+    }
+
+    // how many moves a side makes in its turn, unless one of them checks
+    fn moves_per_turn(&self) -> u8 {
+        1
+    }
+
+    // whether a side that can capture has to
+    fn forced_captures(&self) -> bool {
+        false
+    }
+
+    // the pieces a pawn can become on the far row
+    fn promotions(&self) -> &'static [PieceType] {
+        &[
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+        ]
+    }
+
+    // narrows the moves the pieces can make, after king safety and castling,
+    // down to the ones the variant allows
+    fn restrict(&self, _board: &ChessBoard, _moves: &mut Vec<Move>) {}
+
+    // how the game ends when the side to move has no legal move
+    fn no_moves(&self, board: &ChessBoard) -> GameResult {
+        if board.in_check(board.turn) {
+            GameResult::Checkmate(board.turn.other())
+        } else {
+            GameResult::Stalemate
+        }
+    }
+
+    // turns the engine's material count, from the side to move's point of
+    // view, into how good the position is for it
+    fn evaluate(&self, material: i32) -> i32 {
+        material
+    }
+}
+
+// the variants there are, to pick on the command line and keep in files
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum VariantKind {
+    #[default]
+    Standard,
+    // fischer random: the pieces behind the pawns are shuffled, and castling
+    // puts king and rook where they would be in standard chess
+    Chess960,
+    // losing chess: whoever runs out of pieces or moves first wins. captures
+    // are compulsory and the king is a piece like any other
+    Antichess,
+    // a board ten files wide with an archbishop and a chancellor added to each
+    // side, between the knight and bishop
+    Capablanca,
+    // marseillais chess: every turn is two moves, and a move that checks ends
+    // the turn early
+    Marseillais,
+}
+
+impl VariantKind {
+    pub fn rules(self) -> &'static dyn Variant {
+        match self {
+            VariantKind::Standard => &Standard,
+            VariantKind::Chess960 => &Chess960,
+            VariantKind::Antichess => &Antichess,
+            VariantKind::Capablanca => &Capablanca,
+            VariantKind::Marseillais => &Marseillais,
+        }
+    }
+
+    // the value of the pgn variant tag, none for standard chess
+    pub fn pgn_name(self) -> Option<&'static str> {
+        match self {
+            VariantKind::Standard => None,
+            VariantKind::Chess960 => Some("Chess960"),
+            VariantKind::Antichess => Some("Antichess"),
+            VariantKind::Capablanca => Some("Capablanca"),
+            VariantKind::Marseillais => Some("Marseillais"),
+        }
+    }
+
+    // from the value of a pgn variant tag, with the names other programs use
+    pub fn from_pgn(name: &str) -> Option<Self> {
+        let name: String = name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+        match name.as_str() {
+            "standard" => Some(VariantKind::Standard),
+            "chess960" | "fischerandom" | "fischerrandom" => Some(VariantKind::Chess960),
+            "antichess" | "losingchess" | "giveaway" => Some(VariantKind::Antichess),
+            "capablanca" | "capablancachess" => Some(VariantKind::Capablanca),
+            "marseillais" | "doublemove" => Some(VariantKind::Marseillais),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Standard;
+
+impl Variant for Standard {
+    fn kind(&self) -> VariantKind {
+        VariantKind::Standard
+    }
+}
+
+#[derive(Debug)]
+struct Chess960;
+
+impl Variant for Chess960 {
+    fn kind(&self) -> VariantKind {
+        VariantKind::Chess960
+    }
+
+    fn setup(&self, rng: &mut Rng) -> ChessBoard {
+        chess960(rng.below(CHESS960_POSITIONS.into()) as u16)
+    }
+}
+
+#[derive(Debug)]
+struct Antichess;
+
+impl Variant for Antichess {
+    fn kind(&self) -> VariantKind {
+        VariantKind::Antichess
+    }
+
+    fn royal_king(&self) -> bool {
+        false
+    }
+
+    fn forced_captures(&self) -> bool {
+        true
+    }
+
+    // the king is a piece like any other, so a pawn can become one
+    fn promotions(&self) -> &'static [PieceType] {
+        &[
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+            PieceType::King,
+        ]
+    }
+
+    fn restrict(&self, board: &ChessBoard, moves: &mut Vec<Move>) {
+        if moves.iter().any(|m| board.is_capture(m)) {
+            moves.retain(|m| board.is_capture(m));
+        }
+    }
+
+    // running out of pieces or being stalemated wins
+    fn no_moves(&self, board: &ChessBoard) -> GameResult {
+        GameResult::NoMoves(board.turn)
+    }
+
+    // the side with less left is the one winning
+    fn evaluate(&self, material: i32) -> i32 {
+        -material
+    }
+}
+
+#[derive(Debug)]
+struct Capablanca;
+
+impl Variant for Capablanca {
+    fn kind(&self) -> VariantKind {
+        VariantKind::Capablanca
+    }
+
+    fn initial(&self) -> ChessBoard {
+        ChessBoard::with_back_rank(&[
+            PieceType::Rook,
+            PieceType::Knight,
+            PieceType::Archbishop,
+            PieceType::Bishop,
+            PieceType::Queen,
+            PieceType::King,
+            PieceType::Bishop,
+            PieceType::Chancellor,
+            PieceType::Knight,
+            PieceType::Rook,
+        ])
+        .with_variant(self.kind())
+    }
+
+    fn promotions(&self) -> &'static [PieceType] {
+        &[
+            PieceType::Queen,
+            PieceType::Chancellor,
+            PieceType::Archbishop,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+        ]
+    }
+}
+
+#[derive(Debug)]
+struct Marseillais;
+
+impl Variant for Marseillais {
+    fn kind(&self) -> VariantKind {
+        VariantKind::Marseillais
+    }
+
+    fn moves_per_turn(&self) -> u8 {
+        2
+    }
+}
+
+// the number of chess960 starting positions
+pub const CHESS960_POSITIONS: u16 = 960;
+
+// the pieces behind the pawns in chess960 position n, numbered like scharnagl
+// does, where 518 is the usual rnbqkbnr
+pub fn back_rank(n: u16) -> [PieceType; 8] {
+    let mut rank = [None; 8];
+    let n = n as usize % CHESS960_POSITIONS as usize;
+    // a bishop on a light and one on a dark square
+    rank[n % 4 * 2 + 1] = Some(PieceType::Bishop);
+    rank[n / 4 % 4 * 2] = Some(PieceType::Bishop);
+    let n = n / 16;
+    // the rest go on the empty squares from left to right
+    let mut place = |skip: usize, piece| {
+        let col = (0..8).filter(|&col| rank[col].is_none()).nth(skip).unwrap();
+        rank[col] = Some(piece);
+    };
+    place(n % 6, PieceType::Queen);
+    let (first, second) = [
+        (0, 0),
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 1),
+        (1, 2),
+        (1, 3),
+        (2, 2),
+        (2, 3),
+        (3, 3),
+    ][n / 6];
+    // the second knight goes on an empty square counted after the first is placed
+    place(first, PieceType::Knight);
+    place(second, PieceType::Knight);
+    place(0, PieceType::Rook);
+    place(0, PieceType::King);
+    place(0, PieceType::Rook);
+    rank.map(Option::unwrap)
+}
+
+pub fn chess960(n: u16) -> ChessBoard {
+    ChessBoard::with_back_rank(&back_rank(n)).with_variant(VariantKind::Chess960)
+}
+
+impl ChessBoard {
+    // the rules this position is played by
+    pub fn rules(&self) -> &'static dyn Variant {
+        self.variant.rules()
+    }
+
+    // the same position played by the rules of the variant
+    pub fn with_variant(mut self, variant: VariantKind) -> Self {
+        self.variant = variant;
+        if !variant.rules().royal_king() {
+            self.castling = [[None; 2]; 2];
+        }
+        self
+    }
+
+    // whether the side to move has to capture, which leaves it only captures
+    pub fn must_capture(&self) -> bool {
+        self.rules().forced_captures() && self.legal_moves().iter().any(|m| self.is_capture(m))
+    }
+}
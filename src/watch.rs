@@ -0,0 +1,145 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::display::{clear_screen, DisplayOptions, Highlights};
+use crate::game::Game;
+use crate::game::GameResult;
+use crate::net::Remote;
+use crate::pgn;
+use crate::ChessBoard;
+
+// follows a pgn file that another program keeps adding moves to, showing the
+// position whenever it changes until the game is over, or with follow on to
+// every game added after it, like a relay or an engine match writes them.
+// the last game of the file is the one shown. nothing is ever asked
+pub fn run(
+    path: &Path,
+    start: &ChessBoard,
+    options: &DisplayOptions,
+    interval: Duration,
+    follow: bool,
+) -> Result<(), String> {
+    // the game shown, counted in the file, its plies and whether it has a
+    // result
+    let mut shown = None;
+    loop {
+        let file = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        let games = pgn::games(&file);
+        let number = games.len().max(1);
+        let text = games.last().map_or("", String::as_str);
+        let tags = pgn::tags(text);
+        // a game with its own start says so in its tags, others start like
+        // the command line says
+        let board = match tags
+            .iter()
+            .any(|(name, _)| name == "FEN" || name == "Variant")
+        {
+            true => pgn::start(text, start.variant)?,
+            false => start.clone(),
+        };
+        let mut game = Game::from_board(board);
+        for san in pgn::movetext(text) {
+            // the writer may be in the middle of a move, the rest comes next time
+            match game.board.parse_san(&san) {
+                Some(mve) => {
+                    game.play(&mve);
+                }
+                None => break,
+            }
+        }
+
+        let result = pgn::result(text);
+        let now = (number, game.history.len(), result.is_some());
+        if shown != Some(now) {
+            shown = Some(now);
+            if follow {
+                let tag = |wanted: &str| {
+                    tags.iter()
+                        .find(|(name, _)| name == wanted)
+                        .map_or("?", |(_, value)| value.as_str())
+                };
+                clear_screen(options);
+                println!("game {}: {} - {}", number, tag("White"), tag("Black"));
+            }
+            show(&game, options, follow);
+            if let Some(result) = result.filter(|_| game.result.is_none()) {
+                println!("result: {}", result);
+            }
+        }
+
+        let over = game.result.is_some() || result.is_some();
+        if over && !follow {
+            return Ok(());
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+// follows a game hosted over tcp as a spectator, until the game is over or the
+// host goes away
+pub fn follow(
+    mut remote: Remote,
+    start: &ChessBoard,
+    options: &DisplayOptions,
+) -> Result<(), String> {
+    let mut game = Game::from_board(start.clone());
+    // lines don't say who sent them, but a second draw before the offer lapses
+    // is the other player accepting
+    let mut offered = None;
+    show(&game, options, false);
+    while game.result.is_none() {
+        let Some(line) = remote.receive() else {
+            println!("the host closed the connection");
+            return Ok(());
+        };
+        let turn = game.board.turn;
+        match line.as_str() {
+            "resign" => game.end(GameResult::Resignation(turn.other())),
+            "draw" if offered.is_some_and(|at| game.history.len() <= at + 1) => {
+                game.end(GameResult::DrawAgreed)
+            }
+            "draw" => {
+                offered = Some(game.history.len());
+                println!("a draw is offered");
+                continue;
+            }
+            "claim" => match game.draw_claim() {
+                Some(claim) => game.end(GameResult::DrawClaimed(claim)),
+                None => continue,
+            },
+            uci => match game.board.parse_uci(uci) {
+                Some(mve) if game.board.is_legal(&mve) => {
+                    game.play(&mve);
+                }
+                _ => return Err(format!("the host sent an invalid move: {}", uci)),
+            },
+        }
+        show(&game, options, false);
+    }
+    Ok(())
+}
+
+// the position, the last move and how the game stands. kept is for a screen
+// already cleared for a heading
+fn show(game: &Game, options: &DisplayOptions, kept: bool) {
+    if !kept {
+        clear_screen(options);
+    }
+    game.board.print(options, &Highlights::default());
+    if let Some(played) = game.history.last() {
+        let number = game.history.len().div_ceil(2);
+        let dots = if game.history.len() % 2 == 1 {
+            "."
+        } else {
+            "..."
+        };
+        println!(
+            "last move: {}{} {}",
+            number,
+            dots,
+            options.move_text(&played.san, &played.mve)
+        );
+    }
+    println!("{}", options.locale.status(game));
+}
@@ -0,0 +1,149 @@
+use std::net::TcpStream;
+use std::sync::Arc;
+
+use rustls::ServerConfig;
+
+use crate::digest::sha1;
+use crate::transport::{Connection, Sender};
+
+// the part of rfc 6455 a local game server needs: the opening handshake and
+// unfragmented text frames. clients mask what they send, the server doesn't
+pub struct WebSocket {
+    connection: Connection,
+}
+
+// the longest message read, so a broken client can't fill the memory
+const MAX_MESSAGE: u64 = 64 * 1024;
+
+// appended to the client's key before hashing, fixed by the rfc
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+impl WebSocket {
+    // reads the http upgrade request and answers it. a request that isn't for
+    // a websocket gets the page when there is one, and an error either way
+    pub fn accept(
+        stream: TcpStream,
+        tls: Option<&Arc<ServerConfig>>,
+        page: Option<&str>,
+    ) -> Result<Self, String> {
+        let mut connection = Connection::accept(stream, tls)?;
+        let mut key = None;
+        loop {
+            let Some(line) = connection.read_line(8 * 1024) else {
+                return Err(String::from("the connection closed"));
+            };
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                    key = Some(value.trim().to_string());
+                }
+            }
+        }
+        let writer = connection.sender();
+        let Some(key) = key else {
+            let response = match page {
+                Some(page) => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    page.len(),
+                    page
+                ),
+                None => String::from("HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n"),
+            };
+            let _ = writer.send(response.as_bytes());
+            return Err(String::from("not a websocket request"));
+        };
+        let accept = base64(&sha1(format!("{}{}", key, GUID).as_bytes()));
+        let response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+            accept
+        );
+        writer
+            .send(response.as_bytes())
+            .map_err(|e| e.to_string())?;
+        Ok(WebSocket { connection })
+    }
+
+    // a second handle for sending while another thread waits in receive
+    pub fn sender(&self) -> Sender {
+        self.connection.sender()
+    }
+
+    // the next text message, none once the client closes or breaks the protocol.
+    // pings are answered on the way
+    pub fn receive(&mut self) -> Option<String> {
+        loop {
+            let head = self.connection.read_exact(2)?;
+            let fin = head[0] & 0x80 != 0;
+            let opcode = head[0] & 0x0f;
+            let masked = head[1] & 0x80 != 0;
+            let len = match head[1] & 0x7f {
+                126 => {
+                    let len = self.connection.read_exact(2)?;
+                    u16::from_be_bytes([len[0], len[1]]) as u64
+                }
+                127 => {
+                    let len = self.connection.read_exact(8)?;
+                    u64::from_be_bytes(len.try_into().ok()?)
+                }
+                len => len as u64,
+            };
+            if !fin || !masked || len > MAX_MESSAGE {
+                return None;
+            }
+            let mask = self.connection.read_exact(4)?;
+            let mut payload = self.connection.read_exact(len as usize)?;
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+            match opcode {
+                0x1 => return String::from_utf8(payload).ok(),
+                0x9 => send_frame(&self.connection.sender(), 0xa, &payload).ok()?,
+                0xa => {}
+                _ => return None,
+            }
+        }
+    }
+}
+
+// sends one text message to a websocket client
+pub fn send(sender: &Sender, text: &str) -> std::io::Result<()> {
+    send_frame(sender, 0x1, text.as_bytes())
+}
+
+fn send_frame(sender: &Sender, opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xffff => {
+            frame.push(126);
+            frame.extend((len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend((len as u64).to_be_bytes());
+        }
+    }
+    frame.extend(payload);
+    sender.send(&frame)
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,60 @@
+use crate::{ChessBoard, Color, PieceType, MAX_FILES, SQUARES};
+
+const PIECE_KEYS: usize = 2 * PieceType::ALL.len() * SQUARES;
+
+// one per color, side and rook column, after the one for the side to move
+const CASTLING_KEYS: usize = 2 * 2 * MAX_FILES as usize;
+
+// one per column of the en passant square, after the castling keys
+const EN_PASSANT_KEYS: usize = PIECE_KEYS + 1 + CASTLING_KEYS;
+
+// the last key is for the second move of a turn of two
+const KEY_COUNT: usize = EN_PASSANT_KEYS + MAX_FILES as usize + 1;
+
+// xorshift64*, evaluated at compile time so the keys are the same on every run
+const fn generate_keys() -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut i = 0;
+    while i < keys.len() {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        keys[i] = state.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        i += 1;
+    }
+    keys
+}
+
+static KEYS: [u64; KEY_COUNT] = generate_keys();
+
+impl ChessBoard {
+    // the key after the piece keys is used for the side to move, the ones
+    // after it for castling rights, the en passant column and for a turn
+    // already under way
+    pub fn zobrist_key(&self) -> u64 {
+        let mut key = match self.turn {
+            Color::White => 0,
+            Color::Black => KEYS[PIECE_KEYS],
+        };
+        for color in [Color::White, Color::Black] {
+            for p in self.pieces(color) {
+                let piece = color.idx() * PieceType::ALL.len() + p.piece.idx();
+                key ^= KEYS[piece * SQUARES + p.pos.to_idx()];
+            }
+            for (side, right) in self.castling[color.idx()].iter().enumerate() {
+                if let Some(col) = right {
+                    let right = (color.idx() * 2 + side) * MAX_FILES as usize + *col as usize;
+                    key ^= KEYS[PIECE_KEYS + 1 + right];
+                }
+            }
+        }
+        if let Some(pos) = self.en_passant {
+            key ^= KEYS[EN_PASSANT_KEYS + pos.col as usize];
+        }
+        if self.turn_moves > 0 {
+            key ^= KEYS[KEY_COUNT - 1];
+        }
+        key
+    }
+}